@@ -1,4 +1,6 @@
 use crate::rpc;
+use kc_core::app_error::AppError;
+use tauri::Emitter;
 
 #[tauri::command]
 pub fn vault_init(req: rpc::VaultInitReq) -> rpc::RpcResponse<rpc::VaultInitRes> {
@@ -214,6 +216,25 @@ pub fn ingest_inbox_stop(
     rpc::ingest_inbox_stop_rpc(req)
 }
 
+#[tauri::command]
+pub fn inbox_watch_start(
+    req: rpc::InboxWatchStartReq,
+) -> rpc::RpcResponse<rpc::InboxWatchStartRes> {
+    rpc::inbox_watch_start_rpc(req)
+}
+
+#[tauri::command]
+pub fn inbox_watch_stop(req: rpc::InboxWatchStopReq) -> rpc::RpcResponse<rpc::InboxWatchStopRes> {
+    rpc::inbox_watch_stop_rpc(req)
+}
+
+#[tauri::command]
+pub fn inbox_watch_status(
+    req: rpc::InboxWatchStatusReq,
+) -> rpc::RpcResponse<rpc::InboxWatchStatusRes> {
+    rpc::inbox_watch_status_rpc(req)
+}
+
 #[tauri::command]
 pub fn search_query(req: rpc::SearchQueryReq) -> rpc::RpcResponse<rpc::SearchQueryRes> {
     rpc::search_query_rpc(req)
@@ -239,6 +260,68 @@ pub fn ask_question(req: rpc::AskQuestionReq) -> rpc::RpcResponse<rpc::AskQuesti
     rpc::ask_question_rpc(req)
 }
 
+// Runs off the main thread so `ask_cancel` can be invoked while the answer streams.
+#[tauri::command]
+pub async fn ask_question_stream(
+    app: tauri::AppHandle,
+    req: rpc::AskQuestionStreamReq,
+) -> rpc::RpcResponse<rpc::AskQuestionRes> {
+    tauri::async_runtime::spawn_blocking(move || {
+        rpc::ask_question_stream_rpc(req, &mut |event| {
+            let _ = app.emit(rpc::ASK_STREAM_EVENT, event);
+        })
+    })
+    .await
+    .unwrap_or_else(|_| rpc::RpcResponse::err(AppError::internal("ask stream task failed")))
+}
+
+#[tauri::command]
+pub fn ask_cancel(req: rpc::AskCancelReq) -> rpc::RpcResponse<rpc::AskCancelRes> {
+    rpc::ask_cancel_rpc(req)
+}
+
+#[tauri::command]
+pub fn ask_session_create(req: rpc::AskSessionCreateReq) -> rpc::RpcResponse<kc_ask::AskSessionV1> {
+    rpc::ask_session_create_rpc(req)
+}
+
+#[tauri::command]
+pub fn ask_session_list(req: rpc::AskSessionListReq) -> rpc::RpcResponse<rpc::AskSessionListRes> {
+    rpc::ask_session_list_rpc(req)
+}
+
+#[tauri::command]
+pub fn ask_session_get(req: rpc::AskSessionGetReq) -> rpc::RpcResponse<kc_ask::AskSessionDetailV1> {
+    rpc::ask_session_get_rpc(req)
+}
+
+#[tauri::command]
+pub fn ask_session_delete(
+    req: rpc::AskSessionDeleteReq,
+) -> rpc::RpcResponse<rpc::AskSessionDeleteRes> {
+    rpc::ask_session_delete_rpc(req)
+}
+
+#[tauri::command]
+pub fn trace_list(req: rpc::TraceListReq) -> rpc::RpcResponse<rpc::TraceListRes> {
+    rpc::trace_list_rpc(req)
+}
+
+#[tauri::command]
+pub fn trace_get(req: rpc::TraceGetReq) -> rpc::RpcResponse<kc_ask::trace::TraceLogV1> {
+    rpc::trace_get_rpc(req)
+}
+
+#[tauri::command]
+pub fn trace_prune(req: rpc::TracePruneReq) -> rpc::RpcResponse<kc_ask::trace::TracePruneReportV1> {
+    rpc::trace_prune_rpc(req)
+}
+
+#[tauri::command]
+pub fn trace_replay(req: rpc::TraceReplayReq) -> rpc::RpcResponse<rpc::AskQuestionRes> {
+    rpc::trace_replay_rpc(req)
+}
+
 #[tauri::command]
 pub fn events_list(req: rpc::EventsListReq) -> rpc::RpcResponse<rpc::EventsListRes> {
     rpc::events_list_rpc(req)
@@ -249,6 +332,33 @@ pub fn jobs_list(req: rpc::JobsListReq) -> rpc::RpcResponse<rpc::JobsListRes> {
     rpc::jobs_list_rpc(req)
 }
 
+#[tauri::command]
+pub fn jobs_cancel(req: rpc::JobsCancelReq) -> rpc::RpcResponse<rpc::JobsCancelRes> {
+    rpc::jobs_cancel_rpc(req)
+}
+
+#[tauri::command]
+pub fn doc_delete(req: rpc::DocDeleteReq) -> rpc::RpcResponse<rpc::DocDeleteRes> {
+    rpc::doc_delete_rpc(req)
+}
+
+#[tauri::command]
+pub fn doc_tombstones_list(
+    req: rpc::DocTombstonesListReq,
+) -> rpc::RpcResponse<rpc::DocTombstonesListRes> {
+    rpc::doc_tombstones_list_rpc(req)
+}
+
+#[tauri::command]
+pub fn doc_versions(req: rpc::DocVersionsReq) -> rpc::RpcResponse<rpc::DocVersionsRes> {
+    rpc::doc_versions_rpc(req)
+}
+
+#[tauri::command]
+pub fn jobs_run(req: rpc::JobsRunReq) -> rpc::RpcResponse<rpc::JobsRunRes> {
+    rpc::jobs_run_rpc(req)
+}
+
 #[tauri::command]
 pub fn sync_status(req: rpc::SyncStatusReq) -> rpc::RpcResponse<rpc::SyncStatusRes> {
     rpc::sync_status_rpc(req)
@@ -36,13 +36,31 @@ fn main() {
         commands::ingest_scan_folder,
         commands::ingest_inbox_start,
         commands::ingest_inbox_stop,
+        commands::inbox_watch_start,
+        commands::inbox_watch_stop,
+        commands::inbox_watch_status,
         commands::search_query,
         commands::locator_resolve,
         commands::export_bundle,
         commands::verify_bundle,
         commands::ask_question,
+        commands::ask_question_stream,
+        commands::ask_cancel,
+        commands::ask_session_create,
+        commands::ask_session_list,
+        commands::ask_session_get,
+        commands::ask_session_delete,
+        commands::trace_list,
+        commands::trace_get,
+        commands::trace_prune,
+        commands::trace_replay,
         commands::events_list,
         commands::jobs_list,
+        commands::jobs_cancel,
+        commands::jobs_run,
+        commands::doc_delete,
+        commands::doc_tombstones_list,
+        commands::doc_versions,
         commands::sync_status,
         commands::sync_push,
         commands::sync_pull,
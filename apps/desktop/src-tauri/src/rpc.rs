@@ -1,11 +1,35 @@
-use kc_ask::{AskRequest, AskService, RetrievedOnlyAskService};
+use kc_ask::session::{ask_session_create, ask_session_delete, ask_session_get, ask_sessions_list};
+use kc_ask::trace::{
+    trace_replay_request, TraceLogV1, TracePruneReportV1, TraceStore, TraceSummaryV1,
+};
+use kc_ask::{
+    AskCancelToken, AskRequest, AskSessionDetailV1, AskSessionTurnV1, AskSessionV1,
+    AskStreamEventV1, RetrievedOnlyAskService,
+};
 use kc_cli::verifier::verify_bundle;
-use kc_core::app_error::AppError;
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::diversify::DiversifyReportV1;
+use kc_core::faithfulness::AnswerVerificationV1;
+use kc_core::inbox::InboxWatchConfigV1;
 use kc_core::locator::LocatorV1;
+use kc_core::pipeline::PipelineServices;
 use kc_core::rpc_service;
+use kc_core::search::{SearchFacetsV1, SearchFilterV1, SearchHitV1, SearchReq};
+use kc_core::vault::vault_open;
+use kc_extract::DefaultExtractor;
+use kc_index::open_vault_indexes;
 use serde::de::Error as DeError;
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+// Tauri event carrying `AskStreamEventRes` payloads for `ask_question_stream`.
+pub const ASK_STREAM_EVENT: &str = "ask://stream";
+
+static ASK_STREAMS: OnceLock<Mutex<BTreeMap<String, AskCancelToken>>> = OnceLock::new();
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RpcResponse<T> {
@@ -558,6 +582,7 @@ pub struct IngestInboxStartRes {
 pub struct IngestInboxStopReq {
     pub vault_path: String,
     pub job_id: String,
+    pub now_ms: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -565,6 +590,46 @@ pub struct IngestInboxStopRes {
     pub stopped: bool,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InboxWatchStartReq {
+    pub vault_path: String,
+    pub source_kind: String,
+    pub debounce_ms: Option<i64>,
+    pub poll_interval_ms: Option<i64>,
+    pub backend: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboxWatchStartRes {
+    pub started: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InboxWatchStopReq {
+    pub vault_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboxWatchStopRes {
+    pub stopped: bool,
+    pub backend: Option<String>,
+    pub processed: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InboxWatchStatusReq {
+    pub vault_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboxWatchStatusRes {
+    pub running: bool,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SearchQueryReq {
@@ -572,18 +637,16 @@ pub struct SearchQueryReq {
     pub query: String,
     pub now_ms: i64,
     pub limit: Option<usize>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SearchHit {
-    pub doc_id: String,
-    pub score: f64,
-    pub snippet: String,
+    pub include_superseded: Option<bool>,
+    pub filter: Option<SearchFilterV1>,
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchQueryRes {
-    pub hits: Vec<SearchHit>,
+    pub hits: Vec<SearchHitV1>,
+    pub facets: SearchFacetsV1,
+    pub diversify: DiversifyReportV1,
 }
 
 #[derive(Debug, Deserialize)]
@@ -630,12 +693,122 @@ pub struct AskQuestionReq {
     pub vault_path: String,
     pub question: String,
     pub now_ms: i64,
+    pub filter: Option<SearchFilterV1>,
+    pub profile: Option<String>,
+    pub min_score: Option<f64>,
+    pub session_id: Option<String>,
 }
 
+// `turn` is the recorded session turn when the question was asked in a session.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AskQuestionRes {
     pub answer_text: String,
     pub trace_path: String,
+    pub verification: Option<AnswerVerificationV1>,
+    pub turn: Option<AskSessionTurnV1>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskQuestionStreamReq {
+    pub vault_path: String,
+    pub question: String,
+    pub now_ms: i64,
+    pub filter: Option<SearchFilterV1>,
+    pub profile: Option<String>,
+    pub min_score: Option<f64>,
+    pub session_id: Option<String>,
+    pub stream_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskStreamEventRes {
+    pub stream_id: String,
+    #[serde(flatten)]
+    pub event: AskStreamEventV1,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskCancelReq {
+    pub stream_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskCancelRes {
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskSessionCreateReq {
+    pub vault_path: String,
+    pub title: Option<String>,
+    pub now_ms: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskSessionListReq {
+    pub vault_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskSessionListRes {
+    pub sessions: Vec<AskSessionV1>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskSessionGetReq {
+    pub vault_path: String,
+    pub session_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AskSessionDeleteReq {
+    pub vault_path: String,
+    pub session_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AskSessionDeleteRes {
+    pub deleted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceListReq {
+    pub vault_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraceListRes {
+    pub traces: Vec<TraceSummaryV1>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceGetReq {
+    pub vault_path: String,
+    pub trace_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TracePruneReq {
+    pub vault_path: String,
+    pub now_ms: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceReplayReq {
+    pub vault_path: String,
+    pub trace_id: String,
+    pub now_ms: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -663,9 +836,115 @@ pub struct JobsListReq {
     pub vault_path: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobItem {
+    pub job_id: String,
+    pub kind: String,
+    pub state: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub next_run_at_ms: i64,
+    pub progress_done: i64,
+    pub progress_total: i64,
+    pub last_error: Option<AppError>,
+    pub updated_at_ms: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JobsListRes {
-    pub jobs: Vec<String>,
+    pub jobs: Vec<JobItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobsCancelReq {
+    pub vault_path: String,
+    pub job_id: String,
+    pub now_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobsCancelRes {
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobsRunReq {
+    pub vault_path: String,
+    pub now_ms: i64,
+    pub max_jobs: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobsRunRes {
+    pub jobs: Vec<JobItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocDeleteReq {
+    pub vault_path: String,
+    pub doc_id: String,
+    pub now_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocTombstoneItem {
+    pub doc_id: String,
+    pub original_object_hash: String,
+    pub canonical_object_hash: Option<String>,
+    pub deleted_event_id: i64,
+    pub deleted_at_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocDeleteRes {
+    pub tombstone: DocTombstoneItem,
+    pub event_hash: String,
+    pub removed_chunks: i64,
+    pub unreferenced_object_hashes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocTombstonesListReq {
+    pub vault_path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocTombstonesListRes {
+    pub tombstones: Vec<DocTombstoneItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DocVersionsReq {
+    pub vault_path: String,
+    pub doc_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocVersionItem {
+    pub version: i64,
+    pub doc_id: String,
+    pub event_id: i64,
+    pub ingested_at_ms: i64,
+    pub bytes: Option<i64>,
+    pub mime: Option<String>,
+    pub superseded_by_doc_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocVersionHistoryItem {
+    pub source_path: String,
+    pub current_doc_id: String,
+    pub versions: Vec<DocVersionItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocVersionsRes {
+    pub histories: Vec<DocVersionHistoryItem>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -737,6 +1016,8 @@ pub struct SyncMergePreviewReq {
 pub struct SyncMergeChangeSetRes {
     pub object_hashes: Vec<String>,
     pub lineage_overlay_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doc_tombstone_ids: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1570,13 +1851,33 @@ pub fn vault_recovery_escrow_rotate_all_rpc(
     }
 }
 
+fn with_vault_pipeline<T>(
+    vault_path: &std::path::Path,
+    run: impl FnOnce(&PipelineServices<'_>) -> AppResult<T>,
+) -> AppResult<T> {
+    let vault = vault_open(vault_path)?;
+    let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+    let indexes = open_vault_indexes(vault_path)?;
+    let chunking = default_chunking_config_v1();
+    run(&PipelineServices {
+        extractor: &extractor,
+        lexical: &indexes.lexical,
+        vector: &indexes.vector,
+        chunking: &chunking,
+    })
+}
+
 pub fn ingest_scan_folder_rpc(req: IngestScanFolderReq) -> RpcResponse<IngestScanFolderRes> {
-    match rpc_service::ingest_scan_folder_service(
-        std::path::Path::new(&req.vault_path),
-        std::path::Path::new(&req.scan_root),
-        &req.source_kind,
-        req.now_ms,
-    ) {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match with_vault_pipeline(vault_path, |services| {
+        rpc_service::ingest_scan_folder_service(
+            vault_path,
+            std::path::Path::new(&req.scan_root),
+            &req.source_kind,
+            req.now_ms,
+            services,
+        )
+    }) {
         Ok(ingested) => RpcResponse::ok(IngestScanFolderRes { ingested }),
         Err(error) => RpcResponse::err(error),
     }
@@ -1598,29 +1899,84 @@ pub fn ingest_inbox_start_rpc(req: IngestInboxStartReq) -> RpcResponse<IngestInb
 }
 
 pub fn ingest_inbox_stop_rpc(req: IngestInboxStopReq) -> RpcResponse<IngestInboxStopRes> {
-    let _ = req.vault_path;
-    match rpc_service::ingest_inbox_stop_service(&req.job_id) {
+    match rpc_service::ingest_inbox_stop_service(
+        std::path::Path::new(&req.vault_path),
+        &req.job_id,
+        req.now_ms,
+    ) {
         Ok(stopped) => RpcResponse::ok(IngestInboxStopRes { stopped }),
         Err(error) => RpcResponse::err(error),
     }
 }
 
+pub fn inbox_watch_start_rpc(req: InboxWatchStartReq) -> RpcResponse<InboxWatchStartRes> {
+    let mut config = InboxWatchConfigV1::new(&req.source_kind);
+    if let Some(debounce_ms) = req.debounce_ms {
+        config.debounce_ms = debounce_ms;
+    }
+    if let Some(poll_interval_ms) = req.poll_interval_ms {
+        config.poll_interval_ms = poll_interval_ms;
+    }
+    if let Some(backend) = req.backend {
+        config.backend = backend;
+    }
+    let vault_path = std::path::PathBuf::from(&req.vault_path);
+    let thread_vault_path = vault_path.clone();
+    match rpc_service::inbox_watch_start_service(&vault_path, config, move |run| {
+        with_vault_pipeline(&thread_vault_path, |services| run(services))
+    }) {
+        Ok(started) => RpcResponse::ok(InboxWatchStartRes { started }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn inbox_watch_stop_rpc(req: InboxWatchStopReq) -> RpcResponse<InboxWatchStopRes> {
+    match rpc_service::inbox_watch_stop_service(std::path::Path::new(&req.vault_path)) {
+        Ok(Some(summary)) => RpcResponse::ok(InboxWatchStopRes {
+            stopped: true,
+            backend: Some(summary.backend),
+            processed: summary.processed,
+            failed: summary.failed,
+        }),
+        Ok(None) => RpcResponse::ok(InboxWatchStopRes {
+            stopped: false,
+            backend: None,
+            processed: 0,
+            failed: 0,
+        }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn inbox_watch_status_rpc(req: InboxWatchStatusReq) -> RpcResponse<InboxWatchStatusRes> {
+    match rpc_service::inbox_watch_status_service(std::path::Path::new(&req.vault_path)) {
+        Ok(running) => RpcResponse::ok(InboxWatchStatusRes { running }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
 pub fn search_query_rpc(req: SearchQueryReq) -> RpcResponse<SearchQueryRes> {
-    match rpc_service::search_query_service(
-        std::path::Path::new(&req.vault_path),
-        &req.query,
-        req.now_ms,
-        req.limit.unwrap_or(20),
-    ) {
-        Ok(hits) => RpcResponse::ok(SearchQueryRes {
-            hits: hits
-                .into_iter()
-                .map(|h| SearchHit {
-                    doc_id: h.doc_id,
-                    score: h.score,
-                    snippet: h.snippet,
-                })
-                .collect(),
+    let vault_path = std::path::Path::new(&req.vault_path);
+    let filter = req.filter.unwrap_or_default();
+    match with_vault_pipeline(vault_path, |services| {
+        rpc_service::search_query_service(
+            vault_path,
+            services.lexical,
+            services.vector,
+            SearchReq {
+                query: &req.query,
+                limit: req.limit.unwrap_or(20),
+                include_superseded: req.include_superseded.unwrap_or(false),
+                filter: &filter,
+                profile: req.profile.as_deref(),
+                now_ms: req.now_ms,
+            },
+        )
+    }) {
+        Ok(result) => RpcResponse::ok(SearchQueryRes {
+            hits: result.hits,
+            facets: result.facets,
+            diversify: result.diversify,
         }),
         Err(error) => RpcResponse::err(error),
     }
@@ -1655,21 +2011,242 @@ pub fn verify_bundle_rpc(req: VerifyBundleReq) -> RpcResponse<VerifyBundleRes> {
     }
 }
 
+fn ask_question_res(
+    service: &RetrievedOnlyAskService,
+    req: AskRequest,
+    session_id: Option<&str>,
+    cancel: &AskCancelToken,
+    on_event: &mut dyn FnMut(AskStreamEventV1) -> AppResult<()>,
+) -> AppResult<AskQuestionRes> {
+    match session_id {
+        Some(session_id) => {
+            let turn = service.ask_session_stream(session_id, req, cancel, on_event)?;
+            Ok(AskQuestionRes {
+                answer_text: turn.answer_text.clone(),
+                trace_path: turn.trace_path.clone(),
+                verification: turn.verification.clone(),
+                turn: Some(turn),
+            })
+        }
+        None => {
+            let out = service.ask_stream(req, cancel, on_event)?;
+            Ok(AskQuestionRes {
+                answer_text: out.answer_text,
+                trace_path: out.trace_path.display().to_string(),
+                verification: out.verification,
+                turn: None,
+            })
+        }
+    }
+}
+
 pub fn ask_question_rpc(req: AskQuestionReq) -> RpcResponse<AskQuestionRes> {
-    let service = RetrievedOnlyAskService::default();
-    match service.ask(AskRequest {
-        vault_path: std::path::PathBuf::from(&req.vault_path),
-        question: req.question,
-        now_ms: req.now_ms,
-    }) {
-        Ok(out) => RpcResponse::ok(AskQuestionRes {
-            answer_text: out.answer_text,
-            trace_path: out.trace_path.display().to_string(),
+    match ask_question_res(
+        &RetrievedOnlyAskService::default(),
+        AskRequest {
+            vault_path: std::path::PathBuf::from(&req.vault_path),
+            question: req.question,
+            filter: req.filter.unwrap_or_default(),
+            profile: req.profile,
+            min_score: req.min_score,
+            now_ms: req.now_ms,
+        },
+        req.session_id.as_deref(),
+        &AskCancelToken::default(),
+        &mut |_| Ok(()),
+    ) {
+        Ok(res) => RpcResponse::ok(res),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+fn lock_ask_streams() -> AppResult<std::sync::MutexGuard<'static, BTreeMap<String, AskCancelToken>>>
+{
+    ASK_STREAMS
+        .get_or_init(|| Mutex::new(BTreeMap::new()))
+        .lock()
+        .map_err(|_| {
+            AppError::new(
+                "KC_INTERNAL_ERROR",
+                "ask",
+                "failed acquiring ask stream lock",
+                true,
+                serde_json::json!({}),
+            )
+        })
+}
+
+fn register_ask_stream(stream_id: &str) -> AppResult<AskCancelToken> {
+    let mut streams = lock_ask_streams()?;
+    if stream_id.is_empty() || streams.contains_key(stream_id) {
+        return Err(AppError::new(
+            "KC_ASK_STREAM_INVALID",
+            "ask",
+            "ask stream id is empty or already in use",
+            false,
+            serde_json::json!({ "stream_id": stream_id }),
+        ));
+    }
+    let token = AskCancelToken::default();
+    streams.insert(stream_id.to_string(), token.clone());
+    Ok(token)
+}
+
+// Runs ask with `emit` receiving each stream event; the returned envelope matches
+// `ask_question` and is only `ok` after the trace is written.
+pub fn ask_question_stream_rpc(
+    req: AskQuestionStreamReq,
+    emit: &mut dyn FnMut(AskStreamEventRes),
+) -> RpcResponse<AskQuestionRes> {
+    let cancel = match register_ask_stream(&req.stream_id) {
+        Ok(cancel) => cancel,
+        Err(error) => return RpcResponse::err(error),
+    };
+    let stream_id = req.stream_id;
+    let result = ask_question_res(
+        &RetrievedOnlyAskService::default(),
+        AskRequest {
+            vault_path: std::path::PathBuf::from(&req.vault_path),
+            question: req.question,
+            filter: req.filter.unwrap_or_default(),
+            profile: req.profile,
+            min_score: req.min_score,
+            now_ms: req.now_ms,
+        },
+        req.session_id.as_deref(),
+        &cancel,
+        &mut |event| {
+            emit(AskStreamEventRes {
+                stream_id: stream_id.clone(),
+                event,
+            });
+            Ok(())
+        },
+    );
+    if let Ok(mut streams) = lock_ask_streams() {
+        streams.remove(&stream_id);
+    }
+    match result {
+        Ok(res) => RpcResponse::ok(res),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn ask_cancel_rpc(req: AskCancelReq) -> RpcResponse<AskCancelRes> {
+    match lock_ask_streams() {
+        Ok(streams) => {
+            let token = streams.get(&req.stream_id);
+            if let Some(token) = token {
+                token.cancel();
+            }
+            RpcResponse::ok(AskCancelRes {
+                cancelled: token.is_some(),
+            })
+        }
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn ask_session_create_rpc(req: AskSessionCreateReq) -> RpcResponse<AskSessionV1> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match vault_open(vault_path)
+        .and_then(|vault| open_db(&vault_path.join(vault.db.relative_path)))
+        .and_then(|conn| ask_session_create(&conn, req.title.as_deref(), req.now_ms))
+    {
+        Ok(session) => RpcResponse::ok(session),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn ask_session_list_rpc(req: AskSessionListReq) -> RpcResponse<AskSessionListRes> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match vault_open(vault_path)
+        .and_then(|vault| open_db(&vault_path.join(vault.db.relative_path)))
+        .and_then(|conn| ask_sessions_list(&conn))
+    {
+        Ok(sessions) => RpcResponse::ok(AskSessionListRes { sessions }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn ask_session_get_rpc(req: AskSessionGetReq) -> RpcResponse<AskSessionDetailV1> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match vault_open(vault_path)
+        .and_then(|vault| open_db(&vault_path.join(vault.db.relative_path)))
+        .and_then(|conn| ask_session_get(&conn, &req.session_id))
+    {
+        Ok(detail) => RpcResponse::ok(detail),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn ask_session_delete_rpc(req: AskSessionDeleteReq) -> RpcResponse<AskSessionDeleteRes> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match vault_open(vault_path)
+        .and_then(|vault| open_db(&vault_path.join(vault.db.relative_path)))
+        .and_then(|conn| ask_session_delete(&conn, &req.session_id))
+    {
+        Ok(()) => RpcResponse::ok(AskSessionDeleteRes { deleted: true }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+fn trace_store(vault_path: &std::path::Path) -> AppResult<TraceStore> {
+    TraceStore::open(
+        vault_path,
+        &RetrievedOnlyAskService::default().trace_dir_name,
+    )
+}
+
+pub fn trace_list_rpc(req: TraceListReq) -> RpcResponse<TraceListRes> {
+    match trace_store(std::path::Path::new(&req.vault_path)).and_then(|store| store.list()) {
+        Ok(list) => RpcResponse::ok(TraceListRes {
+            traces: list.traces,
+            skipped: list.skipped,
         }),
         Err(error) => RpcResponse::err(error),
     }
 }
 
+pub fn trace_get_rpc(req: TraceGetReq) -> RpcResponse<TraceLogV1> {
+    match trace_store(std::path::Path::new(&req.vault_path))
+        .and_then(|store| store.get(&req.trace_id))
+    {
+        Ok(trace) => RpcResponse::ok(trace),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn trace_prune_rpc(req: TracePruneReq) -> RpcResponse<TracePruneReportV1> {
+    match trace_store(std::path::Path::new(&req.vault_path))
+        .and_then(|store| store.prune(req.now_ms))
+    {
+        Ok(report) => RpcResponse::ok(report),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+// Asks a recorded trace's question again; the response describes the new ask and its trace.
+pub fn trace_replay_rpc(req: TraceReplayReq) -> RpcResponse<AskQuestionRes> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    let service = RetrievedOnlyAskService::default();
+    match trace_store(vault_path)
+        .and_then(|store| store.get(&req.trace_id))
+        .and_then(|trace| trace_replay_request(vault_path, &trace, req.now_ms))
+        .and_then(|ask_req| {
+            ask_question_res(
+                &service,
+                ask_req,
+                None,
+                &AskCancelToken::default(),
+                &mut |_| Ok(()),
+            )
+        }) {
+        Ok(res) => RpcResponse::ok(res),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
 pub fn events_list_rpc(req: EventsListReq) -> RpcResponse<EventsListRes> {
     match rpc_service::events_list_service(
         std::path::Path::new(&req.vault_path),
@@ -1689,9 +2266,111 @@ pub fn events_list_rpc(req: EventsListReq) -> RpcResponse<EventsListRes> {
     }
 }
 
+fn map_job(job: kc_core::jobs::JobRecordV1) -> JobItem {
+    JobItem {
+        job_id: job.job_id,
+        kind: job.kind,
+        state: job.state,
+        attempts: job.attempts,
+        max_attempts: job.max_attempts,
+        next_run_at_ms: job.next_run_at_ms,
+        progress_done: job.progress_done,
+        progress_total: job.progress_total,
+        last_error: job.last_error,
+        updated_at_ms: job.updated_at_ms,
+    }
+}
+
 pub fn jobs_list_rpc(req: JobsListReq) -> RpcResponse<JobsListRes> {
     match rpc_service::jobs_list_service(std::path::Path::new(&req.vault_path)) {
-        Ok(jobs) => RpcResponse::ok(JobsListRes { jobs }),
+        Ok(jobs) => RpcResponse::ok(JobsListRes {
+            jobs: jobs.into_iter().map(map_job).collect(),
+        }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn jobs_cancel_rpc(req: JobsCancelReq) -> RpcResponse<JobsCancelRes> {
+    match rpc_service::jobs_cancel_service(
+        std::path::Path::new(&req.vault_path),
+        &req.job_id,
+        req.now_ms,
+    ) {
+        Ok(cancelled) => RpcResponse::ok(JobsCancelRes { cancelled }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn jobs_run_rpc(req: JobsRunReq) -> RpcResponse<JobsRunRes> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match with_vault_pipeline(vault_path, |services| {
+        rpc_service::jobs_run_service(vault_path, req.now_ms, req.max_jobs.unwrap_or(16), services)
+    }) {
+        Ok(jobs) => RpcResponse::ok(JobsRunRes {
+            jobs: jobs.into_iter().map(map_job).collect(),
+        }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+fn map_doc_tombstone(tombstone: kc_core::tombstone::DocTombstoneV1) -> DocTombstoneItem {
+    DocTombstoneItem {
+        doc_id: tombstone.doc_id,
+        original_object_hash: tombstone.original_object_hash,
+        canonical_object_hash: tombstone.canonical_object_hash,
+        deleted_event_id: tombstone.deleted_event_id,
+        deleted_at_ms: tombstone.deleted_at_ms,
+    }
+}
+
+pub fn doc_delete_rpc(req: DocDeleteReq) -> RpcResponse<DocDeleteRes> {
+    let vault_path = std::path::Path::new(&req.vault_path);
+    match with_vault_pipeline(vault_path, |services| {
+        rpc_service::doc_delete_service(vault_path, &req.doc_id, req.now_ms, services)
+    }) {
+        Ok(out) => RpcResponse::ok(DocDeleteRes {
+            tombstone: map_doc_tombstone(out.tombstone),
+            event_hash: out.event_hash,
+            removed_chunks: out.removed_chunks,
+            unreferenced_object_hashes: out.unreferenced_object_hashes,
+        }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn doc_tombstones_list_rpc(req: DocTombstonesListReq) -> RpcResponse<DocTombstonesListRes> {
+    match rpc_service::doc_tombstones_list_service(std::path::Path::new(&req.vault_path)) {
+        Ok(tombstones) => RpcResponse::ok(DocTombstonesListRes {
+            tombstones: tombstones.into_iter().map(map_doc_tombstone).collect(),
+        }),
+        Err(error) => RpcResponse::err(error),
+    }
+}
+
+pub fn doc_versions_rpc(req: DocVersionsReq) -> RpcResponse<DocVersionsRes> {
+    match rpc_service::doc_versions_service(std::path::Path::new(&req.vault_path), &req.doc_id) {
+        Ok(histories) => RpcResponse::ok(DocVersionsRes {
+            histories: histories
+                .into_iter()
+                .map(|history| DocVersionHistoryItem {
+                    source_path: history.source_path,
+                    current_doc_id: history.current_doc_id,
+                    versions: history
+                        .versions
+                        .into_iter()
+                        .map(|v| DocVersionItem {
+                            version: v.version,
+                            doc_id: v.doc_id,
+                            event_id: v.event_id,
+                            ingested_at_ms: v.ingested_at_ms,
+                            bytes: v.bytes,
+                            mime: v.mime,
+                            superseded_by_doc_id: v.superseded_by_doc_id,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }),
         Err(error) => RpcResponse::err(error),
     }
 }
@@ -1711,6 +2390,7 @@ fn map_sync_merge_change_set(
     SyncMergeChangeSetRes {
         object_hashes: set.object_hashes,
         lineage_overlay_ids: set.lineage_overlay_ids,
+        doc_tombstone_ids: set.doc_tombstone_ids,
     }
 }
 
@@ -1759,12 +2439,20 @@ pub fn sync_push_rpc(req: SyncPushReq) -> RpcResponse<SyncPushRes> {
 }
 
 pub fn sync_pull_rpc(req: SyncPullReq) -> RpcResponse<SyncPullRes> {
-    match rpc_service::sync_pull_service(
-        std::path::Path::new(&req.vault_path),
+    let vault_path = std::path::Path::new(&req.vault_path);
+    let pulled = rpc_service::sync_pull_service(
+        vault_path,
         &req.target_path,
         req.now_ms,
         req.auto_merge.as_deref(),
-    ) {
+    )
+    .and_then(|out| {
+        with_vault_pipeline(vault_path, |services| {
+            rpc_service::doc_tombstones_purge_service(vault_path, services)
+        })?;
+        Ok(out)
+    });
+    match pulled {
         Ok(out) => RpcResponse::ok(SyncPullRes {
             snapshot_id: out.snapshot_id,
             manifest_hash: out.manifest_hash,
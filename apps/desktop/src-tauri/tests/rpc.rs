@@ -1,26 +1,35 @@
 use apps_desktop_tauri::commands;
 use apps_desktop_tauri::rpc::{
-    ingest_inbox_start_rpc, ingest_inbox_stop_rpc, jobs_list_rpc, lineage_lock_acquire_rpc,
-    lineage_lock_acquire_scope_rpc, lineage_lock_release_rpc, lineage_lock_status_rpc,
-    lineage_overlay_add_rpc, lineage_overlay_list_rpc, lineage_overlay_remove_rpc,
-    lineage_policy_add_rpc, lineage_policy_bind_rpc, lineage_policy_list_rpc, lineage_query_rpc,
-    lineage_query_v2_rpc, lineage_role_grant_rpc, lineage_role_list_rpc, lineage_role_revoke_rpc,
-    sync_merge_preview_rpc, sync_pull_rpc, sync_push_rpc, sync_status_rpc, trust_device_enroll_rpc,
-    trust_device_list_rpc, trust_device_verify_chain_rpc, trust_identity_complete_rpc,
-    trust_identity_start_rpc, trust_policy_set_tenant_template_rpc, trust_provider_discover_rpc,
-    vault_encryption_enable_rpc, vault_encryption_migrate_rpc, vault_encryption_status_rpc,
-    vault_init_rpc, vault_lock_rpc, vault_lock_status_rpc, vault_open_rpc,
-    vault_recovery_escrow_enable_rpc, vault_recovery_escrow_provider_add_rpc,
-    vault_recovery_escrow_provider_list_rpc, vault_recovery_escrow_restore_rpc,
-    vault_recovery_escrow_rotate_all_rpc, vault_recovery_escrow_rotate_rpc,
-    vault_recovery_escrow_status_rpc, vault_recovery_generate_rpc, vault_recovery_status_rpc,
-    vault_recovery_verify_rpc, vault_unlock_rpc, IngestInboxStartReq, IngestInboxStopReq,
-    JobsListReq, LineageLockAcquireReq, LineageLockAcquireScopeReq, LineageLockReleaseReq,
-    LineageLockStatusReq, LineageOverlayAddReq, LineageOverlayListReq, LineageOverlayRemoveReq,
-    LineagePolicyAddReq, LineagePolicyBindReq, LineagePolicyListReq, LineageQueryReq,
-    LineageQueryV2Req, LineageRoleGrantReq, LineageRoleListReq, LineageRoleRevokeReq, RpcResponse,
-    SyncMergePreviewReq, SyncPullReq, SyncPushReq, SyncStatusReq, TrustDeviceEnrollReq,
-    TrustDeviceListReq, TrustDeviceVerifyChainReq, TrustIdentityCompleteReq, TrustIdentityStartReq,
+    ask_cancel_rpc, ask_question_rpc, ask_question_stream_rpc, ask_session_create_rpc,
+    ask_session_delete_rpc, ask_session_get_rpc, ask_session_list_rpc, doc_delete_rpc,
+    doc_tombstones_list_rpc, doc_versions_rpc, inbox_watch_start_rpc, inbox_watch_status_rpc,
+    inbox_watch_stop_rpc, ingest_inbox_start_rpc, ingest_inbox_stop_rpc, jobs_cancel_rpc,
+    jobs_list_rpc, jobs_run_rpc, lineage_lock_acquire_rpc, lineage_lock_acquire_scope_rpc,
+    lineage_lock_release_rpc, lineage_lock_status_rpc, lineage_overlay_add_rpc,
+    lineage_overlay_list_rpc, lineage_overlay_remove_rpc, lineage_policy_add_rpc,
+    lineage_policy_bind_rpc, lineage_policy_list_rpc, lineage_query_rpc, lineage_query_v2_rpc,
+    lineage_role_grant_rpc, lineage_role_list_rpc, lineage_role_revoke_rpc, sync_merge_preview_rpc,
+    sync_pull_rpc, sync_push_rpc, sync_status_rpc, trace_get_rpc, trace_list_rpc, trace_prune_rpc,
+    trace_replay_rpc, trust_device_enroll_rpc, trust_device_list_rpc,
+    trust_device_verify_chain_rpc, trust_identity_complete_rpc, trust_identity_start_rpc,
+    trust_policy_set_tenant_template_rpc, trust_provider_discover_rpc, vault_encryption_enable_rpc,
+    vault_encryption_migrate_rpc, vault_encryption_status_rpc, vault_init_rpc, vault_lock_rpc,
+    vault_lock_status_rpc, vault_open_rpc, vault_recovery_escrow_enable_rpc,
+    vault_recovery_escrow_provider_add_rpc, vault_recovery_escrow_provider_list_rpc,
+    vault_recovery_escrow_restore_rpc, vault_recovery_escrow_rotate_all_rpc,
+    vault_recovery_escrow_rotate_rpc, vault_recovery_escrow_status_rpc,
+    vault_recovery_generate_rpc, vault_recovery_status_rpc, vault_recovery_verify_rpc,
+    vault_unlock_rpc, AskCancelReq, AskQuestionReq, AskQuestionStreamReq, AskSessionCreateReq,
+    AskSessionDeleteReq, AskSessionGetReq, AskSessionListReq, DocDeleteReq, DocTombstonesListReq,
+    DocVersionsReq, InboxWatchStartReq, InboxWatchStatusReq, InboxWatchStopReq,
+    IngestInboxStartReq, IngestInboxStopReq, JobsCancelReq, JobsListReq, JobsRunReq,
+    LineageLockAcquireReq, LineageLockAcquireScopeReq, LineageLockReleaseReq, LineageLockStatusReq,
+    LineageOverlayAddReq, LineageOverlayListReq, LineageOverlayRemoveReq, LineagePolicyAddReq,
+    LineagePolicyBindReq, LineagePolicyListReq, LineageQueryReq, LineageQueryV2Req,
+    LineageRoleGrantReq, LineageRoleListReq, LineageRoleRevokeReq, RpcResponse,
+    SyncMergePreviewReq, SyncPullReq, SyncPushReq, SyncStatusReq, TraceGetReq, TraceListReq,
+    TracePruneReq, TraceReplayReq, TrustDeviceEnrollReq, TrustDeviceListReq,
+    TrustDeviceVerifyChainReq, TrustIdentityCompleteReq, TrustIdentityStartReq,
     TrustPolicySetTenantTemplateReq, TrustProviderDiscoverReq, VaultEncryptionEnableReq,
     VaultEncryptionMigrateReq, VaultEncryptionStatusReq, VaultInitReq, VaultLockReq,
     VaultLockStatusReq, VaultOpenReq, VaultRecoveryEscrowEnableReq,
@@ -30,6 +39,7 @@ use apps_desktop_tauri::rpc::{
     VaultRecoveryVerifyReq, VaultUnlockReq,
 };
 use kc_core::app_error::AppError;
+use kc_core::vault::{vault_open, vault_save, VaultTraceDefaults, VaultTraceRetentionDefaults};
 use std::sync::{Mutex, OnceLock};
 
 fn env_lock() -> &'static Mutex<()> {
@@ -1114,11 +1124,544 @@ fn rpc_ingest_inbox_start_and_stop() {
     let stopped = ingest_inbox_stop_rpc(IngestInboxStopReq {
         vault_path: root.to_string_lossy().to_string(),
         job_id,
+        now_ms: 3,
     });
     match stopped {
         RpcResponse::Ok { data } => assert!(data.stopped),
         RpcResponse::Err { error } => panic!("inbox stop failed: {}", error.code),
     }
+
+    let jobs = jobs_list_rpc(JobsListReq {
+        vault_path: root.to_string_lossy().to_string(),
+    });
+    match jobs {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.jobs.len(), 1);
+            assert_eq!(data.jobs[0].state, "cancelled");
+        }
+        RpcResponse::Err { error } => panic!("jobs list failed: {}", error.code),
+    }
+}
+
+#[test]
+fn rpc_inbox_watch_processes_dropped_files_until_stopped() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let init = vault_init_rpc(VaultInitReq {
+        vault_path: root.to_string_lossy().to_string(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    });
+    match init {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+
+    let started = inbox_watch_start_rpc(InboxWatchStartReq {
+        vault_path: root.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        debounce_ms: Some(0),
+        poll_interval_ms: Some(20),
+        backend: Some("poll".to_string()),
+    });
+    match started {
+        RpcResponse::Ok { data } => assert!(data.started),
+        RpcResponse::Err { error } => panic!("inbox watch start failed: {}", error.code),
+    }
+
+    std::fs::write(root.join("Inbox/note.txt"), b"watched note").expect("write inbox file");
+    let processed_dir = root.join("Inbox/processed");
+    for _ in 0..500 {
+        if std::fs::read_dir(&processed_dir)
+            .map(|entries| entries.count())
+            .unwrap_or(0)
+            > 0
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+
+    match inbox_watch_status_rpc(InboxWatchStatusReq {
+        vault_path: root.to_string_lossy().to_string(),
+    }) {
+        RpcResponse::Ok { data } => assert!(data.running),
+        RpcResponse::Err { error } => panic!("inbox watch status failed: {}", error.code),
+    }
+
+    match inbox_watch_stop_rpc(InboxWatchStopReq {
+        vault_path: root.to_string_lossy().to_string(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert!(data.stopped);
+            assert_eq!(data.processed, 1);
+            assert_eq!(data.failed, 0);
+        }
+        RpcResponse::Err { error } => panic!("inbox watch stop failed: {}", error.code),
+    }
+
+    match inbox_watch_stop_rpc(InboxWatchStopReq {
+        vault_path: root.to_string_lossy().to_string(),
+    }) {
+        RpcResponse::Ok { data } => assert!(!data.stopped),
+        RpcResponse::Err { error } => panic!("inbox watch stop failed: {}", error.code),
+    }
+}
+
+#[test]
+fn rpc_jobs_run_processes_queued_inbox_job_and_cancel_is_noop_after() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let input = root.join("note.txt");
+    std::fs::write(&input, b"hello jobs").expect("write input");
+
+    let init = vault_init_rpc(VaultInitReq {
+        vault_path: root.to_string_lossy().to_string(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    });
+    match init {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+
+    let job_id = match ingest_inbox_start_rpc(IngestInboxStartReq {
+        vault_path: root.to_string_lossy().to_string(),
+        file_path: input.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        now_ms: 2,
+    }) {
+        RpcResponse::Ok { data } => data.job_id,
+        RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+    };
+
+    let ran = jobs_run_rpc(JobsRunReq {
+        vault_path: root.to_string_lossy().to_string(),
+        now_ms: 3,
+        max_jobs: None,
+    });
+    match ran {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.jobs.len(), 1);
+            assert_eq!(data.jobs[0].job_id, job_id);
+            assert_eq!(data.jobs[0].state, "succeeded");
+            assert_eq!(data.jobs[0].progress_done, 1);
+        }
+        RpcResponse::Err { error } => panic!("jobs run failed: {}", error.code),
+    }
+
+    let cancelled = jobs_cancel_rpc(JobsCancelReq {
+        vault_path: root.to_string_lossy().to_string(),
+        job_id,
+        now_ms: 4,
+    });
+    match cancelled {
+        RpcResponse::Ok { data } => assert!(!data.cancelled),
+        RpcResponse::Err { error } => panic!("jobs cancel failed: {}", error.code),
+    }
+}
+
+#[test]
+fn rpc_ask_question_stream_emits_events_and_honours_cancel() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault_path = root.to_string_lossy().to_string();
+    let input = root.join("note.txt");
+    std::fs::write(&input, b"rotate the signing keys every quarter").expect("write input");
+    match vault_init_rpc(VaultInitReq {
+        vault_path: vault_path.clone(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+    match ingest_inbox_start_rpc(IngestInboxStartReq {
+        vault_path: vault_path.clone(),
+        file_path: input.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        now_ms: 2,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+    }
+    match jobs_run_rpc(JobsRunReq {
+        vault_path: vault_path.clone(),
+        now_ms: 3,
+        max_jobs: None,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("jobs run failed: {}", error.code),
+    }
+    let req = |stream_id: &str| AskQuestionStreamReq {
+        vault_path: vault_path.clone(),
+        question: "signing keys".to_string(),
+        now_ms: 4,
+        filter: None,
+        profile: None,
+        min_score: None,
+        session_id: None,
+        stream_id: stream_id.to_string(),
+    };
+
+    let mut events = Vec::new();
+    let res = ask_question_stream_rpc(req("s1"), &mut |event| {
+        events.push(serde_json::to_value(event).expect("event json"));
+    });
+    let trace_path = match res {
+        RpcResponse::Ok { data } => data.trace_path,
+        RpcResponse::Err { error } => panic!("ask stream failed: {}", error.code),
+    };
+    assert!(events.iter().all(|e| e["stream_id"] == "s1"));
+    assert_eq!(events.first().expect("first event")["event"], "retrieval");
+    let last = events.last().expect("last event");
+    assert_eq!(last["event"], "final");
+    assert_eq!(last["trace_path"], trace_path.as_str());
+
+    let res = ask_question_stream_rpc(req("s2"), &mut |_| match ask_cancel_rpc(AskCancelReq {
+        stream_id: "s2".to_string(),
+    }) {
+        RpcResponse::Ok { data } => assert!(data.cancelled),
+        RpcResponse::Err { error } => panic!("ask cancel failed: {}", error.code),
+    });
+    match res {
+        RpcResponse::Ok { .. } => panic!("cancelled stream must not succeed"),
+        RpcResponse::Err { error } => assert_eq!(error.code, "KC_ASK_CANCELLED"),
+    }
+    match ask_cancel_rpc(AskCancelReq {
+        stream_id: "s2".to_string(),
+    }) {
+        RpcResponse::Ok { data } => assert!(!data.cancelled),
+        RpcResponse::Err { error } => panic!("ask cancel failed: {}", error.code),
+    }
+    let mut events = Vec::new();
+    let res = ask_question_stream_rpc(
+        AskQuestionStreamReq {
+            min_score: Some(1.0),
+            ..req("s3")
+        },
+        &mut |event| events.push(event),
+    );
+    match res {
+        RpcResponse::Ok { .. } => panic!("no hit reaches min_score 1.0"),
+        RpcResponse::Err { error } => {
+            assert_eq!(error.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+            assert_eq!(error.details["below_min_score"], 1);
+        }
+    }
+    assert!(events.is_empty());
+}
+
+#[test]
+fn rpc_ask_sessions_record_turns_and_delete() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault_path = root.to_string_lossy().to_string();
+    let input = root.join("note.txt");
+    std::fs::write(&input, b"rotate the signing keys every quarter").expect("write input");
+    match vault_init_rpc(VaultInitReq {
+        vault_path: vault_path.clone(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+    match ingest_inbox_start_rpc(IngestInboxStartReq {
+        vault_path: vault_path.clone(),
+        file_path: input.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        now_ms: 2,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+    }
+    match jobs_run_rpc(JobsRunReq {
+        vault_path: vault_path.clone(),
+        now_ms: 3,
+        max_jobs: None,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("jobs run failed: {}", error.code),
+    }
+
+    let session_id = match ask_session_create_rpc(AskSessionCreateReq {
+        vault_path: vault_path.clone(),
+        title: None,
+        now_ms: 4,
+    }) {
+        RpcResponse::Ok { data } => data.session_id,
+        RpcResponse::Err { error } => panic!("session create failed: {}", error.code),
+    };
+    let ask = |question: &str, now_ms: i64| {
+        ask_question_rpc(AskQuestionReq {
+            vault_path: vault_path.clone(),
+            question: question.to_string(),
+            now_ms,
+            filter: None,
+            profile: None,
+            min_score: None,
+            session_id: Some(session_id.clone()),
+        })
+    };
+    let first = match ask("signing keys", 5) {
+        RpcResponse::Ok { data } => data.turn.expect("session turn"),
+        RpcResponse::Err { error } => panic!("first turn failed: {}", error.code),
+    };
+    let second = match ask("when do they rotate?", 6) {
+        RpcResponse::Ok { data } => data.turn.expect("session turn"),
+        RpcResponse::Err { error } => panic!("second turn failed: {}", error.code),
+    };
+    assert_eq!(second.turn_index, 1);
+    assert_eq!(second.standalone_query, "rotate signing keys");
+    assert_eq!(
+        second.parent_trace_id.as_deref(),
+        Some(first.trace_id.as_str())
+    );
+
+    match ask_session_list_rpc(AskSessionListReq {
+        vault_path: vault_path.clone(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.sessions.len(), 1);
+            assert_eq!(data.sessions[0].turn_count, 2);
+        }
+        RpcResponse::Err { error } => panic!("session list failed: {}", error.code),
+    }
+    match ask_session_get_rpc(AskSessionGetReq {
+        vault_path: vault_path.clone(),
+        session_id: session_id.clone(),
+    }) {
+        RpcResponse::Ok { data } => assert_eq!(data.turns[1].trace_id, second.trace_id),
+        RpcResponse::Err { error } => panic!("session get failed: {}", error.code),
+    }
+    match ask_session_delete_rpc(AskSessionDeleteReq {
+        vault_path: vault_path.clone(),
+        session_id: session_id.clone(),
+    }) {
+        RpcResponse::Ok { data } => assert!(data.deleted),
+        RpcResponse::Err { error } => panic!("session delete failed: {}", error.code),
+    }
+    match ask_session_get_rpc(AskSessionGetReq {
+        vault_path,
+        session_id,
+    }) {
+        RpcResponse::Ok { .. } => panic!("deleted session must not load"),
+        RpcResponse::Err { error } => assert_eq!(error.code, "KC_ASK_SESSION_NOT_FOUND"),
+    }
+}
+
+#[test]
+fn rpc_traces_list_get_replay_and_prune() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault_path = root.to_string_lossy().to_string();
+    let input = root.join("note.txt");
+    std::fs::write(
+        &input,
+        b"rotate the signing keys every quarter with ops@example.com",
+    )
+    .expect("write input");
+    match vault_init_rpc(VaultInitReq {
+        vault_path: vault_path.clone(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+    match ingest_inbox_start_rpc(IngestInboxStartReq {
+        vault_path: vault_path.clone(),
+        file_path: input.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        now_ms: 2,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+    }
+    match jobs_run_rpc(JobsRunReq {
+        vault_path: vault_path.clone(),
+        now_ms: 3,
+        max_jobs: None,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("jobs run failed: {}", error.code),
+    }
+    match ask_question_rpc(AskQuestionReq {
+        vault_path: vault_path.clone(),
+        question: "signing keys".to_string(),
+        now_ms: 4,
+        filter: None,
+        profile: None,
+        min_score: None,
+        session_id: None,
+    }) {
+        RpcResponse::Ok { data } => assert!(data.answer_text.contains("ops@example.com")),
+        RpcResponse::Err { error } => panic!("ask failed: {}", error.code),
+    }
+
+    let recorded = match trace_list_rpc(TraceListReq {
+        vault_path: vault_path.clone(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.traces.len(), 1);
+            assert!(data.traces[0].redacted);
+            data.traces[0].trace_id.clone()
+        }
+        RpcResponse::Err { error } => panic!("trace list failed: {}", error.code),
+    };
+    match trace_get_rpc(TraceGetReq {
+        vault_path: vault_path.clone(),
+        trace_id: recorded.clone(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.question, "signing keys");
+            assert!(!data.answer["text"]
+                .as_str()
+                .expect("answer text")
+                .contains("ops@example.com"));
+        }
+        RpcResponse::Err { error } => panic!("trace get failed: {}", error.code),
+    }
+    match trace_replay_rpc(TraceReplayReq {
+        vault_path: vault_path.clone(),
+        trace_id: recorded.clone(),
+        now_ms: 5,
+    }) {
+        RpcResponse::Ok { data } => assert!(std::path::Path::new(&data.trace_path).exists()),
+        RpcResponse::Err { error } => panic!("trace replay failed: {}", error.code),
+    }
+
+    let mut vault = vault_open(&root).expect("vault open");
+    vault.defaults.trace = Some(VaultTraceDefaults {
+        retention: VaultTraceRetentionDefaults {
+            max_age_days: None,
+            max_count: Some(1),
+        },
+        ..Default::default()
+    });
+    vault_save(&root, &vault).expect("vault save");
+    match trace_prune_rpc(TracePruneReq {
+        vault_path: vault_path.clone(),
+        now_ms: 6,
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.removed, vec![recorded.clone()]);
+            assert_eq!(data.kept, 1);
+        }
+        RpcResponse::Err { error } => panic!("trace prune failed: {}", error.code),
+    }
+    match trace_get_rpc(TraceGetReq {
+        vault_path,
+        trace_id: recorded,
+    }) {
+        RpcResponse::Ok { .. } => panic!("pruned trace must not load"),
+        RpcResponse::Err { error } => assert_eq!(error.code, "KC_TRACE_NOT_FOUND"),
+    }
+}
+
+#[test]
+fn rpc_doc_delete_records_tombstone_and_rejects_repeat() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let input = root.join("note.txt");
+    std::fs::write(&input, b"hello delete").expect("write input");
+
+    match vault_init_rpc(VaultInitReq {
+        vault_path: root.to_string_lossy().to_string(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+    let doc_id = match ingest_inbox_start_rpc(IngestInboxStartReq {
+        vault_path: root.to_string_lossy().to_string(),
+        file_path: input.to_string_lossy().to_string(),
+        source_kind: "notes".to_string(),
+        now_ms: 2,
+    }) {
+        RpcResponse::Ok { data } => data.doc_id,
+        RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+    };
+    match jobs_run_rpc(JobsRunReq {
+        vault_path: root.to_string_lossy().to_string(),
+        now_ms: 3,
+        max_jobs: None,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("jobs run failed: {}", error.code),
+    }
+
+    match doc_delete_rpc(DocDeleteReq {
+        vault_path: root.to_string_lossy().to_string(),
+        doc_id: doc_id.clone(),
+        now_ms: 4,
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.tombstone.doc_id, doc_id);
+            assert_eq!(data.tombstone.deleted_at_ms, 4);
+            assert_eq!(data.removed_chunks, 1);
+        }
+        RpcResponse::Err { error } => panic!("doc delete failed: {}", error.code),
+    }
+    match doc_tombstones_list_rpc(DocTombstonesListReq {
+        vault_path: root.to_string_lossy().to_string(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.tombstones.len(), 1);
+            assert_eq!(data.tombstones[0].doc_id, doc_id);
+        }
+        RpcResponse::Err { error } => panic!("tombstones list failed: {}", error.code),
+    }
+    match doc_delete_rpc(DocDeleteReq {
+        vault_path: root.to_string_lossy().to_string(),
+        doc_id,
+        now_ms: 5,
+    }) {
+        RpcResponse::Ok { .. } => panic!("repeat delete must fail"),
+        RpcResponse::Err { error } => assert_eq!(error.code, "KC_DOC_NOT_FOUND"),
+    }
+}
+
+#[test]
+fn rpc_doc_versions_links_reingested_source_path() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let input = root.join("note.txt");
+
+    match vault_init_rpc(VaultInitReq {
+        vault_path: root.to_string_lossy().to_string(),
+        vault_slug: "demo".to_string(),
+        now_ms: 1,
+    }) {
+        RpcResponse::Ok { .. } => {}
+        RpcResponse::Err { error } => panic!("vault init failed: {}", error.code),
+    }
+    let mut doc_ids = Vec::new();
+    for (body, now_ms) in [(&b"draft"[..], 2), (&b"final"[..], 4)] {
+        std::fs::write(&input, body).expect("write input");
+        let doc_id = match ingest_inbox_start_rpc(IngestInboxStartReq {
+            vault_path: root.to_string_lossy().to_string(),
+            file_path: input.to_string_lossy().to_string(),
+            source_kind: "notes".to_string(),
+            now_ms,
+        }) {
+            RpcResponse::Ok { data } => data.doc_id,
+            RpcResponse::Err { error } => panic!("inbox start failed: {}", error.code),
+        };
+        doc_ids.push(doc_id);
+    }
+
+    match doc_versions_rpc(DocVersionsReq {
+        vault_path: root.to_string_lossy().to_string(),
+        doc_id: doc_ids[0].clone(),
+    }) {
+        RpcResponse::Ok { data } => {
+            assert_eq!(data.histories.len(), 1);
+            let history = &data.histories[0];
+            assert_eq!(history.current_doc_id, doc_ids[1]);
+            assert_eq!(history.versions.len(), 2);
+            assert_eq!(
+                history.versions[0].superseded_by_doc_id.as_deref(),
+                Some(doc_ids[1].as_str())
+            );
+        }
+        RpcResponse::Err { error } => panic!("doc versions failed: {}", error.code),
+    }
 }
 
 #[test]
@@ -1,18 +1,18 @@
 use apps_desktop_tauri::rpc::{
-    AskQuestionReq, LineageLockAcquireReq, LineageLockAcquireScopeReq, LineageLockReleaseReq,
-    LineageLockStatusReq, LineageOverlayAddReq, LineageOverlayListReq, LineageOverlayRemoveReq,
-    LineagePolicyAddReq, LineagePolicyBindReq, LineagePolicyListReq, LineageQueryReq,
-    LineageQueryV2Req, LineageRoleGrantReq, LineageRoleListReq, LineageRoleRevokeReq,
-    SearchQueryReq, SyncMergePreviewReq, SyncPullReq, SyncPushReq, SyncStatusReq,
-    TrustDeviceEnrollReq, TrustDeviceListReq, TrustDeviceVerifyChainReq, TrustIdentityCompleteReq,
-    TrustIdentityStartReq, TrustPolicySetReq, TrustPolicySetTenantTemplateReq, TrustProviderAddReq,
-    TrustProviderDisableReq, TrustProviderDiscoverReq, TrustProviderListReq,
-    VaultEncryptionEnableReq, VaultEncryptionMigrateReq, VaultEncryptionStatusReq, VaultInitReq,
-    VaultLockReq, VaultLockStatusReq, VaultRecoveryEscrowEnableReq,
-    VaultRecoveryEscrowProviderAddReq, VaultRecoveryEscrowProviderListReq,
-    VaultRecoveryEscrowRestoreReq, VaultRecoveryEscrowRotateAllReq, VaultRecoveryEscrowRotateReq,
-    VaultRecoveryEscrowStatusReq, VaultRecoveryGenerateReq, VaultRecoveryStatusReq,
-    VaultRecoveryVerifyReq, VaultUnlockReq,
+    AskCancelReq, AskQuestionReq, AskQuestionStreamReq, LineageLockAcquireReq,
+    LineageLockAcquireScopeReq, LineageLockReleaseReq, LineageLockStatusReq, LineageOverlayAddReq,
+    LineageOverlayListReq, LineageOverlayRemoveReq, LineagePolicyAddReq, LineagePolicyBindReq,
+    LineagePolicyListReq, LineageQueryReq, LineageQueryV2Req, LineageRoleGrantReq,
+    LineageRoleListReq, LineageRoleRevokeReq, SearchQueryReq, SyncMergePreviewReq, SyncPullReq,
+    SyncPushReq, SyncStatusReq, TrustDeviceEnrollReq, TrustDeviceListReq,
+    TrustDeviceVerifyChainReq, TrustIdentityCompleteReq, TrustIdentityStartReq, TrustPolicySetReq,
+    TrustPolicySetTenantTemplateReq, TrustProviderAddReq, TrustProviderDisableReq,
+    TrustProviderDiscoverReq, TrustProviderListReq, VaultEncryptionEnableReq,
+    VaultEncryptionMigrateReq, VaultEncryptionStatusReq, VaultInitReq, VaultLockReq,
+    VaultLockStatusReq, VaultRecoveryEscrowEnableReq, VaultRecoveryEscrowProviderAddReq,
+    VaultRecoveryEscrowProviderListReq, VaultRecoveryEscrowRestoreReq,
+    VaultRecoveryEscrowRotateAllReq, VaultRecoveryEscrowRotateReq, VaultRecoveryEscrowStatusReq,
+    VaultRecoveryGenerateReq, VaultRecoveryStatusReq, VaultRecoveryVerifyReq, VaultUnlockReq,
 };
 
 #[test]
@@ -33,6 +33,15 @@ fn rpc_schema_requires_now_ms_on_deterministic_requests() {
     assert!(serde_json::from_value::<VaultInitReq>(missing_vault_now).is_err());
     assert!(serde_json::from_value::<SearchQueryReq>(missing_search_now).is_err());
     assert!(serde_json::from_value::<AskQuestionReq>(missing_ask_now).is_err());
+
+    let missing_stream_id = serde_json::json!({
+        "vault_path": "/tmp/vault",
+        "question": "What happened?",
+        "now_ms": 1
+    });
+    assert!(serde_json::from_value::<AskQuestionStreamReq>(missing_stream_id).is_err());
+    let extra_cancel = serde_json::json!({ "stream_id": "s1", "vault_path": "/tmp/vault" });
+    assert!(serde_json::from_value::<AskCancelReq>(extra_cancel).is_err());
 }
 
 #[test]
@@ -45,6 +54,14 @@ fn rpc_schema_rejects_unknown_fields() {
     });
     assert!(serde_json::from_value::<SearchQueryReq>(req).is_err());
 
+    let unknown_filter_key = serde_json::json!({
+        "vault_path": "/tmp/vault",
+        "query": "foo",
+        "now_ms": 123,
+        "filter": { "source_kinds": ["notes"], "tag": "nope" }
+    });
+    assert!(serde_json::from_value::<SearchQueryReq>(unknown_filter_key).is_err());
+
     let invalid_status = serde_json::json!({
         "vault_path": "/tmp/vault",
         "extra": "nope"
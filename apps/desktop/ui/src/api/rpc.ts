@@ -35,6 +35,17 @@ function tauriInvoke(): TauriInvoke | null {
   return tauri?.core?.invoke ?? tauri?.tauri?.invoke ?? null;
 }
 
+type TauriListen = (
+  event: string,
+  handler: (event: { payload: unknown }) => void
+) => Promise<() => void>;
+
+function tauriListen(): TauriListen | null {
+  const g = globalThis as Record<string, unknown>;
+  const tauri = g.__TAURI__ as { event?: { listen?: TauriListen } } | undefined;
+  return tauri?.event?.listen ?? null;
+}
+
 export async function rpc<TReq, TRes>(cmd: string, req: TReq): Promise<RpcResp<TRes>> {
   const invoke = tauriInvoke();
   if (!invoke) {
@@ -337,12 +348,58 @@ export type IngestInboxStartReq = {
   now_ms: number;
 };
 export type IngestInboxStartRes = { job_id: string; doc_id: string };
-export type IngestInboxStopReq = { vault_path: string; job_id: string };
+export type IngestInboxStopReq = { vault_path: string; job_id: string; now_ms: number };
 export type IngestInboxStopRes = { stopped: boolean };
-export type SearchQueryReq = { vault_path: string; query: string; now_ms: number; limit?: number };
-export type SearchHit = { doc_id: string; score: number; snippet: string };
-export type SearchQueryRes = { hits: SearchHit[] };
+export type InboxWatchStartReq = {
+  vault_path: string;
+  source_kind: string;
+  debounce_ms?: number;
+  poll_interval_ms?: number;
+  backend?: "auto" | "notify" | "poll";
+};
+export type InboxWatchStartRes = { started: boolean };
+export type InboxWatchStopReq = { vault_path: string };
+export type InboxWatchStopRes = { stopped: boolean; backend: string | null; processed: number; failed: number };
+export type InboxWatchStatusReq = { vault_path: string };
+export type InboxWatchStatusRes = { running: boolean };
+export type SearchFilterV1 = {
+  source_kinds?: string[];
+  mimes?: string[];
+  effective_ts_from_ms?: number | null;
+  effective_ts_to_ms?: number | null;
+  source_path_prefix?: string | null;
+  extractor_name?: string | null;
+};
+export type SearchQueryReq = {
+  vault_path: string;
+  query: string;
+  now_ms: number;
+  limit?: number;
+  include_superseded?: boolean;
+  filter?: SearchFilterV1;
+  profile?: string;
+};
 export type LocatorV1 = { v: number; doc_id: { 0: string } | string; canonical_hash: { 0: string } | string; range: { start: number; end: number }; hints?: unknown };
+export type SearchHit = {
+  chunk_id: string;
+  doc_id: string;
+  ordinal: number;
+  locator: LocatorV1;
+  lexical_rank: number | null;
+  lexical_score: number | null;
+  vector_rank: number | null;
+  final_score: number;
+  snippet: string;
+  collapsed_chunk_ids: string[];
+};
+export type FacetCount = { value: string; count: number };
+export type SearchFacets = { source_kind: FacetCount[]; mime: FacetCount[]; year: FacetCount[] };
+export type DiversifyReport = {
+  collapsed: { chunk_id: string; into: string }[];
+  near_duplicates: { chunk_id: string; duplicate_of: string; distance: number }[];
+  capped: string[];
+};
+export type SearchQueryRes = { hits: SearchHit[]; facets: SearchFacets; diversify: DiversifyReport };
 export type LocatorResolveReq = { vault_path: string; locator: LocatorV1 };
 export type LocatorResolveRes = { text: string };
 export type ExportBundleReq = {
@@ -354,13 +411,189 @@ export type ExportBundleReq = {
 export type ExportBundleRes = { bundle_path: string };
 export type VerifyBundleReq = { bundle_path: string };
 export type VerifyBundleRes = { exit_code: number; report: unknown };
-export type AskQuestionReq = { vault_path: string; question: string; now_ms: number };
-export type AskQuestionRes = { answer_text: string; trace_path: string };
+export type AskQuestionReq = {
+  vault_path: string;
+  question: string;
+  now_ms: number;
+  filter?: SearchFilterV1;
+  profile?: string;
+  min_score?: number;
+  session_id?: string;
+};
+export type SentenceSupportV1 = {
+  sentence_index: number;
+  text: string;
+  score: number;
+  lexical_score: number;
+  nli_score: number | null;
+  alignment_score: number | null;
+  reason: string;
+};
+export type ParagraphSupportV1 = {
+  paragraph_index: number;
+  score: number;
+  supported: boolean;
+  sentences_checked: number;
+  unsupported_sentences: SentenceSupportV1[];
+};
+export type AnswerVerificationV1 = {
+  verifier: string;
+  threshold: number;
+  supported: boolean;
+  paragraphs: ParagraphSupportV1[];
+};
+export type AskSessionV1 = {
+  session_id: string;
+  title: string | null;
+  created_at_ms: number;
+  updated_at_ms: number;
+  turn_count: number;
+};
+export type AskSessionTurnV1 = {
+  session_id: string;
+  turn_index: number;
+  question: string;
+  standalone_query: string;
+  answer_text: string;
+  citations: { paragraph_index: number; locators: LocatorV1[] }[];
+  trace_id: string;
+  trace_path: string;
+  parent_trace_id: string | null;
+  verification: AnswerVerificationV1 | null;
+  created_at_ms: number;
+};
+export type AskSessionDetailV1 = { session: AskSessionV1; turns: AskSessionTurnV1[] };
+export type AskQuestionRes = {
+  answer_text: string;
+  trace_path: string;
+  verification?: AnswerVerificationV1 | null;
+  turn?: AskSessionTurnV1 | null;
+};
+export type AskQuestionStreamReq = AskQuestionReq & { stream_id: string };
+export type AskStreamChunkV1 = {
+  chunk_id: string;
+  doc_id: string;
+  ordinal: number;
+  final_score: number;
+  start: number;
+  end: number;
+};
+export type AskStreamEventV1 =
+  | { event: "retrieval"; profile: string; config_hash: string; chunks: AskStreamChunkV1[] }
+  | { event: "delta"; text: string }
+  | { event: "paragraph"; paragraph_index: number; text: string }
+  | {
+      event: "final";
+      answer_text: string;
+      citations: { paragraph_index: number; locators: LocatorV1[] }[];
+      trace_path: string;
+      verification: AnswerVerificationV1;
+    };
+export type AskStreamEvent = { stream_id: string } & AskStreamEventV1;
+export type AskCancelReq = { stream_id: string };
+export type AskCancelRes = { cancelled: boolean };
+export type AskSessionCreateReq = { vault_path: string; title?: string; now_ms: number };
+export type AskSessionListReq = { vault_path: string };
+export type AskSessionListRes = { sessions: AskSessionV1[] };
+export type AskSessionGetReq = { vault_path: string; session_id: string };
+export type AskSessionDeleteReq = { vault_path: string; session_id: string };
+export type AskSessionDeleteRes = { deleted: boolean };
+export type TraceRedactionV1 = {
+  enabled: boolean;
+  rules: string[];
+  matches: Record<string, number>;
+  fields: string[];
+};
+export type TraceSessionV1 = {
+  session_id: string;
+  turn_index: number;
+  parent_trace_id: string | null;
+  standalone_query: string;
+  rewriter: string;
+};
+export type TraceLogV1 = {
+  schema_version: number;
+  trace_id: string;
+  ts_ms: number;
+  vault_id: string;
+  question: string;
+  retrieval: Record<string, unknown>;
+  model: Record<string, unknown>;
+  answer: Record<string, unknown>;
+  redaction: TraceRedactionV1;
+  session?: TraceSessionV1;
+  verification?: AnswerVerificationV1;
+};
+export type TraceSummaryV1 = {
+  trace_id: string;
+  ts_ms: number;
+  question: string;
+  session_id: string | null;
+  turn_index: number | null;
+  supported: boolean | null;
+  redacted: boolean;
+  encrypted: boolean;
+  path: string;
+};
+export type TraceListReq = { vault_path: string };
+export type TraceListRes = { traces: TraceSummaryV1[]; skipped: string[] };
+export type TraceGetReq = { vault_path: string; trace_id: string };
+export type TracePruneReq = { vault_path: string; now_ms: number };
+export type TracePruneReportV1 = { removed: string[]; kept: number; skipped: string[] };
+export type TraceReplayReq = { vault_path: string; trace_id: string; now_ms: number };
 export type EventsListReq = { vault_path: string; limit?: number };
 export type EventItem = { event_id: number; ts_ms: number; event_type: string };
 export type EventsListRes = { events: EventItem[] };
 export type JobsListReq = { vault_path: string };
-export type JobsListRes = { jobs: string[] };
+export type JobItem = {
+  job_id: string;
+  kind: string;
+  state: "queued" | "running" | "succeeded" | "failed" | "cancelled";
+  attempts: number;
+  max_attempts: number;
+  next_run_at_ms: number;
+  progress_done: number;
+  progress_total: number;
+  last_error: AppError | null;
+  updated_at_ms: number;
+};
+export type JobsListRes = { jobs: JobItem[] };
+export type JobsCancelReq = { vault_path: string; job_id: string; now_ms: number };
+export type JobsCancelRes = { cancelled: boolean };
+export type JobsRunReq = { vault_path: string; now_ms: number; max_jobs?: number };
+export type JobsRunRes = { jobs: JobItem[] };
+export type DocTombstone = {
+  doc_id: string;
+  original_object_hash: string;
+  canonical_object_hash: string | null;
+  deleted_event_id: number;
+  deleted_at_ms: number;
+};
+export type DocDeleteReq = { vault_path: string; doc_id: string; now_ms: number };
+export type DocDeleteRes = {
+  tombstone: DocTombstone;
+  event_hash: string;
+  removed_chunks: number;
+  unreferenced_object_hashes: string[];
+};
+export type DocTombstonesListReq = { vault_path: string };
+export type DocTombstonesListRes = { tombstones: DocTombstone[] };
+export type DocVersion = {
+  version: number;
+  doc_id: string;
+  event_id: number;
+  ingested_at_ms: number;
+  bytes: number | null;
+  mime: string | null;
+  superseded_by_doc_id: string | null;
+};
+export type DocVersionHistory = {
+  source_path: string;
+  current_doc_id: string;
+  versions: DocVersion[];
+};
+export type DocVersionsReq = { vault_path: string; doc_id: string };
+export type DocVersionsRes = { histories: DocVersionHistory[] };
 export type SyncHead = {
   schema_version: number;
   snapshot_id: string;
@@ -400,6 +633,7 @@ export type SyncMergePreviewReq = {
 export type SyncMergeChangeSet = {
   object_hashes: string[];
   lineage_overlay_ids: string[];
+  doc_tombstone_ids?: string[];
 };
 export type SyncMergePreviewReport = {
   schema_version: number;
@@ -679,13 +913,40 @@ export const rpcMethods = {
   ingestScanFolder: (req: IngestScanFolderReq) => rpc<IngestScanFolderReq, IngestScanFolderRes>("ingest_scan_folder", req),
   ingestInboxStart: (req: IngestInboxStartReq) => rpc<IngestInboxStartReq, IngestInboxStartRes>("ingest_inbox_start", req),
   ingestInboxStop: (req: IngestInboxStopReq) => rpc<IngestInboxStopReq, IngestInboxStopRes>("ingest_inbox_stop", req),
+  inboxWatchStart: (req: InboxWatchStartReq) =>
+    rpc<InboxWatchStartReq, InboxWatchStartRes>("inbox_watch_start", req),
+  inboxWatchStop: (req: InboxWatchStopReq) => rpc<InboxWatchStopReq, InboxWatchStopRes>("inbox_watch_stop", req),
+  inboxWatchStatus: (req: InboxWatchStatusReq) =>
+    rpc<InboxWatchStatusReq, InboxWatchStatusRes>("inbox_watch_status", req),
   searchQuery: (req: SearchQueryReq) => rpc<SearchQueryReq, SearchQueryRes>("search_query", req),
   locatorResolve: (req: LocatorResolveReq) => rpc<LocatorResolveReq, LocatorResolveRes>("locator_resolve", req),
   exportBundle: (req: ExportBundleReq) => rpc<ExportBundleReq, ExportBundleRes>("export_bundle", req),
   verifyBundle: (req: VerifyBundleReq) => rpc<VerifyBundleReq, VerifyBundleRes>("verify_bundle", req),
   askQuestion: (req: AskQuestionReq) => rpc<AskQuestionReq, AskQuestionRes>("ask_question", req),
+  askQuestionStream: (req: AskQuestionStreamReq) =>
+    rpc<AskQuestionStreamReq, AskQuestionRes>("ask_question_stream", req),
+  askCancel: (req: AskCancelReq) => rpc<AskCancelReq, AskCancelRes>("ask_cancel", req),
+  askSessionCreate: (req: AskSessionCreateReq) =>
+    rpc<AskSessionCreateReq, AskSessionV1>("ask_session_create", req),
+  askSessionList: (req: AskSessionListReq) =>
+    rpc<AskSessionListReq, AskSessionListRes>("ask_session_list", req),
+  askSessionGet: (req: AskSessionGetReq) =>
+    rpc<AskSessionGetReq, AskSessionDetailV1>("ask_session_get", req),
+  askSessionDelete: (req: AskSessionDeleteReq) =>
+    rpc<AskSessionDeleteReq, AskSessionDeleteRes>("ask_session_delete", req),
+  traceList: (req: TraceListReq) => rpc<TraceListReq, TraceListRes>("trace_list", req),
+  traceGet: (req: TraceGetReq) => rpc<TraceGetReq, TraceLogV1>("trace_get", req),
+  tracePrune: (req: TracePruneReq) => rpc<TracePruneReq, TracePruneReportV1>("trace_prune", req),
+  traceReplay: (req: TraceReplayReq) => rpc<TraceReplayReq, AskQuestionRes>("trace_replay", req),
   eventsList: (req: EventsListReq) => rpc<EventsListReq, EventsListRes>("events_list", req),
   jobsList: (req: JobsListReq) => rpc<JobsListReq, JobsListRes>("jobs_list", req),
+  jobsCancel: (req: JobsCancelReq) => rpc<JobsCancelReq, JobsCancelRes>("jobs_cancel", req),
+  jobsRun: (req: JobsRunReq) => rpc<JobsRunReq, JobsRunRes>("jobs_run", req),
+  docDelete: (req: DocDeleteReq) => rpc<DocDeleteReq, DocDeleteRes>("doc_delete", req),
+  docTombstonesList: (req: DocTombstonesListReq) =>
+    rpc<DocTombstonesListReq, DocTombstonesListRes>("doc_tombstones_list", req),
+  docVersions: (req: DocVersionsReq) =>
+    rpc<DocVersionsReq, DocVersionsRes>("doc_versions", req),
   syncStatus: (req: SyncStatusReq) => rpc<SyncStatusReq, SyncStatusRes>("sync_status", req),
   syncPush: (req: SyncPushReq) => rpc<SyncPushReq, SyncPushRes>("sync_push", req),
   syncPull: (req: SyncPullReq) => rpc<SyncPullReq, SyncPullRes>("sync_pull", req),
@@ -728,6 +989,25 @@ export const rpcMethods = {
 
 export type DesktopRpcApi = typeof rpcMethods;
 
+export const ASK_STREAM_EVENT = "ask://stream";
+
+// Subscribes to one ask stream's events; resolves to null outside the Tauri runtime.
+export async function listenAskStream(
+  streamId: string,
+  onEvent: (event: AskStreamEvent) => void
+): Promise<(() => void) | null> {
+  const listen = tauriListen();
+  if (!listen) {
+    return null;
+  }
+  return listen(ASK_STREAM_EVENT, ({ payload }) => {
+    const event = payload as AskStreamEvent;
+    if (event.stream_id === streamId) {
+      onEvent(event);
+    }
+  });
+}
+
 export function createDesktopRpcApi(): DesktopRpcApi {
   return rpcMethods;
 }
@@ -397,7 +397,8 @@ async function handleAction(action: string): Promise<void> {
       await execute("ingest", () =>
         ingestFeature.ingestInboxStop(api, {
           vault_path: vaultPath(),
-          job_id: inputValue("ingest-job-id")
+          job_id: inputValue("ingest-job-id"),
+          now_ms: nowMs()
         })
       );
       return;
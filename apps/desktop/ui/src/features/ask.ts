@@ -1,7 +1,18 @@
-import type {
-  AskQuestionReq,
-  AskQuestionRes,
-  DesktopRpcApi
+import {
+  listenAskStream,
+  type AskCancelRes,
+  type AskQuestionReq,
+  type AskQuestionRes,
+  type AskQuestionStreamReq,
+  type AskSessionDeleteRes,
+  type AskSessionDetailV1,
+  type AskSessionListRes,
+  type AskSessionV1,
+  type AskStreamEvent,
+  type DesktopRpcApi,
+  type TraceListRes,
+  type TraceLogV1,
+  type TracePruneReportV1
 } from "../api/rpc";
 import { nextStateFromRpc, type ViewState } from "../state/appState";
 
@@ -11,3 +22,95 @@ export async function askQuestion(
 ): Promise<ViewState<AskQuestionRes>> {
   return nextStateFromRpc(await api.askQuestion(req));
 }
+
+export async function askQuestionStream(
+  api: DesktopRpcApi,
+  req: AskQuestionStreamReq,
+  onEvent: (event: AskStreamEvent) => void
+): Promise<ViewState<AskQuestionRes>> {
+  const unlisten = await listenAskStream(req.stream_id, onEvent);
+  try {
+    return nextStateFromRpc(await api.askQuestionStream(req));
+  } finally {
+    unlisten?.();
+  }
+}
+
+export async function cancelAsk(
+  api: DesktopRpcApi,
+  streamId: string
+): Promise<ViewState<AskCancelRes>> {
+  return nextStateFromRpc(await api.askCancel({ stream_id: streamId }));
+}
+
+export async function createAskSession(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  nowMs: number,
+  title?: string
+): Promise<ViewState<AskSessionV1>> {
+  return nextStateFromRpc(
+    await api.askSessionCreate({ vault_path: vaultPath, title, now_ms: nowMs })
+  );
+}
+
+export async function listAskSessions(
+  api: DesktopRpcApi,
+  vaultPath: string
+): Promise<ViewState<AskSessionListRes>> {
+  return nextStateFromRpc(await api.askSessionList({ vault_path: vaultPath }));
+}
+
+export async function getAskSession(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  sessionId: string
+): Promise<ViewState<AskSessionDetailV1>> {
+  return nextStateFromRpc(
+    await api.askSessionGet({ vault_path: vaultPath, session_id: sessionId })
+  );
+}
+
+export async function deleteAskSession(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  sessionId: string
+): Promise<ViewState<AskSessionDeleteRes>> {
+  return nextStateFromRpc(
+    await api.askSessionDelete({ vault_path: vaultPath, session_id: sessionId })
+  );
+}
+
+export async function listTraces(
+  api: DesktopRpcApi,
+  vaultPath: string
+): Promise<ViewState<TraceListRes>> {
+  return nextStateFromRpc(await api.traceList({ vault_path: vaultPath }));
+}
+
+export async function getTrace(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  traceId: string
+): Promise<ViewState<TraceLogV1>> {
+  return nextStateFromRpc(await api.traceGet({ vault_path: vaultPath, trace_id: traceId }));
+}
+
+export async function pruneTraces(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  nowMs: number
+): Promise<ViewState<TracePruneReportV1>> {
+  return nextStateFromRpc(await api.tracePrune({ vault_path: vaultPath, now_ms: nowMs }));
+}
+
+export async function replayTrace(
+  api: DesktopRpcApi,
+  vaultPath: string,
+  traceId: string,
+  nowMs: number
+): Promise<ViewState<AskQuestionRes>> {
+  return nextStateFromRpc(
+    await api.traceReplay({ vault_path: vaultPath, trace_id: traceId, now_ms: nowMs })
+  );
+}
@@ -1,6 +1,18 @@
 import { describe, expect, it } from "vitest";
 import type { DesktopRpcApi, RpcResp } from "../src/api/rpc";
-import { askQuestion } from "../src/features/ask";
+import {
+  askQuestion,
+  askQuestionStream,
+  cancelAsk,
+  createAskSession,
+  deleteAskSession,
+  getAskSession,
+  getTrace,
+  listAskSessions,
+  listTraces,
+  pruneTraces,
+  replayTrace
+} from "../src/features/ask";
 import { loadDocumentRange } from "../src/features/document";
 import { listEvents, listJobs } from "../src/features/events";
 import { exportBundle, verifyBundle } from "../src/features/exportVerify";
@@ -63,6 +75,13 @@ function ok<T>(data: T): Promise<RpcResp<T>> {
 }
 
 function mockApi(): DesktopRpcApi {
+  const session = {
+    session_id: "s-1",
+    title: "keys",
+    created_at_ms: 4,
+    updated_at_ms: 4,
+    turn_count: 0
+  };
   return {
     vaultInit: () => ok({ vault_id: "v1" }),
     vaultOpen: () => ok({ vault_id: "v1", vault_slug: "demo" }),
@@ -310,13 +329,108 @@ function mockApi(): DesktopRpcApi {
     ingestScanFolder: () => ok({ ingested: 2 }),
     ingestInboxStart: () => ok({ job_id: "j1", doc_id: "d1" }),
     ingestInboxStop: () => ok({ stopped: true }),
-    searchQuery: () => ok({ hits: [{ doc_id: "d1", score: 1, snippet: "s" }] }),
+    inboxWatchStart: () => ok({ started: true }),
+    inboxWatchStop: () => ok({ stopped: true, backend: "notify", processed: 1, failed: 0 }),
+    inboxWatchStatus: () => ok({ running: false }),
+    searchQuery: () =>
+      ok({
+        hits: [
+          {
+            chunk_id: "c1",
+            doc_id: "d1",
+            ordinal: 0,
+            locator: { v: 1, doc_id: "d1", canonical_hash: "blake3:h", range: { start: 0, end: 1 } },
+            lexical_rank: 1,
+            lexical_score: 2.5,
+            vector_rank: null,
+            final_score: 0.016,
+            snippet: "s",
+            collapsed_chunk_ids: []
+          }
+        ],
+        facets: {
+          source_kind: [{ value: "notes", count: 1 }],
+          mime: [{ value: "text/plain", count: 1 }],
+          year: [{ value: "2024", count: 1 }]
+        },
+        diversify: { collapsed: [], near_duplicates: [], capped: [] }
+      }),
     locatorResolve: () => ok({ text: "doc text" }),
     exportBundle: () => ok({ bundle_path: "/tmp/bundle" }),
     verifyBundle: () => ok({ exit_code: 0, report: {} }),
     askQuestion: () => ok({ answer_text: "a", trace_path: "/tmp/trace" }),
+    askQuestionStream: () => ok({ answer_text: "a", trace_path: "/tmp/trace" }),
+    askCancel: () => ok({ cancelled: true }),
+    askSessionCreate: () => ok(session),
+    askSessionList: () => ok({ sessions: [session] }),
+    askSessionGet: () => ok({ session, turns: [] }),
+    askSessionDelete: () => ok({ deleted: true }),
+    traceList: () =>
+      ok({
+        traces: [
+          {
+            trace_id: "t-1",
+            ts_ms: 4,
+            question: "keys?",
+            session_id: null,
+            turn_index: null,
+            supported: true,
+            redacted: false,
+            encrypted: false,
+            path: "/tmp/v/trace/t-1.json"
+          }
+        ],
+        skipped: []
+      }),
+    traceGet: () =>
+      ok({
+        schema_version: 1,
+        trace_id: "t-1",
+        ts_ms: 4,
+        vault_id: "v1",
+        question: "keys?",
+        retrieval: {},
+        model: {},
+        answer: { text: "a" },
+        redaction: { enabled: true, rules: ["email"], matches: { email: 0 }, fields: [] }
+      }),
+    tracePrune: () => ok({ removed: ["t-0"], kept: 1, skipped: [] }),
+    traceReplay: () => ok({ answer_text: "a", trace_path: "/tmp/v/trace/t-2.json" }),
     eventsList: () => ok({ events: [{ event_id: 1, ts_ms: 1, event_type: "ingest" }] }),
-    jobsList: () => ok({ jobs: ["j1"] }),
+    jobsList: () =>
+      ok({
+        jobs: [
+          {
+            job_id: "j1",
+            kind: "pipeline.doc",
+            state: "queued",
+            attempts: 0,
+            max_attempts: 5,
+            next_run_at_ms: 2,
+            progress_done: 0,
+            progress_total: 0,
+            last_error: null,
+            updated_at_ms: 2
+          }
+        ]
+      }),
+    jobsCancel: () => ok({ cancelled: true }),
+    jobsRun: () => ok({ jobs: [] }),
+    docDelete: () =>
+      ok({
+        tombstone: {
+          doc_id: "blake3:doc",
+          original_object_hash: "blake3:doc",
+          canonical_object_hash: null,
+          deleted_event_id: 9,
+          deleted_at_ms: 1
+        },
+        event_hash: "blake3:event",
+        removed_chunks: 0,
+        unreferenced_object_hashes: []
+      }),
+    docTombstonesList: () => ok({ tombstones: [] }),
+    docVersions: () => ok({ histories: [] }),
     syncStatus: () =>
       ok({
         target_path: "s3://demo-bucket/kc",
@@ -579,7 +693,8 @@ describe("feature controllers", () => {
     expect(
       await ingestInboxStop(api, {
         vault_path: "/tmp/v",
-        job_id: "j1"
+        job_id: "j1",
+        now_ms: 3
       })
     ).toMatchObject({ kind: "data" });
     expect(
@@ -614,6 +729,22 @@ describe("feature controllers", () => {
         now_ms: 4
       })
     ).toMatchObject({ kind: "data" });
+    expect(
+      await askQuestionStream(
+        api,
+        { vault_path: "/tmp/v", question: "what?", now_ms: 4, stream_id: "s1" },
+        () => undefined
+      )
+    ).toMatchObject({ kind: "data" });
+    expect(await cancelAsk(api, "s1")).toMatchObject({ kind: "data" });
+    expect(await createAskSession(api, "/tmp/v", 4, "keys")).toMatchObject({ kind: "data" });
+    expect(await listAskSessions(api, "/tmp/v")).toMatchObject({ kind: "data" });
+    expect(await getAskSession(api, "/tmp/v", "s-1")).toMatchObject({ kind: "data" });
+    expect(await deleteAskSession(api, "/tmp/v", "s-1")).toMatchObject({ kind: "data" });
+    expect(await listTraces(api, "/tmp/v")).toMatchObject({ kind: "data" });
+    expect(await getTrace(api, "/tmp/v", "t-1")).toMatchObject({ kind: "data" });
+    expect(await pruneTraces(api, "/tmp/v", 5)).toMatchObject({ kind: "data" });
+    expect(await replayTrace(api, "/tmp/v", "t-1", 5)).toMatchObject({ kind: "data" });
     expect(
       await exportBundle(api, {
         vault_path: "/tmp/v",
@@ -44,6 +44,9 @@ describe("rpc client", () => {
       "ingestScanFolder",
       "ingestInboxStart",
       "ingestInboxStop",
+      "inboxWatchStart",
+      "inboxWatchStop",
+      "inboxWatchStatus",
       "searchQuery",
       "locatorResolve",
       "exportBundle",
@@ -51,6 +54,11 @@ describe("rpc client", () => {
       "askQuestion",
       "eventsList",
       "jobsList",
+      "jobsCancel",
+      "jobsRun",
+      "docDelete",
+      "docTombstonesList",
+      "docVersions",
       "syncStatus",
       "syncPush",
       "syncPull",
@@ -1,19 +1,49 @@
-use crate::trace::{write_trace_log, TraceLogV1};
+use crate::http_provider::{answer_paragraphs, vault_ask_provider};
+use crate::session::{
+    ask_session_get, ask_session_turn_insert, redact_session_turn, rewrite_follow_up,
+    AskSessionTurnV1, FOLLOW_UP_REWRITER_ID,
+};
+use crate::stream::{
+    AskCancelToken, AskStreamChunkV1, AskStreamCitationV1, AskStreamEventV1, ParagraphSplitter,
+};
+use crate::trace::{TraceLogV1, TraceSessionV1, TraceStore};
 use kc_core::app_error::{AppError, AppResult};
-use kc_core::index_traits::LexicalCandidate;
+use kc_core::diversify::{diversify_hits, DiversifyCandidate, DiversifyReportV1};
+use kc_core::doc_versions::SUPERSEDED_DOC_IDS_SQL;
+use kc_core::faithfulness::{
+    verify_paragraphs, AlignmentModel, AnswerVerificationV1, NliModel, ParagraphEvidence,
+    SupportModel, DEFAULT_SUPPORT_THRESHOLD,
+};
+use kc_core::index_traits::{LexicalIndex, VectorCandidate, VectorIndex};
 use kc_core::locator::LocatorV1;
 use kc_core::object_store::ObjectStore;
-use kc_core::retrieval::{merge_candidates, RecencyConfigV1, RetrievalConfigV1};
-use kc_core::types::ChunkId;
+use kc_core::rerank::{rerank_candidates, RerankInput, Reranker, DEFAULT_RERANK_TOP_K};
+use kc_core::retrieval::{
+    merge_candidates, retrieval_profile_for_vault, RetrievalConfigV1, RetrievalProfileV1,
+};
+use kc_core::search::{filter_doc_ids, SearchFilterV1};
+use kc_core::trace_redaction::{TraceRedactionV1, TraceRedactor};
+use kc_core::types::{ChunkId, DocId};
 use kc_core::vault::vault_open;
 use kc_core::{db::open_db, locator::resolve_locator_strict, vault::vault_paths};
-use rusqlite::Connection;
+use kc_index::fts::SqliteFtsIndex;
+use kc_index::local_alignment::vault_alignment_model;
+use kc_index::local_reranker::vault_reranker;
+use kc_index::query::parse_query;
+use kc_index::{open_vault_vector_index, vault_fts_config};
+use rusqlite::{Connection, OptionalExtension};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct AskRequest {
     pub vault_path: std::path::PathBuf,
     pub question: String,
+    pub filter: SearchFilterV1,
+    // Retrieval profile name; None uses the vault's default profile.
+    pub profile: Option<String>,
+    // Merged hits scoring below this `final_score` (spec 09 scale) are dropped before
+    // diversification; None keeps every hit.
+    pub min_score: Option<f64>,
     pub now_ms: i64,
 }
 
@@ -21,7 +51,11 @@ pub struct AskRequest {
 pub struct AskResponse {
     pub answer_text: String,
     pub citations: Vec<(i64, Vec<LocatorV1>)>,
+    pub trace_id: String,
     pub trace_path: std::path::PathBuf,
+    // Per-paragraph support for the cited spans; None only from `finalize_answer`, which has
+    // no retrieval to check against.
+    pub verification: Option<AnswerVerificationV1>,
 }
 
 pub trait AskService: Send + Sync {
@@ -33,18 +67,36 @@ pub struct RetrievedContext {
     pub chunk_id: ChunkId,
     pub ordinal: i64,
     pub final_score: f64,
+    pub lexical_rank: Option<i64>,
+    pub vector_rank: Option<i64>,
     pub locator: LocatorV1,
     pub snippet: String,
+    pub collapsed: Vec<ChunkId>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ProviderAnswer {
     pub answer_text: String,
     pub citations: Vec<(i64, Vec<LocatorV1>)>,
+    // Recorded verbatim as the trace's `model` section.
+    pub model: serde_json::Value,
 }
 
 pub trait AskProvider: Send + Sync {
     fn answer(&self, question: &str, contexts: &[RetrievedContext]) -> AppResult<ProviderAnswer>;
+
+    // Passes generated text to `on_delta` as it arrives; an error from `on_delta` aborts the
+    // answer. Providers that cannot stream emit the whole answer as one delta.
+    fn answer_streaming(
+        &self,
+        question: &str,
+        contexts: &[RetrievedContext],
+        on_delta: &mut dyn FnMut(&str) -> AppResult<()>,
+    ) -> AppResult<ProviderAnswer> {
+        let answer = self.answer(question, contexts)?;
+        on_delta(&answer.answer_text)?;
+        Ok(answer)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -72,24 +124,94 @@ impl AskProvider for DeterministicAskProvider {
         Ok(ProviderAnswer {
             answer_text: format!("Q: {}\nA: {}", question.trim(), first_line),
             citations: vec![(0, vec![first.locator.clone()])],
+            model: retrieved_only_model(),
         })
     }
 }
 
+fn retrieved_only_model() -> serde_json::Value {
+    serde_json::json!({ "mode": "retrieved-only" })
+}
+
+const MAX_CONTEXTS: usize = 5;
+const CANDIDATE_DEPTH: usize = 32;
+// Slots kept for the previous turn's cited locators in a session follow-up.
+const MAX_REUSED_CONTEXTS: usize = 2;
+
+// `reranker` overrides the vault's `defaults.reranker`; with neither, the merged order is kept.
+// `provider` likewise overrides `defaults.ask_provider`, falling back to the deterministic one;
+// `lexical` and `vector` override the vault's FTS and LanceDB indexes (a lexical override is
+// expected to leave out superseded docs itself). `nli` adds an NLI model to verification and
+// `alignment` overrides the vault's `defaults.verifier.alignment`; `nli` wins when both are set.
+// `nli` is the only way to get entailment: no local NLI backend ships, and alignment is not one.
 pub struct RetrievedOnlyAskService {
     pub trace_dir_name: String,
-    pub provider: Arc<dyn AskProvider>,
+    pub provider: Option<Arc<dyn AskProvider>>,
+    pub reranker: Option<Arc<dyn Reranker>>,
+    pub lexical: Option<Arc<dyn LexicalIndex>>,
+    pub vector: Option<Arc<dyn VectorIndex>>,
+    pub nli: Option<Arc<dyn NliModel>>,
+    pub alignment: Option<Arc<dyn AlignmentModel>>,
 }
 
 impl Default for RetrievedOnlyAskService {
     fn default() -> Self {
         Self {
             trace_dir_name: "trace".to_string(),
-            provider: Arc::new(DeterministicAskProvider),
+            provider: None,
+            reranker: None,
+            lexical: None,
+            vector: None,
+            nli: None,
+            alignment: None,
         }
     }
 }
 
+// What a session turn adds to a plain ask: the trace link and the previous turn's citations.
+struct SessionTurn {
+    trace: TraceSessionV1,
+    reused: Vec<LocatorV1>,
+}
+
+// What a replay changes: retrieval runs with the recorded profile at the request's (recorded)
+// time, while the new trace is stamped `trace_ts_ms`.
+struct ReplayRun {
+    profile: RetrievalProfileV1,
+    trace_ts_ms: i64,
+}
+
+// What the trace records beyond the request and answer.
+#[derive(Default)]
+struct TraceContext {
+    session: Option<TraceSessionV1>,
+    // Set for replays, whose traces are stamped with the replay time rather than `req.now_ms`
+    // and written without pruning.
+    replay_ts_ms: Option<i64>,
+}
+
+// How many candidates each index returned and how many merged hits `min_score` dropped;
+// reported in the trace and in `KC_ASK_NO_RELEVANT_CONTEXT`.
+#[derive(Debug, Clone, Copy, Default)]
+struct CandidateCounts {
+    lexical: usize,
+    vector: usize,
+    below_min_score: usize,
+}
+
+fn validate_min_score(min_score: Option<f64>) -> AppResult<()> {
+    match min_score {
+        Some(min_score) if !min_score.is_finite() || min_score < 0.0 => Err(AppError::new(
+            "KC_ASK_MIN_SCORE_INVALID",
+            "ask",
+            "min_score must be a finite, non-negative number",
+            false,
+            serde_json::json!({ "min_score": min_score.to_string() }),
+        )),
+        _ => Ok(()),
+    }
+}
+
 fn validate_citations(citations: &[(i64, Vec<LocatorV1>)]) -> AppResult<()> {
     if citations.is_empty() {
         return Err(AppError::new(
@@ -166,150 +288,101 @@ fn normalize_citations(citations: &[(i64, Vec<LocatorV1>)]) -> Vec<(i64, Vec<Loc
 }
 
 impl RetrievedOnlyAskService {
-    fn table_exists(conn: &Connection, table: &str) -> AppResult<bool> {
-        let mut stmt = conn
-            .prepare("SELECT 1 FROM sqlite_master WHERE type='table' AND name=?1 LIMIT 1")
-            .map_err(|e| {
-                AppError::new(
-                    "KC_ASK_PROVIDER_UNAVAILABLE",
-                    "ask",
-                    "failed preparing sqlite master query",
-                    true,
-                    serde_json::json!({ "error": e.to_string() }),
-                )
-            })?;
-        let mut rows = stmt.query([table]).map_err(|e| {
-            AppError::new(
-                "KC_ASK_PROVIDER_UNAVAILABLE",
-                "ask",
-                "failed querying sqlite master",
-                true,
-                serde_json::json!({ "error": e.to_string(), "table": table }),
-            )
-        })?;
-        Ok(rows
-            .next()
-            .map_err(|e| {
-                AppError::new(
-                    "KC_ASK_PROVIDER_UNAVAILABLE",
-                    "ask",
-                    "failed reading sqlite master query result",
-                    true,
-                    serde_json::json!({ "error": e.to_string(), "table": table }),
-                )
-            })?
-            .is_some())
-    }
-
-    fn lexical_candidates(
+    // Vector hits keep their index rank; chunks that no longer exist or belong to a
+    // superseded version are dropped, as search does.
+    fn vector_candidates(
         conn: &Connection,
+        vector: &dyn VectorIndex,
         question: &str,
+        scope: Option<&[DocId]>,
         limit: usize,
-    ) -> AppResult<Vec<LexicalCandidate>> {
-        let mut candidates = Vec::new();
-        let q = question.trim();
-        if !q.is_empty() && Self::table_exists(conn, "chunks_fts")? {
-            let mut stmt = conn
-                .prepare("SELECT chunk_id FROM chunks_fts WHERE chunks_fts MATCH ?1 ORDER BY rank LIMIT ?2")
-                .map_err(|e| {
-                    AppError::new(
-                        "KC_ASK_PROVIDER_UNAVAILABLE",
-                        "ask",
-                        "failed preparing lexical query",
-                        true,
-                        serde_json::json!({ "error": e.to_string() }),
-                    )
-                })?;
-            let rows_result = stmt.query_map(rusqlite::params![q, limit as i64], |row| {
-                row.get::<_, String>(0)
-            });
-            if let Ok(rows) = rows_result {
-                for (idx, row) in rows.enumerate() {
-                    if let Ok(chunk_id) = row {
-                        candidates.push(LexicalCandidate {
-                            chunk_id: ChunkId(chunk_id),
-                            rank: idx as i64 + 1,
-                        });
-                    }
-                }
-            }
-        }
-
-        if candidates.is_empty() {
-            let mut stmt = conn
-                .prepare(
-                    "SELECT c.chunk_id
-                     FROM chunks c
-                     JOIN docs d ON d.doc_id=c.doc_id
-                     ORDER BY d.effective_ts_ms DESC, c.doc_id ASC, c.ordinal ASC, c.chunk_id ASC
-                     LIMIT ?1",
-                )
-                .map_err(|e| {
-                    AppError::new(
-                        "KC_ASK_PROVIDER_UNAVAILABLE",
-                        "ask",
-                        "failed preparing fallback retrieval query",
-                        true,
-                        serde_json::json!({ "error": e.to_string() }),
-                    )
-                })?;
-            let rows = stmt
-                .query_map([limit as i64], |row| row.get::<_, String>(0))
+    ) -> AppResult<Vec<VectorCandidate>> {
+        let candidates = match scope {
+            None => vector.query(question, limit)?,
+            Some(doc_ids) => vector.query_in_docs(question, limit, doc_ids)?,
+        };
+        let sql = format!(
+            "SELECT 1 FROM chunks
+             WHERE chunk_id=?1 AND doc_id NOT IN ({SUPERSEDED_DOC_IDS_SQL})"
+        );
+        let mut live = Vec::with_capacity(candidates.len());
+        for candidate in candidates {
+            let found = conn
+                .query_row(&sql, [candidate.chunk_id.0.as_str()], |_| Ok(()))
+                .optional()
                 .map_err(|e| {
                     AppError::new(
                         "KC_ASK_PROVIDER_UNAVAILABLE",
                         "ask",
-                        "failed executing fallback retrieval query",
+                        "failed checking vector candidate",
                         true,
-                        serde_json::json!({ "error": e.to_string() }),
+                        serde_json::json!({ "error": e.to_string(), "chunk_id": candidate.chunk_id.0 }),
                     )
                 })?;
-            for (idx, row) in rows.enumerate() {
-                let chunk_id = row.map_err(|e| {
-                    AppError::new(
-                        "KC_ASK_PROVIDER_UNAVAILABLE",
-                        "ask",
-                        "failed loading fallback retrieval row",
-                        true,
-                        serde_json::json!({ "error": e.to_string() }),
-                    )
-                })?;
-                candidates.push(LexicalCandidate {
-                    chunk_id: ChunkId(chunk_id),
-                    rank: idx as i64 + 1,
-                });
+            if found.is_some() {
+                live.push(candidate);
             }
         }
-
-        Ok(candidates)
+        Ok(live)
     }
 
     fn load_contexts(
         &self,
         conn: &Connection,
         object_store: &ObjectStore,
-        question: &str,
-        now_ms: i64,
-        recency_enabled: bool,
-    ) -> AppResult<Vec<RetrievedContext>> {
-        let lexical = Self::lexical_candidates(conn, question, 32)?;
-        if lexical.is_empty() {
-            return Ok(Vec::new());
+        req: &AskRequest,
+        query: &str,
+        retrieval_cfg: &RetrievalConfigV1,
+        depth: usize,
+    ) -> AppResult<(Vec<RetrievedContext>, DiversifyReportV1, CandidateCounts)> {
+        // The question's field operators and `filter` are resolved to doc ids once and applied
+        // to both indexes.
+        let parsed = parse_query(query)?;
+        let scope = filter_doc_ids(conn, &[&req.filter, &parsed.filter])?;
+        if scope.as_ref().is_some_and(|doc_ids| doc_ids.is_empty()) {
+            return Ok((
+                Vec::new(),
+                DiversifyReportV1::default(),
+                CandidateCounts::default(),
+            ));
         }
-
-        let retrieval_cfg = RetrievalConfigV1 {
-            rrf_k: 60,
-            w_lex: 1.0,
-            w_vec: 1.0,
-            recency: RecencyConfigV1 {
-                enabled: recency_enabled,
-                window_days: 365,
-                max_boost: 0.20,
-            },
+        let lexical_index: Arc<dyn LexicalIndex> = match &self.lexical {
+            Some(lexical) => lexical.clone(),
+            None => {
+                let vault = vault_open(&req.vault_path)?;
+                Arc::new(
+                    SqliteFtsIndex::open(
+                        &req.vault_path.join(&vault.db.relative_path),
+                        &vault_fts_config(&vault)?,
+                    )?
+                    .excluding_superseded(),
+                )
+            }
+        };
+        let lexical = match scope.as_deref() {
+            None => lexical_index.query(query, CANDIDATE_DEPTH)?,
+            Some(doc_ids) => lexical_index.query_in_docs(query, CANDIDATE_DEPTH, doc_ids)?,
         };
-        let merged = merge_candidates(
+        let vector: Arc<dyn VectorIndex> = match &self.vector {
+            Some(vector) => vector.clone(),
+            None => Arc::new(open_vault_vector_index(&req.vault_path)?),
+        };
+        let vectors = Self::vector_candidates(
+            conn,
+            vector.as_ref(),
+            query,
+            scope.as_deref(),
+            CANDIDATE_DEPTH,
+        )?;
+        let mut counts = CandidateCounts {
+            lexical: lexical.len(),
+            vector: vectors.len(),
+            below_min_score: 0,
+        };
+
+        let mut merged = merge_candidates(
             &lexical,
-            &[],
+            &vectors,
             |chunk_id| {
                 conn.query_row(
                     "SELECT c.doc_id, c.ordinal, d.source_kind, d.effective_ts_ms
@@ -336,15 +409,54 @@ impl RetrievedOnlyAskService {
                     )
                 })
             },
-            &retrieval_cfg,
-            now_ms,
+            retrieval_cfg,
+            req.now_ms,
         )?;
+        if let Some(min_score) = req.min_score {
+            let before = merged.len();
+            merged.retain(|hit| hit.final_score >= min_score);
+            counts.below_min_score = before - merged.len();
+        }
+
+        let mut candidates = Vec::with_capacity(merged.len());
+        for hit in merged {
+            let (start_char, end_char, simhash) = conn
+                .query_row(
+                    "SELECT start_char, end_char, simhash FROM chunks WHERE chunk_id=?1",
+                    [hit.chunk_id.0.clone()],
+                    |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, Option<i64>>(2)?,
+                        ))
+                    },
+                )
+                .map_err(|e| {
+                    AppError::new(
+                        "KC_ASK_PROVIDER_UNAVAILABLE",
+                        "ask",
+                        "failed loading chunk range for retrieval",
+                        true,
+                        serde_json::json!({ "error": e.to_string(), "chunk_id": hit.chunk_id.0 }),
+                    )
+                })?;
+            candidates.push(DiversifyCandidate {
+                hit,
+                start_char,
+                end_char,
+                simhash,
+            });
+        }
+        let (diversified, report) = diversify_hits(candidates, &retrieval_cfg.diversify, depth);
 
         let mut contexts = Vec::new();
-        for merged_hit in merged.into_iter().take(5) {
-            let (doc_id, start_char, end_char, canonical_hash, canonical_object_hash) = conn
+        for diversified_hit in diversified {
+            let merged_hit = diversified_hit.hit;
+            let (start_char, end_char) = (diversified_hit.start_char, diversified_hit.end_char);
+            let (doc_id, canonical_hash, canonical_object_hash) = conn
                 .query_row(
-                    "SELECT c.doc_id, c.start_char, c.end_char, ct.canonical_hash, ct.canonical_object_hash
+                    "SELECT c.doc_id, ct.canonical_hash, ct.canonical_object_hash
                      FROM chunks c
                      JOIN canonical_text ct ON ct.doc_id=c.doc_id
                      WHERE c.chunk_id=?1",
@@ -352,10 +464,8 @@ impl RetrievedOnlyAskService {
                     |row| {
                         Ok((
                             row.get::<_, String>(0)?,
-                            row.get::<_, i64>(1)?,
-                            row.get::<_, i64>(2)?,
-                            row.get::<_, String>(3)?,
-                            row.get::<_, String>(4)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
                         ))
                     },
                 )
@@ -398,11 +508,122 @@ impl RetrievedOnlyAskService {
                 chunk_id: merged_hit.chunk_id,
                 ordinal: merged_hit.ordinal,
                 final_score: merged_hit.final_score,
+                lexical_rank: merged_hit.lexical_rank,
+                vector_rank: merged_hit.vector_rank,
                 locator,
                 snippet,
+                collapsed: diversified_hit.collapsed,
             });
         }
-        Ok(contexts)
+        Ok((contexts, report, counts))
+    }
+
+    // Appends the previous turn's cited locators that still resolve to the current canonical
+    // text of a live doc and do not overlap a fresh context, keeping at most MAX_CONTEXTS.
+    fn with_reused_contexts(
+        conn: &Connection,
+        object_store: &ObjectStore,
+        mut contexts: Vec<RetrievedContext>,
+        reused: &[LocatorV1],
+    ) -> AppResult<(Vec<RetrievedContext>, Vec<LocatorV1>)> {
+        let sql = format!(
+            "SELECT c.chunk_id, c.ordinal
+             FROM chunks c
+             JOIN canonical_text ct ON ct.doc_id=c.doc_id
+             WHERE c.doc_id=?1 AND ct.canonical_hash=?2
+               AND c.start_char<=?3 AND c.end_char>?3
+               AND c.doc_id NOT IN ({SUPERSEDED_DOC_IDS_SQL})
+             ORDER BY c.ordinal ASC LIMIT 1"
+        );
+        let mut extra = Vec::new();
+        for locator in reused {
+            if extra.len() == MAX_REUSED_CONTEXTS {
+                break;
+            }
+            let overlaps = |ctx: &RetrievedContext| {
+                ctx.locator.doc_id == locator.doc_id
+                    && ctx.locator.range.start < locator.range.end
+                    && locator.range.start < ctx.locator.range.end
+            };
+            if contexts.iter().chain(extra.iter()).any(overlaps) {
+                continue;
+            }
+            let chunk = conn
+                .query_row(
+                    &sql,
+                    rusqlite::params![
+                        locator.doc_id.0,
+                        locator.canonical_hash.0,
+                        locator.range.start
+                    ],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+                )
+                .optional()
+                .map_err(|e| {
+                    AppError::new(
+                        "KC_ASK_PROVIDER_UNAVAILABLE",
+                        "ask",
+                        "failed loading chunk for reused citation",
+                        true,
+                        serde_json::json!({ "error": e.to_string(), "doc_id": locator.doc_id.0 }),
+                    )
+                })?;
+            let Some((chunk_id, ordinal)) = chunk else {
+                continue;
+            };
+            extra.push(RetrievedContext {
+                chunk_id: ChunkId(chunk_id),
+                ordinal,
+                final_score: 0.0,
+                lexical_rank: None,
+                vector_rank: None,
+                locator: locator.clone(),
+                snippet: resolve_locator_strict(conn, object_store, locator)?,
+                collapsed: Vec::new(),
+            });
+        }
+        contexts.truncate(MAX_CONTEXTS - extra.len());
+        let used = extra.iter().map(|ctx| ctx.locator.clone()).collect();
+        contexts.extend(extra);
+        Ok((contexts, used))
+    }
+
+    // Reorders the first `top_k` contexts by reranker score and keeps the best MAX_CONTEXTS.
+    // The query is the question's positive terms, as embedded for vector search.
+    fn rerank_contexts(
+        reranker: &dyn Reranker,
+        question: &str,
+        contexts: Vec<RetrievedContext>,
+        top_k: usize,
+    ) -> AppResult<(Vec<RetrievedContext>, serde_json::Value)> {
+        let query = parse_query(question)?.vector_text;
+        let split = top_k.min(contexts.len());
+        let inputs: Vec<RerankInput> = contexts[..split]
+            .iter()
+            .map(|ctx| RerankInput {
+                chunk_id: ctx.chunk_id.clone(),
+                text: ctx.snippet.clone(),
+            })
+            .collect();
+        let reranked = rerank_candidates(reranker, &query, &inputs)?;
+
+        let mut slots: Vec<Option<RetrievedContext>> = contexts.into_iter().map(Some).collect();
+        let mut ordered = Vec::with_capacity(slots.len());
+        for entry in &reranked {
+            if let Some(ctx) = slots[entry.merged_rank - 1].take() {
+                ordered.push(ctx);
+            }
+        }
+        ordered.extend(slots.into_iter().flatten());
+        ordered.truncate(MAX_CONTEXTS);
+
+        let rerank_json = serde_json::json!({
+            "reranker": reranker.reranker_id(),
+            "top_k": top_k,
+            "query": query,
+            "candidates": reranked,
+        });
+        Ok((ordered, rerank_json))
     }
 
     fn finalize_answer_with_retrieval(
@@ -411,6 +632,9 @@ impl RetrievedOnlyAskService {
         answer_text: String,
         citations: Vec<(i64, Vec<LocatorV1>)>,
         retrieval_json: serde_json::Value,
+        model_json: serde_json::Value,
+        context: TraceContext,
+        verification: Option<AnswerVerificationV1>,
     ) -> AppResult<AskResponse> {
         let normalized_citations = normalize_citations(&citations);
         validate_citations(&normalized_citations)?;
@@ -419,25 +643,32 @@ impl RetrievedOnlyAskService {
         let trace = TraceLogV1 {
             schema_version: 1,
             trace_id: uuid::Uuid::new_v4().to_string(),
-            ts_ms: req.now_ms,
-            vault_id: vault.vault_id,
+            ts_ms: context.replay_ts_ms.unwrap_or(req.now_ms),
+            vault_id: vault.vault_id.clone(),
             question: req.question.clone(),
             retrieval: retrieval_json,
-            model: serde_json::json!({ "mode": "retrieved-only" }),
+            model: model_json,
             answer: serde_json::json!({ "text": answer_text }),
-            redaction: serde_json::json!({ "enabled": true }),
+            // Filled in by the trace store's redaction pass.
+            redaction: TraceRedactionV1::default(),
+            session: context.session,
+            verification: verification.clone(),
         };
+        let trace_id = trace.trace_id.clone();
 
-        let trace_path = write_trace_log(
-            &req.vault_path.join(&self.trace_dir_name),
-            &trace,
-            &normalized_citations,
-        )?;
+        let mut store = TraceStore::for_vault(&req.vault_path, &vault, &self.trace_dir_name)?;
+        if context.replay_ts_ms.is_some() {
+            // A replay must not prune the traces it is being compared against.
+            store = store.without_retention();
+        }
+        let trace_path = store.write(trace, &normalized_citations)?;
 
         Ok(AskResponse {
             answer_text,
             citations: normalized_citations,
+            trace_id,
             trace_path,
+            verification,
         })
     }
 
@@ -447,43 +678,290 @@ impl RetrievedOnlyAskService {
         answer_text: String,
         citations: Vec<(i64, Vec<LocatorV1>)>,
     ) -> AppResult<AskResponse> {
-        self.finalize_answer_with_retrieval(req, answer_text, citations, serde_json::json!({}))
+        self.finalize_answer_with_retrieval(
+            req,
+            answer_text,
+            citations,
+            serde_json::json!({}),
+            retrieved_only_model(),
+            TraceContext::default(),
+            None,
+        )
     }
 }
 
 impl AskService for RetrievedOnlyAskService {
     fn ask(&self, req: AskRequest) -> AppResult<AskResponse> {
+        self.ask_stream(req, &AskCancelToken::default(), &mut |_| Ok(()))
+    }
+}
+
+impl RetrievedOnlyAskService {
+    // Same pipeline as `ask`, reporting progress through `on_event`. `final` is only emitted
+    // once citations are validated and the trace is written; cancelling earlier writes nothing.
+    pub fn ask_stream(
+        &self,
+        req: AskRequest,
+        cancel: &AskCancelToken,
+        on_event: &mut dyn FnMut(AskStreamEventV1) -> AppResult<()>,
+    ) -> AppResult<AskResponse> {
+        self.run_ask(&req, None, None, cancel, on_event)
+    }
+
+    pub fn ask_session(&self, session_id: &str, req: AskRequest) -> AppResult<AskSessionTurnV1> {
+        self.ask_session_stream(session_id, req, &AskCancelToken::default(), &mut |_| Ok(()))
+    }
+
+    // Asks the next turn of a session: the question is rewritten against the previous turn
+    // for retrieval, the previous turn's citations are offered again as context, and the
+    // turn is recorded once its trace is written.
+    pub fn ask_session_stream(
+        &self,
+        session_id: &str,
+        req: AskRequest,
+        cancel: &AskCancelToken,
+        on_event: &mut dyn FnMut(AskStreamEventV1) -> AppResult<()>,
+    ) -> AppResult<AskSessionTurnV1> {
         let vault = vault_open(&req.vault_path)?;
-        let conn = open_db(&req.vault_path.join(vault.db.relative_path))?;
-        let object_store = ObjectStore::new(vault_paths(&req.vault_path).objects_dir);
-        let contexts = self.load_contexts(
-            &conn,
-            &object_store,
+        let conn = open_db(&req.vault_path.join(&vault.db.relative_path))?;
+        let detail = ask_session_get(&conn, session_id)?;
+        let previous = detail.turns.last();
+        let standalone_query = rewrite_follow_up(
             &req.question,
-            req.now_ms,
-            vault.defaults.recency.enabled,
-        )?;
+            previous.map(|turn| turn.standalone_query.as_str()),
+        );
+        let turn = SessionTurn {
+            trace: TraceSessionV1 {
+                session_id: session_id.to_string(),
+                turn_index: detail.turns.len() as i64,
+                parent_trace_id: previous.map(|turn| turn.trace_id.clone()),
+                standalone_query: standalone_query.clone(),
+                rewriter: FOLLOW_UP_REWRITER_ID.to_string(),
+            },
+            reused: previous
+                .map(|turn| {
+                    turn.citations
+                        .iter()
+                        .flat_map(|citation| citation.locators.iter().cloned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        let turn_index = turn.trace.turn_index;
+        let parent_trace_id = turn.trace.parent_trace_id.clone();
+        let response = self.run_ask(&req, Some(turn), None, cancel, on_event)?;
+        let turn = AskSessionTurnV1 {
+            session_id: session_id.to_string(),
+            turn_index,
+            question: req.question,
+            standalone_query,
+            answer_text: response.answer_text,
+            citations: response
+                .citations
+                .into_iter()
+                .map(|(paragraph_index, locators)| AskStreamCitationV1 {
+                    paragraph_index,
+                    locators,
+                })
+                .collect(),
+            trace_id: response.trace_id,
+            trace_path: response.trace_path.display().to_string(),
+            parent_trace_id,
+            verification: response.verification,
+            created_at_ms: req.now_ms,
+        };
+        // Like the ask response, the returned turn keeps the original text; only the stored
+        // copy is redacted.
+        let mut stored = turn.clone();
+        redact_session_turn(&mut TraceRedactor::for_vault(&vault)?, &mut stored);
+        ask_session_turn_insert(&conn, &stored)?;
+        Ok(turn)
+    }
+
+    // Re-runs a recorded ask with the recorded retrieval profile, whatever the vault's profile
+    // of that name is now. `req.now_ms` is the recorded time retrieval runs at; the new trace
+    // is stamped `trace_ts_ms`. A session turn is replayed with the citations it reused.
+    pub(crate) fn replay_ask(
+        &self,
+        req: &AskRequest,
+        profile: RetrievalProfileV1,
+        trace_ts_ms: i64,
+        session: Option<(TraceSessionV1, Vec<LocatorV1>)>,
+    ) -> AppResult<AskResponse> {
+        let session = session.map(|(trace, reused)| SessionTurn { trace, reused });
+        self.run_ask(
+            req,
+            session,
+            Some(ReplayRun {
+                profile,
+                trace_ts_ms,
+            }),
+            &AskCancelToken::default(),
+            &mut |_| Ok(()),
+        )
+    }
+
+    // A replay's profile replaces the profile `req` names.
+    fn run_ask(
+        &self,
+        req: &AskRequest,
+        session: Option<SessionTurn>,
+        replay: Option<ReplayRun>,
+        cancel: &AskCancelToken,
+        on_event: &mut dyn FnMut(AskStreamEventV1) -> AppResult<()>,
+    ) -> AppResult<AskResponse> {
+        validate_min_score(req.min_score)?;
+        let query = session.as_ref().map_or(req.question.as_str(), |turn| {
+            turn.trace.standalone_query.as_str()
+        });
+        let vault = vault_open(&req.vault_path)?;
+        let conn = open_db(&req.vault_path.join(&vault.db.relative_path))?;
+        let object_store = ObjectStore::new(vault_paths(&req.vault_path).objects_dir);
+        let profile = match &replay {
+            Some(replay) => replay.profile.clone(),
+            None => retrieval_profile_for_vault(&vault, req.profile.as_deref())?,
+        };
+        let reranker: Option<Arc<dyn Reranker>> = match &self.reranker {
+            Some(reranker) => Some(reranker.clone()),
+            None => vault_reranker(&req.vault_path, &vault)?.map(Arc::from),
+        };
+        let top_k = vault
+            .defaults
+            .reranker
+            .as_ref()
+            .map(|r| r.top_k)
+            .unwrap_or(DEFAULT_RERANK_TOP_K);
+        let depth = if reranker.is_some() {
+            top_k.max(MAX_CONTEXTS)
+        } else {
+            MAX_CONTEXTS
+        };
+        let (contexts, diversify, counts) =
+            self.load_contexts(&conn, &object_store, req, query, &profile.config, depth)?;
+        let (contexts, rerank_json) = match &reranker {
+            Some(reranker) => {
+                let (contexts, rerank_json) =
+                    Self::rerank_contexts(reranker.as_ref(), query, contexts, top_k)?;
+                (contexts, Some(rerank_json))
+            }
+            None => (contexts, None),
+        };
+        let (contexts, reused) = match &session {
+            Some(turn) => Self::with_reused_contexts(&conn, &object_store, contexts, &turn.reused)?,
+            None => (contexts, Vec::new()),
+        };
 
         if contexts.is_empty() {
             return Err(AppError::new(
-                "KC_ASK_PROVIDER_UNAVAILABLE",
+                "KC_ASK_NO_RELEVANT_CONTEXT",
                 "ask",
-                "no retrieved context available for ask",
-                true,
-                serde_json::json!({}),
+                "no relevant context was retrieved for the question",
+                false,
+                serde_json::json!({
+                    "min_score": req.min_score,
+                    "lexical_candidates": counts.lexical,
+                    "vector_candidates": counts.vector,
+                    "below_min_score": counts.below_min_score,
+                }),
             ));
         }
 
-        let provider_answer = self.provider.answer(&req.question, &contexts)?;
+        let provider: Arc<dyn AskProvider> = match &self.provider {
+            Some(provider) => provider.clone(),
+            None => match vault_ask_provider(&vault)? {
+                Some(provider) => Arc::new(provider),
+                None => Arc::new(DeterministicAskProvider),
+            },
+        };
+        cancel.check("retrieval")?;
+        on_event(AskStreamEventV1::Retrieval {
+            profile: profile.name.clone(),
+            config_hash: profile.config_hash.0.clone(),
+            chunks: contexts
+                .iter()
+                .map(|ctx| AskStreamChunkV1 {
+                    chunk_id: ctx.chunk_id.0.clone(),
+                    doc_id: ctx.locator.doc_id.0.clone(),
+                    ordinal: ctx.ordinal,
+                    final_score: ctx.final_score,
+                    start: ctx.locator.range.start,
+                    end: ctx.locator.range.end,
+                })
+                .collect(),
+        })?;
+
+        let mut paragraphs = ParagraphSplitter::default();
+        let provider_answer =
+            provider.answer_streaming(&req.question, &contexts, &mut |delta| {
+                cancel.check("generation")?;
+                on_event(AskStreamEventV1::Delta {
+                    text: delta.to_string(),
+                })?;
+                for (paragraph_index, text) in paragraphs.push(delta) {
+                    on_event(AskStreamEventV1::Paragraph {
+                        paragraph_index,
+                        text,
+                    })?;
+                }
+                Ok(())
+            })?;
+        if let Some((paragraph_index, text)) = paragraphs.finish() {
+            on_event(AskStreamEventV1::Paragraph {
+                paragraph_index,
+                text,
+            })?;
+        }
 
-        // Validate every cited locator can be resolved against canonical text.
-        for (_paragraph, locators) in &provider_answer.citations {
+        // Every cited locator must resolve against canonical text; the resolved spans are the
+        // evidence each paragraph is verified against.
+        let mut evidence: Vec<ParagraphEvidence> = answer_paragraphs(&provider_answer.answer_text)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, text)| ParagraphEvidence {
+                paragraph_index: idx as i64,
+                text,
+                spans: Vec::new(),
+            })
+            .collect();
+        for (paragraph, locators) in &provider_answer.citations {
             for locator in locators {
-                let _ = resolve_locator_strict(&conn, &object_store, locator)?;
+                let span = resolve_locator_strict(&conn, &object_store, locator)?;
+                if let Some(entry) = usize::try_from(*paragraph)
+                    .ok()
+                    .and_then(|idx| evidence.get_mut(idx))
+                {
+                    entry.spans.push(span);
+                }
             }
         }
-
-        let retrieval_json = serde_json::json!({
+        let alignment: Option<Arc<dyn AlignmentModel>> = match (&self.nli, &self.alignment) {
+            (Some(_), _) => None,
+            (None, Some(alignment)) => Some(alignment.clone()),
+            (None, None) => vault_alignment_model(&req.vault_path, &vault)?.map(Arc::from),
+        };
+        let model = match (&self.nli, &alignment) {
+            (Some(nli), _) => Some(SupportModel::Nli(nli.as_ref())),
+            (None, Some(alignment)) => Some(SupportModel::Alignment(alignment.as_ref())),
+            (None, None) => None,
+        };
+        let threshold = vault
+            .defaults
+            .verifier
+            .as_ref()
+            .map(|v| v.threshold)
+            .unwrap_or(DEFAULT_SUPPORT_THRESHOLD);
+        let verification = verify_paragraphs(&evidence, model, threshold)?;
+
+        let mut retrieval_json = serde_json::json!({
+            "filter": req.filter,
+            "profile": profile,
+            "diversify": diversify,
+            "min_score": req.min_score,
+            "candidates": {
+                "lexical": counts.lexical,
+                "vector": counts.vector,
+                "below_min_score": counts.below_min_score,
+            },
             "chunks": contexts
                 .iter()
                 .map(|ctx| serde_json::json!({
@@ -491,20 +969,54 @@ impl AskService for RetrievedOnlyAskService {
                     "doc_id": ctx.locator.doc_id.0.clone(),
                     "ordinal": ctx.ordinal,
                     "final_score": ctx.final_score,
+                    "lexical_rank": ctx.lexical_rank,
+                    "vector_rank": ctx.vector_rank,
                     "range": {
                         "start": ctx.locator.range.start,
                         "end": ctx.locator.range.end
                     },
-                    "snippet": ctx.snippet.clone()
+                    "snippet": ctx.snippet.clone(),
+                    "collapsed_chunk_ids": ctx
+                        .collapsed
+                        .iter()
+                        .map(|chunk_id| chunk_id.0.clone())
+                        .collect::<Vec<_>>()
                 }))
                 .collect::<Vec<_>>(),
         });
+        if let Some(rerank_json) = rerank_json {
+            retrieval_json["rerank"] = rerank_json;
+        }
+        if session.is_some() {
+            retrieval_json["reused_citations"] = serde_json::json!(reused);
+        }
 
-        self.finalize_answer_with_retrieval(
-            &req,
+        cancel.check("finalize")?;
+        let response = self.finalize_answer_with_retrieval(
+            req,
             provider_answer.answer_text,
             provider_answer.citations,
             retrieval_json,
-        )
+            provider_answer.model,
+            TraceContext {
+                session: session.map(|turn| turn.trace),
+                replay_ts_ms: replay.map(|replay| replay.trace_ts_ms),
+            },
+            Some(verification.clone()),
+        )?;
+        on_event(AskStreamEventV1::Final {
+            answer_text: response.answer_text.clone(),
+            citations: response
+                .citations
+                .iter()
+                .map(|(paragraph_index, locators)| AskStreamCitationV1 {
+                    paragraph_index: *paragraph_index,
+                    locators: locators.clone(),
+                })
+                .collect(),
+            trace_path: response.trace_path.display().to_string(),
+            verification,
+        })?;
+        Ok(response)
     }
 }
@@ -0,0 +1,556 @@
+use crate::ask::{AskProvider, ProviderAnswer, RetrievedContext};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::locator::LocatorV1;
+use kc_core::vault::{VaultAskProviderDefaults, VaultJsonV3};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use std::io::{BufRead, BufReader, Read};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+pub const OPENAI_CHAT_BACKEND: &str = "openai_chat";
+pub const LLAMA_CPP_BACKEND: &str = "llama_cpp";
+pub const PROMPT_VERSION: i64 = 1;
+
+const ERROR_BODY_PREVIEW_CHARS: usize = 512;
+
+pub type ParagraphCitations = Vec<(i64, Vec<LocatorV1>)>;
+
+const SYSTEM_PROMPT: &str = "Answer the question using only the numbered sources. \
+Write one or more paragraphs separated by blank lines. End every paragraph with the \
+markers of the sources it relies on, such as [1] or [2][3]. Never cite a number that is \
+not listed. If the sources do not answer the question, say so and cite the closest source.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpAskBackend {
+    OpenAiChat,
+    LlamaCpp,
+}
+
+impl HttpAskBackend {
+    pub fn parse(name: &str) -> AppResult<Self> {
+        match name {
+            OPENAI_CHAT_BACKEND => Ok(Self::OpenAiChat),
+            LLAMA_CPP_BACKEND => Ok(Self::LlamaCpp),
+            other => Err(provider_invalid(
+                "unsupported ask provider backend",
+                serde_json::json!({
+                    "backend": other,
+                    "supported": [OPENAI_CHAT_BACKEND, LLAMA_CPP_BACKEND],
+                }),
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::OpenAiChat => OPENAI_CHAT_BACKEND,
+            Self::LlamaCpp => LLAMA_CPP_BACKEND,
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Self::OpenAiChat => "/v1/chat/completions",
+            Self::LlamaCpp => "/completion",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpAskProviderConfig {
+    pub backend: HttpAskBackend,
+    pub endpoint: String,
+    pub model: String,
+    pub temperature: f64,
+    pub max_tokens: u32,
+    pub timeout_ms: u64,
+    pub api_key: Option<String>,
+}
+
+// Talks to an OpenAI-compatible or llama.cpp server over http or https. `timeout_ms` is a
+// deadline for the whole request, streamed body included. Redirects are not followed.
+#[derive(Debug, Clone)]
+pub struct HttpAskProvider {
+    config: HttpAskProviderConfig,
+    url: Url,
+    client: Client,
+}
+
+fn provider_invalid(message: &str, details: serde_json::Value) -> AppError {
+    AppError::new("KC_ASK_PROVIDER_INVALID", "ask", message, false, details)
+}
+
+fn provider_unavailable(endpoint: &str, err: impl std::fmt::Display) -> AppError {
+    AppError::new(
+        "KC_ASK_PROVIDER_UNAVAILABLE",
+        "ask",
+        "ask provider endpoint is unreachable",
+        true,
+        serde_json::json!({ "endpoint": endpoint, "error": err.to_string() }),
+    )
+}
+
+fn response_invalid(message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(
+        "KC_ASK_PROVIDER_RESPONSE_INVALID",
+        "ask",
+        message,
+        false,
+        details,
+    )
+}
+
+// The backend's request URL under the endpoint's base path.
+fn parse_endpoint(endpoint: &str, backend: HttpAskBackend) -> AppResult<Url> {
+    let invalid = |reason: &str| {
+        provider_invalid(
+            "ask provider endpoint must be an http:// or https:// URL",
+            serde_json::json!({ "endpoint": endpoint, "reason": reason }),
+        )
+    };
+    let mut url = Url::parse(endpoint).map_err(|e| invalid(&e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid("unsupported scheme"));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("missing host"));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query or fragment"));
+    }
+    let path = format!("{}{}", url.path().trim_end_matches('/'), backend.path());
+    url.set_path(&path);
+    Ok(url)
+}
+
+fn is_loopback(url: &Url) -> bool {
+    let host = url
+        .host_str()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+impl HttpAskProvider {
+    pub fn new(config: HttpAskProviderConfig) -> AppResult<Self> {
+        if !config.temperature.is_finite() || config.temperature < 0.0 {
+            return Err(provider_invalid(
+                "ask provider temperature must be a non-negative number",
+                serde_json::json!({ "temperature": config.temperature }),
+            ));
+        }
+        if config.max_tokens == 0 || config.model.trim().is_empty() {
+            return Err(provider_invalid(
+                "ask provider requires a model and a positive max_tokens",
+                serde_json::json!({ "model": config.model, "max_tokens": config.max_tokens }),
+            ));
+        }
+        let url = parse_endpoint(&config.endpoint, config.backend)?;
+        // A bearer token never travels in cleartext beyond this machine.
+        if config.api_key.is_some() && url.scheme() != "https" && !is_loopback(&url) {
+            return Err(provider_invalid(
+                "ask provider api key requires an https:// or loopback endpoint",
+                serde_json::json!({ "endpoint": config.endpoint }),
+            ));
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms.max(1)))
+            .redirect(Policy::none())
+            .build()
+            .map_err(|e| {
+                provider_invalid(
+                    "failed to build the ask provider HTTP client",
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+        Ok(Self {
+            config,
+            url,
+            client,
+        })
+    }
+
+    pub fn from_defaults(defaults: &VaultAskProviderDefaults) -> AppResult<Self> {
+        let api_key = match &defaults.api_key_env {
+            Some(name) => Some(std::env::var(name).map_err(|_| {
+                provider_invalid(
+                    "ask provider api key environment variable is not set",
+                    serde_json::json!({ "api_key_env": name }),
+                )
+            })?),
+            None => None,
+        };
+        Self::new(HttpAskProviderConfig {
+            backend: HttpAskBackend::parse(&defaults.backend)?,
+            endpoint: defaults.endpoint.clone(),
+            model: defaults.model.clone(),
+            temperature: defaults.temperature,
+            max_tokens: defaults.max_tokens,
+            timeout_ms: defaults.timeout_ms,
+            api_key,
+        })
+    }
+
+    pub fn config(&self) -> &HttpAskProviderConfig {
+        &self.config
+    }
+
+    fn request_body(
+        &self,
+        question: &str,
+        contexts: &[RetrievedContext],
+        stream: bool,
+    ) -> serde_json::Value {
+        let (system, user) = build_prompt(question, contexts);
+        match self.config.backend {
+            HttpAskBackend::OpenAiChat => serde_json::json!({
+                "model": self.config.model,
+                "messages": [
+                    { "role": "system", "content": system },
+                    { "role": "user", "content": user },
+                ],
+                "temperature": self.config.temperature,
+                "max_tokens": self.config.max_tokens,
+                "stream": stream,
+            }),
+            HttpAskBackend::LlamaCpp => serde_json::json!({
+                "prompt": format!("{system}\n\n{user}\n\nAnswer:\n"),
+                "temperature": self.config.temperature,
+                "n_predict": self.config.max_tokens,
+                "stream": stream,
+            }),
+        }
+    }
+
+    // Returns the generated text and the model id reported by the server, if any.
+    fn parse_completion(&self, value: &serde_json::Value) -> AppResult<(String, Option<String>)> {
+        let text = match self.config.backend {
+            HttpAskBackend::OpenAiChat => value["choices"][0]["message"]["content"].as_str(),
+            HttpAskBackend::LlamaCpp => value["content"].as_str(),
+        };
+        let text = text.ok_or_else(|| {
+            response_invalid(
+                "ask provider response is missing the completion text",
+                serde_json::json!({ "backend": self.config.backend.as_str() }),
+            )
+        })?;
+        Ok((
+            text.to_string(),
+            value["model"].as_str().map(str::to_string),
+        ))
+    }
+
+    pub fn model_json(&self, model_id: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "mode": "http",
+            "backend": self.config.backend.as_str(),
+            "endpoint": self.config.endpoint,
+            "model_id": model_id.unwrap_or(&self.config.model),
+            "requested_model": self.config.model,
+            "prompt_version": PROMPT_VERSION,
+            "parameters": {
+                "temperature": self.config.temperature,
+                "max_tokens": self.config.max_tokens,
+            },
+        })
+    }
+
+    // Sends the request and returns whether the body is an event stream, and the body reader;
+    // non-2xx statuses become errors.
+    fn send(&self, body: &serde_json::Value) -> AppResult<(bool, Box<dyn BufRead>)> {
+        let deadline = Instant::now() + Duration::from_millis(self.config.timeout_ms.max(1));
+        let payload = serde_json::to_vec(body).map_err(|e| {
+            response_invalid(
+                "failed to encode ask provider request",
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+        let accept = if body["stream"] == true {
+            "text/event-stream"
+        } else {
+            "application/json"
+        };
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, accept)
+            .body(payload);
+        if let Some(key) = &self.config.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .map_err(|e| provider_unavailable(&self.config.endpoint, e))?;
+
+        let status = response.status().as_u16();
+        let event_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.to_ascii_lowercase().starts_with("text/event-stream"));
+        let mut body = BufReader::new(DeadlineBody {
+            inner: response,
+            deadline,
+        });
+        if !(200..300).contains(&status) {
+            let mut bytes = Vec::new();
+            body.read_to_end(&mut bytes)
+                .map_err(|e| provider_unavailable(&self.config.endpoint, e))?;
+            let preview: String = String::from_utf8_lossy(&bytes)
+                .chars()
+                .take(ERROR_BODY_PREVIEW_CHARS)
+                .collect();
+            return Err(response_invalid(
+                "ask provider returned an error status",
+                serde_json::json!({ "status": status, "body": preview }),
+            ));
+        }
+        Ok((event_stream, Box::new(body)))
+    }
+
+    fn read_json(&self, body: &mut dyn BufRead) -> AppResult<serde_json::Value> {
+        let mut bytes = Vec::new();
+        body.read_to_end(&mut bytes)
+            .map_err(|e| provider_unavailable(&self.config.endpoint, e))?;
+        serde_json::from_slice(&bytes).map_err(|e| {
+            response_invalid(
+                "ask provider response is not valid JSON",
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })
+    }
+
+    // Reads server-sent events until `[DONE]`, a llama.cpp `stop`, or the end of the body.
+    fn read_event_stream(
+        &self,
+        body: Box<dyn BufRead>,
+        on_delta: &mut dyn FnMut(&str) -> AppResult<()>,
+    ) -> AppResult<(String, Option<String>)> {
+        let mut text = String::new();
+        let mut model_id = None;
+        for line in body.lines() {
+            let line = line.map_err(|e| provider_unavailable(&self.config.endpoint, e))?;
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                break;
+            }
+            let event: serde_json::Value = serde_json::from_str(data).map_err(|e| {
+                response_invalid(
+                    "ask provider stream event is not valid JSON",
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+            if model_id.is_none() {
+                model_id = event["model"].as_str().map(str::to_string);
+            }
+            let (piece, stop) = match self.config.backend {
+                HttpAskBackend::OpenAiChat => {
+                    (event["choices"][0]["delta"]["content"].as_str(), false)
+                }
+                HttpAskBackend::LlamaCpp => (
+                    event["content"].as_str(),
+                    event["stop"].as_bool().unwrap_or(false),
+                ),
+            };
+            if let Some(piece) = piece.filter(|piece| !piece.is_empty()) {
+                text.push_str(piece);
+                on_delta(piece)?;
+            }
+            if stop {
+                break;
+            }
+        }
+        Ok((text, model_id))
+    }
+
+    fn cited_answer(
+        &self,
+        text: &str,
+        model_id: Option<&str>,
+        contexts: &[RetrievedContext],
+    ) -> AppResult<ProviderAnswer> {
+        let (answer_text, citations) = parse_cited_answer(text, contexts)?;
+        Ok(ProviderAnswer {
+            answer_text,
+            citations,
+            model: self.model_json(model_id),
+        })
+    }
+}
+
+impl AskProvider for HttpAskProvider {
+    fn answer(&self, question: &str, contexts: &[RetrievedContext]) -> AppResult<ProviderAnswer> {
+        let (_, mut body) = self.send(&self.request_body(question, contexts, false))?;
+        let response = self.read_json(body.as_mut())?;
+        let (text, model_id) = self.parse_completion(&response)?;
+        self.cited_answer(&text, model_id.as_deref(), contexts)
+    }
+
+    // Servers that ignore `stream: true` and answer with plain JSON are handled as one delta.
+    fn answer_streaming(
+        &self,
+        question: &str,
+        contexts: &[RetrievedContext],
+        on_delta: &mut dyn FnMut(&str) -> AppResult<()>,
+    ) -> AppResult<ProviderAnswer> {
+        let (event_stream, mut body) = self.send(&self.request_body(question, contexts, true))?;
+        let (text, model_id) = if event_stream {
+            self.read_event_stream(body, on_delta)?
+        } else {
+            let response = self.read_json(body.as_mut())?;
+            let (text, model_id) = self.parse_completion(&response)?;
+            on_delta(&text)?;
+            (text, model_id)
+        };
+        self.cited_answer(&text, model_id.as_deref(), contexts)
+    }
+}
+
+// Fails reads once the request deadline has passed. The client's own timeout bounds each
+// read, so a stalled read still ends; this bounds the request as a whole.
+struct DeadlineBody {
+    inner: Response,
+    deadline: Instant,
+}
+
+impl Read for DeadlineBody {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if Instant::now() >= self.deadline {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "ask provider request exceeded timeout_ms",
+            ));
+        }
+        self.inner.read(buf)
+    }
+}
+
+// Sources are numbered from 1 in context order; markers in the answer refer to these numbers.
+pub fn build_prompt(question: &str, contexts: &[RetrievedContext]) -> (String, String) {
+    let mut user = String::from("Sources:\n");
+    for (idx, ctx) in contexts.iter().enumerate() {
+        user.push_str(&format!("\n[{}] {}\n", idx + 1, ctx.snippet.trim()));
+    }
+    user.push_str(&format!("\nQuestion: {}", question.trim()));
+    (SYSTEM_PROMPT.to_string(), user)
+}
+
+// Numbers inside `[1]`, `[1][2]` or `[1, 2]`; brackets holding anything else are ignored.
+fn citation_markers(paragraph: &str) -> Vec<usize> {
+    let mut out = Vec::new();
+    let mut rest = paragraph;
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else {
+            break;
+        };
+        let inner = &rest[..close];
+        if let Some(nested) = inner.rfind('[') {
+            rest = &rest[nested..];
+            continue;
+        }
+        let numbers: Option<Vec<usize>> = inner
+            .split(',')
+            .map(|n| {
+                let n = n.trim();
+                if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                n.parse().ok()
+            })
+            .collect();
+        out.extend(numbers.unwrap_or_default());
+        rest = &rest[close + 1..];
+    }
+    out
+}
+
+// Blank-line separated paragraphs, numbered as citations number them.
+pub(crate) fn answer_paragraphs(text: &str) -> Vec<String> {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line.trim_end());
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    paragraphs
+}
+
+// Splits the answer into blank-line separated paragraphs and maps each paragraph's markers to
+// the cited contexts' locators. Markers stay in the returned text.
+pub fn parse_cited_answer(
+    text: &str,
+    contexts: &[RetrievedContext],
+) -> AppResult<(String, ParagraphCitations)> {
+    let paragraphs = answer_paragraphs(text);
+    if paragraphs.is_empty() {
+        return Err(response_invalid(
+            "ask provider returned an empty answer",
+            serde_json::json!({}),
+        ));
+    }
+
+    let mut citations = Vec::with_capacity(paragraphs.len());
+    for (idx, paragraph) in paragraphs.iter().enumerate() {
+        let mut markers = citation_markers(paragraph);
+        if markers.is_empty() {
+            return Err(AppError::new(
+                "KC_ASK_MISSING_CITATIONS",
+                "ask",
+                "answer paragraph has no citation markers",
+                false,
+                serde_json::json!({ "paragraph_index": idx }),
+            ));
+        }
+        if let Some(bad) = markers.iter().find(|m| **m == 0 || **m > contexts.len()) {
+            return Err(AppError::new(
+                "KC_ASK_INVALID_CITATIONS",
+                "ask",
+                "answer cites a source that was not provided",
+                false,
+                serde_json::json!({
+                    "paragraph_index": idx,
+                    "marker": bad,
+                    "sources": contexts.len(),
+                }),
+            ));
+        }
+        markers.sort_unstable();
+        markers.dedup();
+        citations.push((
+            idx as i64,
+            markers
+                .into_iter()
+                .map(|m| contexts[m - 1].locator.clone())
+                .collect(),
+        ));
+    }
+    Ok((paragraphs.join("\n\n"), citations))
+}
+
+pub fn vault_ask_provider(vault: &VaultJsonV3) -> AppResult<Option<HttpAskProvider>> {
+    vault
+        .defaults
+        .ask_provider
+        .as_ref()
+        .map(HttpAskProvider::from_defaults)
+        .transpose()
+}
@@ -1,4 +1,10 @@
 pub mod ask;
+pub mod http_provider;
+pub mod replay;
+pub mod session;
+pub mod stream;
 pub mod trace;
 
 pub use ask::{AskRequest, AskResponse, AskService, RetrievedOnlyAskService};
+pub use session::{AskSessionDetailV1, AskSessionTurnV1, AskSessionV1};
+pub use stream::{AskCancelToken, AskStreamEventV1};
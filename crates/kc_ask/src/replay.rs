@@ -0,0 +1,233 @@
+use crate::ask::RetrievedOnlyAskService;
+use crate::trace::{trace_error, trace_replay_request, TraceLogV1, TraceStore};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::locator::LocatorV1;
+use kc_core::retrieval::{hash_retrieval_config, retrieval_profile_for_vault, RetrievalProfileV1};
+use kc_core::vault::vault_open;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Scores are compared after a JSON round trip, so exact equality is too strict.
+const SCORE_EPSILON: f64 = 1e-9;
+
+// What the replay ran with next to what the trace recorded. The replay always uses the
+// recorded profile config; `current_config_hash` shows whether the vault's profile of that
+// name has changed since, and is None when the profile no longer exists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayConfigV1 {
+    pub profile: String,
+    pub config_hash: String,
+    pub current_config_hash: Option<String>,
+    pub recorded_reranker: Option<String>,
+    pub replayed_reranker: Option<String>,
+    pub model_changed: bool,
+}
+
+// A chunk retrieved by both runs at a different 1-based rank or with a different score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayChunkChangeV1 {
+    pub chunk_id: String,
+    pub recorded_rank: usize,
+    pub replayed_rank: usize,
+    pub recorded_score: f64,
+    pub replayed_score: f64,
+}
+
+// `removed` follows recorded order, `added` and `changed` replayed order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayChunkDriftV1 {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ReplayChunkChangeV1>,
+}
+
+impl ReplayChunkDriftV1 {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Both texts are as stored in the traces, so they are compared after redaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayAnswerDriftV1 {
+    pub text_changed: bool,
+    pub citations_changed: bool,
+    pub recorded_text: String,
+    pub replayed_text: String,
+    pub recorded_supported: Option<bool>,
+    pub replayed_supported: Option<bool>,
+}
+
+impl ReplayAnswerDriftV1 {
+    pub fn is_empty(&self) -> bool {
+        !self.text_changed
+            && !self.citations_changed
+            && self.recorded_supported == self.replayed_supported
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceReplayReportV1 {
+    pub trace_id: String,
+    pub replay_trace_id: String,
+    pub replay_trace_path: String,
+    pub drift: bool,
+    pub config: ReplayConfigV1,
+    pub chunks: ReplayChunkDriftV1,
+    pub answer: ReplayAnswerDriftV1,
+}
+
+fn replay_unavailable(trace: &TraceLogV1, reason: &str) -> AppError {
+    trace_error(
+        "KC_TRACE_REPLAY_UNAVAILABLE",
+        "trace cannot be replayed",
+        serde_json::json!({ "trace_id": trace.trace_id, "reason": reason }),
+    )
+}
+
+fn retrieved_chunks(trace: &TraceLogV1) -> Vec<(String, f64)> {
+    trace
+        .retrieval
+        .get("chunks")
+        .and_then(|c| c.as_array())
+        .map(|chunks| {
+            chunks
+                .iter()
+                .filter_map(|chunk| {
+                    Some((
+                        chunk.get("chunk_id")?.as_str()?.to_string(),
+                        chunk
+                            .get("final_score")
+                            .and_then(|s| s.as_f64())
+                            .unwrap_or_default(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn reranker_id(trace: &TraceLogV1) -> Option<String> {
+    trace
+        .retrieval
+        .get("rerank")
+        .and_then(|r| r.get("reranker"))
+        .and_then(|r| r.as_str())
+        .map(str::to_string)
+}
+
+fn answer_text(trace: &TraceLogV1) -> String {
+    trace
+        .answer
+        .get("text")
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn chunk_drift(recorded: &[(String, f64)], replayed: &[(String, f64)]) -> ReplayChunkDriftV1 {
+    let rank_in =
+        |chunks: &[(String, f64)], chunk_id: &str| chunks.iter().position(|(id, _)| id == chunk_id);
+    let mut drift = ReplayChunkDriftV1 {
+        removed: recorded
+            .iter()
+            .filter(|(id, _)| rank_in(replayed, id).is_none())
+            .map(|(id, _)| id.clone())
+            .collect(),
+        ..ReplayChunkDriftV1::default()
+    };
+    for (idx, (chunk_id, score)) in replayed.iter().enumerate() {
+        let Some(recorded_idx) = rank_in(recorded, chunk_id) else {
+            drift.added.push(chunk_id.clone());
+            continue;
+        };
+        let recorded_score = recorded[recorded_idx].1;
+        if recorded_idx != idx || (recorded_score - score).abs() > SCORE_EPSILON {
+            drift.changed.push(ReplayChunkChangeV1 {
+                chunk_id: chunk_id.clone(),
+                recorded_rank: recorded_idx + 1,
+                replayed_rank: idx + 1,
+                recorded_score,
+                replayed_score: *score,
+            });
+        }
+    }
+    drift
+}
+
+// Asks `recorded` again with the retrieval profile config it recorded and at its recorded
+// `ts_ms`, so a change in the results comes from the vault's chunks, indexes, reranker or
+// answer provider rather than from the profile or recency. The replay writes a new trace
+// stamped `now_ms`, without pruning, which the report is diffed from.
+pub fn replay_trace(
+    service: &RetrievedOnlyAskService,
+    vault_path: &Path,
+    recorded: &TraceLogV1,
+    now_ms: i64,
+) -> AppResult<TraceReplayReportV1> {
+    let mut req = trace_replay_request(vault_path, recorded, recorded.ts_ms)?;
+    let profile: RetrievalProfileV1 = recorded
+        .retrieval
+        .get("profile")
+        .cloned()
+        .and_then(|p| serde_json::from_value(p).ok())
+        .ok_or_else(|| replay_unavailable(recorded, "recorded retrieval profile is not valid"))?;
+    if hash_retrieval_config(&profile.config)? != profile.config_hash {
+        return Err(replay_unavailable(
+            recorded,
+            "recorded retrieval config does not match its config_hash",
+        ));
+    }
+    let session = match &recorded.session {
+        Some(session) => {
+            // The provider answers the turn's own question; retrieval uses the standalone query.
+            if recorded.redaction.fields.iter().any(|f| f == "question") {
+                return Err(replay_unavailable(recorded, "question was redacted"));
+            }
+            req.question = recorded.question.clone();
+            let reused: Vec<LocatorV1> = match recorded.retrieval.get("reused_citations") {
+                Some(reused) => serde_json::from_value(reused.clone()).map_err(|_| {
+                    replay_unavailable(recorded, "recorded reused citations are not valid")
+                })?,
+                None => Vec::new(),
+            };
+            Some((session.clone(), reused))
+        }
+        None => None,
+    };
+
+    let vault = vault_open(vault_path)?;
+    let current_config_hash = retrieval_profile_for_vault(&vault, Some(profile.name.as_str()))
+        .ok()
+        .map(|current| current.config_hash.0);
+    let res = service.replay_ask(&req, profile.clone(), now_ms, session)?;
+    let replayed =
+        TraceStore::for_vault(vault_path, &vault, &service.trace_dir_name)?.get(&res.trace_id)?;
+
+    let chunks = chunk_drift(&retrieved_chunks(recorded), &retrieved_chunks(&replayed));
+    let answer = ReplayAnswerDriftV1 {
+        text_changed: answer_text(recorded) != answer_text(&replayed),
+        citations_changed: recorded.retrieval.get("citations")
+            != replayed.retrieval.get("citations"),
+        recorded_text: answer_text(recorded),
+        replayed_text: answer_text(&replayed),
+        recorded_supported: recorded.verification.as_ref().map(|v| v.supported),
+        replayed_supported: replayed.verification.as_ref().map(|v| v.supported),
+    };
+    Ok(TraceReplayReportV1 {
+        trace_id: recorded.trace_id.clone(),
+        replay_trace_id: res.trace_id,
+        replay_trace_path: res.trace_path.display().to_string(),
+        drift: !chunks.is_empty() || !answer.is_empty(),
+        config: ReplayConfigV1 {
+            profile: profile.name,
+            config_hash: profile.config_hash.0,
+            current_config_hash,
+            recorded_reranker: reranker_id(recorded),
+            replayed_reranker: reranker_id(&replayed),
+            model_changed: recorded.model != replayed.model,
+        },
+        chunks,
+        answer,
+    })
+}
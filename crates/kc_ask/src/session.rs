@@ -0,0 +1,344 @@
+use crate::stream::AskStreamCitationV1;
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::faithfulness::AnswerVerificationV1;
+use kc_core::trace_redaction::TraceRedactor;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+pub const FOLLOW_UP_REWRITER_ID: &str = "follow-up-terms-v1";
+// A question with fewer content terms than this, or with an anaphor, is treated as a
+// follow-up and borrows terms from the previous turn's standalone query.
+const MIN_STANDALONE_TERMS: usize = 3;
+const MAX_CARRIED_TERMS: usize = 6;
+
+const ANAPHORS: &[&str] = &[
+    "it", "its", "this", "that", "these", "those", "they", "them", "their", "he", "him", "his",
+    "she", "her", "there",
+];
+const STOPWORDS: &[&str] = &[
+    "a", "about", "again", "also", "an", "and", "are", "as", "at", "be", "by", "can", "could",
+    "did", "do", "does", "else", "for", "from", "how", "in", "is", "me", "more", "my", "of", "on",
+    "or", "our", "should", "tell", "the", "to", "was", "we", "were", "what", "when", "where",
+    "which", "who", "why", "with", "would", "you", "your",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AskSessionV1 {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+    pub turn_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskSessionTurnV1 {
+    pub session_id: String,
+    pub turn_index: i64,
+    pub question: String,
+    pub standalone_query: String,
+    pub answer_text: String,
+    pub citations: Vec<AskStreamCitationV1>,
+    pub trace_id: String,
+    pub trace_path: String,
+    pub parent_trace_id: Option<String>,
+    pub verification: Option<AnswerVerificationV1>,
+    pub created_at_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskSessionDetailV1 {
+    pub session: AskSessionV1,
+    pub turns: Vec<AskSessionTurnV1>,
+}
+
+fn session_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "ask", message, false, details)
+}
+
+fn db_error(message: &str, e: rusqlite::Error) -> AppError {
+    session_error(
+        "KC_ASK_SESSION_PERSIST_FAILED",
+        message,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+// Stored standalone queries are redacted; a `[REDACTED:<rule>]` placeholder names a rule, not
+// content, so it never becomes a term.
+fn strip_redactions(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[REDACTED:") {
+        let Some(len) = rest[start..].find(']') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push(' ');
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn content_terms(text: &str) -> Vec<String> {
+    let text = strip_redactions(text);
+    let mut terms: Vec<String> = Vec::new();
+    for word in words(&text) {
+        if word.chars().count() < 2
+            || STOPWORDS.contains(&word.as_str())
+            || ANAPHORS.contains(&word.as_str())
+            || terms.contains(&word)
+        {
+            continue;
+        }
+        terms.push(word);
+    }
+    terms
+}
+
+// Standalone questions pass through unchanged so their query syntax still applies. A
+// follow-up is reduced to its content terms plus up to MAX_CARRIED_TERMS terms of the
+// previous standalone query, as plain terms the query parser ANDs together.
+pub fn rewrite_follow_up(question: &str, previous_query: Option<&str>) -> String {
+    let Some(previous_query) = previous_query else {
+        return question.to_string();
+    };
+    let terms = content_terms(question);
+    let anaphoric = words(question).any(|word| ANAPHORS.contains(&word.as_str()));
+    if !anaphoric && terms.len() >= MIN_STANDALONE_TERMS {
+        return question.to_string();
+    }
+    let carried: Vec<String> = content_terms(previous_query)
+        .into_iter()
+        .filter(|term| !terms.contains(term))
+        .take(MAX_CARRIED_TERMS)
+        .collect();
+    if carried.is_empty() {
+        return question.to_string();
+    }
+    terms
+        .into_iter()
+        .chain(carried)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn ask_session_create(
+    conn: &Connection,
+    title: Option<&str>,
+    now_ms: i64,
+) -> AppResult<AskSessionV1> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let title = title.map(str::trim).filter(|title| !title.is_empty());
+    conn.execute(
+        "INSERT INTO ask_sessions(session_id, title, created_at_ms, updated_at_ms)
+         VALUES (?1, ?2, ?3, ?3)",
+        params![session_id, title, now_ms],
+    )
+    .map_err(|e| db_error("failed inserting ask session", e))?;
+    Ok(AskSessionV1 {
+        session_id,
+        title: title.map(str::to_string),
+        created_at_ms: now_ms,
+        updated_at_ms: now_ms,
+        turn_count: 0,
+    })
+}
+
+const SESSION_SELECT: &str = "SELECT s.session_id, s.title, s.created_at_ms, s.updated_at_ms,
+            (SELECT COUNT(*) FROM ask_session_turns t WHERE t.session_id=s.session_id)
+     FROM ask_sessions s";
+
+fn row_to_session(row: &rusqlite::Row<'_>) -> rusqlite::Result<AskSessionV1> {
+    Ok(AskSessionV1 {
+        session_id: row.get(0)?,
+        title: row.get(1)?,
+        created_at_ms: row.get(2)?,
+        updated_at_ms: row.get(3)?,
+        turn_count: row.get(4)?,
+    })
+}
+
+fn row_to_turn(row: &rusqlite::Row<'_>) -> rusqlite::Result<AskSessionTurnV1> {
+    let citations_json: String = row.get(5)?;
+    let citations = serde_json::from_str(&citations_json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let verification_json: Option<String> = row.get(9)?;
+    let verification = verification_json
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+    Ok(AskSessionTurnV1 {
+        session_id: row.get(0)?,
+        turn_index: row.get(1)?,
+        question: row.get(2)?,
+        standalone_query: row.get(3)?,
+        answer_text: row.get(4)?,
+        citations,
+        trace_id: row.get(6)?,
+        trace_path: row.get(7)?,
+        parent_trace_id: row.get(8)?,
+        verification,
+        created_at_ms: row.get(10)?,
+    })
+}
+
+// Most recently used first.
+pub fn ask_sessions_list(conn: &Connection) -> AppResult<Vec<AskSessionV1>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{SESSION_SELECT} ORDER BY s.updated_at_ms DESC, s.session_id ASC"
+        ))
+        .map_err(|e| db_error("failed preparing ask sessions query", e))?;
+    let rows = stmt
+        .query_map([], row_to_session)
+        .map_err(|e| db_error("failed querying ask sessions", e))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row.map_err(|e| db_error("failed decoding ask session row", e))?);
+    }
+    Ok(out)
+}
+
+pub fn ask_session_get(conn: &Connection, session_id: &str) -> AppResult<AskSessionDetailV1> {
+    let session = conn
+        .query_row(
+            &format!("{SESSION_SELECT} WHERE s.session_id=?1"),
+            params![session_id],
+            row_to_session,
+        )
+        .optional()
+        .map_err(|e| db_error("failed loading ask session", e))?
+        .ok_or_else(|| {
+            session_error(
+                "KC_ASK_SESSION_NOT_FOUND",
+                "ask session does not exist",
+                serde_json::json!({ "session_id": session_id }),
+            )
+        })?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, turn_index, question, standalone_query, answer_text,
+                    citations_json, trace_id, trace_path, parent_trace_id, verification_json,
+                    created_at_ms
+             FROM ask_session_turns WHERE session_id=?1 ORDER BY turn_index ASC",
+        )
+        .map_err(|e| db_error("failed preparing ask session turns query", e))?;
+    let rows = stmt
+        .query_map(params![session_id], row_to_turn)
+        .map_err(|e| db_error("failed querying ask session turns", e))?;
+    let mut turns = Vec::new();
+    for row in rows {
+        turns.push(row.map_err(|e| db_error("failed decoding ask session turn row", e))?);
+    }
+    Ok(AskSessionDetailV1 { session, turns })
+}
+
+// Removes the session and its turns. Turn traces are audit records and stay on disk.
+pub fn ask_session_delete(conn: &Connection, session_id: &str) -> AppResult<()> {
+    ask_session_get(conn, session_id)?;
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| db_error("failed starting ask session delete", e))?;
+    tx.execute(
+        "DELETE FROM ask_session_turns WHERE session_id=?1",
+        params![session_id],
+    )
+    .map_err(|e| db_error("failed deleting ask session turns", e))?;
+    tx.execute(
+        "DELETE FROM ask_sessions WHERE session_id=?1",
+        params![session_id],
+    )
+    .map_err(|e| db_error("failed deleting ask session", e))?;
+    tx.commit()
+        .map_err(|e| db_error("failed committing ask session delete", e))
+}
+
+// Redacts the copy of a turn that is stored with the vault's trace rules, the same fields its
+// trace redacts: question, standalone query, answer and flagged sentences.
+pub(crate) fn redact_session_turn(redactor: &mut TraceRedactor, turn: &mut AskSessionTurnV1) {
+    turn.question = redactor.redact("question", &turn.question);
+    turn.standalone_query = redactor.redact("standalone_query", &turn.standalone_query);
+    turn.answer_text = redactor.redact("answer_text", &turn.answer_text);
+    if let Some(verification) = turn.verification.as_mut() {
+        for paragraph in verification.paragraphs.iter_mut() {
+            for sentence in paragraph.unsupported_sentences.iter_mut() {
+                sentence.text = redactor.redact("verification", &sentence.text);
+                sentence.reason = redactor.redact("verification", &sentence.reason);
+            }
+        }
+    }
+}
+
+// Appends a turn; another turn recorded at the same index since the session was loaded
+// fails with KC_ASK_SESSION_CONFLICT.
+pub(crate) fn ask_session_turn_insert(conn: &Connection, turn: &AskSessionTurnV1) -> AppResult<()> {
+    let serialize_failed = |e: serde_json::Error| {
+        session_error(
+            "KC_ASK_SESSION_PERSIST_FAILED",
+            "failed serializing ask session turn",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    };
+    let citations_json = serde_json::to_string(&turn.citations).map_err(serialize_failed)?;
+    let verification_json = turn
+        .verification
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(serialize_failed)?;
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| db_error("failed starting ask session turn insert", e))?;
+    tx.execute(
+        "INSERT INTO ask_session_turns(session_id, turn_index, question, standalone_query,
+           answer_text, citations_json, trace_id, trace_path, parent_trace_id, verification_json,
+           created_at_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            turn.session_id,
+            turn.turn_index,
+            turn.question,
+            turn.standalone_query,
+            turn.answer_text,
+            citations_json,
+            turn.trace_id,
+            turn.trace_path,
+            turn.parent_trace_id,
+            verification_json,
+            turn.created_at_ms,
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _)
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            session_error(
+                "KC_ASK_SESSION_CONFLICT",
+                "another turn was recorded for this session concurrently",
+                serde_json::json!({
+                    "session_id": turn.session_id,
+                    "turn_index": turn.turn_index,
+                }),
+            )
+        }
+        e => db_error("failed inserting ask session turn", e),
+    })?;
+    tx.execute(
+        "UPDATE ask_sessions SET updated_at_ms=MAX(updated_at_ms, ?2) WHERE session_id=?1",
+        params![turn.session_id, turn.created_at_ms],
+    )
+    .map_err(|e| db_error("failed updating ask session", e))?;
+    tx.commit()
+        .map_err(|e| db_error("failed committing ask session turn", e))
+}
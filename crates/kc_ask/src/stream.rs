@@ -0,0 +1,124 @@
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::faithfulness::AnswerVerificationV1;
+use kc_core::locator::LocatorV1;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Shared flag checked between ask stages and on every streamed delta. Cancelling before the
+// trace is written leaves no trace behind.
+#[derive(Debug, Clone, Default)]
+pub struct AskCancelToken(Arc<AtomicBool>);
+
+impl AskCancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self, stage: &str) -> AppResult<()> {
+        if self.is_cancelled() {
+            return Err(AppError::new(
+                "KC_ASK_CANCELLED",
+                "ask",
+                "ask was cancelled",
+                false,
+                serde_json::json!({ "stage": stage }),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskStreamChunkV1 {
+    pub chunk_id: String,
+    pub doc_id: String,
+    pub ordinal: i64,
+    pub final_score: f64,
+    pub start: i64,
+    pub end: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AskStreamCitationV1 {
+    pub paragraph_index: i64,
+    pub locators: Vec<LocatorV1>,
+}
+
+// Emitted in order: one `retrieval`, any number of `delta` and `paragraph`, then one `final`
+// after citations are validated and the trace is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AskStreamEventV1 {
+    Retrieval {
+        profile: String,
+        config_hash: String,
+        chunks: Vec<AskStreamChunkV1>,
+    },
+    Delta {
+        text: String,
+    },
+    Paragraph {
+        paragraph_index: i64,
+        text: String,
+    },
+    Final {
+        answer_text: String,
+        citations: Vec<AskStreamCitationV1>,
+        trace_path: String,
+        verification: AnswerVerificationV1,
+    },
+}
+
+// Splits streamed text into paragraphs as soon as a blank line closes one, numbering them
+// the same way citation parsing does.
+#[derive(Debug, Default)]
+pub(crate) struct ParagraphSplitter {
+    pending: String,
+    next_index: i64,
+}
+
+fn blank_line(text: &str) -> Option<(usize, usize)> {
+    let mut search = 0;
+    while let Some(offset) = text[search..].find('\n') {
+        let newline = search + offset;
+        let after = &text[newline + 1..];
+        let spaces = after.len() - after.trim_start_matches([' ', '\t', '\r']).len();
+        if after[spaces..].starts_with('\n') {
+            return Some((newline, newline + spaces + 2));
+        }
+        search = newline + 1;
+    }
+    None
+}
+
+impl ParagraphSplitter {
+    fn take(&mut self, end: usize) -> Option<(i64, String)> {
+        let paragraph = self.pending[..end].trim().to_string();
+        if paragraph.is_empty() {
+            return None;
+        }
+        self.next_index += 1;
+        Some((self.next_index - 1, paragraph))
+    }
+
+    pub(crate) fn push(&mut self, delta: &str) -> Vec<(i64, String)> {
+        self.pending.push_str(delta);
+        let mut out = Vec::new();
+        while let Some((end, rest)) = blank_line(&self.pending) {
+            out.extend(self.take(end));
+            self.pending.drain(..rest);
+        }
+        out
+    }
+
+    pub(crate) fn finish(&mut self) -> Option<(i64, String)> {
+        let out = self.take(self.pending.len());
+        self.pending.clear();
+        out
+    }
+}
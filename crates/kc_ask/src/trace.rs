@@ -1,8 +1,23 @@
+use crate::ask::AskRequest;
 use kc_core::app_error::{AppError, AppResult};
+use kc_core::faithfulness::AnswerVerificationV1;
 use kc_core::locator::LocatorV1;
+use kc_core::object_store::{is_encrypted_payload, ObjectStoreEncryptionContext};
+use kc_core::search::SearchFilterV1;
+use kc_core::trace_redaction::{TraceRedactionV1, TraceRedactor};
+use kc_core::vault::{
+    vault_open, VaultJsonV3, VaultTraceRedactionDefaults, VaultTraceRetentionDefaults,
+};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Encrypted vaults take the trace key from the same passphrase variable as remote sync.
+pub const TRACE_PASSPHRASE_ENV: &str = "KC_VAULT_PASSPHRASE";
+const PLAIN_SUFFIX: &str = ".json";
+const ENCRYPTED_SUFFIX: &str = ".json.kce";
+const DAY_MS: i64 = 86_400_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraceLogV1 {
@@ -14,24 +29,64 @@ pub struct TraceLogV1 {
     pub retrieval: serde_json::Value,
     pub model: serde_json::Value,
     pub answer: serde_json::Value,
-    pub redaction: serde_json::Value,
+    pub redaction: TraceRedactionV1,
+    // Present only for session turns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<TraceSessionV1>,
+    // Present for asks that ran the verification pass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<AnswerVerificationV1>,
 }
 
-pub fn write_trace_log(
-    trace_dir: &Path,
+// Links a session turn's trace to the session and to the previous turn's trace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceSessionV1 {
+    pub session_id: String,
+    pub turn_index: i64,
+    pub parent_trace_id: Option<String>,
+    pub standalone_query: String,
+    pub rewriter: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceSummaryV1 {
+    pub trace_id: String,
+    pub ts_ms: i64,
+    pub question: String,
+    pub session_id: Option<String>,
+    pub turn_index: Option<i64>,
+    // None for traces written without a verification pass.
+    pub supported: Option<bool>,
+    pub redacted: bool,
+    pub encrypted: bool,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceListV1 {
+    pub traces: Vec<TraceSummaryV1>,
+    // Trace files that could not be read, decrypted or parsed; listing goes on without them.
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracePruneReportV1 {
+    pub removed: Vec<String>,
+    pub kept: usize,
+    // Trace files whose metadata could not be read or that could not be removed.
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
+pub(crate) fn trace_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "trace", message, false, details)
+}
+
+fn trace_log_value(
     trace: &TraceLogV1,
     citations: &[(i64, Vec<LocatorV1>)],
-) -> AppResult<PathBuf> {
-    fs::create_dir_all(trace_dir).map_err(|e| {
-        AppError::new(
-            "KC_TRACE_WRITE_FAILED",
-            "trace",
-            "failed to create trace directory",
-            false,
-            serde_json::json!({ "error": e.to_string(), "path": trace_dir }),
-        )
-    })?;
-
+) -> AppResult<serde_json::Value> {
     let mut sorted: Vec<(i64, Vec<LocatorV1>)> = citations
         .iter()
         .map(|(paragraph_idx, locators)| {
@@ -56,47 +111,455 @@ pub fn write_trace_log(
     });
 
     let mut value = serde_json::to_value(trace).map_err(|e| {
-        AppError::new(
+        trace_error(
             "KC_TRACE_WRITE_FAILED",
-            "trace",
             "failed to serialize trace log",
-            false,
             serde_json::json!({ "error": e.to_string() }),
         )
     })?;
 
     value["retrieval"]["citations"] = serde_json::to_value(sorted).map_err(|e| {
-        AppError::new(
+        trace_error(
             "KC_TRACE_WRITE_FAILED",
-            "trace",
             "failed to serialize citations",
-            false,
             serde_json::json!({ "error": e.to_string() }),
         )
     })?;
+    Ok(value)
+}
 
-    let path = trace_dir.join(format!("{}.json", trace.trace_id));
-    fs::write(
-        &path,
-        serde_json::to_vec_pretty(&value).map_err(|e| {
-            AppError::new(
-                "KC_TRACE_WRITE_FAILED",
-                "trace",
-                "failed to serialize trace log JSON",
-                false,
-                serde_json::json!({ "error": e.to_string() }),
-            )
-        })?,
-    )
-    .map_err(|e| {
-        AppError::new(
+fn write_trace_bytes(
+    trace_dir: &Path,
+    file_name: &str,
+    bytes: &[u8],
+    ts_ms: i64,
+) -> AppResult<PathBuf> {
+    fs::create_dir_all(trace_dir).map_err(|e| {
+        trace_error(
+            "KC_TRACE_WRITE_FAILED",
+            "failed to create trace directory",
+            serde_json::json!({ "error": e.to_string(), "path": trace_dir }),
+        )
+    })?;
+    let path = trace_dir.join(file_name);
+    fs::write(&path, bytes).map_err(|e| {
+        trace_error(
             "KC_TRACE_WRITE_FAILED",
-            "trace",
             "failed to write trace log",
-            false,
             serde_json::json!({ "error": e.to_string(), "path": path }),
         )
     })?;
-
+    // Retention reads a trace's age from its mtime, so pruning never has to open the file.
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|f| f.set_modified(ts_system_time(ts_ms)))
+        .map_err(|e| {
+            trace_error(
+                "KC_TRACE_WRITE_FAILED",
+                "failed to stamp trace log mtime",
+                serde_json::json!({ "error": e.to_string(), "path": path }),
+            )
+        })?;
     Ok(path)
 }
+
+fn ts_system_time(ts_ms: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ts_ms.max(0) as u64)
+}
+
+fn modified_ms(path: &Path) -> std::io::Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0))
+}
+
+// `<trace_id>.json` or `<trace_id>.json.kce`; anything else in the directory is not a trace.
+fn trace_file_id(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(ENCRYPTED_SUFFIX)
+        .or_else(|| name.strip_suffix(PLAIN_SUFFIX))
+        .filter(|id| uuid::Uuid::parse_str(id).is_ok())
+}
+
+fn pretty_json(value: &serde_json::Value) -> AppResult<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(|e| {
+        trace_error(
+            "KC_TRACE_WRITE_FAILED",
+            "failed to serialize trace log JSON",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })
+}
+
+// Writes `trace` as-is, in plaintext. Asks go through `TraceStore::write`, which redacts and
+// encrypts first.
+pub fn write_trace_log(
+    trace_dir: &Path,
+    trace: &TraceLogV1,
+    citations: &[(i64, Vec<LocatorV1>)],
+) -> AppResult<PathBuf> {
+    let value = trace_log_value(trace, citations)?;
+    write_trace_bytes(
+        trace_dir,
+        &format!("{}{PLAIN_SUFFIX}", trace.trace_id),
+        &pretty_json(&value)?,
+        trace.ts_ms,
+    )
+}
+
+fn redact_value(redactor: &mut TraceRedactor, value: &mut serde_json::Value, field: &str) {
+    if let Some(text) = value.as_str() {
+        *value = serde_json::Value::String(redactor.redact(field, text));
+    }
+}
+
+// Redacts free text only: the question, snippets, rerank query, answer, standalone query and
+// flagged sentences. IDs, locators, scores and config stay intact.
+fn redact_trace(redactor: &mut TraceRedactor, trace: &mut TraceLogV1) {
+    trace.question = redactor.redact("question", &trace.question);
+    if let Some(chunks) = trace
+        .retrieval
+        .get_mut("chunks")
+        .and_then(|c| c.as_array_mut())
+    {
+        for (idx, chunk) in chunks.iter_mut().enumerate() {
+            if let Some(snippet) = chunk.get_mut("snippet") {
+                redact_value(
+                    redactor,
+                    snippet,
+                    &format!("retrieval.chunks[{idx}].snippet"),
+                );
+            }
+        }
+    }
+    if let Some(query) = trace
+        .retrieval
+        .get_mut("rerank")
+        .and_then(|r| r.get_mut("query"))
+    {
+        redact_value(redactor, query, "retrieval.rerank.query");
+    }
+    if let Some(text) = trace.answer.get_mut("text") {
+        redact_value(redactor, text, "answer.text");
+    }
+    if let Some(session) = trace.session.as_mut() {
+        session.standalone_query =
+            redactor.redact("session.standalone_query", &session.standalone_query);
+    }
+    if let Some(verification) = trace.verification.as_mut() {
+        for (p_idx, paragraph) in verification.paragraphs.iter_mut().enumerate() {
+            for (s_idx, sentence) in paragraph.unsupported_sentences.iter_mut().enumerate() {
+                let field =
+                    format!("verification.paragraphs[{p_idx}].unsupported_sentences[{s_idx}]");
+                sentence.text = redactor.redact(&format!("{field}.text"), &sentence.text);
+                sentence.reason = redactor.redact(&format!("{field}.reason"), &sentence.reason);
+            }
+        }
+    }
+    trace.redaction = redactor.report();
+}
+
+// The trace directory of one vault, applying its `defaults.trace` policy: redaction before
+// every write, encryption when the object store is encrypted, and retention after each write.
+pub struct TraceStore {
+    trace_dir: PathBuf,
+    redaction: VaultTraceRedactionDefaults,
+    retention: VaultTraceRetentionDefaults,
+    prune_on_write: bool,
+    encryption: Option<ObjectStoreEncryptionContext>,
+}
+
+impl TraceStore {
+    pub fn for_vault(
+        vault_path: &Path,
+        vault: &VaultJsonV3,
+        trace_dir_name: &str,
+    ) -> AppResult<Self> {
+        let policy = vault.defaults.trace.clone().unwrap_or_default();
+        // Surfaces bad rules before anything is written.
+        TraceRedactor::from_config(&policy.redaction)?;
+        let passphrase = std::env::var(TRACE_PASSPHRASE_ENV).ok();
+        Ok(Self {
+            trace_dir: vault_path.join(trace_dir_name),
+            redaction: policy.redaction,
+            retention: policy.retention,
+            prune_on_write: true,
+            encryption: vault.object_store_encryption_context(passphrase.as_deref())?,
+        })
+    }
+
+    pub fn open(vault_path: &Path, trace_dir_name: &str) -> AppResult<Self> {
+        let vault = vault_open(vault_path)?;
+        Self::for_vault(vault_path, &vault, trace_dir_name)
+    }
+
+    // Writes through this store never prune, e.g. for replays that are diffed against a
+    // recorded trace. `prune` itself still applies the vault's retention.
+    pub fn without_retention(self) -> Self {
+        Self {
+            prune_on_write: false,
+            ..self
+        }
+    }
+
+    pub fn trace_dir(&self) -> &Path {
+        &self.trace_dir
+    }
+
+    pub fn write(
+        &self,
+        mut trace: TraceLogV1,
+        citations: &[(i64, Vec<LocatorV1>)],
+    ) -> AppResult<PathBuf> {
+        let mut redactor = TraceRedactor::from_config(&self.redaction)?;
+        redact_trace(&mut redactor, &mut trace);
+        let bytes = pretty_json(&trace_log_value(&trace, citations)?)?;
+        let path = match &self.encryption {
+            Some(enc) => write_trace_bytes(
+                &self.trace_dir,
+                &format!("{}{ENCRYPTED_SUFFIX}", trace.trace_id),
+                &enc.seal(&format!("trace:{}", trace.trace_id), &bytes)?,
+                trace.ts_ms,
+            )?,
+            None => write_trace_bytes(
+                &self.trace_dir,
+                &format!("{}{PLAIN_SUFFIX}", trace.trace_id),
+                &bytes,
+                trace.ts_ms,
+            )?,
+        };
+        if self.prune_on_write
+            && (self.retention.max_age_days.is_some() || self.retention.max_count.is_some())
+        {
+            self.prune(trace.ts_ms)?;
+        }
+        Ok(path)
+    }
+
+    fn read(&self, trace_id: &str, path: &Path) -> AppResult<TraceLogV1> {
+        let raw = fs::read(path).map_err(|e| {
+            trace_error(
+                "KC_TRACE_READ_FAILED",
+                "failed to read trace log",
+                serde_json::json!({ "error": e.to_string(), "path": path }),
+            )
+        })?;
+        let bytes = if is_encrypted_payload(&raw) {
+            let enc = self.encryption.as_ref().ok_or_else(|| {
+                AppError::new(
+                    "KC_ENCRYPTION_REQUIRED",
+                    "encryption",
+                    "encrypted trace log requires encryption context",
+                    false,
+                    serde_json::json!({ "trace_id": trace_id, "env": TRACE_PASSPHRASE_ENV }),
+                )
+            })?;
+            enc.open(&format!("trace:{trace_id}"), &raw)?
+        } else {
+            raw
+        };
+        serde_json::from_slice(&bytes).map_err(|e| {
+            trace_error(
+                "KC_TRACE_READ_FAILED",
+                "trace log is not valid trace JSON",
+                serde_json::json!({ "error": e.to_string(), "path": path }),
+            )
+        })
+    }
+
+    // Every trace file's path and trace_id, in directory order, without reading the files.
+    fn trace_files(&self) -> AppResult<Vec<(String, PathBuf)>> {
+        let dir = match fs::read_dir(&self.trace_dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(trace_error(
+                    "KC_TRACE_READ_FAILED",
+                    "failed to list trace directory",
+                    serde_json::json!({ "error": e.to_string(), "path": self.trace_dir }),
+                ))
+            }
+        };
+        let mut out = Vec::new();
+        for entry in dir {
+            let path = entry
+                .map_err(|e| {
+                    trace_error(
+                        "KC_TRACE_READ_FAILED",
+                        "failed to list trace directory",
+                        serde_json::json!({ "error": e.to_string(), "path": self.trace_dir }),
+                    )
+                })?
+                .path();
+            if let Some(trace_id) = trace_file_id(&path) {
+                out.push((trace_id.to_string(), path));
+            }
+        }
+        Ok(out)
+    }
+
+    // Every readable trace file, newest first (ties by trace_id descending), and the paths of
+    // those that are not. A missing encryption context still fails, as it hides every trace.
+    fn entries(&self) -> AppResult<(Vec<(TraceLogV1, PathBuf)>, Vec<String>)> {
+        let mut out = Vec::new();
+        let mut skipped = Vec::new();
+        for (trace_id, path) in self.trace_files()? {
+            match self.read(&trace_id, &path) {
+                Ok(trace) => out.push((trace, path)),
+                Err(error) if error.code == "KC_ENCRYPTION_REQUIRED" => return Err(error),
+                Err(_) => skipped.push(path.display().to_string()),
+            }
+        }
+        out.sort_by(|a, b| {
+            b.0.ts_ms
+                .cmp(&a.0.ts_ms)
+                .then_with(|| b.0.trace_id.cmp(&a.0.trace_id))
+        });
+        skipped.sort();
+        Ok((out, skipped))
+    }
+
+    pub fn list(&self) -> AppResult<TraceListV1> {
+        let (entries, skipped) = self.entries()?;
+        let traces = entries
+            .into_iter()
+            .map(|(trace, path)| TraceSummaryV1 {
+                session_id: trace.session.as_ref().map(|s| s.session_id.clone()),
+                turn_index: trace.session.as_ref().map(|s| s.turn_index),
+                supported: trace.verification.as_ref().map(|v| v.supported),
+                redacted: trace.redaction.redacted_any(),
+                encrypted: path.to_string_lossy().ends_with(ENCRYPTED_SUFFIX),
+                path: path.display().to_string(),
+                trace_id: trace.trace_id,
+                ts_ms: trace.ts_ms,
+                question: trace.question,
+            })
+            .collect();
+        Ok(TraceListV1 { traces, skipped })
+    }
+
+    // Reads a trace file from anywhere, e.g. one kept as a regression fixture. An encrypted
+    // trace must keep its `<trace_id>.json.kce` name, which its key is bound to.
+    pub fn read_file(&self, path: &Path) -> AppResult<TraceLogV1> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let trace_id = name
+            .strip_suffix(ENCRYPTED_SUFFIX)
+            .or_else(|| name.strip_suffix(PLAIN_SUFFIX))
+            .unwrap_or(name);
+        self.read(trace_id, path)
+    }
+
+    pub fn get(&self, trace_id: &str) -> AppResult<TraceLogV1> {
+        let not_found = || {
+            trace_error(
+                "KC_TRACE_NOT_FOUND",
+                "trace log not found",
+                serde_json::json!({ "trace_id": trace_id }),
+            )
+        };
+        if uuid::Uuid::parse_str(trace_id).is_err() {
+            return Err(not_found());
+        }
+        for suffix in [ENCRYPTED_SUFFIX, PLAIN_SUFFIX] {
+            let path = self.trace_dir.join(format!("{trace_id}{suffix}"));
+            if path.exists() {
+                return self.read(trace_id, &path);
+            }
+        }
+        Err(not_found())
+    }
+
+    // Deletes traces older than `max_age_days` before `now_ms`, then all but the newest
+    // `max_count`. Age comes from each file's mtime, stamped with `ts_ms` at write, so no trace
+    // is decrypted or parsed; files whose metadata cannot be read are skipped and reported.
+    pub fn prune(&self, now_ms: i64) -> AppResult<TracePruneReportV1> {
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for (trace_id, path) in self.trace_files()? {
+            match modified_ms(&path) {
+                Ok(ts_ms) => files.push((ts_ms, trace_id, path)),
+                Err(_) => skipped.push(path.display().to_string()),
+            }
+        }
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.cmp(&a.1)));
+        let max_age_ms = self
+            .retention
+            .max_age_days
+            .map(|days| (days as i64).saturating_mul(DAY_MS));
+        let mut removed = Vec::new();
+        let mut kept = 0usize;
+        for (ts_ms, trace_id, path) in files {
+            let expired = max_age_ms.is_some_and(|max| now_ms.saturating_sub(ts_ms) > max);
+            let over_count = self.retention.max_count.is_some_and(|max| kept >= max);
+            if !expired && !over_count {
+                kept += 1;
+                continue;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => removed.push(trace_id),
+                Err(_) => skipped.push(path.display().to_string()),
+            }
+        }
+        skipped.sort();
+        Ok(TracePruneReportV1 {
+            removed,
+            kept,
+            skipped,
+        })
+    }
+}
+
+// The request `trace` was recorded from, to ask again against the vault's current state. A
+// session turn replays its standalone query as a plain ask. Redacted questions and traces
+// without recorded retrieval cannot be replayed.
+pub fn trace_replay_request(
+    vault_path: &Path,
+    trace: &TraceLogV1,
+    now_ms: i64,
+) -> AppResult<AskRequest> {
+    let unavailable = |reason: &str| {
+        trace_error(
+            "KC_TRACE_REPLAY_UNAVAILABLE",
+            "trace cannot be replayed",
+            serde_json::json!({ "trace_id": trace.trace_id, "reason": reason }),
+        )
+    };
+    let vault = vault_open(vault_path)?;
+    if trace.vault_id != vault.vault_id {
+        return Err(unavailable("trace was recorded in a different vault"));
+    }
+    let (question, field) = match &trace.session {
+        Some(session) => (session.standalone_query.clone(), "session.standalone_query"),
+        None => (trace.question.clone(), "question"),
+    };
+    if trace.redaction.fields.iter().any(|f| f == field) {
+        return Err(unavailable("question was redacted"));
+    }
+    let Some(profile) = trace
+        .retrieval
+        .get("profile")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+    else {
+        return Err(unavailable("trace has no recorded retrieval"));
+    };
+    let filter: SearchFilterV1 = match trace.retrieval.get("filter") {
+        Some(filter) => serde_json::from_value(filter.clone())
+            .map_err(|_| unavailable("recorded filter is not a valid search filter"))?,
+        None => SearchFilterV1::default(),
+    };
+    Ok(AskRequest {
+        vault_path: vault_path.to_path_buf(),
+        question,
+        filter,
+        profile: Some(profile.to_string()),
+        min_score: trace.retrieval.get("min_score").and_then(|m| m.as_f64()),
+        now_ms,
+    })
+}
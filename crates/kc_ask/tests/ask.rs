@@ -1,14 +1,24 @@
-use kc_ask::{AskRequest, AskService, RetrievedOnlyAskService};
+use kc_ask::ask::{AskProvider, ProviderAnswer, RetrievedContext};
+use kc_ask::{AskCancelToken, AskRequest, AskService, AskStreamEventV1, RetrievedOnlyAskService};
+use kc_core::app_error::AppResult;
 use kc_core::canonical::persist_canonical_text;
 use kc_core::chunking::{chunk_document, default_chunking_config_v1};
 use kc_core::db::open_db;
+use kc_core::faithfulness::NliModel;
 use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::index_traits::{IndexChunk, VectorCandidate, VectorIndex};
 use kc_core::ingest::{ingest_bytes, IngestBytesReq};
 use kc_core::locator::{LocatorRange, LocatorV1};
 use kc_core::object_store::ObjectStore;
+use kc_core::rerank::{RerankInput, RerankScore, Reranker};
+use kc_core::search::SearchFilterV1;
 use kc_core::services::CanonicalTextArtifact;
-use kc_core::types::{CanonicalHash, DocId};
-use kc_core::vault::vault_init;
+use kc_core::types::{CanonicalHash, ChunkId, DocId};
+use kc_core::vault::{
+    vault_init, vault_open, vault_save, VaultRerankerDefaults, VaultVerifierDefaults,
+};
+use kc_index::open_vault_vector_index;
+use std::sync::Arc;
 
 fn sample_locator(v: i64) -> LocatorV1 {
     LocatorV1 {
@@ -33,6 +43,9 @@ fn ask_missing_citations_hard_fails() {
     let req = AskRequest {
         vault_path: root,
         question: "What happened?".to_string(),
+        filter: SearchFilterV1::default(),
+        profile: None,
+        min_score: None,
         now_ms: 2,
     };
 
@@ -51,6 +64,9 @@ fn ask_invalid_citations_hard_fails() {
     let req = AskRequest {
         vault_path: root,
         question: "What happened?".to_string(),
+        filter: SearchFilterV1::default(),
+        profile: None,
+        min_score: None,
         now_ms: 2,
     };
 
@@ -73,6 +89,9 @@ fn ask_writes_trace_for_valid_citations() {
     let req = AskRequest {
         vault_path: root,
         question: "What happened?".to_string(),
+        filter: SearchFilterV1::default(),
+        profile: None,
+        min_score: None,
         now_ms: 2,
     };
 
@@ -176,12 +195,33 @@ fn ask_runs_retrieved_only_end_to_end() {
         )
         .expect("insert fts row");
     }
+    let index_chunks: Vec<IndexChunk> = chunks
+        .iter()
+        .map(|chunk| IndexChunk {
+            chunk_id: chunk.chunk_id.clone(),
+            doc_id: chunk.doc_id.clone(),
+            ordinal: chunk.ordinal,
+            text: canonical_text_str
+                .chars()
+                .skip(chunk.start_char as usize)
+                .take((chunk.end_char - chunk.start_char) as usize)
+                .collect(),
+        })
+        .collect();
+    open_vault_vector_index(&root)
+        .expect("vector index")
+        .rebuild_for_doc(&ingested.doc_id, &index_chunks)
+        .expect("index vectors");
 
+    // "what" is not in the text, so FTS misses and only the vector index can answer.
     let service = RetrievedOnlyAskService::default();
     let out = service
         .ask(AskRequest {
             vault_path: root.clone(),
             question: "What is the evidence?".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
             now_ms: 2,
         })
         .expect("ask");
@@ -200,6 +240,9 @@ fn ask_runs_retrieved_only_end_to_end() {
         .expect("retrieval chunks");
     assert!(!retrieval_chunks.is_empty());
     assert!(retrieval_chunks[0].get("chunk_id").is_some());
+    assert_eq!(retrieval_chunks[0]["lexical_rank"], serde_json::Value::Null);
+    assert_eq!(retrieval_chunks[0]["vector_rank"], 1);
+    assert_eq!(trace["retrieval"]["candidates"]["lexical"], 0);
 }
 
 #[test]
@@ -236,6 +279,9 @@ fn ask_normalizes_citation_locator_ordering() {
             &AskRequest {
                 vault_path: root,
                 question: "q".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
                 now_ms: 3,
             },
             "answer".to_string(),
@@ -248,3 +294,778 @@ fn ask_normalizes_citation_locator_ordering() {
     assert_eq!(ordered[0].doc_id.0, locator_a.doc_id.0);
     assert_eq!(ordered[1].doc_id.0, locator_b.doc_id.0);
 }
+
+fn index_plain_doc(
+    conn: &rusqlite::Connection,
+    store: &ObjectStore,
+    text: &str,
+    source_path: &str,
+    now_ms: i64,
+) -> DocId {
+    let ingested = ingest_bytes(
+        conn,
+        store,
+        IngestBytesReq {
+            bytes: text.as_bytes(),
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: now_ms,
+            source_path: Some(source_path),
+            now_ms,
+        },
+    )
+    .expect("ingest");
+    let canonical_hash = blake3_hex_prefixed(text.as_bytes());
+    let artifact = CanonicalTextArtifact {
+        doc_id: ingested.doc_id.clone(),
+        canonical_bytes: text.as_bytes().to_vec(),
+        canonical_hash: CanonicalHash(canonical_hash.clone()),
+        canonical_object_hash: kc_core::types::ObjectHash(canonical_hash),
+        extractor_name: "test".to_string(),
+        extractor_version: "1".to_string(),
+        extractor_flags_json: "{}".to_string(),
+        normalization_version: 1,
+        toolchain_json: "{}".to_string(),
+    };
+    persist_canonical_text(conn, store, &artifact, now_ms).expect("persist canonical");
+    let chunks = chunk_document(
+        &ingested.doc_id,
+        text,
+        "text/plain",
+        &default_chunking_config_v1(),
+    )
+    .expect("chunk document");
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
+         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+    )
+    .expect("create fts");
+    for chunk in &chunks {
+        conn.execute(
+            "INSERT INTO chunks(chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                chunk.chunk_id.0,
+                chunk.doc_id.0,
+                chunk.ordinal,
+                chunk.start_char,
+                chunk.end_char,
+                chunk.chunking_config_hash.0,
+                "notes"
+            ],
+        )
+        .expect("insert chunk");
+        conn.execute(
+            "INSERT INTO chunks_fts(chunk_id, doc_id, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![chunk.chunk_id.0, chunk.doc_id.0, text],
+        )
+        .expect("insert fts row");
+    }
+    ingested.doc_id
+}
+
+#[test]
+fn ask_skips_superseded_doc_versions() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+
+    let old = index_plain_doc(
+        &conn,
+        &store,
+        "Budget draft is 10.\n",
+        "/notes/budget.md",
+        1,
+    );
+    let new = index_plain_doc(
+        &conn,
+        &store,
+        "Budget final is 12.\n",
+        "/notes/budget.md",
+        2,
+    );
+
+    let out = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 3,
+        })
+        .expect("ask");
+
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.trace_path).expect("read trace"))
+            .expect("trace json");
+    let doc_ids: Vec<&str> = trace["retrieval"]["chunks"]
+        .as_array()
+        .expect("retrieval chunks")
+        .iter()
+        .map(|c| c["doc_id"].as_str().expect("doc id"))
+        .collect();
+    assert_eq!(doc_ids, vec![new.0.as_str()]);
+    assert!(!doc_ids.contains(&old.0.as_str()));
+    assert_eq!(
+        trace["retrieval"]["diversify"],
+        serde_json::json!({ "collapsed": [], "near_duplicates": [], "capped": [] })
+    );
+    assert_eq!(
+        trace["retrieval"]["chunks"][0]["collapsed_chunk_ids"],
+        serde_json::json!([])
+    );
+}
+
+#[test]
+fn ask_restricts_retrieval_to_filtered_docs() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+
+    index_plain_doc(
+        &conn,
+        &store,
+        "Budget for notes is 10.\n",
+        "/notes/budget.md",
+        1,
+    );
+    let archived = index_plain_doc(
+        &conn,
+        &store,
+        "Budget for archive is 12.\n",
+        "/archive/budget.md",
+        2,
+    );
+
+    let filter = SearchFilterV1 {
+        source_path_prefix: Some("/archive/".to_string()),
+        ..SearchFilterV1::default()
+    };
+    let out = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: filter.clone(),
+            profile: None,
+            min_score: None,
+            now_ms: 3,
+        })
+        .expect("ask");
+
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.trace_path).expect("read trace"))
+            .expect("trace json");
+    let doc_ids: Vec<&str> = trace["retrieval"]["chunks"]
+        .as_array()
+        .expect("retrieval chunks")
+        .iter()
+        .map(|c| c["doc_id"].as_str().expect("doc id"))
+        .collect();
+    assert_eq!(doc_ids, vec![archived.0.as_str()]);
+    assert_eq!(
+        trace["retrieval"]["filter"]["source_path_prefix"],
+        "/archive/"
+    );
+
+    let err = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root,
+            question: "budget".to_string(),
+            filter: SearchFilterV1 {
+                mimes: vec!["application/pdf".to_string()],
+                ..SearchFilterV1::default()
+            },
+            profile: None,
+            min_score: None,
+            now_ms: 4,
+        })
+        .expect_err("no docs match the filter");
+    assert_eq!(err.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+    assert!(!err.retryable);
+}
+
+struct FixedVectorIndex(Vec<ChunkId>);
+
+impl VectorIndex for FixedVectorIndex {
+    fn rebuild_for_doc(&self, _doc_id: &DocId, _chunks: &[IndexChunk]) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn delete_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn query(&self, _query: &str, limit: usize) -> AppResult<Vec<VectorCandidate>> {
+        Ok(self
+            .0
+            .iter()
+            .take(limit)
+            .enumerate()
+            .map(|(idx, chunk_id)| VectorCandidate {
+                chunk_id: chunk_id.clone(),
+                rank: idx as i64 + 1,
+            })
+            .collect())
+    }
+
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        _doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>> {
+        self.query(query, limit)
+    }
+}
+
+fn doc_chunk_ids(conn: &rusqlite::Connection, doc_id: &DocId) -> Vec<ChunkId> {
+    let mut stmt = conn
+        .prepare("SELECT chunk_id FROM chunks WHERE doc_id=?1 ORDER BY ordinal")
+        .expect("prepare chunks");
+    stmt.query_map([doc_id.0.as_str()], |row| row.get::<_, String>(0))
+        .expect("query chunks")
+        .map(|row| ChunkId(row.expect("chunk id")))
+        .collect()
+}
+
+#[test]
+fn ask_returns_no_relevant_context_instead_of_recent_chunks() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget is 10.\n", "/notes/budget.md", 1);
+
+    let mut events = Vec::new();
+    let err = RetrievedOnlyAskService::default()
+        .ask_stream(
+            AskRequest {
+                vault_path: root.clone(),
+                question: "quarterly roadmap".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
+                now_ms: 2,
+            },
+            &AskCancelToken::default(),
+            &mut |event| {
+                events.push(event);
+                Ok(())
+            },
+        )
+        .expect_err("nothing matches the question");
+    assert_eq!(err.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+    assert_eq!(err.details["lexical_candidates"], 0);
+    assert_eq!(err.details["vector_candidates"], 0);
+    assert!(events.is_empty());
+    assert!(!root.join("trace").exists());
+}
+
+#[test]
+fn ask_merges_vector_hits_and_applies_min_score() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    let budget = index_plain_doc(&conn, &store, "Budget is 10.\n", "/notes/budget.md", 1);
+    let travel = index_plain_doc(&conn, &store, "Travel costs 4.\n", "/notes/travel.md", 1);
+    let mut vector_hits = doc_chunk_ids(&conn, &travel);
+    vector_hits.push(ChunkId("blake3:missing".to_string()));
+    vector_hits.extend(doc_chunk_ids(&conn, &budget));
+    let service = RetrievedOnlyAskService {
+        vector: Some(Arc::new(FixedVectorIndex(vector_hits))),
+        ..RetrievedOnlyAskService::default()
+    };
+    let ask = |min_score: Option<f64>| {
+        service.ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score,
+            now_ms: 2,
+        })
+    };
+
+    let out = ask(None).expect("ask");
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.trace_path).expect("read trace"))
+            .expect("trace json");
+    let chunks = trace["retrieval"]["chunks"].as_array().expect("chunks");
+    let doc_ids: Vec<&str> = chunks
+        .iter()
+        .map(|c| c["doc_id"].as_str().expect("doc id"))
+        .collect();
+    assert_eq!(doc_ids, vec![budget.0.as_str(), travel.0.as_str()]);
+    assert_eq!(chunks[0]["lexical_rank"], 1);
+    assert_eq!(chunks[0]["vector_rank"], 3);
+    assert_eq!(chunks[1]["lexical_rank"], serde_json::Value::Null);
+    assert_eq!(chunks[1]["vector_rank"], 1);
+    assert_eq!(
+        trace["retrieval"]["candidates"],
+        serde_json::json!({ "lexical": 1, "vector": 2, "below_min_score": 0 })
+    );
+
+    let top = chunks[0]["final_score"].as_f64().expect("score");
+    let out = ask(Some(top)).expect("ask above threshold");
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(out.trace_path).expect("read trace"))
+            .expect("trace json");
+    assert_eq!(trace["retrieval"]["min_score"], top);
+    assert_eq!(trace["retrieval"]["candidates"]["below_min_score"], 1);
+    assert_eq!(
+        trace["retrieval"]["chunks"]
+            .as_array()
+            .expect("chunks")
+            .len(),
+        1
+    );
+
+    let err = ask(Some(top * 2.0)).expect_err("every hit is below the threshold");
+    assert_eq!(err.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+    assert_eq!(err.details["below_min_score"], 2);
+
+    let err = ask(Some(-1.0)).expect_err("negative threshold");
+    assert_eq!(err.code, "KC_ASK_MIN_SCORE_INVALID");
+}
+
+#[test]
+fn ask_rejects_invalid_query_syntax_instead_of_falling_back() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget is 10.\n", "/notes/budget.md", 1);
+
+    let err = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget (2024".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 2,
+        })
+        .expect_err("unbalanced parenthesis");
+    assert_eq!(err.code, "KC_QUERY_INVALID");
+    assert_eq!(err.details["position"], 7);
+
+    let out = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root,
+            question: "budget-line OR \"budget is\"".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 2,
+        })
+        .expect("punctuation is matched literally");
+    assert!(out.answer_text.contains("Budget is 10."));
+}
+
+struct PreferReranker(&'static str);
+
+impl Reranker for PreferReranker {
+    fn reranker_id(&self) -> String {
+        format!("prefer:{}", self.0)
+    }
+
+    fn score(
+        &self,
+        _query: &str,
+        candidates: &[RerankInput],
+    ) -> kc_core::app_error::AppResult<Vec<RerankScore>> {
+        Ok(candidates
+            .iter()
+            .map(|c| {
+                let hit = c.text.contains(self.0);
+                RerankScore {
+                    score: if hit { 1.0 } else { 0.0 },
+                    reason: format!("contains {}: {hit}", self.0),
+                }
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn ask_reranks_merged_hits_and_records_scores_in_trace() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget for notes is 10.\n", "/notes/a.md", 1);
+    index_plain_doc(
+        &conn,
+        &store,
+        "Budget for archive is 12.\n",
+        "/notes/b.md",
+        1,
+    );
+
+    let trace_chunks = |trace: &serde_json::Value| -> Vec<String> {
+        trace["retrieval"]["chunks"]
+            .as_array()
+            .expect("retrieval chunks")
+            .iter()
+            .map(|c| c["snippet"].as_str().expect("snippet").to_string())
+            .collect()
+    };
+    let read_trace = |path: &std::path::Path| -> serde_json::Value {
+        serde_json::from_slice(&std::fs::read(path).expect("read trace")).expect("trace json")
+    };
+
+    let plain = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 3,
+        })
+        .expect("ask");
+    let plain_trace = read_trace(&plain.trace_path);
+    assert!(plain_trace["retrieval"].get("rerank").is_none());
+    let merged = trace_chunks(&plain_trace);
+    assert_eq!(merged.len(), 2);
+    let last = merged[1].clone();
+    let needle = if last.contains("archive") {
+        "archive"
+    } else {
+        "notes"
+    };
+
+    let service = RetrievedOnlyAskService {
+        reranker: Some(Arc::new(PreferReranker(needle))),
+        ..RetrievedOnlyAskService::default()
+    };
+    let out = service
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 3,
+        })
+        .expect("reranked ask");
+    let trace = read_trace(&out.trace_path);
+    let reranked = trace_chunks(&trace);
+    assert_eq!(reranked[0], last);
+    assert!(out.answer_text.contains(needle));
+    let rerank = &trace["retrieval"]["rerank"];
+    assert_eq!(rerank["reranker"], format!("prefer:{needle}"));
+    assert_eq!(rerank["top_k"], 20);
+    assert_eq!(rerank["query"], "budget");
+    assert_eq!(rerank["candidates"][0]["merged_rank"], 2);
+    assert_eq!(rerank["candidates"][0]["score"], 1.0);
+    assert_eq!(
+        rerank["candidates"][0]["reason"],
+        format!("contains {needle}: true")
+    );
+    assert_eq!(rerank["candidates"][1]["merged_rank"], 1);
+
+    let mut vault = vault_open(&root).expect("vault open");
+    vault.defaults.reranker = Some(VaultRerankerDefaults {
+        backend: "lexical_overlap".to_string(),
+        path: None,
+        top_k: 1,
+    });
+    vault_save(&root, &vault).expect("vault save");
+    let configured = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root,
+            question: "budget archive".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 3,
+        })
+        .expect("vault reranker");
+    let trace = read_trace(&configured.trace_path);
+    assert_eq!(trace["retrieval"]["rerank"]["reranker"], "lexical_overlap");
+    let candidates = trace["retrieval"]["rerank"]["candidates"]
+        .as_array()
+        .expect("candidates");
+    assert_eq!(candidates.len(), 1);
+    assert!(candidates[0]["reason"]
+        .as_str()
+        .expect("reason")
+        .starts_with("matched "));
+}
+
+#[test]
+fn ask_records_selected_retrieval_profile_in_trace() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget for notes is 10.\n", "/notes/a.md", 1);
+
+    let mut vault = vault_open(&root).expect("vault open");
+    let mut quiet = kc_core::retrieval::retrieval_config_for_vault(&vault);
+    quiet.source_priors.insert("notes".to_string(), 0.95);
+    vault
+        .defaults
+        .retrieval_profiles
+        .insert("quiet_notes".to_string(), quiet);
+    vault_save(&root, &vault).expect("vault save");
+
+    let ask = |profile: Option<&str>| {
+        RetrievedOnlyAskService::default().ask(AskRequest {
+            vault_path: root.clone(),
+            question: "budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: profile.map(str::to_string),
+            min_score: None,
+            now_ms: 3,
+        })
+    };
+    let read_trace = |path: &std::path::Path| -> serde_json::Value {
+        serde_json::from_slice(&std::fs::read(path).expect("read trace")).expect("trace json")
+    };
+
+    let default = read_trace(&ask(None).expect("default ask").trace_path);
+    assert_eq!(default["retrieval"]["profile"]["name"], "default");
+    let selected = read_trace(&ask(Some("quiet_notes")).expect("profile ask").trace_path);
+    let profile = &selected["retrieval"]["profile"];
+    assert_eq!(profile["name"], "quiet_notes");
+    assert_eq!(profile["config"]["source_priors"]["notes"], 0.95);
+    assert!(profile["config_hash"]
+        .as_str()
+        .expect("config hash")
+        .starts_with("blake3:"));
+    assert_ne!(
+        profile["config_hash"],
+        default["retrieval"]["profile"]["config_hash"]
+    );
+
+    let err = ask(Some("missing")).expect_err("unknown profile");
+    assert_eq!(err.code, "KC_RETRIEVAL_PROFILE_NOT_FOUND");
+}
+
+// Streams a two-paragraph answer in pieces that split the blank line between paragraphs.
+struct PiecewiseProvider;
+
+impl AskProvider for PiecewiseProvider {
+    fn answer(&self, _question: &str, contexts: &[RetrievedContext]) -> AppResult<ProviderAnswer> {
+        Ok(ProviderAnswer {
+            answer_text: "Budget is 10 [1].\n\nNo other figures [1].".to_string(),
+            citations: vec![
+                (0, vec![contexts[0].locator.clone()]),
+                (1, vec![contexts[0].locator.clone()]),
+            ],
+            model: serde_json::json!({ "mode": "piecewise" }),
+        })
+    }
+
+    fn answer_streaming(
+        &self,
+        question: &str,
+        contexts: &[RetrievedContext],
+        on_delta: &mut dyn FnMut(&str) -> AppResult<()>,
+    ) -> AppResult<ProviderAnswer> {
+        for piece in ["Budget is 10 [1].\n", "\nNo other", " figures [1]."] {
+            on_delta(piece)?;
+        }
+        self.answer(question, contexts)
+    }
+}
+
+#[test]
+fn ask_stream_emits_retrieval_deltas_paragraphs_then_final_after_trace() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget for notes is 10.\n", "/notes/a.md", 1);
+
+    let service = RetrievedOnlyAskService {
+        provider: Some(Arc::new(PiecewiseProvider)),
+        ..RetrievedOnlyAskService::default()
+    };
+    let mut events = Vec::new();
+    let res = service
+        .ask_stream(
+            AskRequest {
+                vault_path: root.clone(),
+                question: "budget".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
+                now_ms: 3,
+            },
+            &AskCancelToken::default(),
+            &mut |event| {
+                if let AskStreamEventV1::Final { trace_path, .. } = &event {
+                    assert!(std::path::Path::new(trace_path).exists());
+                }
+                events.push(serde_json::to_value(&event).expect("event json"));
+                Ok(())
+            },
+        )
+        .expect("ask stream");
+
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().expect("event kind"))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "retrieval",
+            "delta",
+            "delta",
+            "paragraph",
+            "delta",
+            "paragraph",
+            "final"
+        ]
+    );
+    assert_eq!(events[0]["profile"], "default");
+    assert_eq!(events[0]["chunks"].as_array().expect("chunks").len(), 1);
+    assert_eq!(events[3]["paragraph_index"], 0);
+    assert_eq!(events[3]["text"], "Budget is 10 [1].");
+    assert_eq!(events[5]["paragraph_index"], 1);
+    assert_eq!(events[5]["text"], "No other figures [1].");
+    let last = &events[6];
+    assert_eq!(last["answer_text"], res.answer_text.as_str());
+    assert_eq!(last["citations"].as_array().expect("citations").len(), 2);
+    assert_eq!(last["trace_path"], res.trace_path.display().to_string());
+    assert_eq!(last["verification"]["supported"], false);
+
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&res.trace_path).expect("read trace"))
+            .expect("trace json");
+    assert_eq!(trace["model"]["mode"], "piecewise");
+}
+
+struct ConstantNli(f64);
+
+impl NliModel for ConstantNli {
+    fn model_id(&self) -> String {
+        "test/constant".to_string()
+    }
+
+    fn entailment(&self, _premise: &str, hypotheses: &[&str]) -> AppResult<Vec<f64>> {
+        Ok(vec![self.0; hypotheses.len()])
+    }
+}
+
+#[test]
+fn ask_verifies_each_paragraph_against_its_cited_spans() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget for notes is 10.\n", "/notes/a.md", 1);
+    let req = AskRequest {
+        vault_path: root.clone(),
+        question: "budget".to_string(),
+        filter: SearchFilterV1::default(),
+        profile: None,
+        min_score: None,
+        now_ms: 3,
+    };
+
+    let out = RetrievedOnlyAskService {
+        provider: Some(Arc::new(PiecewiseProvider)),
+        ..RetrievedOnlyAskService::default()
+    }
+    .ask(req.clone())
+    .expect("ask");
+    let verification = out.verification.clone().expect("verification");
+    assert_eq!(verification.verifier, "lexical_entailment");
+    assert_eq!(verification.threshold, 0.5);
+    assert!(!verification.supported);
+    assert!(verification.paragraphs[0].supported);
+    assert_eq!(verification.paragraphs[0].score, 1.0);
+    let flagged = &verification.paragraphs[1].unsupported_sentences;
+    assert_eq!(flagged.len(), 1);
+    assert_eq!(flagged[0].text, "No other figures.");
+    assert_eq!(flagged[0].score, 0.0);
+    assert_eq!(
+        flagged[0].reason,
+        "matched 0/1 terms; missing [figure]; negation not in evidence"
+    );
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&out.trace_path).expect("read trace"))
+            .expect("trace json");
+    assert_eq!(
+        trace["verification"],
+        serde_json::to_value(&verification).expect("verification json")
+    );
+
+    let mut vault = vault_open(&root).expect("vault open");
+    vault.defaults.verifier = Some(VaultVerifierDefaults {
+        threshold: 0.6,
+        alignment: None,
+    });
+    vault_save(&root, &vault).expect("vault save");
+    let out = RetrievedOnlyAskService {
+        provider: Some(Arc::new(PiecewiseProvider)),
+        nli: Some(Arc::new(ConstantNli(1.0))),
+        ..RetrievedOnlyAskService::default()
+    }
+    .ask(req)
+    .expect("ask with nli");
+    let verification = out.verification.expect("verification");
+    assert_eq!(
+        verification.verifier,
+        "lexical_entailment+nli:test/constant"
+    );
+    assert_eq!(verification.threshold, 0.6);
+    let flagged = &verification.paragraphs[1].unsupported_sentences;
+    assert_eq!(flagged[0].score, 0.5);
+    assert_eq!(flagged[0].lexical_score, 0.0);
+    assert_eq!(flagged[0].nli_score, Some(1.0));
+    assert_eq!(flagged[0].alignment_score, None);
+}
+
+#[test]
+fn ask_stream_cancellation_stops_before_trace_write() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget for notes is 10.\n", "/notes/a.md", 1);
+    let req = AskRequest {
+        vault_path: root.clone(),
+        question: "budget".to_string(),
+        filter: SearchFilterV1::default(),
+        profile: None,
+        min_score: None,
+        now_ms: 3,
+    };
+    let service = RetrievedOnlyAskService {
+        provider: Some(Arc::new(PiecewiseProvider)),
+        ..RetrievedOnlyAskService::default()
+    };
+
+    let cancel = AskCancelToken::default();
+    let mut kinds = Vec::new();
+    let err = service
+        .ask_stream(req.clone(), &cancel, &mut |event| {
+            if matches!(event, AskStreamEventV1::Delta { .. }) {
+                cancel.cancel();
+            }
+            kinds.push(serde_json::to_value(&event).expect("event json")["event"].clone());
+            Ok(())
+        })
+        .expect_err("cancelled mid-answer");
+    assert_eq!(err.code, "KC_ASK_CANCELLED");
+    assert_eq!(err.details["stage"], "generation");
+    assert_eq!(kinds, vec!["retrieval", "delta"]);
+
+    let cancelled = AskCancelToken::default();
+    cancelled.cancel();
+    let err = service
+        .ask_stream(req, &cancelled, &mut |_| Ok(()))
+        .expect_err("cancelled up front");
+    assert_eq!(err.details["stage"], "retrieval");
+    assert!(!root.join("trace").exists());
+}
@@ -0,0 +1,515 @@
+use kc_ask::ask::{AskProvider, RetrievedContext};
+use kc_ask::http_provider::{
+    parse_cited_answer, HttpAskBackend, HttpAskProvider, HttpAskProviderConfig,
+};
+use kc_ask::{AskRequest, AskService, RetrievedOnlyAskService};
+use kc_core::canonical::persist_canonical_text;
+use kc_core::chunking::{chunk_document, default_chunking_config_v1};
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::locator::{LocatorRange, LocatorV1};
+use kc_core::object_store::ObjectStore;
+use kc_core::search::SearchFilterV1;
+use kc_core::services::CanonicalTextArtifact;
+use kc_core::types::{CanonicalHash, ChunkId, DocId};
+use kc_core::vault::{vault_init, vault_open, vault_save, VaultAskProviderDefaults};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+struct CapturedRequest {
+    request_line: String,
+    headers: Vec<String>,
+    body: serde_json::Value,
+}
+
+fn json_response(status: u16, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status} Stand-in\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+// Server-sent events, one transfer-encoding chunk per event.
+fn event_stream_response(events: &[serde_json::Value], done: bool) -> String {
+    let mut out = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n".to_string();
+    let mut lines: Vec<String> = events.iter().map(|e| format!("data: {e}\n\n")).collect();
+    if done {
+        lines.push("data: [DONE]\n\n".to_string());
+    }
+    for line in lines {
+        out.push_str(&format!("{:x}\r\n{line}\r\n", line.len()));
+    }
+    out.push_str("0\r\n\r\n");
+    out
+}
+
+fn stand_in_server(
+    responses: Vec<(u16, String)>,
+) -> (
+    String,
+    mpsc::Receiver<CapturedRequest>,
+    thread::JoinHandle<()>,
+) {
+    raw_stand_in_server(
+        responses
+            .into_iter()
+            .map(|(status, body)| json_response(status, &body))
+            .collect(),
+    )
+}
+
+// Serves one canned response per accepted connection and reports what each request carried.
+fn raw_stand_in_server(
+    responses: Vec<String>,
+) -> (
+    String,
+    mpsc::Receiver<CapturedRequest>,
+    thread::JoinHandle<()>,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind stand-in server");
+    let endpoint = format!("http://{}", listener.local_addr().expect("local addr"));
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        for response in responses {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).expect("request line");
+            let mut headers = Vec::new();
+            let mut content_length = 0usize;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("header line");
+                let line = line.trim_end().to_string();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().expect("content length");
+                }
+                headers.push(line);
+            }
+            let mut payload = vec![0u8; content_length];
+            reader.read_exact(&mut payload).expect("request body");
+            tx.send(CapturedRequest {
+                request_line: request_line.trim_end().to_string(),
+                headers,
+                body: serde_json::from_slice(&payload).expect("request json"),
+            })
+            .expect("send captured request");
+            let mut stream = stream;
+            stream
+                .write_all(response.as_bytes())
+                .expect("write response");
+        }
+    });
+    (endpoint, rx, handle)
+}
+
+fn context(idx: i64) -> RetrievedContext {
+    RetrievedContext {
+        chunk_id: ChunkId(format!("chunk-{idx}")),
+        ordinal: idx,
+        final_score: 1.0,
+        lexical_rank: Some(idx + 1),
+        vector_rank: None,
+        locator: LocatorV1 {
+            v: 1,
+            doc_id: DocId(format!("blake3:doc{idx}")),
+            canonical_hash: CanonicalHash(format!("blake3:canon{idx}")),
+            range: LocatorRange {
+                start: idx * 10,
+                end: idx * 10 + 5,
+            },
+            hints: None,
+        },
+        snippet: format!("snippet number {idx}"),
+        collapsed: Vec::new(),
+    }
+}
+
+fn provider(backend: HttpAskBackend, endpoint: &str) -> HttpAskProvider {
+    HttpAskProvider::new(HttpAskProviderConfig {
+        backend,
+        endpoint: endpoint.to_string(),
+        model: "local-model".to_string(),
+        temperature: 0.2,
+        max_tokens: 128,
+        timeout_ms: 5_000,
+        api_key: Some("secret-token".to_string()),
+    })
+    .expect("provider config")
+}
+
+#[test]
+fn openai_chat_provider_maps_paragraph_markers_to_locators() {
+    let completion = serde_json::json!({
+        "model": "served-model-q4",
+        "choices": [{ "message": { "role": "assistant",
+            "content": "Budgets rose in March [1][2].\n\nTravel was cut [2, 3]." } }],
+    })
+    .to_string();
+    let (endpoint, requests, server) = stand_in_server(vec![(200, completion)]);
+    let contexts = vec![context(1), context(2), context(3)];
+
+    let answer = provider(HttpAskBackend::OpenAiChat, &endpoint)
+        .answer("What changed?", &contexts)
+        .expect("answer");
+    server.join().expect("server thread");
+
+    assert_eq!(
+        answer.answer_text,
+        "Budgets rose in March [1][2].\n\nTravel was cut [2, 3]."
+    );
+    let cited: Vec<(i64, Vec<i64>)> = answer
+        .citations
+        .iter()
+        .map(|(p, locs)| (*p, locs.iter().map(|l| l.range.start).collect()))
+        .collect();
+    assert_eq!(cited, vec![(0, vec![10, 20]), (1, vec![20, 30])]);
+    assert_eq!(answer.model["mode"], "http");
+    assert_eq!(answer.model["backend"], "openai_chat");
+    assert_eq!(answer.model["model_id"], "served-model-q4");
+    assert_eq!(answer.model["requested_model"], "local-model");
+    assert_eq!(answer.model["parameters"]["max_tokens"], 128);
+
+    let request = requests.recv().expect("captured request");
+    assert_eq!(request.request_line, "POST /v1/chat/completions HTTP/1.1");
+    assert!(request
+        .headers
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case("authorization: Bearer secret-token")));
+    assert_eq!(request.body["model"], "local-model");
+    assert_eq!(request.body["max_tokens"], 128);
+    let user = request.body["messages"][1]["content"]
+        .as_str()
+        .expect("user message");
+    assert!(user.contains("[1] snippet number 1"));
+    assert!(user.contains("[3] snippet number 3"));
+    assert!(user.ends_with("Question: What changed?"));
+}
+
+#[test]
+fn llama_cpp_provider_posts_completion_prompt() {
+    let completion = serde_json::json!({ "content": "Only one source applies [1]." }).to_string();
+    let (endpoint, requests, server) = stand_in_server(vec![(200, completion)]);
+
+    let answer = provider(HttpAskBackend::LlamaCpp, &format!("{endpoint}/llm/"))
+        .answer("Which?", &[context(4)])
+        .expect("answer");
+    server.join().expect("server thread");
+
+    assert_eq!(answer.citations.len(), 1);
+    assert_eq!(answer.citations[0].1[0].range.start, 40);
+    assert_eq!(answer.model["model_id"], "local-model");
+    let request = requests.recv().expect("captured request");
+    assert_eq!(request.request_line, "POST /llm/completion HTTP/1.1");
+    assert_eq!(request.body["n_predict"], 128);
+    assert!(request.body["prompt"]
+        .as_str()
+        .expect("prompt")
+        .contains("[1] snippet number 4"));
+}
+
+#[test]
+fn streaming_providers_forward_event_stream_deltas() {
+    let openai = event_stream_response(
+        &[
+            serde_json::json!({ "model": "served-stream", "choices": [{ "delta": { "role": "assistant" } }] }),
+            serde_json::json!({ "choices": [{ "delta": { "content": "Budget is " } }] }),
+            serde_json::json!({ "choices": [{ "delta": { "content": "10 [1]." } }] }),
+        ],
+        true,
+    );
+    let llama = event_stream_response(
+        &[
+            serde_json::json!({ "content": "Ten [1].", "stop": false }),
+            serde_json::json!({ "content": "", "stop": true }),
+        ],
+        false,
+    );
+    let (endpoint, requests, server) = raw_stand_in_server(vec![
+        openai,
+        llama,
+        json_response(200, r#"{"content":"Whole answer [1]."}"#),
+    ]);
+    let contexts = vec![context(1)];
+
+    let mut deltas = Vec::new();
+    let answer = provider(HttpAskBackend::OpenAiChat, &endpoint)
+        .answer_streaming("budget?", &contexts, &mut |delta| {
+            deltas.push(delta.to_string());
+            Ok(())
+        })
+        .expect("openai stream");
+    assert_eq!(deltas, vec!["Budget is ", "10 [1]."]);
+    assert_eq!(answer.answer_text, "Budget is 10 [1].");
+    assert_eq!(answer.model["model_id"], "served-stream");
+    let request = requests.recv().expect("openai request");
+    assert_eq!(request.body["stream"], true);
+    assert!(request
+        .headers
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case("accept: text/event-stream")));
+
+    let mut deltas = Vec::new();
+    let answer = provider(HttpAskBackend::LlamaCpp, &endpoint)
+        .answer_streaming("budget?", &contexts, &mut |delta| {
+            deltas.push(delta.to_string());
+            Ok(())
+        })
+        .expect("llama stream");
+    assert_eq!(deltas, vec!["Ten [1]."]);
+    assert_eq!(answer.citations.len(), 1);
+
+    let mut deltas = Vec::new();
+    let answer = provider(HttpAskBackend::LlamaCpp, &endpoint)
+        .answer_streaming("budget?", &contexts, &mut |delta| {
+            deltas.push(delta.to_string());
+            Ok(())
+        })
+        .expect("non-streaming server");
+    server.join().expect("server thread");
+    assert_eq!(deltas, vec!["Whole answer [1]."]);
+    assert_eq!(answer.answer_text, "Whole answer [1].");
+}
+
+#[test]
+fn cited_answer_rejects_missing_and_out_of_range_markers() {
+    let contexts = vec![context(1), context(2)];
+
+    let err = parse_cited_answer("Cited [1].\n\nUncited [see above].", &contexts)
+        .expect_err("missing markers");
+    assert_eq!(err.code, "KC_ASK_MISSING_CITATIONS");
+    assert_eq!(err.details["paragraph_index"], 1);
+
+    let err = parse_cited_answer("Cited [3].", &contexts).expect_err("out of range");
+    assert_eq!(err.code, "KC_ASK_INVALID_CITATIONS");
+    assert_eq!(err.details["marker"], 3);
+
+    let err = parse_cited_answer("Zero [0].", &contexts).expect_err("zero marker");
+    assert_eq!(err.code, "KC_ASK_INVALID_CITATIONS");
+
+    let (_, citations) =
+        parse_cited_answer("Repeated [2] and [2][1].", &contexts).expect("dedupe markers");
+    assert_eq!(citations[0].1.len(), 2);
+    assert_eq!(citations[0].1[0].range.start, 10);
+}
+
+#[test]
+fn provider_reports_error_status_and_unreachable_endpoint() {
+    let (endpoint, _requests, server) =
+        stand_in_server(vec![(500, "{\"error\":\"model not loaded\"}".to_string())]);
+    let err = provider(HttpAskBackend::OpenAiChat, &endpoint)
+        .answer("q", &[context(1)])
+        .expect_err("server error");
+    server.join().expect("server thread");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_RESPONSE_INVALID");
+    assert_eq!(err.details["status"], 500);
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let closed = format!("http://{}", listener.local_addr().expect("addr"));
+    drop(listener);
+    let err = provider(HttpAskBackend::OpenAiChat, &closed)
+        .answer("q", &[context(1)])
+        .expect_err("connection refused");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_UNAVAILABLE");
+    assert!(err.retryable);
+
+    let config = |endpoint: &str, api_key: Option<&str>| HttpAskProviderConfig {
+        backend: HttpAskBackend::OpenAiChat,
+        endpoint: endpoint.to_string(),
+        model: "m".to_string(),
+        temperature: 0.0,
+        max_tokens: 16,
+        timeout_ms: 1_000,
+        api_key: api_key.map(str::to_string),
+    };
+    HttpAskProvider::new(config("https://api.example.com/prefix/", Some("k"))).expect("https");
+    HttpAskProvider::new(config("http://localhost:8080", Some("k"))).expect("loopback");
+    HttpAskProvider::new(config("http://[::1]:8080", Some("k"))).expect("loopback v6");
+    HttpAskProvider::new(config("http://10.0.0.5:8080", None)).expect("no key");
+    let err = HttpAskProvider::new(config("http://10.0.0.5:8080", Some("k")))
+        .expect_err("cleartext key to a remote host");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_INVALID");
+    let err = HttpAskProvider::new(config("ftp://localhost", None)).expect_err("scheme");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_INVALID");
+    let err = HttpAskBackend::parse("ollama").expect_err("unknown backend");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_INVALID");
+}
+
+#[test]
+fn provider_timeout_bounds_the_whole_streamed_response() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let endpoint = format!("http://{}", listener.local_addr().expect("addr"));
+    // Each event arrives well within the timeout, but the stream never ends.
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+        let mut content_length = 0usize;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("header line");
+            if line.trim_end().is_empty() {
+                break;
+            }
+            if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = len.trim().parse().expect("content length");
+            }
+        }
+        reader
+            .read_exact(&mut vec![0u8; content_length])
+            .expect("request body");
+        let mut stream = stream;
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n";
+        if stream.write_all(head.as_bytes()).is_err() {
+            return;
+        }
+        for _ in 0..40 {
+            let event = "data: {\"content\":\"more \",\"stop\":false}\n\n";
+            let chunk = format!("{:x}\r\n{event}\r\n", event.len());
+            if stream.write_all(chunk.as_bytes()).is_err() {
+                return;
+            }
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+    });
+
+    let provider = HttpAskProvider::new(HttpAskProviderConfig {
+        backend: HttpAskBackend::LlamaCpp,
+        endpoint,
+        model: "local-model".to_string(),
+        temperature: 0.0,
+        max_tokens: 16,
+        timeout_ms: 300,
+        api_key: None,
+    })
+    .expect("provider");
+    let started = std::time::Instant::now();
+    let mut deltas = 0;
+    let err = provider
+        .answer_streaming("q", &[context(1)], &mut |_| {
+            deltas += 1;
+            Ok(())
+        })
+        .expect_err("deadline");
+    assert_eq!(err.code, "KC_ASK_PROVIDER_UNAVAILABLE");
+    assert!(deltas > 0);
+    assert!(started.elapsed() < std::time::Duration::from_millis(1_500));
+    server.join().expect("server thread");
+}
+
+#[test]
+fn ask_uses_vault_provider_and_records_model_in_trace() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    let text = "Budget for travel is 10.\n";
+    let ingested = ingest_bytes(
+        &conn,
+        &store,
+        IngestBytesReq {
+            bytes: text.as_bytes(),
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: 1,
+            source_path: Some("/notes/a.md"),
+            now_ms: 1,
+        },
+    )
+    .expect("ingest");
+    let canonical_hash = blake3_hex_prefixed(text.as_bytes());
+    persist_canonical_text(
+        &conn,
+        &store,
+        &CanonicalTextArtifact {
+            doc_id: ingested.doc_id.clone(),
+            canonical_bytes: text.as_bytes().to_vec(),
+            canonical_hash: CanonicalHash(canonical_hash.clone()),
+            canonical_object_hash: kc_core::types::ObjectHash(canonical_hash),
+            extractor_name: "test".to_string(),
+            extractor_version: "1".to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: "{}".to_string(),
+        },
+        1,
+    )
+    .expect("persist canonical");
+    let chunks = chunk_document(
+        &ingested.doc_id,
+        text,
+        "text/plain",
+        &default_chunking_config_v1(),
+    )
+    .expect("chunk document");
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
+         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+    )
+    .expect("create fts");
+    for chunk in &chunks {
+        conn.execute(
+            "INSERT INTO chunks(chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                chunk.chunk_id.0,
+                chunk.doc_id.0,
+                chunk.ordinal,
+                chunk.start_char,
+                chunk.end_char,
+                chunk.chunking_config_hash.0,
+                "notes"
+            ],
+        )
+        .expect("insert chunk");
+        conn.execute(
+            "INSERT INTO chunks_fts(chunk_id, doc_id, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![chunk.chunk_id.0, chunk.doc_id.0, text],
+        )
+        .expect("insert fts row");
+    }
+
+    let completion = serde_json::json!({
+        "model": "served-model",
+        "choices": [{ "message": { "content": "Travel budget is 10 [1]." } }],
+    })
+    .to_string();
+    let (endpoint, _requests, server) = stand_in_server(vec![(200, completion)]);
+    let mut vault = vault_open(&root).expect("vault open");
+    vault.defaults.ask_provider = Some(VaultAskProviderDefaults {
+        backend: "openai_chat".to_string(),
+        endpoint,
+        model: "local-model".to_string(),
+        temperature: 0.0,
+        max_tokens: 64,
+        timeout_ms: 5_000,
+        api_key_env: None,
+    });
+    vault_save(&root, &vault).expect("vault save");
+
+    let res = RetrievedOnlyAskService::default()
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "travel budget".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 2,
+        })
+        .expect("ask");
+    server.join().expect("server thread");
+
+    assert_eq!(res.answer_text, "Travel budget is 10 [1].");
+    assert_eq!(res.citations[0].1[0].doc_id, ingested.doc_id);
+    let trace: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&res.trace_path).expect("read trace"))
+            .expect("trace json");
+    assert_eq!(trace["model"]["mode"], "http");
+    assert_eq!(trace["model"]["model_id"], "served-model");
+    assert_eq!(trace["model"]["parameters"]["max_tokens"], 64);
+}
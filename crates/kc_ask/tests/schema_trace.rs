@@ -1,6 +1,10 @@
 use jsonschema::validator_for;
+use kc_ask::trace::{write_trace_log, TraceLogV1, TraceSessionV1};
 use kc_ask::{AskRequest, RetrievedOnlyAskService};
+use kc_core::faithfulness::{verify_paragraphs, ParagraphEvidence};
 use kc_core::locator::{LocatorRange, LocatorV1};
+use kc_core::search::SearchFilterV1;
+use kc_core::trace_redaction::TraceRedactionV1;
 use kc_core::types::{CanonicalHash, DocId};
 use kc_core::vault::vault_init;
 
@@ -41,7 +45,63 @@ fn trace_schema() -> serde_json::Value {
         "retrieval": { "type": "object" },
         "model": { "type": "object" },
         "answer": { "type": "object" },
-        "redaction": { "type": "object" }
+        "redaction": {
+          "type": "object",
+          "required": ["enabled"],
+          "properties": {
+            "enabled": { "type": "boolean" },
+            "rules": { "type": "array", "items": { "type": "string" } },
+            "matches": {
+              "type": "object",
+              "additionalProperties": { "type": "integer", "minimum": 0 }
+            },
+            "fields": { "type": "array", "items": { "type": "string" } }
+          },
+          "additionalProperties": false
+        },
+        "session": {
+          "type": "object",
+          "required": ["session_id", "turn_index", "parent_trace_id", "standalone_query", "rewriter"],
+          "properties": {
+            "session_id": { "type": "string" },
+            "turn_index": { "type": "integer", "minimum": 0 },
+            "parent_trace_id": { "type": ["string", "null"] },
+            "standalone_query": { "type": "string" },
+            "rewriter": { "type": "string" }
+          },
+          "additionalProperties": false
+        },
+        "verification": {
+          "type": "object",
+          "required": ["verifier", "threshold", "supported", "paragraphs"],
+          "properties": {
+            "verifier": { "type": "string" },
+            "threshold": { "type": "number", "minimum": 0, "maximum": 1 },
+            "supported": { "type": "boolean" },
+            "paragraphs": {
+              "type": "array",
+              "items": {
+                "type": "object",
+                "required": ["paragraph_index", "score", "supported", "sentences_checked", "unsupported_sentences"],
+                "properties": {
+                  "paragraph_index": { "type": "integer", "minimum": 0 },
+                  "score": { "type": "number" },
+                  "supported": { "type": "boolean" },
+                  "sentences_checked": { "type": "integer", "minimum": 0 },
+                  "unsupported_sentences": {
+                    "type": "array",
+                    "items": {
+                      "type": "object",
+                      "required": ["sentence_index", "text", "score", "lexical_score", "nli_score", "alignment_score", "reason"]
+                    }
+                  }
+                },
+                "additionalProperties": false
+              }
+            }
+          },
+          "additionalProperties": false
+        }
       },
       "additionalProperties": false
     })
@@ -58,6 +118,9 @@ fn schema_trace_accepts_written_trace() {
             &AskRequest {
                 vault_path: root,
                 question: "What happened?".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
                 now_ms: 2,
             },
             "answer".to_string(),
@@ -88,3 +151,84 @@ fn schema_trace_rejects_non_uuid_ids() {
     });
     assert!(!schema.is_valid(&invalid));
 }
+
+#[test]
+fn schema_trace_accepts_session_turn_link() {
+    let root = tempfile::tempdir().expect("tempdir");
+    let trace = TraceLogV1 {
+        schema_version: 1,
+        trace_id: "7d3c3a51-0c57-4c3a-9d4e-0b0f1d7c2a10".to_string(),
+        ts_ms: 2,
+        vault_id: "0f8e2a6c-7d3b-4c1e-8a5f-3b2d1c0e9f87".to_string(),
+        question: "when is it due?".to_string(),
+        retrieval: serde_json::json!({}),
+        model: serde_json::json!({}),
+        answer: serde_json::json!({ "text": "answer" }),
+        redaction: TraceRedactionV1 {
+            enabled: true,
+            ..TraceRedactionV1::default()
+        },
+        session: Some(TraceSessionV1 {
+            session_id: "s1".to_string(),
+            turn_index: 1,
+            parent_trace_id: Some("5b1f0c2e-3a4d-4e6f-8a7b-9c0d1e2f3a4b".to_string()),
+            standalone_query: "due signing keys".to_string(),
+            rewriter: "follow-up-terms-v1".to_string(),
+        }),
+        verification: None,
+    };
+    let path =
+        write_trace_log(root.path(), &trace, &[(0, vec![sample_locator()])]).expect("write trace");
+
+    let mut value: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(path).expect("read trace")).expect("parse trace");
+    let schema = validator_for(&trace_schema()).expect("compile trace schema");
+    assert!(schema.is_valid(&value));
+    value["session"]["turn_index"] = serde_json::json!(-1);
+    assert!(!schema.is_valid(&value));
+}
+
+#[test]
+fn schema_trace_accepts_verification_report() {
+    let root = tempfile::tempdir().expect("tempdir");
+    let verification = verify_paragraphs(
+        &[ParagraphEvidence {
+            paragraph_index: 0,
+            text: "Keys rotate every quarter. The budget is 10 [1].".to_string(),
+            spans: vec!["Signing keys rotate every quarter.".to_string()],
+        }],
+        None,
+        0.5,
+    )
+    .expect("verify");
+    let trace = TraceLogV1 {
+        schema_version: 1,
+        trace_id: "7d3c3a51-0c57-4c3a-9d4e-0b0f1d7c2a10".to_string(),
+        ts_ms: 2,
+        vault_id: "0f8e2a6c-7d3b-4c1e-8a5f-3b2d1c0e9f87".to_string(),
+        question: "how often do keys rotate?".to_string(),
+        retrieval: serde_json::json!({}),
+        model: serde_json::json!({}),
+        answer: serde_json::json!({ "text": "answer" }),
+        redaction: TraceRedactionV1 {
+            enabled: true,
+            ..TraceRedactionV1::default()
+        },
+        session: None,
+        verification: Some(verification),
+    };
+    let path =
+        write_trace_log(root.path(), &trace, &[(0, vec![sample_locator()])]).expect("write trace");
+
+    let mut value: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(path).expect("read trace")).expect("parse trace");
+    let schema = validator_for(&trace_schema()).expect("compile trace schema");
+    assert!(schema.is_valid(&value));
+    assert_eq!(value["verification"]["supported"], false);
+    assert_eq!(
+        value["verification"]["paragraphs"][0]["unsupported_sentences"][0]["text"],
+        "The budget is 10."
+    );
+    value["verification"]["threshold"] = serde_json::json!(2.0);
+    assert!(!schema.is_valid(&value));
+}
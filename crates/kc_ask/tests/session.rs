@@ -0,0 +1,296 @@
+use kc_ask::session::{
+    ask_session_create, ask_session_delete, ask_session_get, ask_sessions_list, rewrite_follow_up,
+};
+use kc_ask::{AskRequest, RetrievedOnlyAskService};
+use kc_core::canonical::persist_canonical_text;
+use kc_core::chunking::{chunk_document, default_chunking_config_v1};
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::object_store::ObjectStore;
+use kc_core::search::SearchFilterV1;
+use kc_core::services::CanonicalTextArtifact;
+use kc_core::types::{CanonicalHash, DocId};
+use kc_core::vault::vault_init;
+
+fn index_plain_doc(
+    conn: &rusqlite::Connection,
+    store: &ObjectStore,
+    text: &str,
+    source_path: &str,
+) -> DocId {
+    let ingested = ingest_bytes(
+        conn,
+        store,
+        IngestBytesReq {
+            bytes: text.as_bytes(),
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: 1,
+            source_path: Some(source_path),
+            now_ms: 1,
+        },
+    )
+    .expect("ingest");
+    let canonical_hash = blake3_hex_prefixed(text.as_bytes());
+    let artifact = CanonicalTextArtifact {
+        doc_id: ingested.doc_id.clone(),
+        canonical_bytes: text.as_bytes().to_vec(),
+        canonical_hash: CanonicalHash(canonical_hash.clone()),
+        canonical_object_hash: kc_core::types::ObjectHash(canonical_hash),
+        extractor_name: "test".to_string(),
+        extractor_version: "1".to_string(),
+        extractor_flags_json: "{}".to_string(),
+        normalization_version: 1,
+        toolchain_json: "{}".to_string(),
+    };
+    persist_canonical_text(conn, store, &artifact, 1).expect("persist canonical");
+    let chunks = chunk_document(
+        &ingested.doc_id,
+        text,
+        "text/plain",
+        &default_chunking_config_v1(),
+    )
+    .expect("chunk document");
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
+         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+    )
+    .expect("create fts");
+    for chunk in &chunks {
+        conn.execute(
+            "INSERT INTO chunks(chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                chunk.chunk_id.0,
+                chunk.doc_id.0,
+                chunk.ordinal,
+                chunk.start_char,
+                chunk.end_char,
+                chunk.chunking_config_hash.0,
+                "notes"
+            ],
+        )
+        .expect("insert chunk");
+        conn.execute(
+            "INSERT INTO chunks_fts(chunk_id, doc_id, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![chunk.chunk_id.0, chunk.doc_id.0, text],
+        )
+        .expect("insert fts row");
+    }
+    ingested.doc_id
+}
+
+fn read_trace(path: &str) -> serde_json::Value {
+    serde_json::from_slice(&std::fs::read(path).expect("read trace")).expect("trace json")
+}
+
+#[test]
+fn rewrite_follow_up_carries_prior_terms_only_for_follow_ups() {
+    assert_eq!(
+        rewrite_follow_up("when is it due?", None),
+        "when is it due?"
+    );
+    assert_eq!(
+        rewrite_follow_up(
+            "source:/notes budget review meeting",
+            Some("signing keys rotation")
+        ),
+        "source:/notes budget review meeting"
+    );
+    assert_eq!(
+        rewrite_follow_up("When is it due?", Some("Signing keys rotation (quarterly)")),
+        "due signing keys rotation quarterly"
+    );
+    assert_eq!(
+        rewrite_follow_up("what about rotation?", Some("signing keys rotation")),
+        "rotation signing keys"
+    );
+    assert_eq!(
+        rewrite_follow_up("and then?", Some("what is it?")),
+        "and then?"
+    );
+    assert_eq!(
+        rewrite_follow_up(
+            "who owns it?",
+            Some("keys rotated from [REDACTED:ipv4] by [REDACTED:email]")
+        ),
+        "owns keys rotated"
+    );
+}
+
+#[test]
+fn session_turns_rewrite_follow_ups_link_traces_and_reuse_citations() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    let keys = index_plain_doc(
+        &conn,
+        &store,
+        "Signing keys rotate every quarter.\n",
+        "/notes/keys.md",
+    );
+    index_plain_doc(&conn, &store, "Lunch menu is pasta.\n", "/notes/lunch.md");
+
+    let session = ask_session_create(&conn, Some("  Key rotation "), 5).expect("create");
+    assert_eq!(session.title.as_deref(), Some("Key rotation"));
+    assert_eq!(session.turn_count, 0);
+
+    let service = RetrievedOnlyAskService::default();
+    let ask = |question: &str, now_ms: i64| {
+        service.ask_session(
+            &session.session_id,
+            AskRequest {
+                vault_path: root.clone(),
+                question: question.to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
+                now_ms,
+            },
+        )
+    };
+
+    let first = ask("signing keys", 6).expect("first turn");
+    assert_eq!(first.turn_index, 0);
+    assert_eq!(first.standalone_query, "signing keys");
+    assert_eq!(first.parent_trace_id, None);
+    assert_eq!(first.citations[0].locators[0].doc_id, keys);
+    let first_trace = read_trace(&first.trace_path);
+    assert_eq!(first_trace["trace_id"], first.trace_id.as_str());
+    assert_eq!(first_trace["session"]["turn_index"], 0);
+    assert_eq!(
+        first_trace["session"]["parent_trace_id"],
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        first_trace["retrieval"]["reused_citations"],
+        serde_json::json!([])
+    );
+
+    // Nothing matches "lunch menu signing keys" as a whole, so the answer comes from the
+    // previous turn's citation.
+    let second = ask("what about the lunch menu?", 7).expect("follow-up turn");
+    assert_eq!(second.turn_index, 1);
+    assert_eq!(second.standalone_query, "lunch menu signing keys");
+    assert_eq!(
+        second.parent_trace_id.as_deref(),
+        Some(first.trace_id.as_str())
+    );
+    assert_eq!(second.citations[0].locators[0].doc_id, keys);
+    let second_trace = read_trace(&second.trace_path);
+    assert_eq!(second_trace["question"], "what about the lunch menu?");
+    assert_eq!(
+        second_trace["session"],
+        serde_json::json!({
+            "session_id": session.session_id,
+            "turn_index": 1,
+            "parent_trace_id": first.trace_id,
+            "standalone_query": "lunch menu signing keys",
+            "rewriter": "follow-up-terms-v1",
+        })
+    );
+    assert_eq!(
+        second_trace["retrieval"]["reused_citations"],
+        serde_json::json!(first.citations[0].locators)
+    );
+    assert_eq!(second_trace["retrieval"]["candidates"]["lexical"], 0);
+
+    let sessions = ask_sessions_list(&conn).expect("list");
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].turn_count, 2);
+    assert_eq!(sessions[0].updated_at_ms, 7);
+    let detail = ask_session_get(&conn, &session.session_id).expect("get");
+    let questions: Vec<&str> = detail.turns.iter().map(|t| t.question.as_str()).collect();
+    assert_eq!(
+        questions,
+        vec!["signing keys", "what about the lunch menu?"]
+    );
+
+    ask_session_delete(&conn, &session.session_id).expect("delete");
+    assert!(ask_sessions_list(&conn).expect("list").is_empty());
+    let err = ask_session_get(&conn, &session.session_id).expect_err("deleted");
+    assert_eq!(err.code, "KC_ASK_SESSION_NOT_FOUND");
+    let err = ask("signing keys", 8).expect_err("deleted session");
+    assert_eq!(err.code, "KC_ASK_SESSION_NOT_FOUND");
+    assert!(std::path::Path::new(&second.trace_path).exists());
+}
+
+#[test]
+fn session_turn_without_context_is_not_recorded() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(&conn, &store, "Budget is 10.\n", "/notes/budget.md");
+    let session = ask_session_create(&conn, None, 1).expect("create");
+
+    let err = RetrievedOnlyAskService::default()
+        .ask_session(
+            &session.session_id,
+            AskRequest {
+                vault_path: root.clone(),
+                question: "quarterly roadmap".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
+                now_ms: 2,
+            },
+        )
+        .expect_err("no context");
+    assert_eq!(err.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+    let detail = ask_session_get(&conn, &session.session_id).expect("get");
+    assert!(detail.turns.is_empty());
+    assert_eq!(detail.session.updated_at_ms, 1);
+}
+
+#[test]
+fn session_turns_are_returned_as_asked_and_stored_redacted() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "ask", 1).expect("vault init");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(
+        &conn,
+        &store,
+        "Signing keys are rotated by alice@example.com.\n",
+        "/notes/keys.md",
+    );
+    let session = ask_session_create(&conn, None, 1).expect("create");
+
+    let turn = RetrievedOnlyAskService::default()
+        .ask_session(
+            &session.session_id,
+            AskRequest {
+                vault_path: root.clone(),
+                question: "does alice@example.com rotate signing keys?".to_string(),
+                filter: SearchFilterV1::default(),
+                profile: None,
+                min_score: None,
+                now_ms: 2,
+            },
+        )
+        .expect("turn");
+    assert_eq!(turn.question, "does alice@example.com rotate signing keys?");
+    assert!(turn.answer_text.contains("alice@example.com"));
+
+    let detail = ask_session_get(&conn, &session.session_id).expect("get");
+    let stored = &detail.turns[0];
+    for text in [
+        &stored.question,
+        &stored.standalone_query,
+        &stored.answer_text,
+    ] {
+        assert!(!text.contains("alice@example.com"), "{text}");
+    }
+    assert!(stored.answer_text.contains("[REDACTED:email]"));
+    let raw: String = conn
+        .query_row(
+            "SELECT question || standalone_query || answer_text FROM ask_session_turns",
+            [],
+            |row| row.get(0),
+        )
+        .expect("raw turn");
+    assert!(!raw.contains("alice@example.com"));
+}
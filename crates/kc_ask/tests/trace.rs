@@ -0,0 +1,475 @@
+use kc_ask::replay::replay_trace;
+use kc_ask::trace::{trace_replay_request, TraceLogV1, TraceStore, TRACE_PASSPHRASE_ENV};
+use kc_ask::{AskRequest, AskService, RetrievedOnlyAskService};
+use kc_core::canonical::persist_canonical_text;
+use kc_core::chunking::{chunk_document, default_chunking_config_v1};
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::object_store::ObjectStore;
+use kc_core::retrieval::retrieval_config_for_vault;
+use kc_core::rpc_service::vault_encryption_enable_service;
+use kc_core::search::SearchFilterV1;
+use kc_core::services::CanonicalTextArtifact;
+use kc_core::trace_redaction::TraceRedactionV1;
+use kc_core::types::{CanonicalHash, DocId};
+use kc_core::vault::{
+    vault_init, vault_open, vault_save, VaultRedactionPattern, VaultTraceDefaults,
+    VaultTraceRetentionDefaults,
+};
+
+fn index_plain_doc(
+    conn: &rusqlite::Connection,
+    store: &ObjectStore,
+    source_path: &str,
+    text: &str,
+) -> DocId {
+    let ingested = ingest_bytes(
+        conn,
+        store,
+        IngestBytesReq {
+            bytes: text.as_bytes(),
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: 1,
+            source_path: Some(source_path),
+            now_ms: 1,
+        },
+    )
+    .expect("ingest");
+    let canonical_hash = blake3_hex_prefixed(text.as_bytes());
+    let artifact = CanonicalTextArtifact {
+        doc_id: ingested.doc_id.clone(),
+        canonical_bytes: text.as_bytes().to_vec(),
+        canonical_hash: CanonicalHash(canonical_hash.clone()),
+        canonical_object_hash: kc_core::types::ObjectHash(canonical_hash),
+        extractor_name: "test".to_string(),
+        extractor_version: "1".to_string(),
+        extractor_flags_json: "{}".to_string(),
+        normalization_version: 1,
+        toolchain_json: "{}".to_string(),
+    };
+    persist_canonical_text(conn, store, &artifact, 1).expect("persist canonical");
+    let chunks = chunk_document(
+        &ingested.doc_id,
+        text,
+        "text/plain",
+        &default_chunking_config_v1(),
+    )
+    .expect("chunk document");
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
+         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+    )
+    .expect("create fts");
+    for chunk in &chunks {
+        conn.execute(
+            "INSERT INTO chunks(chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                chunk.chunk_id.0,
+                chunk.doc_id.0,
+                chunk.ordinal,
+                chunk.start_char,
+                chunk.end_char,
+                chunk.chunking_config_hash.0,
+                "notes"
+            ],
+        )
+        .expect("insert chunk");
+        conn.execute(
+            "INSERT INTO chunks_fts(chunk_id, doc_id, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![chunk.chunk_id.0, chunk.doc_id.0, text],
+        )
+        .expect("insert fts row");
+    }
+    ingested.doc_id
+}
+
+const DAY_MS: i64 = 86_400_000;
+const RECORDED_MS: i64 = 100 * DAY_MS;
+
+fn manual_trace(vault_id: &str, trace_id: &str, ts_ms: i64) -> TraceLogV1 {
+    TraceLogV1 {
+        schema_version: 1,
+        trace_id: trace_id.to_string(),
+        ts_ms,
+        vault_id: vault_id.to_string(),
+        question: "who rotates the signing keys?".to_string(),
+        retrieval: serde_json::json!({}),
+        model: serde_json::json!({}),
+        answer: serde_json::json!({ "text": "alice@example.com rotates them." }),
+        redaction: TraceRedactionV1::default(),
+        session: None,
+        verification: None,
+    }
+}
+
+#[test]
+fn trace_store_redacts_trace_text_and_lists_gets_and_replays_traces() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    vault_init(&root, "demo", 1).expect("vault init");
+    let mut vault = vault_open(&root).expect("vault open");
+    let mut policy = VaultTraceDefaults::default();
+    policy.redaction.patterns.push(VaultRedactionPattern {
+        name: "ticket".to_string(),
+        regex: r"\bOPS-\d+\b".to_string(),
+    });
+    vault.defaults.trace = Some(policy);
+    vault_save(&root, &vault).expect("vault save");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    let doc_id = index_plain_doc(
+        &conn,
+        &store,
+        "/notes/keys.txt",
+        "Rotate the signing keys with alice@example.com under OPS-42.",
+    );
+
+    let service = RetrievedOnlyAskService::default();
+    let out = service
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "signing keys".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 2,
+        })
+        .expect("ask");
+    // Only the trace is redacted; the caller still gets the answer as generated.
+    assert!(out.answer_text.contains("alice@example.com"));
+
+    let raw = std::fs::read_to_string(&out.trace_path).expect("read trace");
+    assert!(!raw.contains("alice@example.com"));
+    assert!(!raw.contains("OPS-42"));
+    let value: serde_json::Value = serde_json::from_str(&raw).expect("trace json");
+    assert!(value["answer"]["text"]
+        .as_str()
+        .expect("answer text")
+        .contains("[REDACTED:email] under [REDACTED:ticket]"));
+    assert_eq!(value["retrieval"]["chunks"][0]["doc_id"], doc_id.0);
+    assert_eq!(value["redaction"]["enabled"], true);
+    assert_eq!(
+        value["redaction"]["rules"],
+        serde_json::json!(["email", "ipv4", "credit_card", "phone", "ticket"])
+    );
+    let fields: Vec<&str> = value["redaction"]["fields"]
+        .as_array()
+        .expect("fields")
+        .iter()
+        .map(|f| f.as_str().expect("field"))
+        .collect();
+    assert!(fields.contains(&"retrieval.chunks[0].snippet"));
+    assert!(fields.contains(&"answer.text"));
+    assert!(!fields.contains(&"question"));
+
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let listed = traces.list().expect("list").traces;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].trace_id, out.trace_id);
+    assert_eq!(listed[0].question, "signing keys");
+    assert!(listed[0].redacted);
+    assert!(!listed[0].encrypted);
+    let trace = traces.get(&out.trace_id).expect("get");
+    assert_eq!(trace.redaction.matches["ticket"], 2);
+    let err = traces.get("../vault").expect_err("not a trace id");
+    assert_eq!(err.code, "KC_TRACE_NOT_FOUND");
+
+    let replay = trace_replay_request(&root, &trace, 3).expect("replay request");
+    assert_eq!(replay.question, "signing keys");
+    assert_eq!(replay.profile.as_deref(), Some("default"));
+    assert_eq!(replay.now_ms, 3);
+    let replayed = service.ask(replay).expect("replayed ask");
+    assert_ne!(replayed.trace_id, out.trace_id);
+    let listed = traces.list().expect("list after replay").traces;
+    assert_eq!(listed[0].trace_id, replayed.trace_id);
+    assert_eq!(listed.len(), 2);
+
+    let mut redacted = trace.clone();
+    redacted.redaction.fields.push("question".to_string());
+    let err = trace_replay_request(&root, &redacted, 3).expect_err("redacted question");
+    assert_eq!(err.code, "KC_TRACE_REPLAY_UNAVAILABLE");
+    let err = trace_replay_request(&root, &manual_trace(&trace.vault_id, &trace.trace_id, 2), 3)
+        .expect_err("no retrieval");
+    assert_eq!(err.code, "KC_TRACE_REPLAY_UNAVAILABLE");
+}
+
+#[test]
+fn trace_store_prunes_by_count_after_writes_and_by_age_on_demand() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let mut vault = vault_init(&root, "demo", 1).expect("vault init");
+    vault.defaults.trace = Some(VaultTraceDefaults {
+        retention: VaultTraceRetentionDefaults {
+            max_age_days: Some(1),
+            max_count: Some(2),
+        },
+        ..VaultTraceDefaults::default()
+    });
+    vault_save(&root, &vault).expect("vault save");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let ids = [
+        "00000000-0000-4000-8000-000000000001",
+        "00000000-0000-4000-8000-000000000002",
+        "00000000-0000-4000-8000-000000000003",
+    ];
+    for (ts_ms, id) in ids.iter().enumerate() {
+        traces
+            .write(manual_trace(&vault.vault_id, id, ts_ms as i64 + 1), &[])
+            .expect("write trace");
+    }
+    let listed: Vec<String> = traces
+        .list()
+        .expect("list")
+        .traces
+        .into_iter()
+        .map(|t| t.trace_id)
+        .collect();
+    assert_eq!(listed, vec![ids[2], ids[1]]);
+    assert!(!root.join("trace").join(format!("{}.json", ids[0])).exists());
+
+    let report = traces.prune(86_400_000 + 3).expect("prune by age");
+    assert_eq!(report.removed, vec![ids[1]]);
+    assert_eq!(report.kept, 1);
+    let remaining = traces.get(ids[2]).expect("newest trace");
+    assert_eq!(remaining.answer["text"], "[REDACTED:email] rotates them.");
+}
+
+#[test]
+fn trace_store_lists_past_unreadable_trace_files_and_reports_them() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault = vault_init(&root, "demo", 1).expect("vault init");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let good = "00000000-0000-4000-8000-0000000000b1";
+    traces
+        .write(manual_trace(&vault.vault_id, good, 5), &[])
+        .expect("write trace");
+    let corrupt = root
+        .join("trace")
+        .join("00000000-0000-4000-8000-0000000000b2.json");
+    std::fs::write(&corrupt, b"{ not a trace").expect("write corrupt trace");
+
+    let listed = traces.list().expect("list");
+    let ids: Vec<&str> = listed.traces.iter().map(|t| t.trace_id.as_str()).collect();
+    assert_eq!(ids, vec![good]);
+    assert_eq!(listed.skipped, vec![corrupt.display().to_string()]);
+}
+
+#[test]
+fn trace_store_prunes_from_file_mtimes_without_reading_traces() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let mut vault = vault_init(&root, "demo", 1).expect("vault init");
+    vault.defaults.trace = Some(VaultTraceDefaults {
+        retention: VaultTraceRetentionDefaults {
+            max_age_days: Some(1),
+            max_count: Some(5),
+        },
+        ..VaultTraceDefaults::default()
+    });
+    vault_save(&root, &vault).expect("vault save");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let trace_dir = root.join("trace");
+    std::fs::create_dir_all(&trace_dir).expect("trace dir");
+    let garbage = trace_dir.join("00000000-0000-4000-8000-0000000000ff.json");
+    std::fs::write(&garbage, b"not a trace").expect("write garbage");
+    std::fs::File::options()
+        .write(true)
+        .open(&garbage)
+        .and_then(|f| f.set_modified(std::time::UNIX_EPOCH))
+        .expect("age garbage");
+
+    let ts_ms = 20 * DAY_MS;
+    let path = traces
+        .write(
+            manual_trace(
+                &vault.vault_id,
+                "00000000-0000-4000-8000-000000000001",
+                ts_ms,
+            ),
+            &[],
+        )
+        .expect("write despite an unreadable trace file");
+    let modified = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .expect("mtime");
+    assert_eq!(
+        modified,
+        std::time::UNIX_EPOCH + std::time::Duration::from_millis(ts_ms as u64)
+    );
+    assert!(!garbage.exists());
+
+    let report = traces.prune(ts_ms).expect("prune");
+    assert!(report.removed.is_empty());
+    assert_eq!(report.kept, 1);
+    assert!(report.skipped.is_empty());
+}
+
+#[test]
+fn trace_store_encrypts_traces_when_the_object_store_is_encrypted() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault = vault_init(&root, "demo", 1).expect("vault init");
+    vault_encryption_enable_service(&root, "trace-passphrase").expect("enable encryption");
+    let trace_id = "00000000-0000-4000-8000-0000000000aa";
+
+    std::env::remove_var(TRACE_PASSPHRASE_ENV);
+    let err = TraceStore::open(&root, "trace")
+        .err()
+        .expect("passphrase required");
+    assert_eq!(err.code, "KC_ENCRYPTION_REQUIRED");
+
+    std::env::set_var(TRACE_PASSPHRASE_ENV, "trace-passphrase");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let path = traces
+        .write(manual_trace(&vault.vault_id, trace_id, 5), &[])
+        .expect("write trace");
+    std::env::remove_var(TRACE_PASSPHRASE_ENV);
+    assert!(path.to_string_lossy().ends_with(".json.kce"));
+    let raw = std::fs::read(&path).expect("read raw trace");
+    assert!(raw.starts_with(b"KCE1"));
+    assert!(!String::from_utf8_lossy(&raw).contains("signing keys"));
+
+    let trace = traces.get(trace_id).expect("decrypt trace");
+    assert_eq!(trace.question, "who rotates the signing keys?");
+    let listed = traces.list().expect("list").traces;
+    assert!(listed[0].encrypted);
+    assert!(listed[0].redacted);
+}
+
+#[test]
+fn replay_trace_reuses_the_recorded_profile_and_reports_retrieval_drift() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let mut vault = vault_init(&root, "demo", 1).expect("vault init");
+    // Recency makes scores depend on the ask time, which a replay must keep.
+    let mut tuned = retrieval_config_for_vault(&vault);
+    tuned.recency.enabled = true;
+    vault
+        .defaults
+        .retrieval_profiles
+        .insert("tuned".to_string(), tuned.clone());
+    vault_save(&root, &vault).expect("vault save");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(
+        &conn,
+        &store,
+        "/notes/keys.txt",
+        "Rotate the signing keys every quarter.",
+    );
+
+    let service = RetrievedOnlyAskService::default();
+    let out = service
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "signing keys".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: Some("tuned".to_string()),
+            min_score: None,
+            now_ms: RECORDED_MS,
+        })
+        .expect("ask");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let recorded = traces.read_file(&out.trace_path).expect("read trace file");
+
+    // A year later the document is outside the recency window; replaying at the recorded
+    // time still reproduces the recorded scores.
+    let later_ms = RECORDED_MS + 400 * DAY_MS;
+    let report = replay_trace(&service, &root, &recorded, later_ms).expect("replay");
+    assert!(!report.drift);
+    let replayed = traces.get(&report.replay_trace_id).expect("replay trace");
+    assert_eq!(replayed.ts_ms, later_ms);
+    assert!(report.chunks.is_empty() && report.answer.is_empty());
+    assert_eq!(report.trace_id, out.trace_id);
+    assert_ne!(report.replay_trace_id, out.trace_id);
+    assert_eq!(report.config.profile, "tuned");
+    assert_eq!(
+        report.config.current_config_hash.as_deref(),
+        Some(report.config.config_hash.as_str())
+    );
+    assert!(!report.config.model_changed);
+
+    // Editing the vault's profile does not change what the trace replays with.
+    vault.defaults.retrieval_profiles.insert(
+        "tuned".to_string(),
+        kc_core::retrieval::RetrievalConfigV1 {
+            w_lex: 3.0,
+            ..tuned
+        },
+    );
+    vault_save(&root, &vault).expect("vault save");
+    let report = replay_trace(&service, &root, &recorded, later_ms + 1).expect("replay after edit");
+    assert!(!report.drift);
+    assert_ne!(
+        report.config.current_config_hash.as_deref(),
+        Some(report.config.config_hash.as_str())
+    );
+
+    index_plain_doc(
+        &conn,
+        &store,
+        "/notes/rotation.txt",
+        "Signing keys: the signing keys live in the vault.",
+    );
+    let report =
+        replay_trace(&service, &root, &recorded, later_ms + 2).expect("replay after ingest");
+    assert!(report.drift);
+    assert_eq!(report.chunks.added.len(), 1);
+    assert!(report.chunks.removed.is_empty());
+
+    let mut tampered = recorded.clone();
+    tampered.retrieval["profile"]["config_hash"] = serde_json::json!("blake3:00");
+    let err = replay_trace(&service, &root, &tampered, later_ms + 3).expect_err("tampered config");
+    assert_eq!(err.code, "KC_TRACE_REPLAY_UNAVAILABLE");
+}
+
+#[test]
+fn replay_trace_does_not_prune_recorded_traces() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let mut vault = vault_init(&root, "demo", 1).expect("vault init");
+    vault.defaults.trace = Some(VaultTraceDefaults {
+        retention: VaultTraceRetentionDefaults {
+            max_age_days: Some(1),
+            max_count: Some(1),
+        },
+        ..VaultTraceDefaults::default()
+    });
+    vault_save(&root, &vault).expect("vault save");
+    let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(root.join("store/objects"));
+    index_plain_doc(
+        &conn,
+        &store,
+        "/notes/keys.txt",
+        "Rotate the signing keys every quarter.",
+    );
+
+    let service = RetrievedOnlyAskService::default();
+    let out = service
+        .ask(AskRequest {
+            vault_path: root.clone(),
+            question: "signing keys".to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: RECORDED_MS,
+        })
+        .expect("ask");
+    let traces = TraceStore::open(&root, "trace").expect("trace store");
+    let recorded = traces.get(&out.trace_id).expect("recorded trace");
+
+    // Both retention limits would drop the recorded trace if the replay write pruned.
+    let later_ms = RECORDED_MS + 30 * DAY_MS;
+    let report = replay_trace(&service, &root, &recorded, later_ms).expect("replay");
+    assert!(!report.drift);
+    assert!(out.trace_path.exists());
+    let listed: Vec<String> = traces
+        .list()
+        .expect("list")
+        .traces
+        .into_iter()
+        .map(|t| t.trace_id)
+        .collect();
+    assert_eq!(listed, vec![report.replay_trace_id.clone(), out.trace_id]);
+
+    let report = traces.prune(later_ms).expect("prune");
+    assert_eq!(report.removed, vec![recorded.trace_id]);
+}
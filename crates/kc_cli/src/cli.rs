@@ -35,10 +35,88 @@ pub enum Command {
         #[command(subcommand)]
         cmd: IndexCmd,
     },
+    Doc {
+        #[command(subcommand)]
+        cmd: DocCmd,
+    },
     Gc {
         #[command(subcommand)]
         cmd: GcCmd,
     },
+    Search {
+        vault_path: String,
+        query: String,
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long = "include-superseded")]
+        include_superseded: bool,
+        #[arg(long = "source-kind")]
+        source_kinds: Vec<String>,
+        #[arg(long = "mime")]
+        mimes: Vec<String>,
+        #[arg(long = "from-ms")]
+        from_ms: Option<i64>,
+        #[arg(long = "to-ms")]
+        to_ms: Option<i64>,
+        #[arg(long = "path-prefix")]
+        path_prefix: Option<String>,
+        #[arg(long)]
+        extractor: Option<String>,
+        #[arg(long)]
+        profile: Option<String>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    // `ask <vault_path> <question>` asks; `ask session ...` manages conversation sessions,
+    // `ask trace ...` browses, prunes and replays trace logs and `ask replay <trace_path>`
+    // diffs a replay against its trace.
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Ask {
+        #[command(subcommand)]
+        cmd: Option<AskCmd>,
+        #[arg(required = true)]
+        vault_path: Option<String>,
+        #[arg(required = true)]
+        question: Option<String>,
+        #[arg(long)]
+        stream: bool,
+        #[arg(long)]
+        session: Option<String>,
+        #[arg(long = "source-kind")]
+        source_kinds: Vec<String>,
+        #[arg(long = "mime")]
+        mimes: Vec<String>,
+        #[arg(long = "from-ms")]
+        from_ms: Option<i64>,
+        #[arg(long = "to-ms")]
+        to_ms: Option<i64>,
+        #[arg(long = "path-prefix")]
+        path_prefix: Option<String>,
+        #[arg(long)]
+        extractor: Option<String>,
+        #[arg(long)]
+        profile: Option<String>,
+        #[arg(long = "min-score")]
+        min_score: Option<f64>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    Eval {
+        vault_path: String,
+        judgments_path: String,
+        #[arg(long)]
+        k: Option<usize>,
+        #[arg(long = "profile")]
+        profiles: Vec<String>,
+        #[arg(long)]
+        ask: bool,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    Jobs {
+        #[command(subcommand)]
+        cmd: JobsCmd,
+    },
     Deps {
         #[command(subcommand)]
         cmd: DepsCmd,
@@ -61,6 +139,71 @@ pub enum Command {
     },
 }
 
+#[derive(Subcommand)]
+pub enum AskCmd {
+    Session {
+        #[command(subcommand)]
+        cmd: AskSessionCmd,
+    },
+    Trace {
+        #[command(subcommand)]
+        cmd: AskTraceCmd,
+    },
+    // Replays a trace file with its recorded retrieval profile and reports drift; the vault
+    // defaults to the directory containing the trace directory.
+    Replay {
+        trace_path: String,
+        #[arg(long = "vault")]
+        vault_path: Option<String>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AskSessionCmd {
+    New {
+        vault_path: String,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    List {
+        vault_path: String,
+    },
+    Show {
+        vault_path: String,
+        session_id: String,
+    },
+    Delete {
+        vault_path: String,
+        session_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AskTraceCmd {
+    List {
+        vault_path: String,
+    },
+    Show {
+        vault_path: String,
+        trace_id: String,
+    },
+    Prune {
+        vault_path: String,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    Replay {
+        vault_path: String,
+        trace_id: String,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum VaultCmd {
     Init {
@@ -349,6 +492,21 @@ pub enum IngestCmd {
         file_path: String,
         source_kind: String,
     },
+    InboxWatch {
+        vault_path: String,
+        source_kind: String,
+        #[arg(long = "debounce-ms", default_value_t = 500)]
+        debounce_ms: i64,
+        #[arg(long = "poll-interval-ms", default_value_t = 1000)]
+        poll_interval_ms: i64,
+        #[arg(long, default_value = "auto")]
+        backend: String,
+        #[arg(long = "max-runtime-ms")]
+        max_runtime_ms: Option<i64>,
+    },
+    InboxStop {
+        vault_path: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -358,7 +516,68 @@ pub enum IndexCmd {
 
 #[derive(Subcommand)]
 pub enum GcCmd {
-    Run { vault_path: String },
+    Run {
+        vault_path: String,
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum JobsCmd {
+    List {
+        vault_path: String,
+    },
+    Cancel {
+        vault_path: String,
+        job_id: String,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    EnqueueScan {
+        vault_path: String,
+        scan_root: String,
+        source_kind: String,
+        #[arg(long = "max-attempts")]
+        max_attempts: Option<i64>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    Run {
+        vault_path: String,
+        #[arg(long = "max-jobs", default_value_t = 16)]
+        max_jobs: usize,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DocCmd {
+    Delete {
+        vault_path: String,
+        doc_id: String,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
+    Tombstones {
+        vault_path: String,
+    },
+    Versions {
+        vault_path: String,
+        doc_id: String,
+    },
+    Reextract {
+        vault_path: String,
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+        #[arg(long)]
+        limit: Option<usize>,
+        #[arg(long = "now-ms")]
+        now_ms: Option<i64>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -0,0 +1,283 @@
+use kc_ask::replay::{replay_trace, TraceReplayReportV1};
+use kc_ask::session::{ask_session_create, ask_session_delete, ask_session_get, ask_sessions_list};
+use kc_ask::trace::{trace_replay_request, TraceStore};
+use kc_ask::{AskCancelToken, AskRequest, AskResponse, AskService, RetrievedOnlyAskService};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::db::open_db;
+use kc_core::vault::vault_open;
+use std::io::Write;
+use std::path::Path;
+
+fn write_failed(e: std::io::Error) -> AppError {
+    AppError::new(
+        "KC_INTERNAL_ERROR",
+        "ask",
+        "failed writing ask output",
+        false,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn print_json(value: &impl serde::Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| "{}".to_string())
+    );
+}
+
+fn ask_report(res: &AskResponse) -> serde_json::Value {
+    serde_json::json!({
+        "answer_text": res.answer_text,
+        "citations": res
+            .citations
+            .iter()
+            .map(|(paragraph_index, locators)| serde_json::json!({
+                "paragraph_index": paragraph_index,
+                "locators": locators,
+            }))
+            .collect::<Vec<_>>(),
+        "trace_id": res.trace_id,
+        "trace_path": res.trace_path.display().to_string(),
+        "verification": res.verification,
+    })
+}
+
+// With `stream`, every event is written as one JSON line as soon as it happens and a
+// failure ends the stream with an `error` line. With `session`, the question is asked as
+// the session's next turn and the recorded turn is printed.
+pub fn write_ask(
+    out: &mut dyn Write,
+    req: AskRequest,
+    session: Option<&str>,
+    stream: bool,
+) -> AppResult<()> {
+    let service = RetrievedOnlyAskService::default();
+    if !stream {
+        let report = match session {
+            Some(session_id) => serde_json::to_value(service.ask_session(session_id, req)?)
+                .unwrap_or(serde_json::Value::Null),
+            None => ask_report(&service.ask(req)?),
+        };
+        writeln!(
+            out,
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+        )
+        .map_err(write_failed)?;
+        return Ok(());
+    }
+
+    let cancel = AskCancelToken::default();
+    let mut on_event = |event| {
+        let line = serde_json::to_string(&event).unwrap_or_else(|_| "{}".to_string());
+        writeln!(out, "{line}")
+            .and_then(|_| out.flush())
+            .map_err(write_failed)
+    };
+    let result = match session {
+        Some(session_id) => service
+            .ask_session_stream(session_id, req, &cancel, &mut on_event)
+            .map(|_| ()),
+        None => service.ask_stream(req, &cancel, &mut on_event).map(|_| ()),
+    };
+    if let Err(err) = &result {
+        let line = serde_json::json!({ "event": "error", "error": err });
+        writeln!(out, "{line}").map_err(write_failed)?;
+    }
+    result
+}
+
+pub fn run_ask(req: AskRequest, session: Option<&str>, stream: bool) -> AppResult<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    write_ask(&mut out, req, session, stream)
+}
+
+pub fn run_session_new(vault_path: &str, title: Option<&str>, now_ms: i64) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    print_json(&ask_session_create(&conn, title, now_ms)?);
+    Ok(())
+}
+
+pub fn run_session_list(vault_path: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    print_json(&ask_sessions_list(&conn)?);
+    Ok(())
+}
+
+pub fn run_session_show(vault_path: &str, session_id: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    print_json(&ask_session_get(&conn, session_id)?);
+    Ok(())
+}
+
+pub fn run_session_delete(vault_path: &str, session_id: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    ask_session_delete(&conn, session_id)?;
+    print_json(&serde_json::json!({ "session_id": session_id, "deleted": true }));
+    Ok(())
+}
+
+fn trace_store(vault_path: &str) -> AppResult<TraceStore> {
+    TraceStore::open(
+        Path::new(vault_path),
+        &RetrievedOnlyAskService::default().trace_dir_name,
+    )
+}
+
+pub fn run_trace_list(vault_path: &str) -> AppResult<()> {
+    print_json(&trace_store(vault_path)?.list()?);
+    Ok(())
+}
+
+pub fn run_trace_show(vault_path: &str, trace_id: &str) -> AppResult<()> {
+    print_json(&trace_store(vault_path)?.get(trace_id)?);
+    Ok(())
+}
+
+pub fn run_trace_prune(vault_path: &str, now_ms: i64) -> AppResult<()> {
+    print_json(&trace_store(vault_path)?.prune(now_ms)?);
+    Ok(())
+}
+
+// Asks the trace's question again with its recorded filter, profile and min score, writing a
+// new trace.
+pub fn run_trace_replay(vault_path: &str, trace_id: &str, now_ms: i64) -> AppResult<()> {
+    let trace = trace_store(vault_path)?.get(trace_id)?;
+    let req = trace_replay_request(Path::new(vault_path), &trace, now_ms)?;
+    let res = RetrievedOnlyAskService::default().ask(req)?;
+    let mut report = ask_report(&res);
+    report["replayed_trace_id"] = serde_json::json!(trace.trace_id);
+    print_json(&report);
+    Ok(())
+}
+
+// Without `vault_path`, the trace is taken to sit in its vault's trace directory.
+pub fn run_replay(
+    trace_path: &str,
+    vault_path: Option<&str>,
+    now_ms: i64,
+) -> AppResult<TraceReplayReportV1> {
+    let trace_path = Path::new(trace_path);
+    let vault_path = match vault_path {
+        Some(vault_path) => Path::new(vault_path),
+        None => trace_path
+            .parent()
+            .and_then(|trace_dir| trace_dir.parent())
+            .unwrap_or(Path::new("")),
+    };
+    let service = RetrievedOnlyAskService::default();
+    let trace = TraceStore::open(vault_path, &service.trace_dir_name)?.read_file(trace_path)?;
+    replay_trace(&service, vault_path, &trace, now_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_ask;
+    use kc_ask::session::ask_session_create;
+    use kc_ask::AskRequest;
+    use kc_core::chunking::default_chunking_config_v1;
+    use kc_core::db::open_db;
+    use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+    use kc_core::object_store::ObjectStore;
+    use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+    use kc_core::search::SearchFilterV1;
+    use kc_core::vault::vault_init;
+    use kc_extract::DefaultExtractor;
+    use kc_index::open_vault_indexes;
+
+    #[test]
+    fn ask_stream_writes_one_json_event_per_line() {
+        let root = tempfile::tempdir().expect("tempdir").keep();
+        let vault = vault_init(&root, "demo", 1).expect("vault init");
+        let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+        let store = ObjectStore::new(root.join("store/objects"));
+        let indexes = open_vault_indexes(&root).expect("indexes");
+        let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+        let chunking = default_chunking_config_v1();
+        let services = PipelineServices {
+            extractor: &extractor,
+            lexical: &indexes.lexical,
+            vector: &indexes.vector,
+            chunking: &chunking,
+        };
+        let doc = ingest_bytes(
+            &conn,
+            &store,
+            IngestBytesReq {
+                bytes: b"rotate the signing keys every quarter",
+                mime: "text/plain",
+                source_kind: "notes",
+                effective_ts_ms: 1,
+                source_path: None,
+                now_ms: 1,
+            },
+        )
+        .expect("ingest");
+        run_doc_pipeline(&conn, &store, &services, &doc.doc_id, 1).expect("pipeline");
+        drop(indexes);
+        let req = |question: &str| AskRequest {
+            vault_path: root.clone(),
+            question: question.to_string(),
+            filter: SearchFilterV1::default(),
+            profile: None,
+            min_score: None,
+            now_ms: 2,
+        };
+
+        let mut out = Vec::new();
+        write_ask(&mut out, req("signing keys"), None, true).expect("streamed ask");
+        let events: Vec<serde_json::Value> = String::from_utf8(out)
+            .expect("utf8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|e| e["event"].as_str().expect("event kind"))
+            .collect();
+        assert_eq!(kinds, vec!["retrieval", "delta", "paragraph", "final"]);
+        let last = &events[3];
+        assert_eq!(last["citations"][0]["locators"][0]["doc_id"], doc.doc_id.0);
+        assert!(std::path::Path::new(last["trace_path"].as_str().expect("trace path")).exists());
+
+        let mut out = Vec::new();
+        let err = write_ask(&mut out, req("keys\" OR (menu"), None, true).expect_err("bad query");
+        let line: serde_json::Value =
+            serde_json::from_slice(out.trim_ascii_end()).expect("error line");
+        assert_eq!(line["event"], "error");
+        assert_eq!(line["error"]["code"], err.code);
+
+        let mut out = Vec::new();
+        let err = write_ask(
+            &mut out,
+            AskRequest {
+                min_score: Some(1.0),
+                ..req("signing keys")
+            },
+            None,
+            false,
+        )
+        .expect_err("threshold above every hit");
+        assert_eq!(err.code, "KC_ASK_NO_RELEVANT_CONTEXT");
+        assert!(out.is_empty());
+
+        let session = ask_session_create(&conn, None, 2).expect("session");
+        let mut out = Vec::new();
+        write_ask(
+            &mut out,
+            req("signing keys"),
+            Some(&session.session_id),
+            false,
+        )
+        .expect("session turn");
+        let turn: serde_json::Value = serde_json::from_slice(&out).expect("turn json");
+        assert_eq!(turn["session_id"], session.session_id.as_str());
+        assert_eq!(turn["turn_index"], 0);
+        assert_eq!(turn["citations"][0]["locators"][0]["doc_id"], doc.doc_id.0);
+    }
+}
@@ -0,0 +1,90 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::doc_versions::doc_version_history;
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::PipelineServices;
+use kc_core::reextract::reextract_stale_docs;
+use kc_core::tombstone::{doc_delete, doc_tombstones_list};
+use kc_core::types::DocId;
+use kc_core::vault::{vault_open, vault_paths};
+use kc_extract::DefaultExtractor;
+use kc_index::open_vault_indexes;
+use std::path::Path;
+
+pub fn run_delete(vault_path: &str, doc_id: &str, now_ms: i64) -> AppResult<()> {
+    let root = Path::new(vault_path);
+    let vault = vault_open(root)?;
+    let conn = open_db(&root.join(&vault.db.relative_path))?;
+    let indexes = open_vault_indexes(root)?;
+    let out = doc_delete(
+        &conn,
+        &indexes.lexical,
+        &indexes.vector,
+        &DocId(doc_id.to_string()),
+        now_ms,
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_tombstones(vault_path: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let tombstones = doc_tombstones_list(&conn)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&tombstones).unwrap_or_else(|_| "[]".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_versions(vault_path: &str, doc_id: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let history = doc_version_history(&conn, doc_id)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&history).unwrap_or_else(|_| "[]".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_reextract(
+    vault_path: &str,
+    dry_run: bool,
+    limit: Option<usize>,
+    now_ms: i64,
+) -> AppResult<()> {
+    let root = Path::new(vault_path);
+    let vault = vault_open(root)?;
+    let conn = open_db(&root.join(&vault.db.relative_path))?;
+    let store = ObjectStore::new(vault_paths(root).objects_dir);
+    let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+    let indexes = open_vault_indexes(root)?;
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &extractor,
+        lexical: &indexes.lexical,
+        vector: &indexes.vector,
+        chunking: &chunking,
+    };
+
+    let report = reextract_stale_docs(
+        &conn,
+        &store,
+        &services,
+        &extractor.identity(),
+        dry_run,
+        limit,
+        now_ms,
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
@@ -0,0 +1,511 @@
+use kc_ask::{AskRequest, AskService, RetrievedOnlyAskService};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::locator::LocatorRange;
+use kc_core::retrieval::retrieval_profile_for_vault;
+use kc_core::rpc_service::search_query_service;
+use kc_core::search::{SearchFilterV1, SearchReq};
+use kc_core::vault::vault_open;
+use kc_index::open_vault_indexes;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+// A judgment matches a retrieved range of the same doc; without `range` any range of the doc
+// matches. `grade` is the graded gain used by nDCG.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JudgmentV1 {
+    pub doc_id: String,
+    #[serde(default)]
+    pub range: Option<LocatorRange>,
+    #[serde(default = "default_grade")]
+    pub grade: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JudgedQueryV1 {
+    pub id: String,
+    pub query: String,
+    #[serde(default)]
+    pub filter: SearchFilterV1,
+    pub relevant: Vec<JudgmentV1>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JudgmentsV1 {
+    pub schema_version: u32,
+    pub queries: Vec<JudgedQueryV1>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EvalMetricsV1 {
+    pub recall_at_k: f64,
+    pub mrr: f64,
+    pub ndcg_at_k: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalQueryV1 {
+    pub id: String,
+    pub retrieved: usize,
+    pub judged_found: usize,
+    pub first_relevant_rank: Option<usize>,
+    pub metrics: EvalMetricsV1,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalModeV1 {
+    pub summary: EvalMetricsV1,
+    pub queries: Vec<EvalQueryV1>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalRunV1 {
+    pub profile: String,
+    pub config_hash: String,
+    pub search: EvalModeV1,
+    pub ask: Option<EvalModeV1>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReportV1 {
+    pub schema_version: u32,
+    pub judgments_hash: String,
+    pub k: usize,
+    pub now_ms: i64,
+    pub embedding_model_id: String,
+    pub runs: Vec<EvalRunV1>,
+}
+
+fn default_grade() -> u32 {
+    1
+}
+
+fn judgments_invalid(message: &str, details: serde_json::Value) -> AppError {
+    AppError::new("KC_EVAL_JUDGMENTS_INVALID", "eval", message, false, details)
+}
+
+pub fn parse_judgments(bytes: &[u8]) -> AppResult<JudgmentsV1> {
+    let judgments: JudgmentsV1 = serde_json::from_slice(bytes).map_err(|e| {
+        judgments_invalid(
+            "judgments file is not valid judgments JSON",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+    if judgments.schema_version != 1 {
+        return Err(judgments_invalid(
+            "unsupported judgments schema_version",
+            serde_json::json!({ "expected": 1, "actual": judgments.schema_version }),
+        ));
+    }
+    let mut ids = BTreeSet::new();
+    for query in &judgments.queries {
+        if !ids.insert(query.id.as_str()) {
+            return Err(judgments_invalid(
+                "judged query ids must be unique",
+                serde_json::json!({ "id": query.id }),
+            ));
+        }
+        if query.query.trim().is_empty() || query.relevant.is_empty() {
+            return Err(judgments_invalid(
+                "judged query needs a query and at least one relevant entry",
+                serde_json::json!({ "id": query.id }),
+            ));
+        }
+        for judgment in &query.relevant {
+            let bad_range = judgment
+                .range
+                .as_ref()
+                .is_some_and(|r| r.start < 0 || r.end <= r.start);
+            if judgment.grade == 0 || bad_range {
+                return Err(judgments_invalid(
+                    "judgment needs a positive grade and a non-empty range",
+                    serde_json::json!({ "id": query.id, "doc_id": judgment.doc_id }),
+                ));
+            }
+        }
+    }
+    Ok(judgments)
+}
+
+fn round6(value: f64) -> f64 {
+    (value * 1_000_000.0).round() / 1_000_000.0
+}
+
+fn matches(judgment: &JudgmentV1, doc_id: &str, range: &LocatorRange) -> bool {
+    judgment.doc_id == doc_id
+        && judgment
+            .range
+            .as_ref()
+            .is_none_or(|r| range.start < r.end && r.start < range.end)
+}
+
+// Scores the first `k` retrieved (doc_id, range) entries. Each judgment is credited at most
+// once, to the first entry matching it, so repeated passages do not inflate the gain.
+pub fn score_ranking(
+    retrieved: &[(String, LocatorRange)],
+    relevant: &[JudgmentV1],
+    k: usize,
+) -> (EvalMetricsV1, usize, Option<usize>) {
+    let mut credited = vec![false; relevant.len()];
+    let mut first_relevant_rank = None;
+    let mut dcg = 0.0;
+    for (idx, (doc_id, range)) in retrieved.iter().take(k).enumerate() {
+        let best = relevant
+            .iter()
+            .enumerate()
+            .filter(|(j, judgment)| !credited[*j] && matches(judgment, doc_id, range))
+            .max_by(|(ja, a), (jb, b)| a.grade.cmp(&b.grade).then(jb.cmp(ja)));
+        if let Some((j, judgment)) = best {
+            credited[j] = true;
+            first_relevant_rank.get_or_insert(idx + 1);
+            dcg += judgment.grade as f64 / (idx as f64 + 2.0).log2();
+        }
+    }
+    let mut ideal: Vec<u32> = relevant.iter().map(|j| j.grade).collect();
+    ideal.sort_unstable_by(|a, b| b.cmp(a));
+    let idcg: f64 = ideal
+        .iter()
+        .take(k)
+        .enumerate()
+        .map(|(idx, grade)| *grade as f64 / (idx as f64 + 2.0).log2())
+        .sum();
+    let found = credited.iter().filter(|c| **c).count();
+    let metrics = EvalMetricsV1 {
+        recall_at_k: round6(found as f64 / relevant.len().max(1) as f64),
+        mrr: round6(first_relevant_rank.map_or(0.0, |rank| 1.0 / rank as f64)),
+        ndcg_at_k: round6(if idcg > 0.0 { dcg / idcg } else { 0.0 }),
+    };
+    (metrics, found, first_relevant_rank)
+}
+
+fn summarize(queries: Vec<EvalQueryV1>) -> EvalModeV1 {
+    let n = queries.len().max(1) as f64;
+    let mean = |f: fn(&EvalMetricsV1) -> f64| {
+        round6(queries.iter().map(|q| f(&q.metrics)).sum::<f64>() / n)
+    };
+    EvalModeV1 {
+        summary: EvalMetricsV1 {
+            recall_at_k: mean(|m| m.recall_at_k),
+            mrr: mean(|m| m.mrr),
+            ndcg_at_k: mean(|m| m.ndcg_at_k),
+        },
+        queries,
+    }
+}
+
+// A failed query (e.g. `KC_QUERY_INVALID`, or ask finding no context) scores zero and records
+// the error code instead of aborting the run.
+fn eval_query(
+    query: &JudgedQueryV1,
+    k: usize,
+    retrieved: AppResult<Vec<(String, LocatorRange)>>,
+) -> EvalQueryV1 {
+    match retrieved {
+        Ok(retrieved) => {
+            let (metrics, judged_found, first_relevant_rank) =
+                score_ranking(&retrieved, &query.relevant, k);
+            EvalQueryV1 {
+                id: query.id.clone(),
+                retrieved: retrieved.len().min(k),
+                judged_found,
+                first_relevant_rank,
+                metrics,
+                error: None,
+            }
+        }
+        Err(error) => EvalQueryV1 {
+            id: query.id.clone(),
+            retrieved: 0,
+            judged_found: 0,
+            first_relevant_rank: None,
+            metrics: EvalMetricsV1::default(),
+            error: Some(error.code),
+        },
+    }
+}
+
+pub fn evaluate(
+    vault_path: &Path,
+    judgments_bytes: &[u8],
+    k: usize,
+    profiles: &[String],
+    with_ask: bool,
+    now_ms: i64,
+) -> AppResult<EvalReportV1> {
+    let judgments = parse_judgments(judgments_bytes)?;
+    let vault = vault_open(vault_path)?;
+    let indexes = open_vault_indexes(vault_path)?;
+    // Eval asks write their traces to a throwaway dir, so they neither land in the vault's
+    // trace log nor trigger its retention pruning.
+    let ask_trace_dir = with_ask.then(tempfile::tempdir).transpose().map_err(|e| {
+        AppError::new(
+            "KC_EVAL_TRACE_DIR_FAILED",
+            "eval",
+            "failed creating a temporary trace directory for eval asks",
+            true,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+    let requested: Vec<Option<&str>> = if profiles.is_empty() {
+        vec![None]
+    } else {
+        profiles.iter().map(|p| Some(p.as_str())).collect()
+    };
+
+    let mut runs = Vec::new();
+    for name in requested {
+        let profile = retrieval_profile_for_vault(&vault, name)?;
+        let search = judgments
+            .queries
+            .iter()
+            .map(|query| {
+                let hits = search_query_service(
+                    vault_path,
+                    &indexes.lexical,
+                    &indexes.vector,
+                    SearchReq {
+                        query: &query.query,
+                        limit: k,
+                        include_superseded: false,
+                        filter: &query.filter,
+                        profile: Some(&profile.name),
+                        now_ms,
+                    },
+                )
+                .map(|result| {
+                    result
+                        .hits
+                        .into_iter()
+                        .map(|hit| (hit.doc_id, hit.locator.range))
+                        .collect()
+                });
+                eval_query(query, k, hits)
+            })
+            .collect();
+        let ask = ask_trace_dir.as_ref().map(|trace_dir| {
+            let service = RetrievedOnlyAskService {
+                trace_dir_name: trace_dir.path().to_string_lossy().into_owned(),
+                ..RetrievedOnlyAskService::default()
+            };
+            let queries = judgments
+                .queries
+                .iter()
+                .map(|query| {
+                    let cited = service
+                        .ask(AskRequest {
+                            vault_path: vault_path.to_path_buf(),
+                            question: query.query.clone(),
+                            filter: query.filter.clone(),
+                            profile: Some(profile.name.clone()),
+                            min_score: None,
+                            now_ms,
+                        })
+                        .map(|out| {
+                            out.citations
+                                .into_iter()
+                                .flat_map(|(_, locators)| locators)
+                                .map(|locator| (locator.doc_id.0, locator.range))
+                                .collect()
+                        });
+                    eval_query(query, k, cited)
+                })
+                .collect();
+            summarize(queries)
+        });
+        runs.push(EvalRunV1 {
+            profile: profile.name,
+            config_hash: profile.config_hash.0,
+            search: summarize(search),
+            ask,
+        });
+    }
+
+    Ok(EvalReportV1 {
+        schema_version: 1,
+        judgments_hash: blake3_hex_prefixed(judgments_bytes),
+        k,
+        now_ms,
+        embedding_model_id: vault.defaults.embedding_model_id,
+        runs,
+    })
+}
+
+pub fn run_eval(
+    vault_path: &str,
+    judgments_path: &str,
+    k: usize,
+    profiles: &[String],
+    with_ask: bool,
+    now_ms: i64,
+) -> AppResult<()> {
+    let bytes = fs::read(judgments_path).map_err(|e| {
+        judgments_invalid(
+            "failed reading judgments file",
+            serde_json::json!({ "error": e.to_string(), "path": judgments_path }),
+        )
+    })?;
+    let report = evaluate(
+        Path::new(vault_path),
+        &bytes,
+        k.max(1),
+        profiles,
+        with_ask,
+        now_ms,
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, parse_judgments, score_ranking, JudgmentV1};
+    use kc_core::chunking::default_chunking_config_v1;
+    use kc_core::db::open_db;
+    use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+    use kc_core::locator::LocatorRange;
+    use kc_core::object_store::ObjectStore;
+    use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+    use kc_core::vault::vault_init;
+    use kc_extract::DefaultExtractor;
+    use kc_index::open_vault_indexes;
+
+    fn range(start: i64, end: i64) -> LocatorRange {
+        LocatorRange { start, end }
+    }
+
+    #[test]
+    fn eval_scores_recall_mrr_and_ndcg_crediting_each_judgment_once() {
+        let relevant = vec![
+            JudgmentV1 {
+                doc_id: "a".to_string(),
+                range: Some(range(0, 10)),
+                grade: 2,
+            },
+            JudgmentV1 {
+                doc_id: "b".to_string(),
+                range: None,
+                grade: 1,
+            },
+        ];
+        let retrieved = vec![
+            ("c".to_string(), range(0, 10)),
+            ("a".to_string(), range(5, 15)),
+            ("a".to_string(), range(0, 3)),
+            ("b".to_string(), range(40, 50)),
+        ];
+
+        let (top3, found, first) = score_ranking(&retrieved, &relevant, 3);
+        assert_eq!((found, first), (1, Some(2)));
+        assert_eq!(top3.recall_at_k, 0.5);
+        assert_eq!(top3.mrr, 0.5);
+        let ideal = 2.0 + 1.0 / 3f64.log2();
+        let expected = ((2.0 / 3f64.log2()) / ideal * 1e6).round() / 1e6;
+        assert_eq!(top3.ndcg_at_k, expected);
+
+        let (top4, found, _) = score_ranking(&retrieved, &relevant, 4);
+        assert_eq!(found, 2);
+        assert_eq!(top4.recall_at_k, 1.0);
+
+        let (none, found, first) = score_ranking(&retrieved[..1], &relevant, 10);
+        assert_eq!((found, first), (0, None));
+        assert_eq!((none.mrr, none.ndcg_at_k), (0.0, 0.0));
+    }
+
+    #[test]
+    fn eval_rejects_malformed_judgments() {
+        for bytes in [
+            &br#"{"schema_version":2,"queries":[]}"#[..],
+            br#"{"schema_version":1,"queries":[{"id":"q","query":"x","relevant":[],"extra":1}]}"#,
+            br#"{"schema_version":1,"queries":[{"id":"q","query":"x","relevant":[{"doc_id":"d"}]},{"id":"q","query":"y","relevant":[{"doc_id":"d"}]}]}"#,
+            br#"{"schema_version":1,"queries":[{"id":"q","query":"x","relevant":[{"doc_id":"d","grade":0}]}]}"#,
+        ] {
+            let err = parse_judgments(bytes).expect_err("invalid judgments");
+            assert_eq!(err.code, "KC_EVAL_JUDGMENTS_INVALID");
+        }
+    }
+
+    #[test]
+    fn eval_reports_search_and_ask_metrics_per_profile() {
+        let root = tempfile::tempdir().expect("tempdir").keep();
+        let vault = vault_init(&root, "demo", 1).expect("vault init");
+        let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+        let store = ObjectStore::new(root.join("store/objects"));
+        let indexes = open_vault_indexes(&root).expect("indexes");
+        let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+        let chunking = default_chunking_config_v1();
+        let services = PipelineServices {
+            extractor: &extractor,
+            lexical: &indexes.lexical,
+            vector: &indexes.vector,
+            chunking: &chunking,
+        };
+        let mut doc_ids = Vec::new();
+        for (bytes, now_ms) in [
+            (&b"rotate the signing keys every quarter"[..], 1),
+            (&b"the cafeteria menu changes weekly"[..], 2),
+        ] {
+            let doc = ingest_bytes(
+                &conn,
+                &store,
+                IngestBytesReq {
+                    bytes,
+                    mime: "text/plain",
+                    source_kind: "notes",
+                    effective_ts_ms: now_ms,
+                    source_path: None,
+                    now_ms,
+                },
+            )
+            .expect("ingest");
+            run_doc_pipeline(&conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+            doc_ids.push(doc.doc_id.0);
+        }
+        drop(indexes);
+
+        let judgments = serde_json::to_vec(&serde_json::json!({
+            "schema_version": 1,
+            "queries": [
+                { "id": "keys", "query": "signing keys", "relevant": [{ "doc_id": doc_ids[0] }] },
+                { "id": "menu", "query": "menu", "relevant": [{ "doc_id": doc_ids[1], "grade": 2 }] },
+                { "id": "broken", "query": "keys\" OR (menu", "relevant": [{ "doc_id": doc_ids[0] }] }
+            ]
+        }))
+        .expect("judgments json");
+
+        let report = evaluate(&root, &judgments, 5, &[], true, 3).expect("evaluate");
+        assert_eq!(report.runs.len(), 1);
+        let run = &report.runs[0];
+        assert_eq!(run.profile, "default");
+        assert!(run.config_hash.starts_with("blake3:"));
+        assert_eq!(run.search.queries[0].first_relevant_rank, Some(1));
+        assert_eq!(run.search.queries[1].metrics.ndcg_at_k, 1.0);
+        assert_eq!(
+            run.search.queries[2].error.as_deref(),
+            Some("KC_QUERY_INVALID")
+        );
+        assert_eq!(run.search.summary.mrr, 0.666667);
+        let ask = run.ask.as_ref().expect("ask metrics");
+        assert_eq!(ask.queries[0].first_relevant_rank, Some(1));
+        assert_eq!(ask.queries[0].metrics.recall_at_k, 1.0);
+
+        let again = evaluate(&root, &judgments, 5, &[], false, 3).expect("evaluate again");
+        assert!(again.runs[0].ask.is_none());
+        assert_eq!(
+            serde_json::to_value(&again.runs[0].search).expect("search json"),
+            serde_json::to_value(&run.search).expect("search json")
+        );
+
+        let err = evaluate(&root, &judgments, 5, &["missing".to_string()], false, 3)
+            .expect_err("unknown profile");
+        assert_eq!(err.code, "KC_RETRIEVAL_PROFILE_NOT_FOUND");
+    }
+}
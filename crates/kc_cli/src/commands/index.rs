@@ -1,46 +1,19 @@
 use kc_core::app_error::{AppError, AppResult};
 use kc_core::canonical::load_canonical_text;
 use kc_core::db::open_db;
+use kc_core::diversify::simhash64;
 use kc_core::object_store::ObjectStore;
 use kc_core::types::{ChunkId, DocId};
 use kc_core::vault::{vault_open, vault_paths};
-use kc_index::embedding::{Embedder, EmbeddingIdentity};
+use kc_index::embedding::Embedder;
+use kc_index::embedding_registry::EmbedderRegistry;
 use kc_index::fts::{rebuild_rows, FtsRow};
 use kc_index::vector::{LanceDbVectorIndex, VectorRow};
+use kc_index::{vault_fts_config, vault_vector_index_config};
 use std::path::Path;
 
-struct DeterministicEmbedder;
-
-impl Embedder for DeterministicEmbedder {
-    fn identity(&self) -> EmbeddingIdentity {
-        EmbeddingIdentity {
-            model_id: "deterministic-v1".to_string(),
-            model_hash: "blake3:deterministic-v1".to_string(),
-            dims: 8,
-            provider: "kc_cli".to_string(),
-            provider_version: "1".to_string(),
-            flags_json: serde_json::json!({ "algorithm": "byte-histogram-8" }).to_string(),
-        }
-    }
-
-    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
-        Ok(texts
-            .iter()
-            .map(|text| {
-                let mut bins = [0f32; 8];
-                for byte in text.bytes() {
-                    let idx = (byte as usize) % 8;
-                    bins[idx] += 1.0;
-                }
-                let norm = bins.iter().map(|x| x * x).sum::<f32>().sqrt();
-                if norm > 0.0 {
-                    bins.iter_mut().for_each(|x| *x /= norm);
-                }
-                bins.to_vec()
-            })
-            .collect())
-    }
-}
+// chunk_id, ordinal, start_char, end_char, simhash
+type ChunkRow = (String, i64, i64, i64, Option<i64>);
 
 fn slice_chars(text: &str, start: i64, end: i64) -> String {
     text.chars()
@@ -52,7 +25,7 @@ fn slice_chars(text: &str, start: i64, end: i64) -> String {
 pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
     let vault = vault_open(Path::new(vault_path))?;
     let paths = vault_paths(Path::new(vault_path));
-    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let conn = open_db(&Path::new(vault_path).join(&vault.db.relative_path))?;
     let object_store = ObjectStore::new(paths.objects_dir.clone());
 
     let mut canonical_stmt = conn
@@ -102,7 +75,7 @@ pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
 
         let mut chunk_stmt = conn
             .prepare(
-                "SELECT chunk_id, ordinal, start_char, end_char
+                "SELECT chunk_id, ordinal, start_char, end_char, simhash
                  FROM chunks
                  WHERE doc_id=?1
                  ORDER BY ordinal ASC, chunk_id ASC",
@@ -117,9 +90,15 @@ pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
                 )
             })?;
 
-        let chunk_rows: Vec<(String, i64, i64, i64)> = chunk_stmt
+        let chunk_rows: Vec<ChunkRow> = chunk_stmt
             .query_map([doc_id.clone()], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
             })
             .map_err(|e| {
                 AppError::new(
@@ -152,8 +131,24 @@ pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
             continue;
         }
 
-        for (chunk_id, ordinal, start, end) in chunk_rows {
+        for (chunk_id, ordinal, start, end, simhash) in chunk_rows {
             let content = slice_chars(&canonical, start, end);
+            // Chunks stored before migration 0016 have no fingerprint yet.
+            if simhash.is_none() {
+                conn.execute(
+                    "UPDATE chunks SET simhash=?1 WHERE chunk_id=?2",
+                    (simhash64(&content), chunk_id.as_str()),
+                )
+                .map_err(|e| {
+                    AppError::new(
+                        "KC_FTS_REBUILD_FAILED",
+                        "index",
+                        "failed backfilling chunk simhash",
+                        false,
+                        serde_json::json!({ "error": e.to_string(), "chunk_id": chunk_id }),
+                    )
+                })?;
+            }
             fts_rows.push(FtsRow {
                 chunk_id: chunk_id.clone(),
                 doc_id: doc_id.clone(),
@@ -170,9 +165,10 @@ pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
         }
     }
 
-    rebuild_rows(&conn, &fts_rows)?;
+    rebuild_rows(&conn, &vault_fts_config(&vault)?, &fts_rows)?;
 
-    let embedder = DeterministicEmbedder;
+    let embedder = EmbedderRegistry::for_vault(Path::new(vault_path), &vault)?
+        .load(&vault.defaults.embedding_model_id)?;
     let texts: Vec<String> = vector_rows.iter().map(|r| r.text.clone()).collect();
     let vectors = embedder.embed(&texts)?;
     for (row, vec) in vector_rows.iter_mut().zip(vectors) {
@@ -180,7 +176,11 @@ pub fn run_rebuild(vault_path: &str) -> AppResult<()> {
     }
 
     let vectors_path = paths.vectors_dir.join("lancedb-v1");
-    let mut vector_index = LanceDbVectorIndex::open(embedder, vectors_path)?;
+    let mut vector_index = LanceDbVectorIndex::open_for_rebuild(
+        embedder,
+        vectors_path,
+        vault_vector_index_config(&vault)?,
+    )?;
     vector_index.upsert_rows(vector_rows)?;
 
     println!("index rebuild completed");
@@ -192,6 +192,7 @@ mod tests {
     use super::run_rebuild;
     use kc_core::canonical::persist_canonical_text;
     use kc_core::db::open_db;
+    use kc_core::diversify::simhash64;
     use kc_core::hashing::blake3_hex_prefixed;
     use kc_core::ingest::{ingest_bytes, IngestBytesReq};
     use kc_core::object_store::ObjectStore;
@@ -200,7 +201,7 @@ mod tests {
     use kc_core::vault::vault_init;
 
     #[test]
-    fn index_rebuild_populates_fts_and_vectors() {
+    fn index_rebuild_populates_fts_and_vectors_and_backfills_simhash() {
         let root = tempfile::tempdir().expect("tempdir").keep();
         vault_init(&root, "demo", 1).expect("vault init");
         let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
@@ -225,7 +226,7 @@ mod tests {
             &conn,
             &store,
             &CanonicalTextArtifact {
-                doc_id: ingested.doc_id,
+                doc_id: ingested.doc_id.clone(),
                 canonical_bytes: canonical,
                 canonical_hash: CanonicalHash(hash.clone()),
                 canonical_object_hash: ObjectHash(hash),
@@ -238,6 +239,13 @@ mod tests {
             1,
         )
         .expect("persist canonical");
+        // A chunk written before chunks carried a fingerprint.
+        conn.execute(
+            "INSERT INTO chunks (chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind, simhash)
+             VALUES ('c1', ?1, 0, 6, 19, 'cfg', 'notes', NULL)",
+            [ingested.doc_id.0.as_str()],
+        )
+        .expect("insert chunk");
 
         run_rebuild(root.to_string_lossy().as_ref()).expect("rebuild");
 
@@ -246,5 +254,13 @@ mod tests {
             .expect("count fts rows");
         assert!(fts_rows >= 1);
         assert!(root.join("index/vectors/lancedb-v1").exists());
+        let simhash: Option<i64> = conn
+            .query_row(
+                "SELECT simhash FROM chunks WHERE chunk_id='c1'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("simhash");
+        assert_eq!(simhash, Some(simhash64("deterministic")));
     }
 }
@@ -1,10 +1,19 @@
 use kc_core::app_error::{AppError, AppResult};
+use kc_core::chunking::default_chunking_config_v1;
 use kc_core::db::open_db;
-use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::inbox::{
+    inbox_move_processed, inbox_watch_request_stop, inbox_watch_run, InboxWatchConfigV1,
+};
+use kc_core::ingest::{file_effective_ts_ms, ingest_bytes, IngestBytesReq};
 use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::types::DocId;
 use kc_core::vault::{vault_open, vault_paths};
+use kc_extract::DefaultExtractor;
+use kc_index::open_vault_indexes;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
 
 fn now_ms() -> i64 {
     let now = std::time::SystemTime::now()
@@ -13,15 +22,6 @@ fn now_ms() -> i64 {
     now.as_millis() as i64
 }
 
-fn effective_ts_ms(path: &Path, fallback_ms: i64) -> i64 {
-    fs::metadata(path)
-        .and_then(|m| m.modified())
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(fallback_ms)
-}
-
 fn detect_mime(path: &Path) -> &'static str {
     match path
         .extension()
@@ -41,6 +41,9 @@ fn ingest_one(vault_path: &Path, file_path: &Path, source_kind: &str) -> AppResu
     let paths = vault_paths(vault_path);
     let db = open_db(&vault_path.join(opened.db.relative_path))?;
     let store = ObjectStore::new(paths.objects_dir);
+    let extractor = DefaultExtractor::for_vault_toolchain(&opened.toolchain);
+    let indexes = open_vault_indexes(vault_path)?;
+    let chunking = default_chunking_config_v1();
 
     let bytes = fs::read(file_path).map_err(|e| {
         AppError::new(
@@ -60,13 +63,30 @@ fn ingest_one(vault_path: &Path, file_path: &Path, source_kind: &str) -> AppResu
             bytes: &bytes,
             mime: detect_mime(file_path),
             source_kind,
-            effective_ts_ms: effective_ts_ms(file_path, now),
+            effective_ts_ms: file_effective_ts_ms(file_path, now),
             source_path: file_path.to_str(),
             now_ms: now,
         },
     )?;
+    let indexed = run_doc_pipeline(
+        &db,
+        &store,
+        &PipelineServices {
+            extractor: &extractor,
+            lexical: &indexes.lexical,
+            vector: &indexes.vector,
+            chunking: &chunking,
+        },
+        &doc.doc_id,
+        now,
+    )?;
 
-    println!("ingested {} -> {}", file_path.display(), doc.doc_id.0);
+    println!(
+        "ingested {} -> {} ({} chunks)",
+        file_path.display(),
+        doc.doc_id.0,
+        indexed.chunk_count
+    );
     Ok(())
 }
 
@@ -107,26 +127,45 @@ pub fn ingest_inbox_once(vault_path: &str, file_path: &str, source_kind: &str) -
             )
         })?;
 
-    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
-    let ext = file.extension().and_then(|s| s.to_str()).unwrap_or("");
-    let suffix = doc_id.chars().skip(7).take(8).collect::<String>();
-    let mut target = Path::new(vault_path)
-        .join("Inbox/processed")
-        .join(format!("{}__{}", stem, suffix));
-    if !ext.is_empty() {
-        target.set_extension(ext);
-    }
-
-    fs::rename(&file, &target).map_err(|e| {
-        AppError::new(
-            "KC_INBOX_MOVE_FAILED",
-            "ingest",
-            "failed to move inbox file into processed",
-            false,
-            serde_json::json!({ "error": e.to_string(), "from": file, "to": target }),
-        )
-    })?;
+    let target = inbox_move_processed(Path::new(vault_path), &file, &DocId(doc_id))?;
 
     println!("moved {} -> {}", file.display(), target.display());
     Ok(())
 }
+
+pub fn ingest_inbox_watch(vault_path: &str, config: &InboxWatchConfigV1) -> AppResult<()> {
+    let root = Path::new(vault_path);
+    let opened = vault_open(root)?;
+    let db = open_db(&root.join(&opened.db.relative_path))?;
+    let store = ObjectStore::new(vault_paths(root).objects_dir);
+    let extractor = DefaultExtractor::for_vault_toolchain(&opened.toolchain);
+    let indexes = open_vault_indexes(root)?;
+    let chunking = default_chunking_config_v1();
+    let stop = AtomicBool::new(false);
+
+    println!("watching {}", vault_paths(root).inbox_dir.display());
+    let summary = inbox_watch_run(
+        &db,
+        &store,
+        &PipelineServices {
+            extractor: &extractor,
+            lexical: &indexes.lexical,
+            vector: &indexes.vector,
+            chunking: &chunking,
+        },
+        root,
+        config,
+        &stop,
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&summary).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+pub fn ingest_inbox_stop(vault_path: &str) -> AppResult<()> {
+    let path = inbox_watch_request_stop(Path::new(vault_path))?;
+    println!("requested inbox watch stop via {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::jobs::{
+    job_cancel, job_enqueue, jobs_list, run_pending_jobs, JOB_DEFAULT_MAX_ATTEMPTS,
+    JOB_KIND_INGEST_SCAN_FOLDER,
+};
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::PipelineServices;
+use kc_core::vault::{vault_open, vault_paths};
+use kc_extract::DefaultExtractor;
+use kc_index::open_vault_indexes;
+use std::path::Path;
+
+pub fn run_list(vault_path: &str) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let jobs = jobs_list(&conn)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&jobs).unwrap_or_else(|_| "[]".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_cancel(vault_path: &str, job_id: &str, now_ms: i64) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let cancelled = job_cancel(&conn, job_id, now_ms)?;
+    let out = serde_json::json!({
+        "job_id": job_id,
+        "cancelled": cancelled,
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_enqueue_scan(
+    vault_path: &str,
+    scan_root: &str,
+    source_kind: &str,
+    max_attempts: Option<i64>,
+    now_ms: i64,
+) -> AppResult<()> {
+    let vault = vault_open(Path::new(vault_path))?;
+    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let job = job_enqueue(
+        &conn,
+        JOB_KIND_INGEST_SCAN_FOLDER,
+        &serde_json::json!({
+            "scan_root": scan_root,
+            "source_kind": source_kind,
+        }),
+        max_attempts.unwrap_or(JOB_DEFAULT_MAX_ATTEMPTS),
+        now_ms,
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&job).unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+pub fn run_pending(vault_path: &str, max_jobs: usize, now_ms: i64) -> AppResult<()> {
+    let root = Path::new(vault_path);
+    let vault = vault_open(root)?;
+    let conn = open_db(&root.join(&vault.db.relative_path))?;
+    let store = ObjectStore::new(vault_paths(root).objects_dir);
+    let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+    let indexes = open_vault_indexes(root)?;
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &extractor,
+        lexical: &indexes.lexical,
+        vector: &indexes.vector,
+        chunking: &chunking,
+    };
+
+    let finished = run_pending_jobs(&conn, &store, &services, now_ms, max_jobs)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&finished).unwrap_or_else(|_| "[]".to_string())
+    );
+    Ok(())
+}
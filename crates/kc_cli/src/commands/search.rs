@@ -0,0 +1,172 @@
+use kc_core::app_error::AppResult;
+use kc_core::rpc_service::search_query_service;
+use kc_core::search::{SearchFilterV1, SearchReq};
+use kc_index::open_vault_indexes;
+use std::path::Path;
+
+pub fn run_search(
+    vault_path: &str,
+    query: &str,
+    limit: usize,
+    include_superseded: bool,
+    filter: &SearchFilterV1,
+    profile: Option<&str>,
+    now_ms: i64,
+) -> AppResult<()> {
+    let root = Path::new(vault_path);
+    let indexes = open_vault_indexes(root)?;
+    let result = search_query_service(
+        root,
+        &indexes.lexical,
+        &indexes.vector,
+        SearchReq {
+            query,
+            limit,
+            include_superseded,
+            filter,
+            profile,
+            now_ms,
+        },
+    )?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "query": query,
+            "filter": filter,
+            "profile": profile,
+            "hits": result.hits,
+            "facets": result.facets,
+            "diversify": result.diversify,
+        }))
+        .unwrap_or_else(|_| "{}".to_string())
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use kc_core::chunking::default_chunking_config_v1;
+    use kc_core::db::open_db;
+    use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+    use kc_core::object_store::ObjectStore;
+    use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+    use kc_core::rpc_service::search_query_service;
+    use kc_core::search::{SearchFilterV1, SearchReq};
+    use kc_core::vault::vault_init;
+    use kc_extract::DefaultExtractor;
+    use kc_index::open_vault_indexes;
+
+    #[test]
+    fn search_ranks_chunks_from_fts_and_vector_indexes() {
+        let root = tempfile::tempdir().expect("tempdir").keep();
+        let vault = vault_init(&root, "demo", 1).expect("vault init");
+        let conn = open_db(&root.join("db/knowledge.sqlite")).expect("open db");
+        let store = ObjectStore::new(root.join("store/objects"));
+        let indexes = open_vault_indexes(&root).expect("indexes");
+        let extractor = DefaultExtractor::for_vault_toolchain(&vault.toolchain);
+        let chunking = default_chunking_config_v1();
+        let services = PipelineServices {
+            extractor: &extractor,
+            lexical: &indexes.lexical,
+            vector: &indexes.vector,
+            chunking: &chunking,
+        };
+        for (bytes, now_ms) in [
+            (&b"rotate the signing keys every quarter"[..], 1),
+            (&b"the cafeteria menu changes weekly"[..], 2),
+        ] {
+            let doc = ingest_bytes(
+                &conn,
+                &store,
+                IngestBytesReq {
+                    bytes,
+                    mime: "text/plain",
+                    source_kind: "notes",
+                    effective_ts_ms: now_ms,
+                    source_path: None,
+                    now_ms,
+                },
+            )
+            .expect("ingest");
+            run_doc_pipeline(&conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+        }
+
+        let hits = search_query_service(
+            &root,
+            &indexes.lexical,
+            &indexes.vector,
+            SearchReq {
+                query: "signing keys",
+                limit: 5,
+                include_superseded: false,
+                filter: &SearchFilterV1::default(),
+                profile: None,
+                now_ms: 3,
+            },
+        )
+        .expect("search")
+        .hits;
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].lexical_rank, Some(1));
+        assert!(hits[0].vector_rank.is_some());
+        assert!(hits[0].snippet.contains("signing keys"));
+        assert!(hits[1].lexical_rank.is_none());
+        assert!(hits[0].final_score > hits[1].final_score);
+
+        let err = search_query_service(
+            &root,
+            &indexes.lexical,
+            &indexes.vector,
+            SearchReq {
+                query: "keys\" OR (menu",
+                limit: 5,
+                include_superseded: false,
+                filter: &SearchFilterV1::default(),
+                profile: None,
+                now_ms: 3,
+            },
+        )
+        .expect_err("unterminated phrase");
+        assert_eq!(err.code, "KC_QUERY_INVALID");
+        assert_eq!(err.details["position"], 4);
+
+        let other_kind = search_query_service(
+            &root,
+            &indexes.lexical,
+            &indexes.vector,
+            SearchReq {
+                query: "signing keys source:manuals",
+                limit: 5,
+                include_superseded: false,
+                filter: &SearchFilterV1::default(),
+                profile: None,
+                now_ms: 3,
+            },
+        )
+        .expect("field operator search");
+        assert!(other_kind.hits.is_empty());
+
+        let later = search_query_service(
+            &root,
+            &indexes.lexical,
+            &indexes.vector,
+            SearchReq {
+                query: "signing keys",
+                limit: 5,
+                include_superseded: false,
+                filter: &SearchFilterV1 {
+                    effective_ts_from_ms: Some(2),
+                    ..SearchFilterV1::default()
+                },
+                profile: None,
+                now_ms: 3,
+            },
+        )
+        .expect("filtered search");
+        assert_eq!(later.hits.len(), 1);
+        assert!(later.hits[0].snippet.contains("cafeteria"));
+        assert_eq!(later.hits[0].lexical_rank, None);
+        assert_eq!(later.hits[0].vector_rank, Some(1));
+        assert_eq!(later.facets.source_kind[0].count, 1);
+    }
+}
@@ -4,7 +4,9 @@ use kc_core::sync::{
     sync_merge_preview_target_with_policy, sync_pull_target_with_mode, sync_push_target,
     sync_status_target,
 };
+use kc_core::tombstone::doc_tombstones_purge_indexes;
 use kc_core::vault::vault_open;
+use kc_index::open_vault_indexes;
 use std::path::Path;
 
 pub fn run_status(vault_path: &str, target_path: &str) -> AppResult<()> {
@@ -36,7 +38,7 @@ pub fn run_pull(
     auto_merge_mode: Option<&str>,
 ) -> AppResult<()> {
     let vault = vault_open(Path::new(vault_path))?;
-    let conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    let conn = open_db(&Path::new(vault_path).join(&vault.db.relative_path))?;
     let out = sync_pull_target_with_mode(
         &conn,
         Path::new(vault_path),
@@ -44,6 +46,9 @@ pub fn run_pull(
         now_ms,
         auto_merge_mode,
     )?;
+    let indexes = open_vault_indexes(Path::new(vault_path))?;
+    let post_conn = open_db(&Path::new(vault_path).join(vault.db.relative_path))?;
+    doc_tombstones_purge_indexes(&post_conn, &indexes.lexical, &indexes.vector)?;
     println!(
         "{}",
         serde_json::to_string_pretty(&out).unwrap_or_else(|_| "{}".to_string())
@@ -1,13 +1,18 @@
 mod cli;
 mod commands {
+    pub mod ask;
     pub mod bench;
     pub mod deps;
+    pub mod doc;
+    pub mod eval;
     pub mod export;
     pub mod fixtures;
     pub mod gc;
     pub mod index;
     pub mod ingest;
+    pub mod jobs;
     pub mod lineage;
+    pub mod search;
     pub mod sync;
     pub mod trust;
     pub mod vault;
@@ -17,13 +22,17 @@ mod verifier;
 
 use clap::Parser;
 use cli::{
-    BenchCmd, Cli, Command, DepsCmd, FixturesCmd, GcCmd, IndexCmd, IngestCmd, LineageCmd,
-    LineageLockCmd, LineageOverlayCmd, LineagePolicyCmd, LineageRoleCmd, SyncCmd, TrustCmd,
-    TrustDeviceCmd, TrustIdentityCmd, TrustPolicyCmd, TrustProviderCmd, VaultCmd,
-    VaultDbEncryptCmd, VaultEncryptCmd, VaultRecoveryCmd, VaultRecoveryEscrowCmd,
-    VaultRecoveryEscrowProviderCmd,
+    AskCmd, AskSessionCmd, AskTraceCmd, BenchCmd, Cli, Command, DepsCmd, DocCmd, FixturesCmd,
+    GcCmd, IndexCmd, IngestCmd, JobsCmd, LineageCmd, LineageLockCmd, LineageOverlayCmd,
+    LineagePolicyCmd, LineageRoleCmd, SyncCmd, TrustCmd, TrustDeviceCmd, TrustIdentityCmd,
+    TrustPolicyCmd, TrustProviderCmd, VaultCmd, VaultDbEncryptCmd, VaultEncryptCmd,
+    VaultRecoveryCmd, VaultRecoveryEscrowCmd, VaultRecoveryEscrowProviderCmd,
 };
+use kc_ask::AskRequest;
+use kc_core::inbox::InboxWatchConfigV1;
+use kc_core::search::SearchFilterV1;
 use kc_core::vault::{vault_init, vault_open};
+use std::path::PathBuf;
 
 fn now_ms() -> i64 {
     let now = std::time::SystemTime::now()
@@ -178,6 +187,24 @@ fn main() {
                 file_path,
                 source_kind,
             } => commands::ingest::ingest_inbox_once(&vault_path, &file_path, &source_kind),
+            IngestCmd::InboxWatch {
+                vault_path,
+                source_kind,
+                debounce_ms,
+                poll_interval_ms,
+                backend,
+                max_runtime_ms,
+            } => commands::ingest::ingest_inbox_watch(
+                &vault_path,
+                &InboxWatchConfigV1 {
+                    source_kind,
+                    debounce_ms,
+                    poll_interval_ms,
+                    backend,
+                    max_runtime_ms,
+                },
+            ),
+            IngestCmd::InboxStop { vault_path } => commands::ingest::ingest_inbox_stop(&vault_path),
         },
         Command::Export {
             vault_path,
@@ -201,7 +228,212 @@ fn main() {
             IndexCmd::Rebuild { vault_path } => commands::index::run_rebuild(&vault_path),
         },
         Command::Gc { cmd } => match cmd {
-            GcCmd::Run { vault_path } => commands::gc::run_gc(&vault_path),
+            GcCmd::Run {
+                vault_path,
+                dry_run,
+                now_ms: now_ms_opt,
+            } => commands::gc::run_gc(&vault_path, dry_run, now_ms_opt.unwrap_or_else(now_ms)),
+        },
+        Command::Search {
+            vault_path,
+            query,
+            limit,
+            include_superseded,
+            source_kinds,
+            mimes,
+            from_ms,
+            to_ms,
+            path_prefix,
+            extractor,
+            profile,
+            now_ms: now_ms_opt,
+        } => commands::search::run_search(
+            &vault_path,
+            &query,
+            limit.unwrap_or(20),
+            include_superseded,
+            &SearchFilterV1 {
+                source_kinds,
+                mimes,
+                effective_ts_from_ms: from_ms,
+                effective_ts_to_ms: to_ms,
+                source_path_prefix: path_prefix,
+                extractor_name: extractor,
+            },
+            profile.as_deref(),
+            now_ms_opt.unwrap_or_else(now_ms),
+        ),
+        Command::Ask {
+            cmd: Some(AskCmd::Session { cmd }),
+            ..
+        } => match cmd {
+            AskSessionCmd::New {
+                vault_path,
+                title,
+                now_ms: now_ms_opt,
+            } => commands::ask::run_session_new(
+                &vault_path,
+                title.as_deref(),
+                now_ms_opt.unwrap_or_else(now_ms),
+            ),
+            AskSessionCmd::List { vault_path } => commands::ask::run_session_list(&vault_path),
+            AskSessionCmd::Show {
+                vault_path,
+                session_id,
+            } => commands::ask::run_session_show(&vault_path, &session_id),
+            AskSessionCmd::Delete {
+                vault_path,
+                session_id,
+            } => commands::ask::run_session_delete(&vault_path, &session_id),
+        },
+        Command::Ask {
+            cmd: Some(AskCmd::Trace { cmd }),
+            ..
+        } => match cmd {
+            AskTraceCmd::List { vault_path } => commands::ask::run_trace_list(&vault_path),
+            AskTraceCmd::Show {
+                vault_path,
+                trace_id,
+            } => commands::ask::run_trace_show(&vault_path, &trace_id),
+            AskTraceCmd::Prune {
+                vault_path,
+                now_ms: now_ms_opt,
+            } => commands::ask::run_trace_prune(&vault_path, now_ms_opt.unwrap_or_else(now_ms)),
+            AskTraceCmd::Replay {
+                vault_path,
+                trace_id,
+                now_ms: now_ms_opt,
+            } => commands::ask::run_trace_replay(
+                &vault_path,
+                &trace_id,
+                now_ms_opt.unwrap_or_else(now_ms),
+            ),
+        },
+        Command::Ask {
+            cmd:
+                Some(AskCmd::Replay {
+                    trace_path,
+                    vault_path,
+                    now_ms: now_ms_opt,
+                }),
+            ..
+        } => commands::ask::run_replay(
+            &trace_path,
+            vault_path.as_deref(),
+            now_ms_opt.unwrap_or_else(now_ms),
+        )
+        .map(|report| {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string())
+            );
+            if report.drift {
+                std::process::exit(2);
+            }
+        }),
+        Command::Ask {
+            cmd: None,
+            vault_path,
+            question,
+            stream,
+            session,
+            source_kinds,
+            mimes,
+            from_ms,
+            to_ms,
+            path_prefix,
+            extractor,
+            profile,
+            min_score,
+            now_ms: now_ms_opt,
+        } => commands::ask::run_ask(
+            AskRequest {
+                // clap requires both positionals when no subcommand is given.
+                vault_path: PathBuf::from(vault_path.unwrap_or_default()),
+                question: question.unwrap_or_default(),
+                filter: SearchFilterV1 {
+                    source_kinds,
+                    mimes,
+                    effective_ts_from_ms: from_ms,
+                    effective_ts_to_ms: to_ms,
+                    source_path_prefix: path_prefix,
+                    extractor_name: extractor,
+                },
+                profile,
+                min_score,
+                now_ms: now_ms_opt.unwrap_or_else(now_ms),
+            },
+            session.as_deref(),
+            stream,
+        ),
+        Command::Eval {
+            vault_path,
+            judgments_path,
+            k,
+            profiles,
+            ask,
+            now_ms: now_ms_opt,
+        } => commands::eval::run_eval(
+            &vault_path,
+            &judgments_path,
+            k.unwrap_or(10),
+            &profiles,
+            ask,
+            now_ms_opt.unwrap_or_else(now_ms),
+        ),
+        Command::Doc { cmd } => match cmd {
+            DocCmd::Delete {
+                vault_path,
+                doc_id,
+                now_ms: now_ms_opt,
+            } => commands::doc::run_delete(&vault_path, &doc_id, now_ms_opt.unwrap_or_else(now_ms)),
+            DocCmd::Tombstones { vault_path } => commands::doc::run_tombstones(&vault_path),
+            DocCmd::Versions { vault_path, doc_id } => {
+                commands::doc::run_versions(&vault_path, &doc_id)
+            }
+            DocCmd::Reextract {
+                vault_path,
+                dry_run,
+                limit,
+                now_ms: now_ms_opt,
+            } => commands::doc::run_reextract(
+                &vault_path,
+                dry_run,
+                limit,
+                now_ms_opt.unwrap_or_else(now_ms),
+            ),
+        },
+        Command::Jobs { cmd } => match cmd {
+            JobsCmd::List { vault_path } => commands::jobs::run_list(&vault_path),
+            JobsCmd::Cancel {
+                vault_path,
+                job_id,
+                now_ms: now_ms_opt,
+            } => {
+                commands::jobs::run_cancel(&vault_path, &job_id, now_ms_opt.unwrap_or_else(now_ms))
+            }
+            JobsCmd::EnqueueScan {
+                vault_path,
+                scan_root,
+                source_kind,
+                max_attempts,
+                now_ms: now_ms_opt,
+            } => commands::jobs::run_enqueue_scan(
+                &vault_path,
+                &scan_root,
+                &source_kind,
+                max_attempts,
+                now_ms_opt.unwrap_or_else(now_ms),
+            ),
+            JobsCmd::Run {
+                vault_path,
+                max_jobs,
+                now_ms: now_ms_opt,
+            } => commands::jobs::run_pending(
+                &vault_path,
+                max_jobs,
+                now_ms_opt.unwrap_or_else(now_ms),
+            ),
         },
         Command::Deps { cmd } => match cmd {
             DepsCmd::Check => commands::deps::run_check(),
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS jobs (
+  job_id TEXT PRIMARY KEY,
+  kind TEXT NOT NULL,
+  payload_json TEXT NOT NULL,
+  state TEXT NOT NULL CHECK (state IN ('queued', 'running', 'succeeded', 'failed', 'cancelled')),
+  attempts INTEGER NOT NULL,
+  max_attempts INTEGER NOT NULL,
+  next_run_at_ms INTEGER NOT NULL,
+  lease_expires_at_ms INTEGER,
+  progress_done INTEGER NOT NULL,
+  progress_total INTEGER NOT NULL,
+  last_error_json TEXT,
+  result_json TEXT,
+  created_at_ms INTEGER NOT NULL,
+  updated_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_state_next_run
+  ON jobs(state, next_run_at_ms, job_id);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS doc_tombstones (
+  doc_id TEXT PRIMARY KEY,
+  original_object_hash TEXT NOT NULL,
+  canonical_object_hash TEXT,
+  deleted_event_id INTEGER NOT NULL,
+  deleted_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_tombstones_deleted_at
+  ON doc_tombstones(deleted_at_ms, doc_id);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS doc_versions (
+  source_path TEXT NOT NULL,
+  version INTEGER NOT NULL,
+  doc_id TEXT NOT NULL,
+  event_id INTEGER NOT NULL,
+  PRIMARY KEY (source_path, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_doc_versions_doc
+  ON doc_versions(doc_id, source_path);
+
+INSERT OR IGNORE INTO doc_versions(source_path, version, doc_id, event_id)
+SELECT s.source_path,
+       ROW_NUMBER() OVER (PARTITION BY s.source_path ORDER BY d.ingested_event_id ASC, d.doc_id ASC),
+       s.doc_id,
+       d.ingested_event_id
+FROM doc_sources s
+JOIN docs d ON d.doc_id = s.doc_id;
@@ -0,0 +1,37 @@
+CREATE TABLE IF NOT EXISTS canonical_text_history (
+  doc_id TEXT NOT NULL,
+  canonical_hash TEXT NOT NULL,
+  canonical_object_hash TEXT NOT NULL,
+  extractor_name TEXT NOT NULL,
+  extractor_version TEXT NOT NULL,
+  extractor_flags_json TEXT NOT NULL,
+  normalization_version INTEGER NOT NULL,
+  toolchain_json TEXT NOT NULL,
+  created_event_id INTEGER NOT NULL,
+  PRIMARY KEY (doc_id, canonical_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_canonical_text_history_object
+  ON canonical_text_history(canonical_object_hash);
+
+INSERT OR IGNORE INTO canonical_text_history(
+  doc_id,
+  canonical_hash,
+  canonical_object_hash,
+  extractor_name,
+  extractor_version,
+  extractor_flags_json,
+  normalization_version,
+  toolchain_json,
+  created_event_id
+)
+SELECT doc_id,
+       canonical_hash,
+       canonical_object_hash,
+       extractor_name,
+       extractor_version,
+       extractor_flags_json,
+       normalization_version,
+       toolchain_json,
+       created_event_id
+FROM canonical_text;
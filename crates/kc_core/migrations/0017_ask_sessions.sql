@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS ask_sessions (
+  session_id TEXT PRIMARY KEY,
+  title TEXT,
+  created_at_ms INTEGER NOT NULL,
+  updated_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ask_sessions_updated_at
+  ON ask_sessions(updated_at_ms, session_id);
+
+CREATE TABLE IF NOT EXISTS ask_session_turns (
+  session_id TEXT NOT NULL,
+  turn_index INTEGER NOT NULL,
+  question TEXT NOT NULL,
+  standalone_query TEXT NOT NULL,
+  answer_text TEXT NOT NULL,
+  citations_json TEXT NOT NULL,
+  trace_id TEXT NOT NULL,
+  trace_path TEXT NOT NULL,
+  parent_trace_id TEXT,
+  created_at_ms INTEGER NOT NULL,
+  PRIMARY KEY (session_id, turn_index)
+);
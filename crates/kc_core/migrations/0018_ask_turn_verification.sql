@@ -0,0 +1 @@
+ALTER TABLE ask_session_turns ADD COLUMN verification_json TEXT;
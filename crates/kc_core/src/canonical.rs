@@ -70,6 +70,42 @@ pub fn persist_canonical_text(
         )
     })?;
 
+    // Earlier canonical versions stay in history so locators cut against them keep resolving.
+    conn.execute(
+        "INSERT INTO canonical_text_history (
+          doc_id,
+          canonical_hash,
+          canonical_object_hash,
+          extractor_name,
+          extractor_version,
+          extractor_flags_json,
+          normalization_version,
+          toolchain_json,
+          created_event_id
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT(doc_id, canonical_hash) DO NOTHING",
+        params![
+            artifact.doc_id.0,
+            artifact.canonical_hash.0,
+            stored_hash.0,
+            artifact.extractor_name,
+            artifact.extractor_version,
+            artifact.extractor_flags_json,
+            artifact.normalization_version,
+            artifact.toolchain_json,
+            created_event_id
+        ],
+    )
+    .map_err(|e| {
+        AppError::new(
+            "KC_DB_INTEGRITY_FAILED",
+            "canonical",
+            "failed to record canonical_text_history row",
+            false,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+
     Ok(())
 }
 
@@ -1,5 +1,6 @@
 use crate::app_error::{AppError, AppResult};
 use crate::canon_json::hash_canonical;
+use crate::diversify::simhash64;
 use crate::hashing::blake3_hex_prefixed;
 use crate::types::{ChunkId, ConfigHash, DocId};
 use serde::{Deserialize, Serialize};
@@ -32,6 +33,8 @@ pub struct ChunkRecord {
     pub start_char: i64,
     pub end_char: i64,
     pub chunking_config_hash: ConfigHash,
+    // Near-duplicate fingerprint of the chunk text (`diversify::simhash64`).
+    pub simhash: i64,
 }
 
 pub fn default_chunking_config_v1() -> ChunkingConfigV1 {
@@ -98,6 +101,14 @@ pub fn chunk_document(
         return Ok(Vec::new());
     }
 
+    let offsets: Vec<usize> = canonical_text
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(canonical_text.len()))
+        .collect();
+    let fingerprint = |start: i64, end: i64| {
+        simhash64(&canonical_text[offsets[start as usize]..offsets[end as usize]])
+    };
     let mut chunks = Vec::new();
 
     if mime == "application/pdf" {
@@ -119,6 +130,7 @@ pub fn chunk_document(
                 start_char: start,
                 end_char: end,
                 chunking_config_hash: cfg_hash.clone(),
+                simhash: fingerprint(start, end),
             });
             if end == total {
                 break;
@@ -144,6 +156,7 @@ pub fn chunk_document(
                 start_char: start,
                 end_char: end,
                 chunking_config_hash: cfg_hash.clone(),
+                simhash: fingerprint(start, end),
             });
 
             if end == total {
@@ -7,7 +7,7 @@ use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
 
-const LATEST_SCHEMA_VERSION: i64 = 11;
+const LATEST_SCHEMA_VERSION: i64 = 18;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DbMigrationOutcome {
@@ -924,6 +924,316 @@ pub fn apply_migrations(conn: &Connection) -> AppResult<()> {
             )
         })?;
 
+        tx.pragma_update(None, "user_version", 11i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v11 = schema_version(conn)?;
+    if current_after_v11 < 12 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0012_jobs.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0012",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
+        tx.pragma_update(None, "user_version", 12i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v12 = schema_version(conn)?;
+    if current_after_v12 < 13 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0013_doc_tombstones.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0013",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
+        tx.pragma_update(None, "user_version", 13i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v13 = schema_version(conn)?;
+    if current_after_v13 < 14 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0014_doc_versions.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0014",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
+        tx.pragma_update(None, "user_version", 14i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v14 = schema_version(conn)?;
+    if current_after_v14 < 15 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!(
+            "../migrations/0015_canonical_text_history.sql"
+        ))
+        .map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to apply migration 0015",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.pragma_update(None, "user_version", 15i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v15 = schema_version(conn)?;
+    if current_after_v15 < 16 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0016_chunk_simhash.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0016",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
+        tx.pragma_update(None, "user_version", 16i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v16 = schema_version(conn)?;
+    if current_after_v16 < 17 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0017_ask_sessions.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0017",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
+        tx.pragma_update(None, "user_version", 17i64).map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to set schema user_version",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.commit().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to commit migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    }
+
+    let current_after_v17 = schema_version(conn)?;
+    if current_after_v17 < 18 {
+        let tx = conn.unchecked_transaction().map_err(|e| {
+            AppError::new(
+                "KC_DB_MIGRATION_FAILED",
+                "db",
+                "failed to begin migration transaction",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+
+        tx.execute_batch(include_str!("../migrations/0018_ask_turn_verification.sql"))
+            .map_err(|e| {
+                AppError::new(
+                    "KC_DB_MIGRATION_FAILED",
+                    "db",
+                    "failed to apply migration 0018",
+                    false,
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+            })?;
+
         tx.pragma_update(None, "user_version", LATEST_SCHEMA_VERSION)
             .map_err(|e| {
                 AppError::new(
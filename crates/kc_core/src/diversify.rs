@@ -0,0 +1,242 @@
+use crate::retrieval::MergedHit;
+use crate::types::ChunkId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const SHINGLE_WORDS: usize = 3;
+// Hamming distance at which two fingerprints count as unrelated for MMR.
+const UNRELATED_DISTANCE: f64 = 32.0;
+
+// Applied after merging: chunks of one doc that overlap or touch the best-ranked one collapse
+// into it, near-duplicate fingerprints are dropped, each doc keeps at most `max_per_doc` hits,
+// and the rest are picked by MMR with relevance weight `lambda`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiversifyConfigV1 {
+    pub enabled: bool,
+    pub max_per_doc: usize,
+    pub lambda: f64,
+    pub near_duplicate_distance: u32,
+    pub collapse_adjacent: bool,
+}
+
+impl Default for DiversifyConfigV1 {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_per_doc: 3,
+            lambda: 0.7,
+            near_duplicate_distance: 3,
+            collapse_adjacent: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiversifyCandidate {
+    pub hit: MergedHit,
+    pub start_char: i64,
+    pub end_char: i64,
+    pub simhash: Option<i64>,
+}
+
+// `hit` is the best-ranked chunk of its group; `start_char..end_char` covers every chunk in
+// `collapsed` as well.
+#[derive(Debug, Clone)]
+pub struct DiversifiedHit {
+    pub hit: MergedHit,
+    pub start_char: i64,
+    pub end_char: i64,
+    pub collapsed: Vec<ChunkId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollapsedChunkV1 {
+    pub chunk_id: String,
+    pub into: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NearDuplicateV1 {
+    pub chunk_id: String,
+    pub duplicate_of: String,
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiversifyReportV1 {
+    pub collapsed: Vec<CollapsedChunkV1>,
+    pub near_duplicates: Vec<NearDuplicateV1>,
+    pub capped: Vec<String>,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+// 64-bit simhash over lower-cased word 3-shingles (one shingle when the text is shorter),
+// stored as the signed bit pattern. Text without words fingerprints to 0.
+pub fn simhash64(text: &str) -> i64 {
+    let words = words(text);
+    if words.is_empty() {
+        return 0;
+    }
+    let mut votes = [0i64; 64];
+    for shingle in words.windows(SHINGLE_WORDS.min(words.len())) {
+        let digest = blake3::hash(shingle.join(" ").as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest.as_bytes()[..8]);
+        let bits = u64::from_le_bytes(bytes);
+        for (bit, vote) in votes.iter_mut().enumerate() {
+            if bits >> bit & 1 == 1 {
+                *vote += 1;
+            } else {
+                *vote -= 1;
+            }
+        }
+    }
+    let mut out = 0u64;
+    for (bit, vote) in votes.iter().enumerate() {
+        if *vote > 0 {
+            out |= 1 << bit;
+        }
+    }
+    out as i64
+}
+
+pub fn simhash_distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+fn similarity(a: Option<i64>, b: Option<i64>) -> f64 {
+    match (a, b) {
+        (Some(a), Some(b)) => (1.0 - simhash_distance(a, b) as f64 / UNRELATED_DISTANCE).max(0.0),
+        _ => 0.0,
+    }
+}
+
+// `own_*` is the kept chunk's own range. Collapse tests against it rather than the grown
+// span, so touching chunks stay apart and a group cannot creep across the doc.
+struct Group {
+    hit: DiversifiedHit,
+    own_start: i64,
+    own_end: i64,
+    simhash: Option<i64>,
+}
+
+// `candidates` must be in merged order. Returns at most `limit` hits in selection order with
+// a report of what was folded away; disabled configs only truncate.
+pub fn diversify_hits(
+    candidates: Vec<DiversifyCandidate>,
+    cfg: &DiversifyConfigV1,
+    limit: usize,
+) -> (Vec<DiversifiedHit>, DiversifyReportV1) {
+    let mut report = DiversifyReportV1::default();
+    let mut groups: Vec<Group> = Vec::new();
+    for candidate in candidates {
+        if !cfg.enabled {
+            groups.push(Group {
+                hit: DiversifiedHit {
+                    hit: candidate.hit,
+                    start_char: candidate.start_char,
+                    end_char: candidate.end_char,
+                    collapsed: Vec::new(),
+                },
+                own_start: candidate.start_char,
+                own_end: candidate.end_char,
+                simhash: candidate.simhash,
+            });
+            continue;
+        }
+        if cfg.collapse_adjacent {
+            if let Some(group) = groups.iter_mut().find(|g| {
+                g.hit.hit.doc_id == candidate.hit.doc_id
+                    && candidate.start_char <= g.own_end
+                    && g.own_start <= candidate.end_char
+            }) {
+                group.hit.start_char = group.hit.start_char.min(candidate.start_char);
+                group.hit.end_char = group.hit.end_char.max(candidate.end_char);
+                report.collapsed.push(CollapsedChunkV1 {
+                    chunk_id: candidate.hit.chunk_id.0.clone(),
+                    into: group.hit.hit.chunk_id.0.clone(),
+                });
+                group.hit.collapsed.push(candidate.hit.chunk_id);
+                continue;
+            }
+        }
+        if let Some(simhash) = candidate.simhash {
+            let duplicate = groups.iter().find_map(|g| {
+                let distance = simhash_distance(simhash, g.simhash?);
+                (distance <= cfg.near_duplicate_distance).then_some((g, distance))
+            });
+            if let Some((group, distance)) = duplicate {
+                report.near_duplicates.push(NearDuplicateV1 {
+                    chunk_id: candidate.hit.chunk_id.0,
+                    duplicate_of: group.hit.hit.chunk_id.0.clone(),
+                    distance,
+                });
+                continue;
+            }
+        }
+        groups.push(Group {
+            hit: DiversifiedHit {
+                hit: candidate.hit,
+                start_char: candidate.start_char,
+                end_char: candidate.end_char,
+                collapsed: Vec::new(),
+            },
+            own_start: candidate.start_char,
+            own_end: candidate.end_char,
+            simhash: candidate.simhash,
+        });
+    }
+    if !cfg.enabled {
+        return (
+            groups.into_iter().take(limit).map(|g| g.hit).collect(),
+            report,
+        );
+    }
+
+    let mut per_doc: HashMap<String, usize> = HashMap::new();
+    let mut pool = Vec::with_capacity(groups.len());
+    for group in groups {
+        let count = per_doc.entry(group.hit.hit.doc_id.0.clone()).or_default();
+        if *count >= cfg.max_per_doc {
+            report.capped.push(group.hit.hit.chunk_id.0);
+            continue;
+        }
+        *count += 1;
+        pool.push(group);
+    }
+
+    let best = pool
+        .iter()
+        .map(|g| g.hit.hit.final_score)
+        .fold(0.0_f64, f64::max);
+    let mut selected: Vec<Group> = Vec::new();
+    while selected.len() < limit && !pool.is_empty() {
+        let mut pick = 0;
+        let mut pick_value = f64::NEG_INFINITY;
+        for (idx, group) in pool.iter().enumerate() {
+            let relevance = if best > 0.0 {
+                group.hit.hit.final_score / best
+            } else {
+                0.0
+            };
+            let redundancy = selected
+                .iter()
+                .map(|s| similarity(group.simhash, s.simhash))
+                .fold(0.0_f64, f64::max);
+            let value = cfg.lambda * relevance - (1.0 - cfg.lambda) * redundancy;
+            if value > pick_value {
+                pick = idx;
+                pick_value = value;
+            }
+        }
+        selected.push(pool.remove(pick));
+    }
+    (selected.into_iter().map(|g| g.hit).collect(), report)
+}
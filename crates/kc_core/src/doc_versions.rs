@@ -0,0 +1,135 @@
+use crate::app_error::{AppError, AppResult};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+// A doc is superseded once every source path it was ingested from has a newer head version.
+pub const SUPERSEDED_DOC_IDS_SQL: &str = "SELECT v.doc_id FROM doc_versions v
+     GROUP BY v.doc_id
+     HAVING SUM(v.version = (SELECT MAX(m.version) FROM doc_versions m WHERE m.source_path = v.source_path)) = 0";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocVersionV1 {
+    pub version: i64,
+    pub doc_id: String,
+    pub event_id: i64,
+    pub ingested_at_ms: i64,
+    pub bytes: Option<i64>,
+    pub mime: Option<String>,
+    pub superseded_by_doc_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocVersionHistoryV1 {
+    pub source_path: String,
+    pub current_doc_id: String,
+    pub versions: Vec<DocVersionV1>,
+}
+
+fn version_error(message: &str, e: rusqlite::Error) -> AppError {
+    AppError::new(
+        "KC_DB_INTEGRITY_FAILED",
+        "doc",
+        message,
+        false,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+pub(crate) fn record_doc_version(
+    conn: &Connection,
+    source_path: &str,
+    doc_id: &str,
+    event_id: i64,
+) -> AppResult<Option<i64>> {
+    let head: Option<(i64, String)> = conn
+        .query_row(
+            "SELECT version, doc_id FROM doc_versions WHERE source_path=?1
+             ORDER BY version DESC LIMIT 1",
+            params![source_path],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| version_error("failed loading doc version head", e))?;
+
+    let next = match head {
+        Some((_, head_doc_id)) if head_doc_id == doc_id => return Ok(None),
+        Some((version, _)) => version + 1,
+        None => 1,
+    };
+    conn.execute(
+        "INSERT INTO doc_versions(source_path, version, doc_id, event_id) VALUES (?1, ?2, ?3, ?4)",
+        params![source_path, next, doc_id, event_id],
+    )
+    .map_err(|e| version_error("failed inserting doc version", e))?;
+    Ok(Some(next))
+}
+
+pub fn doc_is_superseded(conn: &Connection, doc_id: &str) -> AppResult<bool> {
+    let sql = format!("SELECT EXISTS(SELECT 1 FROM ({SUPERSEDED_DOC_IDS_SQL}) WHERE doc_id=?1)");
+    conn.query_row(&sql, params![doc_id], |row| row.get(0))
+        .map_err(|e| version_error("failed checking doc supersession", e))
+}
+
+pub fn doc_versions_for_source(
+    conn: &Connection,
+    source_path: &str,
+) -> AppResult<Option<DocVersionHistoryV1>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT v.version, v.doc_id, v.event_id, e.ts_ms, d.bytes, d.mime
+             FROM doc_versions v
+             LEFT JOIN events e ON e.event_id = v.event_id
+             LEFT JOIN docs d ON d.doc_id = v.doc_id
+             WHERE v.source_path=?1
+             ORDER BY v.version ASC",
+        )
+        .map_err(|e| version_error("failed preparing doc version query", e))?;
+    let rows = stmt
+        .query_map(params![source_path], |row| {
+            Ok(DocVersionV1 {
+                version: row.get(0)?,
+                doc_id: row.get(1)?,
+                event_id: row.get(2)?,
+                ingested_at_ms: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                bytes: row.get(4)?,
+                mime: row.get(5)?,
+                superseded_by_doc_id: None,
+            })
+        })
+        .map_err(|e| version_error("failed querying doc versions", e))?;
+    let mut versions = rows
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| version_error("failed decoding doc version row", e))?;
+
+    for idx in 1..versions.len() {
+        let next_doc_id = versions[idx].doc_id.clone();
+        versions[idx - 1].superseded_by_doc_id = Some(next_doc_id);
+    }
+    let Some(current) = versions.last() else {
+        return Ok(None);
+    };
+    Ok(Some(DocVersionHistoryV1 {
+        source_path: source_path.to_string(),
+        current_doc_id: current.doc_id.clone(),
+        versions,
+    }))
+}
+
+pub fn doc_version_history(conn: &Connection, doc_id: &str) -> AppResult<Vec<DocVersionHistoryV1>> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT source_path FROM doc_versions WHERE doc_id=?1 ORDER BY source_path ASC")
+        .map_err(|e| version_error("failed preparing doc source path query", e))?;
+    let source_paths = stmt
+        .query_map(params![doc_id], |row| row.get::<_, String>(0))
+        .map_err(|e| version_error("failed querying doc source paths", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| version_error("failed decoding doc source path", e))?;
+
+    let mut out = Vec::new();
+    for source_path in source_paths {
+        if let Some(history) = doc_versions_for_source(conn, &source_path)? {
+            out.push(history);
+        }
+    }
+    Ok(out)
+}
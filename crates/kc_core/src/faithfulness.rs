@@ -0,0 +1,365 @@
+use crate::app_error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub const DEFAULT_SUPPORT_THRESHOLD: f64 = 0.5;
+pub const LEXICAL_ENTAILMENT_ID: &str = "lexical_entailment";
+
+// A sentence missing a number its evidence does not contain, or negating where the evidence
+// does not, keeps this share of its term coverage.
+const MISMATCH_PENALTY: f64 = 0.5;
+
+const STOPWORDS: &[&str] = &[
+    "a", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by", "can",
+    "could", "did", "do", "does", "each", "for", "from", "had", "has", "have", "how", "if", "in",
+    "into", "is", "it", "its", "may", "might", "more", "most", "must", "of", "on", "or", "other",
+    "our", "shall", "should", "so", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "to", "was", "we", "were", "what", "when", "where",
+    "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+const NEGATIONS: &[&str] = &[
+    "no", "not", "never", "none", "nor", "cannot", "without", "isn", "aren", "wasn", "weren",
+    "doesn", "didn", "shouldn",
+];
+
+pub trait NliModel: Send + Sync {
+    fn model_id(&self) -> String;
+    // One entailment score in [0, 1] per hypothesis, in hypothesis order; higher means
+    // `premise` supports the hypothesis more.
+    fn entailment(&self, premise: &str, hypotheses: &[&str]) -> AppResult<Vec<f64>>;
+}
+
+// Token alignment, not entailment: how well each hypothesis's tokens are matched by the
+// premise's, whatever the two sentences assert.
+pub trait AlignmentModel: Send + Sync {
+    fn model_id(&self) -> String;
+    // One score in [0, 1] per hypothesis, in hypothesis order.
+    fn alignment(&self, premise: &str, hypotheses: &[&str]) -> AppResult<Vec<f64>>;
+}
+
+// The optional model scored next to the lexical heuristics. Its kind names the verifier id
+// suffix and the sentence field its score is reported in.
+#[derive(Clone, Copy)]
+pub enum SupportModel<'a> {
+    Nli(&'a dyn NliModel),
+    Alignment(&'a dyn AlignmentModel),
+}
+
+impl SupportModel<'_> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Nli(_) => "nli",
+            Self::Alignment(_) => "token_alignment",
+        }
+    }
+
+    fn model_id(&self) -> String {
+        match self {
+            Self::Nli(model) => model.model_id(),
+            Self::Alignment(model) => model.model_id(),
+        }
+    }
+
+    fn scores(&self, premise: &str, hypotheses: &[&str]) -> AppResult<Vec<f64>> {
+        match self {
+            Self::Nli(model) => model.entailment(premise, hypotheses),
+            Self::Alignment(model) => model.alignment(premise, hypotheses),
+        }
+    }
+}
+
+// One answer paragraph and the canonical text of every span it cites.
+#[derive(Debug, Clone)]
+pub struct ParagraphEvidence {
+    pub paragraph_index: i64,
+    pub text: String,
+    pub spans: Vec<String>,
+}
+
+// `score` is `lexical_score`, or its mean with the support model's score, reported as
+// `nli_score` or `alignment_score` by the model's kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentenceSupportV1 {
+    pub sentence_index: usize,
+    pub text: String,
+    pub score: f64,
+    pub lexical_score: f64,
+    pub nli_score: Option<f64>,
+    #[serde(default)]
+    pub alignment_score: Option<f64>,
+    pub reason: String,
+}
+
+// `score` is the lowest sentence score, 1.0 when no sentence had content terms to check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParagraphSupportV1 {
+    pub paragraph_index: i64,
+    pub score: f64,
+    pub supported: bool,
+    pub sentences_checked: usize,
+    pub unsupported_sentences: Vec<SentenceSupportV1>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnswerVerificationV1 {
+    pub verifier: String,
+    pub threshold: f64,
+    pub supported: bool,
+    pub paragraphs: Vec<ParagraphSupportV1>,
+}
+
+fn verifier_error(message: &str, details: serde_json::Value) -> AppError {
+    AppError::new("KC_ASK_VERIFIER_FAILED", "ask", message, false, details)
+}
+
+fn round12(value: f64) -> f64 {
+    (value * 1_000_000_000_000.0).round() / 1_000_000_000_000.0
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+// Folds simple plurals so "keys" matches "key".
+fn fold(word: &str) -> String {
+    if word.chars().count() > 3
+        && word.ends_with('s')
+        && !word.ends_with("ss")
+        && !word.chars().any(|c| c.is_ascii_digit())
+    {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+fn content_terms(words: &[String]) -> BTreeSet<String> {
+    words
+        .iter()
+        .filter(|w| {
+            (w.chars().count() > 1 || w.chars().all(|c| c.is_ascii_digit()))
+                && !STOPWORDS.contains(&w.as_str())
+                && !NEGATIONS.contains(&w.as_str())
+        })
+        .map(|w| fold(w))
+        .collect()
+}
+
+fn negated(words: &[String]) -> bool {
+    words.iter().any(|w| NEGATIONS.contains(&w.as_str()))
+}
+
+// Drops citation markers such as `[1]` or `[1, 2]` with the whitespace before them; other
+// bracketed text is kept.
+fn strip_citation_markers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find(']') {
+            Some(close)
+                if !after[..close].trim().is_empty()
+                    && after[..close]
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || c == ',' || c == ' ') =>
+            {
+                out.truncate(out.trim_end().len());
+                rest = &after[close + 1..];
+            }
+            _ => {
+                out.push('[');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Sentences end at a line break or at `.`, `!` or `?` followed by whitespace or the end of
+// the paragraph, so decimals such as `3.5` stay whole. Citation markers are removed first.
+pub fn split_sentences(paragraph: &str) -> Vec<String> {
+    let text = strip_citation_markers(paragraph);
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut current = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        if *c == '\n' {
+            out.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push(*c);
+        let terminal = matches!(c, '.' | '!' | '?');
+        if terminal && chars.get(idx + 1).is_none_or(|next| next.is_whitespace()) {
+            out.push(std::mem::take(&mut current));
+        }
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Share of the sentence's content terms found in the evidence, halved when the sentence has
+// a number the evidence lacks and again when it negates and the evidence never does. None
+// when the sentence has no content terms.
+pub fn lexical_support(sentence: &str, evidence: &str) -> Option<(f64, String)> {
+    let sentence_words = words(sentence);
+    let terms = content_terms(&sentence_words);
+    if terms.is_empty() {
+        return None;
+    }
+    let evidence_words = words(evidence);
+    let evidence_terms: BTreeSet<String> = evidence_words.iter().map(|w| fold(w)).collect();
+    let missing: Vec<&str> = terms
+        .iter()
+        .filter(|t| !evidence_terms.contains(*t))
+        .map(|t| t.as_str())
+        .collect();
+    let mut score = (terms.len() - missing.len()) as f64 / terms.len() as f64;
+    let mut reason = format!(
+        "matched {}/{} terms",
+        terms.len() - missing.len(),
+        terms.len()
+    );
+    if !missing.is_empty() {
+        reason.push_str(&format!("; missing [{}]", missing.join(", ")));
+    }
+    let missing_numbers: Vec<&str> = missing
+        .iter()
+        .copied()
+        .filter(|t| t.chars().any(|c| c.is_ascii_digit()))
+        .collect();
+    if !missing_numbers.is_empty() {
+        score *= MISMATCH_PENALTY;
+        reason.push_str(&format!(
+            "; numbers not in evidence [{}]",
+            missing_numbers.join(", ")
+        ));
+    }
+    if negated(&sentence_words) && !negated(&evidence_words) {
+        score *= MISMATCH_PENALTY;
+        reason.push_str("; negation not in evidence");
+    }
+    Some((round12(score), reason))
+}
+
+fn model_scores(
+    model: SupportModel<'_>,
+    premise: &str,
+    hypotheses: &[&str],
+    paragraph_index: i64,
+) -> AppResult<Vec<f64>> {
+    let scores = model.scores(premise, hypotheses)?;
+    if scores.len() != hypotheses.len() {
+        return Err(verifier_error(
+            "support model returned a different number of scores than sentences",
+            serde_json::json!({
+                "model": model.model_id(),
+                "paragraph_index": paragraph_index,
+                "sentences": hypotheses.len(),
+                "scores": scores.len(),
+            }),
+        ));
+    }
+    if scores
+        .iter()
+        .any(|s| !s.is_finite() || !(0.0..=1.0).contains(s))
+    {
+        return Err(verifier_error(
+            "support model returned a score outside [0, 1]",
+            serde_json::json!({
+                "model": model.model_id(),
+                "paragraph_index": paragraph_index,
+            }),
+        ));
+    }
+    Ok(scores)
+}
+
+// Checks every sentence of every paragraph against the paragraph's own cited spans. A
+// sentence scoring below `threshold` is reported as unsupported, and so is its paragraph.
+pub fn verify_paragraphs(
+    paragraphs: &[ParagraphEvidence],
+    model: Option<SupportModel<'_>>,
+    threshold: f64,
+) -> AppResult<AnswerVerificationV1> {
+    if !threshold.is_finite() || !(0.0..=1.0).contains(&threshold) {
+        return Err(verifier_error(
+            "support threshold must be within [0, 1]",
+            serde_json::json!({ "threshold": threshold.to_string() }),
+        ));
+    }
+    let is_nli = matches!(model, Some(SupportModel::Nli(_)));
+    let mut out = Vec::with_capacity(paragraphs.len());
+    for paragraph in paragraphs {
+        let evidence = paragraph.spans.join("\n\n");
+        let mut scored: Vec<(usize, String, f64, String)> = Vec::new();
+        for (sentence_index, sentence) in split_sentences(&paragraph.text).into_iter().enumerate() {
+            if let Some((score, reason)) = lexical_support(&sentence, &evidence) {
+                scored.push((sentence_index, sentence, score, reason));
+            }
+        }
+        let model_scores = match model {
+            Some(model) if !scored.is_empty() => {
+                let hypotheses: Vec<&str> = scored.iter().map(|s| s.1.as_str()).collect();
+                Some(model_scores(
+                    model,
+                    &evidence,
+                    &hypotheses,
+                    paragraph.paragraph_index,
+                )?)
+            }
+            _ => None,
+        };
+
+        let mut score = 1.0f64;
+        let mut unsupported = Vec::new();
+        for (idx, (sentence_index, text, lexical_score, reason)) in scored.iter().enumerate() {
+            let model_score = model_scores.as_ref().map(|scores| round12(scores[idx]));
+            let sentence_score = match model_score {
+                Some(model_score) => round12((lexical_score + model_score) / 2.0),
+                None => *lexical_score,
+            };
+            score = score.min(sentence_score);
+            if sentence_score < threshold {
+                unsupported.push(SentenceSupportV1 {
+                    sentence_index: *sentence_index,
+                    text: text.clone(),
+                    score: sentence_score,
+                    lexical_score: *lexical_score,
+                    nli_score: model_score.filter(|_| is_nli),
+                    alignment_score: model_score.filter(|_| !is_nli),
+                    reason: reason.clone(),
+                });
+            }
+        }
+        out.push(ParagraphSupportV1 {
+            paragraph_index: paragraph.paragraph_index,
+            score,
+            supported: unsupported.is_empty(),
+            sentences_checked: scored.len(),
+            unsupported_sentences: unsupported,
+        });
+    }
+
+    let verifier = match model {
+        Some(model) => format!(
+            "{LEXICAL_ENTAILMENT_ID}+{}:{}",
+            model.kind(),
+            model.model_id()
+        ),
+        None => LEXICAL_ENTAILMENT_ID.to_string(),
+    };
+    Ok(AnswerVerificationV1 {
+        verifier,
+        threshold,
+        supported: out.iter().all(|p| p.supported),
+        paragraphs: out,
+    })
+}
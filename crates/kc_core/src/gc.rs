@@ -0,0 +1,200 @@
+use crate::app_error::{AppError, AppResult};
+use crate::events::append_event;
+use crate::sync::sync_local_write_lock;
+use crate::vault::vault_paths;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GcReportV1 {
+    pub schema_version: i64,
+    pub dry_run: bool,
+    pub reachable_objects: i64,
+    pub retained_by_snapshot: Vec<String>,
+    pub unreachable_object_hashes: Vec<String>,
+    pub orphan_files: Vec<String>,
+    pub reclaimable_bytes: i64,
+    pub event_hash: Option<String>,
+}
+
+fn gc_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "gc", message, false, details)
+}
+
+fn db_error(message: &str, e: rusqlite::Error) -> AppError {
+    gc_error(
+        "KC_DB_INTEGRITY_FAILED",
+        message,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn query_hashes(conn: &Connection, sql: &str) -> AppResult<BTreeSet<String>> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| db_error("failed preparing gc reachability query", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, Option<String>>(0))
+        .map_err(|e| db_error("failed querying gc reachability", e))?;
+    let mut out = BTreeSet::new();
+    for row in rows {
+        if let Some(hash) = row.map_err(|e| db_error("failed decoding gc reachability row", e))? {
+            out.insert(hash);
+        }
+    }
+    Ok(out)
+}
+
+fn ensure_gc_unlocked(conn: &Connection, vault_path: &Path, now_ms: i64) -> AppResult<()> {
+    if let Some(lock) = sync_local_write_lock(vault_path, now_ms)? {
+        return Err(gc_error(
+            "KC_GC_LOCKED",
+            "gc cannot run while a sync write lock is held",
+            serde_json::json!({
+                "lock": "sync_write",
+                "holder": lock.holder,
+                "expires_at_ms": lock.expires_at_ms,
+            }),
+        ));
+    }
+
+    let lineage_lock: Option<(String, String, i64)> = conn
+        .query_row(
+            "SELECT doc_id, owner, expires_at_ms FROM lineage_edit_locks
+             WHERE expires_at_ms > ?1 ORDER BY doc_id ASC LIMIT 1",
+            params![now_ms],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| db_error("failed checking lineage locks for gc", e))?;
+    if let Some((doc_id, owner, expires_at_ms)) = lineage_lock {
+        return Err(gc_error(
+            "KC_GC_LOCKED",
+            "gc cannot run while a lineage edit lock is held",
+            serde_json::json!({
+                "lock": "lineage_edit",
+                "doc_id": doc_id,
+                "owner": owner,
+                "expires_at_ms": expires_at_ms,
+            }),
+        ));
+    }
+    Ok(())
+}
+
+pub fn gc_run(
+    conn: &Connection,
+    vault_path: &Path,
+    dry_run: bool,
+    now_ms: i64,
+) -> AppResult<GcReportV1> {
+    ensure_gc_unlocked(conn, vault_path, now_ms)?;
+    let paths = vault_paths(vault_path);
+
+    let mut reachable = query_hashes(conn, "SELECT original_object_hash FROM docs")?;
+    reachable.extend(query_hashes(
+        conn,
+        "SELECT canonical_object_hash FROM canonical_text
+         UNION
+         SELECT canonical_object_hash FROM canonical_text_history",
+    )?);
+
+    // The most recent sync snapshot still carries docs that were deleted after it was
+    // taken, so their objects stay until a newer snapshot is recorded.
+    let mut retained_by_snapshot = query_hashes(
+        conn,
+        "SELECT original_object_hash FROM doc_tombstones
+         WHERE deleted_at_ms > (SELECT MAX(created_at_ms) FROM sync_snapshots)
+         UNION
+         SELECT canonical_object_hash FROM doc_tombstones
+         WHERE deleted_at_ms > (SELECT MAX(created_at_ms) FROM sync_snapshots)",
+    )?;
+    retained_by_snapshot.retain(|hash| !reachable.contains(hash));
+    reachable.extend(retained_by_snapshot.iter().cloned());
+
+    let unreachable_object_hashes: Vec<String> =
+        query_hashes(conn, "SELECT object_hash FROM objects")?
+            .into_iter()
+            .filter(|hash| !reachable.contains(hash))
+            .collect();
+
+    let mut orphan_files = Vec::new();
+    let mut orphan_paths = Vec::new();
+    let mut reclaimable_bytes = 0i64;
+    if paths.objects_dir.exists() {
+        let mut entries: Vec<_> = walkdir::WalkDir::new(&paths.objects_dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+            .collect();
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if reachable.contains(&name) {
+                continue;
+            }
+            let len = entry.metadata().map(|m| m.len() as i64).unwrap_or(0);
+            reclaimable_bytes += len;
+            let rel = entry
+                .path()
+                .strip_prefix(&paths.objects_dir)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            orphan_files.push(rel);
+            orphan_paths.push(entry.into_path());
+        }
+    }
+
+    let mut report = GcReportV1 {
+        schema_version: 1,
+        dry_run,
+        reachable_objects: reachable.len() as i64,
+        retained_by_snapshot: retained_by_snapshot.into_iter().collect(),
+        unreachable_object_hashes,
+        orphan_files,
+        reclaimable_bytes,
+        event_hash: None,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| db_error("failed starting gc transaction", e))?;
+    for hash in &report.unreachable_object_hashes {
+        tx.execute("DELETE FROM objects WHERE object_hash=?1", params![hash])
+            .map_err(|e| db_error("failed deleting unreachable object row", e))?;
+    }
+    let event = append_event(
+        &tx,
+        now_ms,
+        "gc.run",
+        &serde_json::json!({
+            "reachable_objects": report.reachable_objects,
+            "retained_by_snapshot": report.retained_by_snapshot,
+            "unreachable_object_hashes": report.unreachable_object_hashes,
+            "orphan_files": report.orphan_files.len(),
+            "reclaimed_bytes": report.reclaimable_bytes,
+        }),
+    )?;
+    tx.commit()
+        .map_err(|e| db_error("failed committing gc transaction", e))?;
+
+    // Rows go first so a failed file removal only leaves an orphan for the next run.
+    for path in orphan_paths {
+        fs::remove_file(&path).map_err(|e| {
+            gc_error(
+                "KC_DB_INTEGRITY_FAILED",
+                "failed removing unreachable object file",
+                serde_json::json!({ "error": e.to_string(), "path": path }),
+            )
+        })?;
+    }
+    report.event_hash = Some(event.event_hash);
+    Ok(report)
+}
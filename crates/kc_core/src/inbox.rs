@@ -0,0 +1,521 @@
+use crate::app_error::{AppError, AppResult};
+use crate::ingest::{file_effective_ts_ms, ingest_bytes, mime_for_path, IngestBytesReq};
+use crate::object_store::ObjectStore;
+use crate::pipeline::{run_doc_pipeline, PipelineServices};
+use crate::types::DocId;
+use crate::vault::vault_paths;
+use notify::{RecursiveMode, Watcher};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+pub const INBOX_WATCH_STOP_FILE: &str = ".kc_watch_stop";
+pub const INBOX_WATCH_BACKEND_AUTO: &str = "auto";
+pub const INBOX_WATCH_BACKEND_NOTIFY: &str = "notify";
+pub const INBOX_WATCH_BACKEND_POLL: &str = "poll";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InboxWatchConfigV1 {
+    pub source_kind: String,
+    pub debounce_ms: i64,
+    pub poll_interval_ms: i64,
+    pub backend: String,
+    pub max_runtime_ms: Option<i64>,
+}
+
+impl InboxWatchConfigV1 {
+    pub fn new(source_kind: &str) -> Self {
+        Self {
+            source_kind: source_kind.to_string(),
+            debounce_ms: 500,
+            poll_interval_ms: 1000,
+            backend: INBOX_WATCH_BACKEND_AUTO.to_string(),
+            max_runtime_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InboxFileOutcomeV1 {
+    pub source_path: String,
+    pub status: String,
+    pub target_path: String,
+    pub doc_id: Option<String>,
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct InboxWatchSummaryV1 {
+    pub backend: String,
+    pub processed: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboxCandidate {
+    pub path: PathBuf,
+    pub len: u64,
+    pub modified_ms: i64,
+}
+
+#[derive(Debug, Clone)]
+struct PendingFile {
+    len: u64,
+    modified_ms: i64,
+    stable_since_ms: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct InboxDebouncer {
+    pending: BTreeMap<PathBuf, PendingFile>,
+}
+
+impl InboxDebouncer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // A file is ready once its size and mtime have not changed for debounce_ms.
+    pub fn observe(
+        &mut self,
+        candidates: &[InboxCandidate],
+        now_ms: i64,
+        debounce_ms: i64,
+    ) -> Vec<PathBuf> {
+        self.pending
+            .retain(|path, _| candidates.iter().any(|c| &c.path == path));
+
+        let mut ready = Vec::new();
+        for candidate in candidates {
+            let entry = self
+                .pending
+                .entry(candidate.path.clone())
+                .or_insert(PendingFile {
+                    len: candidate.len,
+                    modified_ms: candidate.modified_ms,
+                    stable_since_ms: now_ms,
+                });
+            if entry.len != candidate.len || entry.modified_ms != candidate.modified_ms {
+                entry.len = candidate.len;
+                entry.modified_ms = candidate.modified_ms;
+                entry.stable_since_ms = now_ms;
+                continue;
+            }
+            if now_ms - entry.stable_since_ms >= debounce_ms {
+                ready.push(candidate.path.clone());
+            }
+        }
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+}
+
+fn inbox_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "ingest", message, false, details)
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> Option<i64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+}
+
+fn system_now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn is_partial_name(name: &str) -> bool {
+    name.starts_with('.')
+        || name.starts_with('~')
+        || name.ends_with(".part")
+        || name.ends_with(".tmp")
+        || name.ends_with(".crdownload")
+}
+
+pub fn inbox_candidates(inbox_dir: &Path) -> AppResult<Vec<InboxCandidate>> {
+    let entries = match fs::read_dir(inbox_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(inbox_error(
+                "KC_INBOX_WATCH_FAILED",
+                "failed listing inbox directory",
+                serde_json::json!({ "error": e.to_string(), "path": inbox_dir }),
+            ))
+        }
+    };
+
+    let mut out = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_partial_name(&name) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        out.push(InboxCandidate {
+            path: entry.path(),
+            len: metadata.len(),
+            modified_ms: modified_ms(&metadata).unwrap_or_default(),
+        });
+    }
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(out)
+}
+
+pub fn inbox_processed_target(vault_path: &Path, file: &Path, doc_id: &DocId) -> PathBuf {
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let ext = file.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let suffix = doc_id.0.chars().skip(7).take(8).collect::<String>();
+    let mut target = vault_paths(vault_path)
+        .inbox_processed_dir
+        .join(format!("{}__{}", stem, suffix));
+    if !ext.is_empty() {
+        target.set_extension(ext);
+    }
+    target
+}
+
+pub fn inbox_move_processed(vault_path: &Path, file: &Path, doc_id: &DocId) -> AppResult<PathBuf> {
+    let target = inbox_processed_target(vault_path, file, doc_id);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            inbox_error(
+                "KC_INBOX_MOVE_FAILED",
+                "failed to create inbox processed directory",
+                serde_json::json!({ "error": e.to_string(), "path": parent }),
+            )
+        })?;
+    }
+    fs::rename(file, &target).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_MOVE_FAILED",
+            "failed to move inbox file into processed",
+            serde_json::json!({ "error": e.to_string(), "from": file, "to": target }),
+        )
+    })?;
+    Ok(target)
+}
+
+pub fn inbox_quarantine(
+    vault_path: &Path,
+    file: &Path,
+    error: &AppError,
+    now_ms: i64,
+) -> AppResult<PathBuf> {
+    let failed_dir = vault_paths(vault_path).inbox_failed_dir;
+    fs::create_dir_all(&failed_dir).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_MOVE_FAILED",
+            "failed to create inbox failed directory",
+            serde_json::json!({ "error": e.to_string(), "path": failed_dir }),
+        )
+    })?;
+
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let ext = file.extension().and_then(|s| s.to_str()).unwrap_or("");
+    let mut attempt = 0;
+    let target = loop {
+        let name = if attempt == 0 {
+            format!("{}__{}", stem, now_ms)
+        } else {
+            format!("{}__{}_{}", stem, now_ms, attempt)
+        };
+        let mut candidate = failed_dir.join(name);
+        if !ext.is_empty() {
+            candidate.set_extension(ext);
+        }
+        if !candidate.exists() {
+            break candidate;
+        }
+        attempt += 1;
+    };
+
+    fs::rename(file, &target).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_MOVE_FAILED",
+            "failed to move inbox file into failed",
+            serde_json::json!({ "error": e.to_string(), "from": file, "to": target }),
+        )
+    })?;
+
+    let sidecar_path = PathBuf::from(format!("{}.error.json", target.display()));
+    let sidecar = serde_json::json!({
+        "schema_version": 1,
+        "source_path": file,
+        "failed_path": target,
+        "failed_at_ms": now_ms,
+        "error": error,
+    });
+    let bytes = serde_json::to_vec_pretty(&sidecar).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_MOVE_FAILED",
+            "failed to serialize inbox error sidecar",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+    fs::write(&sidecar_path, bytes).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_MOVE_FAILED",
+            "failed to write inbox error sidecar",
+            serde_json::json!({ "error": e.to_string(), "path": sidecar_path }),
+        )
+    })?;
+    Ok(target)
+}
+
+fn ingest_inbox_file(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    file: &Path,
+    source_kind: &str,
+    now_ms: i64,
+) -> AppResult<DocId> {
+    let bytes = fs::read(file).map_err(|e| {
+        AppError::new(
+            "KC_INGEST_READ_FAILED",
+            "ingest",
+            "failed reading file bytes",
+            false,
+            serde_json::json!({ "error": e.to_string(), "path": file }),
+        )
+    })?;
+    let doc = ingest_bytes(
+        conn,
+        object_store,
+        IngestBytesReq {
+            bytes: &bytes,
+            mime: &mime_for_path(file),
+            source_kind,
+            effective_ts_ms: file_effective_ts_ms(file, now_ms),
+            source_path: Some(&file.to_string_lossy()),
+            now_ms,
+        },
+    )?;
+    run_doc_pipeline(conn, object_store, services, &doc.doc_id, now_ms)?;
+    Ok(doc.doc_id)
+}
+
+// A file that cannot be moved afterwards (e.g. deleted or locked since it was debounced) is
+// reported as failed in place, carrying the move error, so one file never stops a watch.
+pub fn inbox_process_file(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    vault_path: &Path,
+    file: &Path,
+    source_kind: &str,
+    now_ms: i64,
+) -> AppResult<InboxFileOutcomeV1> {
+    let source_path = file.to_string_lossy().to_string();
+    let failed = |target: &Path, doc_id: Option<String>, error: AppError| InboxFileOutcomeV1 {
+        source_path: source_path.clone(),
+        status: "failed".to_string(),
+        target_path: target.to_string_lossy().to_string(),
+        doc_id,
+        error: Some(error),
+    };
+    match ingest_inbox_file(conn, object_store, services, file, source_kind, now_ms) {
+        Ok(doc_id) => match inbox_move_processed(vault_path, file, &doc_id) {
+            Ok(target) => Ok(InboxFileOutcomeV1 {
+                source_path: source_path.clone(),
+                status: "processed".to_string(),
+                target_path: target.to_string_lossy().to_string(),
+                doc_id: Some(doc_id.0),
+                error: None,
+            }),
+            Err(move_error) => Ok(failed(file, Some(doc_id.0), move_error)),
+        },
+        Err(error) => match inbox_quarantine(vault_path, file, &error, now_ms) {
+            Ok(target) => Ok(failed(&target, None, error)),
+            Err(mut move_error) => {
+                move_error.details["cause"] = serde_json::to_value(&error).unwrap_or_default();
+                Ok(failed(file, None, move_error))
+            }
+        },
+    }
+}
+
+pub fn inbox_watch_poll_once(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    vault_path: &Path,
+    config: &InboxWatchConfigV1,
+    debouncer: &mut InboxDebouncer,
+    now_ms: i64,
+) -> AppResult<Vec<InboxFileOutcomeV1>> {
+    let candidates = inbox_candidates(&vault_paths(vault_path).inbox_dir)?;
+    let ready = debouncer.observe(&candidates, now_ms, config.debounce_ms);
+    let mut outcomes = Vec::new();
+    for file in ready {
+        outcomes.push(inbox_process_file(
+            conn,
+            object_store,
+            services,
+            vault_path,
+            &file,
+            &config.source_kind,
+            now_ms,
+        )?);
+    }
+    Ok(outcomes)
+}
+
+pub fn inbox_watch_request_stop(vault_path: &Path) -> AppResult<PathBuf> {
+    let path = vault_paths(vault_path)
+        .inbox_dir
+        .join(INBOX_WATCH_STOP_FILE);
+    fs::write(&path, b"stop").map_err(|e| {
+        inbox_error(
+            "KC_INBOX_WATCH_FAILED",
+            "failed to write inbox watch stop request",
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    })?;
+    Ok(path)
+}
+
+fn take_stop_request(stop_file: &Path) -> bool {
+    if stop_file.exists() {
+        let _ = fs::remove_file(stop_file);
+        return true;
+    }
+    false
+}
+
+pub fn inbox_watch_run(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    vault_path: &Path,
+    config: &InboxWatchConfigV1,
+    stop: &AtomicBool,
+) -> AppResult<InboxWatchSummaryV1> {
+    let inbox_dir = vault_paths(vault_path).inbox_dir;
+    fs::create_dir_all(&inbox_dir).map_err(|e| {
+        inbox_error(
+            "KC_INBOX_WATCH_FAILED",
+            "failed to create inbox directory",
+            serde_json::json!({ "error": e.to_string(), "path": inbox_dir }),
+        )
+    })?;
+    let stop_file = inbox_dir.join(INBOX_WATCH_STOP_FILE);
+    take_stop_request(&stop_file);
+
+    let (tx, rx) = mpsc::channel::<()>();
+    let mut watcher = None;
+    match config.backend.as_str() {
+        INBOX_WATCH_BACKEND_POLL => {}
+        INBOX_WATCH_BACKEND_AUTO | INBOX_WATCH_BACKEND_NOTIFY => {
+            let started = notify::recommended_watcher(move |_event| {
+                let _ = tx.send(());
+            })
+            .and_then(|mut w| {
+                w.watch(&inbox_dir, RecursiveMode::NonRecursive)?;
+                Ok(w)
+            });
+            match started {
+                Ok(w) => watcher = Some(w),
+                Err(e) if config.backend == INBOX_WATCH_BACKEND_NOTIFY => {
+                    return Err(inbox_error(
+                        "KC_INBOX_WATCH_FAILED",
+                        "failed to start native inbox watcher",
+                        serde_json::json!({ "error": e.to_string(), "path": inbox_dir }),
+                    ))
+                }
+                Err(_) => {}
+            }
+        }
+        other => {
+            return Err(inbox_error(
+                "KC_INBOX_WATCH_FAILED",
+                "unsupported inbox watch backend",
+                serde_json::json!({
+                    "backend": other,
+                    "supported": [
+                        INBOX_WATCH_BACKEND_AUTO,
+                        INBOX_WATCH_BACKEND_NOTIFY,
+                        INBOX_WATCH_BACKEND_POLL
+                    ]
+                }),
+            ))
+        }
+    }
+
+    let mut summary = InboxWatchSummaryV1 {
+        backend: if watcher.is_some() {
+            INBOX_WATCH_BACKEND_NOTIFY.to_string()
+        } else {
+            INBOX_WATCH_BACKEND_POLL.to_string()
+        },
+        ..InboxWatchSummaryV1::default()
+    };
+    let started_at_ms = system_now_ms();
+    let mut debouncer = InboxDebouncer::new();
+    loop {
+        if stop.load(Ordering::SeqCst) || take_stop_request(&stop_file) {
+            break;
+        }
+        let now_ms = system_now_ms();
+        if let Some(max_runtime_ms) = config.max_runtime_ms {
+            if now_ms - started_at_ms >= max_runtime_ms {
+                break;
+            }
+        }
+
+        for outcome in inbox_watch_poll_once(
+            conn,
+            object_store,
+            services,
+            vault_path,
+            config,
+            &mut debouncer,
+            now_ms,
+        )? {
+            if outcome.status == "processed" {
+                summary.processed += 1;
+            } else {
+                summary.failed += 1;
+            }
+        }
+
+        let wait_ms = if debouncer.has_pending() {
+            config.debounce_ms.min(config.poll_interval_ms)
+        } else {
+            config.poll_interval_ms
+        }
+        .max(10) as u64;
+        if watcher.is_some() {
+            // Native events only shorten the wait; the rescan is the source of truth.
+            if rx.recv_timeout(Duration::from_millis(wait_ms)).is_ok() {
+                while rx.try_recv().is_ok() {}
+            }
+        } else {
+            std::thread::sleep(Duration::from_millis(wait_ms));
+        }
+    }
+
+    Ok(summary)
+}
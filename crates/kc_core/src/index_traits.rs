@@ -1,10 +1,14 @@
 use crate::app_error::AppResult;
+use crate::search::SearchFilterV1;
 use crate::types::{ChunkId, DocId};
 
 #[derive(Debug, Clone)]
 pub struct LexicalCandidate {
     pub chunk_id: ChunkId,
     pub rank: i64,
+    // Relevance reported by the index, higher is better (FTS5 bm25 negated); None when the
+    // index only ranks.
+    pub bm25: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -13,12 +17,41 @@ pub struct VectorCandidate {
     pub rank: i64,
 }
 
+#[derive(Debug, Clone)]
+pub struct IndexChunk {
+    pub chunk_id: ChunkId,
+    pub doc_id: DocId,
+    pub ordinal: i64,
+    pub text: String,
+}
+
 pub trait LexicalIndex: Send + Sync {
-    fn rebuild_for_doc(&self, doc_id: &DocId) -> AppResult<()>;
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()>;
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()>;
     fn query(&self, query: &str, limit: usize) -> AppResult<Vec<LexicalCandidate>>;
+    // Same ranking as `query`, restricted to chunks of `doc_ids` inside the index so the limit
+    // only counts matching chunks.
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<LexicalCandidate>>;
+    // Doc-level restrictions carried by the query text itself (field operators), applied by
+    // the caller alongside the request filter. Indexes without a query language have none.
+    fn query_filter(&self, _query: &str) -> AppResult<SearchFilterV1> {
+        Ok(SearchFilterV1::default())
+    }
 }
 
 pub trait VectorIndex: Send + Sync {
-    fn rebuild_for_doc(&self, doc_id: &DocId) -> AppResult<()>;
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()>;
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()>;
     fn query(&self, query: &str, limit: usize) -> AppResult<Vec<VectorCandidate>>;
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>>;
 }
@@ -1,7 +1,9 @@
 use crate::app_error::{AppError, AppResult};
+use crate::doc_versions::record_doc_version;
 use crate::events::append_event;
 use crate::types::{DocId, ObjectHash};
 use rusqlite::{params, Connection};
+use std::path::Path;
 
 #[derive(Debug, Clone)]
 pub struct IngestedDoc {
@@ -22,6 +24,31 @@ pub struct IngestBytesReq<'a> {
     pub now_ms: i64,
 }
 
+pub fn mime_for_path(path: &Path) -> String {
+    match path
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+    {
+        "md" => "text/markdown".to_string(),
+        "html" | "htm" => "text/html".to_string(),
+        "pdf" => "application/pdf".to_string(),
+        "txt" => "text/plain".to_string(),
+        _ => "application/octet-stream".to_string(),
+    }
+}
+
+// The file's mtime, or `fallback_ms` when it cannot be read (spec 05 timestamp resolution).
+// Every path that ingests a file from disk resolves its effective timestamp here.
+pub fn file_effective_ts_ms(path: &Path, fallback_ms: i64) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(fallback_ms)
+}
+
 pub fn ingest_bytes(
     conn: &Connection,
     object_store: &crate::object_store::ObjectStore,
@@ -72,6 +99,20 @@ pub fn ingest_bytes(
         )
     })?;
 
+    conn.execute(
+        "DELETE FROM doc_tombstones WHERE doc_id=?1",
+        params![doc_id.0],
+    )
+    .map_err(|e| {
+        AppError::new(
+            "KC_DB_INTEGRITY_FAILED",
+            "ingest",
+            "failed to clear doc tombstone",
+            false,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+
     if let Some(path) = source_path {
         conn.execute(
             "INSERT OR IGNORE INTO doc_sources (doc_id, source_path) VALUES (?1, ?2)",
@@ -86,6 +127,7 @@ pub fn ingest_bytes(
                 serde_json::json!({ "error": e.to_string() }),
             )
         })?;
+        record_doc_version(conn, path, &doc_id.0, ingest_event.event_id)?;
     }
 
     let row = conn
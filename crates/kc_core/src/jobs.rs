@@ -0,0 +1,469 @@
+use crate::app_error::{AppError, AppResult};
+use crate::canon_json::to_canonical_bytes;
+use crate::hashing::blake3_hex_prefixed;
+use crate::ingest::{file_effective_ts_ms, ingest_bytes, mime_for_path, IngestBytesReq};
+use crate::object_store::ObjectStore;
+use crate::pipeline::{run_doc_pipeline, PipelineServices};
+use crate::types::DocId;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const JOB_LEASE_MS: i64 = 60 * 1000;
+pub const JOB_DEFAULT_MAX_ATTEMPTS: i64 = 5;
+pub const JOB_BACKOFF_BASE_MS: i64 = 1000;
+pub const JOB_BACKOFF_MAX_MS: i64 = 60 * 1000;
+
+pub const JOB_KIND_INGEST_FILE: &str = "ingest.file";
+pub const JOB_KIND_INGEST_SCAN_FOLDER: &str = "ingest.scan_folder";
+pub const JOB_KIND_PIPELINE_DOC: &str = "pipeline.doc";
+
+pub const JOB_STATE_QUEUED: &str = "queued";
+pub const JOB_STATE_RUNNING: &str = "running";
+pub const JOB_STATE_SUCCEEDED: &str = "succeeded";
+pub const JOB_STATE_FAILED: &str = "failed";
+pub const JOB_STATE_CANCELLED: &str = "cancelled";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JobRecordV1 {
+    pub job_id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub state: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub next_run_at_ms: i64,
+    pub lease_expires_at_ms: Option<i64>,
+    pub progress_done: i64,
+    pub progress_total: i64,
+    pub last_error: Option<AppError>,
+    pub result: Option<serde_json::Value>,
+    pub created_at_ms: i64,
+    pub updated_at_ms: i64,
+}
+
+const JOB_COLUMNS: &str =
+    "job_id, kind, payload_json, state, attempts, max_attempts, next_run_at_ms,
+    lease_expires_at_ms, progress_done, progress_total, last_error_json, result_json,
+    created_at_ms, updated_at_ms";
+
+fn job_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "jobs", message, false, details)
+}
+
+fn db_error(message: &str, e: rusqlite::Error) -> AppError {
+    job_error(
+        "KC_JOB_PERSIST_FAILED",
+        message,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn parse_json_column(raw: Option<String>) -> rusqlite::Result<Option<serde_json::Value>> {
+    match raw {
+        Some(raw) => serde_json::from_str(&raw).map(Some).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        }),
+        None => Ok(None),
+    }
+}
+
+fn row_to_job(row: &rusqlite::Row<'_>) -> rusqlite::Result<JobRecordV1> {
+    let payload = parse_json_column(Some(row.get(2)?))?.unwrap_or(serde_json::Value::Null);
+    let last_error = parse_json_column(row.get(10)?)?
+        .map(serde_json::from_value::<AppError>)
+        .transpose()
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(10, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+    Ok(JobRecordV1 {
+        job_id: row.get(0)?,
+        kind: row.get(1)?,
+        payload,
+        state: row.get(3)?,
+        attempts: row.get(4)?,
+        max_attempts: row.get(5)?,
+        next_run_at_ms: row.get(6)?,
+        lease_expires_at_ms: row.get(7)?,
+        progress_done: row.get(8)?,
+        progress_total: row.get(9)?,
+        last_error,
+        result: parse_json_column(row.get(11)?)?,
+        created_at_ms: row.get(12)?,
+        updated_at_ms: row.get(13)?,
+    })
+}
+
+fn supported_kind(kind: &str) -> bool {
+    matches!(
+        kind,
+        JOB_KIND_INGEST_FILE | JOB_KIND_INGEST_SCAN_FOLDER | JOB_KIND_PIPELINE_DOC
+    )
+}
+
+pub fn job_backoff_ms(attempts: i64) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    (JOB_BACKOFF_BASE_MS * 2i64.pow(exponent)).min(JOB_BACKOFF_MAX_MS)
+}
+
+pub fn job_enqueue(
+    conn: &Connection,
+    kind: &str,
+    payload: &serde_json::Value,
+    max_attempts: i64,
+    now_ms: i64,
+) -> AppResult<JobRecordV1> {
+    if !supported_kind(kind) {
+        return Err(job_error(
+            "KC_JOB_KIND_UNSUPPORTED",
+            "job kind is not supported",
+            serde_json::json!({ "kind": kind }),
+        ));
+    }
+    if max_attempts < 1 {
+        return Err(job_error(
+            "KC_JOB_STATE_INVALID",
+            "job max_attempts must be at least 1",
+            serde_json::json!({ "max_attempts": max_attempts }),
+        ));
+    }
+
+    let payload_bytes = to_canonical_bytes(payload)?;
+    let payload_json = String::from_utf8(payload_bytes).map_err(|e| {
+        job_error(
+            "KC_JOB_PERSIST_FAILED",
+            "job payload is not utf8",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+    let seq: i64 = conn
+        .query_row("SELECT COUNT(*) FROM jobs", [], |row| row.get(0))
+        .map_err(|e| db_error("failed counting jobs", e))?;
+    let job_id = format!(
+        "job:{}",
+        blake3_hex_prefixed(
+            format!("kc.job.v1\n{kind}\n{payload_json}\n{now_ms}\n{seq}").as_bytes()
+        )
+    );
+
+    conn.execute(
+        "INSERT INTO jobs(job_id, kind, payload_json, state, attempts, max_attempts, next_run_at_ms,
+           lease_expires_at_ms, progress_done, progress_total, last_error_json, result_json,
+           created_at_ms, updated_at_ms)
+         VALUES (?1, ?2, ?3, 'queued', 0, ?4, ?5, NULL, 0, 0, NULL, NULL, ?5, ?5)",
+        params![job_id, kind, payload_json, max_attempts, now_ms],
+    )
+    .map_err(|e| db_error("failed inserting job", e))?;
+
+    job_get(conn, &job_id)
+}
+
+pub fn job_get(conn: &Connection, job_id: &str) -> AppResult<JobRecordV1> {
+    conn.query_row(
+        &format!("SELECT {JOB_COLUMNS} FROM jobs WHERE job_id=?1"),
+        params![job_id],
+        row_to_job,
+    )
+    .optional()
+    .map_err(|e| db_error("failed loading job", e))?
+    .ok_or_else(|| {
+        job_error(
+            "KC_JOB_NOT_FOUND",
+            "job does not exist",
+            serde_json::json!({ "job_id": job_id }),
+        )
+    })
+}
+
+pub fn jobs_list(conn: &Connection) -> AppResult<Vec<JobRecordV1>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {JOB_COLUMNS} FROM jobs ORDER BY created_at_ms ASC, job_id ASC"
+        ))
+        .map_err(|e| db_error("failed preparing jobs query", e))?;
+    let rows = stmt
+        .query_map([], row_to_job)
+        .map_err(|e| db_error("failed querying jobs", e))?;
+
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row.map_err(|e| db_error("failed decoding job row", e))?);
+    }
+    Ok(out)
+}
+
+pub fn jobs_recover_expired(conn: &Connection, now_ms: i64) -> AppResult<i64> {
+    let changed = conn
+        .execute(
+            "UPDATE jobs
+             SET state='queued', lease_expires_at_ms=NULL, next_run_at_ms=?1, updated_at_ms=?1
+             WHERE state='running' AND lease_expires_at_ms IS NOT NULL AND lease_expires_at_ms <= ?1",
+            params![now_ms],
+        )
+        .map_err(|e| db_error("failed recovering expired jobs", e))?;
+    Ok(changed as i64)
+}
+
+pub fn job_claim_next(conn: &Connection, now_ms: i64) -> AppResult<Option<JobRecordV1>> {
+    jobs_recover_expired(conn, now_ms)?;
+
+    let next: Option<String> = conn
+        .query_row(
+            "SELECT job_id FROM jobs
+             WHERE state='queued' AND next_run_at_ms <= ?1
+             ORDER BY next_run_at_ms ASC, created_at_ms ASC, job_id ASC
+             LIMIT 1",
+            params![now_ms],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| db_error("failed selecting next job", e))?;
+    let Some(job_id) = next else {
+        return Ok(None);
+    };
+
+    let changed = conn
+        .execute(
+            "UPDATE jobs
+             SET state='running', attempts=attempts + 1, lease_expires_at_ms=?2, updated_at_ms=?3
+             WHERE job_id=?1 AND state='queued'",
+            params![job_id, now_ms + JOB_LEASE_MS, now_ms],
+        )
+        .map_err(|e| db_error("failed claiming job", e))?;
+    if changed == 0 {
+        return Ok(None);
+    }
+    job_get(conn, &job_id).map(Some)
+}
+
+pub fn job_report_progress(
+    conn: &Connection,
+    job_id: &str,
+    done: i64,
+    total: i64,
+    now_ms: i64,
+) -> AppResult<bool> {
+    let changed = conn
+        .execute(
+            "UPDATE jobs
+             SET progress_done=?2, progress_total=?3, lease_expires_at_ms=?4, updated_at_ms=?5
+             WHERE job_id=?1 AND state='running'",
+            params![job_id, done, total, now_ms + JOB_LEASE_MS, now_ms],
+        )
+        .map_err(|e| db_error("failed updating job progress", e))?;
+    Ok(changed > 0)
+}
+
+pub fn job_complete(
+    conn: &Connection,
+    job_id: &str,
+    result: &serde_json::Value,
+    now_ms: i64,
+) -> AppResult<JobRecordV1> {
+    conn.execute(
+        "UPDATE jobs
+         SET state='succeeded', lease_expires_at_ms=NULL, result_json=?2, last_error_json=NULL,
+             updated_at_ms=?3
+         WHERE job_id=?1 AND state='running'",
+        params![job_id, result.to_string(), now_ms],
+    )
+    .map_err(|e| db_error("failed completing job", e))?;
+    job_get(conn, job_id)
+}
+
+pub fn job_fail(
+    conn: &Connection,
+    job_id: &str,
+    error: &AppError,
+    now_ms: i64,
+) -> AppResult<JobRecordV1> {
+    let job = job_get(conn, job_id)?;
+    if job.state != JOB_STATE_RUNNING {
+        return Ok(job);
+    }
+
+    let error_json = serde_json::to_string(error).map_err(|e| {
+        job_error(
+            "KC_JOB_PERSIST_FAILED",
+            "failed serializing job error",
+            serde_json::json!({ "error": e.to_string(), "job_id": job_id }),
+        )
+    })?;
+    let retry = error.retryable && job.attempts < job.max_attempts;
+    let (state, next_run_at_ms) = if retry {
+        (JOB_STATE_QUEUED, now_ms + job_backoff_ms(job.attempts))
+    } else {
+        (JOB_STATE_FAILED, job.next_run_at_ms)
+    };
+
+    conn.execute(
+        "UPDATE jobs
+         SET state=?2, next_run_at_ms=?3, lease_expires_at_ms=NULL, last_error_json=?4,
+             updated_at_ms=?5
+         WHERE job_id=?1 AND state='running'",
+        params![job_id, state, next_run_at_ms, error_json, now_ms],
+    )
+    .map_err(|e| db_error("failed recording job failure", e))?;
+    job_get(conn, job_id)
+}
+
+pub fn job_cancel(conn: &Connection, job_id: &str, now_ms: i64) -> AppResult<bool> {
+    job_get(conn, job_id)?;
+    let changed = conn
+        .execute(
+            "UPDATE jobs
+             SET state='cancelled', lease_expires_at_ms=NULL, updated_at_ms=?2
+             WHERE job_id=?1 AND state IN ('queued', 'running')",
+            params![job_id, now_ms],
+        )
+        .map_err(|e| db_error("failed cancelling job", e))?;
+    Ok(changed > 0)
+}
+
+fn payload_str<'a>(job: &'a JobRecordV1, field: &str) -> AppResult<&'a str> {
+    job.payload
+        .get(field)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            job_error(
+                "KC_JOB_STATE_INVALID",
+                "job payload is missing a required field",
+                serde_json::json!({ "job_id": job.job_id, "field": field }),
+            )
+        })
+}
+
+// A missing or unreadable file will not appear by retrying; other I/O errors may be transient.
+fn read_job_file(path: &Path) -> AppResult<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        let retryable = !matches!(
+            e.kind(),
+            std::io::ErrorKind::NotFound
+                | std::io::ErrorKind::PermissionDenied
+                | std::io::ErrorKind::IsADirectory
+                | std::io::ErrorKind::InvalidInput
+        );
+        AppError::new(
+            "KC_INGEST_FAILED",
+            "ingest",
+            "failed reading job input file",
+            retryable,
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    })
+}
+
+fn ingest_and_process(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    path: &Path,
+    source_kind: &str,
+    now_ms: i64,
+) -> AppResult<DocId> {
+    let bytes = read_job_file(path)?;
+    let doc = ingest_bytes(
+        conn,
+        object_store,
+        IngestBytesReq {
+            bytes: &bytes,
+            mime: &mime_for_path(path),
+            source_kind,
+            effective_ts_ms: file_effective_ts_ms(path, now_ms),
+            source_path: Some(&path.to_string_lossy()),
+            now_ms,
+        },
+    )?;
+    run_doc_pipeline(conn, object_store, services, &doc.doc_id, now_ms)?;
+    Ok(doc.doc_id)
+}
+
+// Returns None when the job was cancelled while it was running.
+fn execute_job(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    job: &JobRecordV1,
+    now_ms: i64,
+) -> AppResult<Option<serde_json::Value>> {
+    match job.kind.as_str() {
+        JOB_KIND_PIPELINE_DOC => {
+            let doc_id = DocId(payload_str(job, "doc_id")?.to_string());
+            if !job_report_progress(conn, &job.job_id, 0, 1, now_ms)? {
+                return Ok(None);
+            }
+            let out = run_doc_pipeline(conn, object_store, services, &doc_id, now_ms)?;
+            job_report_progress(conn, &job.job_id, 1, 1, now_ms)?;
+            Ok(Some(serde_json::json!({
+                "doc_id": out.doc_id.0,
+                "chunk_count": out.chunk_count,
+            })))
+        }
+        JOB_KIND_INGEST_FILE => {
+            let path = PathBuf::from(payload_str(job, "file_path")?);
+            let source_kind = payload_str(job, "source_kind")?;
+            if !job_report_progress(conn, &job.job_id, 0, 1, now_ms)? {
+                return Ok(None);
+            }
+            let doc_id =
+                ingest_and_process(conn, object_store, services, &path, source_kind, now_ms)?;
+            job_report_progress(conn, &job.job_id, 1, 1, now_ms)?;
+            Ok(Some(serde_json::json!({ "doc_id": doc_id.0 })))
+        }
+        JOB_KIND_INGEST_SCAN_FOLDER => {
+            let scan_root = PathBuf::from(payload_str(job, "scan_root")?);
+            let source_kind = payload_str(job, "source_kind")?;
+            let mut files: Vec<PathBuf> = walkdir::WalkDir::new(&scan_root)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.path().to_path_buf())
+                .collect();
+            files.sort();
+
+            let total = files.len() as i64;
+            let resume_from = job.progress_done.clamp(0, total);
+            if !job_report_progress(conn, &job.job_id, resume_from, total, now_ms)? {
+                return Ok(None);
+            }
+            for (idx, path) in files.iter().enumerate().skip(resume_from as usize) {
+                ingest_and_process(conn, object_store, services, path, source_kind, now_ms)?;
+                if !job_report_progress(conn, &job.job_id, idx as i64 + 1, total, now_ms)? {
+                    return Ok(None);
+                }
+            }
+            Ok(Some(serde_json::json!({
+                "ingested": total,
+                "resumed_from": resume_from,
+            })))
+        }
+        other => Err(job_error(
+            "KC_JOB_KIND_UNSUPPORTED",
+            "job kind is not supported",
+            serde_json::json!({ "job_id": job.job_id, "kind": other }),
+        )),
+    }
+}
+
+pub fn run_pending_jobs(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    now_ms: i64,
+    max_jobs: usize,
+) -> AppResult<Vec<JobRecordV1>> {
+    let mut finished = Vec::new();
+    while finished.len() < max_jobs {
+        let Some(job) = job_claim_next(conn, now_ms)? else {
+            break;
+        };
+        let record = match execute_job(conn, object_store, services, &job, now_ms) {
+            Ok(Some(result)) => job_complete(conn, &job.job_id, &result, now_ms)?,
+            Ok(None) => job_get(conn, &job.job_id)?,
+            Err(error) => job_fail(conn, &job.job_id, &error, now_ms)?,
+        };
+        finished.push(record);
+    }
+    Ok(finished)
+}
@@ -3,16 +3,23 @@ pub mod canon_json;
 pub mod canonical;
 pub mod chunking;
 pub mod db;
+pub mod diversify;
+pub mod doc_versions;
 pub mod events;
 pub mod export;
+pub mod faithfulness;
+pub mod gc;
 pub mod hashing;
+pub mod inbox;
 pub mod index_traits;
 pub mod ingest;
+pub mod jobs;
 pub mod lineage;
 pub mod lineage_governance;
 pub mod lineage_policy;
 pub mod locator;
 pub mod object_store;
+pub mod pipeline;
 pub mod recovery;
 pub mod recovery_escrow;
 pub mod recovery_escrow_aws;
@@ -21,14 +28,19 @@ pub mod recovery_escrow_gcp;
 pub mod recovery_escrow_hsm;
 pub mod recovery_escrow_local;
 pub mod recovery_escrow_private_kms;
+pub mod reextract;
+pub mod rerank;
 pub mod retrieval;
 pub mod rpc_service;
+pub mod search;
 pub mod services;
 pub mod snippet;
 pub mod sync;
 pub mod sync_merge;
 pub mod sync_s3;
 pub mod sync_transport;
+pub mod tombstone;
+pub mod trace_redaction;
 pub mod trust;
 pub mod trust_identity;
 pub mod trust_policy;
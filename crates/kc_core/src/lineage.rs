@@ -1,4 +1,5 @@
 use crate::app_error::{AppError, AppResult};
+use crate::doc_versions::doc_version_history;
 use crate::hashing::blake3_hex_prefixed;
 use crate::lineage_governance::ensure_lineage_permission;
 use crate::lineage_policy::ensure_lineage_policy_allows;
@@ -410,6 +411,45 @@ pub fn query_lineage(
         );
     }
 
+    for history in doc_version_history(conn, seed_doc_id)? {
+        let Some(pos) = history
+            .versions
+            .iter()
+            .rposition(|v| v.doc_id == seed_doc_id)
+        else {
+            continue;
+        };
+        let lo = pos.saturating_sub(depth as usize);
+        let hi = (pos + depth as usize).min(history.versions.len() - 1);
+        for idx in lo..hi {
+            let older = &history.versions[idx];
+            let newer = &history.versions[idx + 1];
+            if older.doc_id == newer.doc_id {
+                continue;
+            }
+            for version in [older, newer] {
+                add_node(
+                    &mut nodes_by_id,
+                    format!("doc:{}", version.doc_id),
+                    "doc",
+                    version.doc_id.clone(),
+                    serde_json::json!({
+                        "doc_id": version.doc_id,
+                        "source_path": history.source_path,
+                        "version": version.version
+                    }),
+                );
+            }
+            add_edge(
+                &mut edge_keys,
+                format!("doc:{}", newer.doc_id),
+                format!("doc:{}", older.doc_id),
+                "supersedes",
+                format!("doc_versions:{}@{}", history.source_path, newer.version),
+            );
+        }
+    }
+
     let canonical_row = conn.query_row(
         "SELECT canonical_hash, canonical_object_hash, extractor_name, extractor_version, normalization_version, toolchain_json, created_event_id
          FROM canonical_text WHERE doc_id=?1",
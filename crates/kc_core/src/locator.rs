@@ -1,5 +1,6 @@
 use crate::app_error::{AppError, AppResult};
 use crate::types::{CanonicalHash, DocId, ObjectHash};
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,15 +62,37 @@ pub fn resolve_locator_strict(
             )
         })?;
 
-    if stored_hash != locator.canonical_hash.0 {
-        return Err(AppError::new(
-            "KC_LOCATOR_CANONICAL_HASH_MISMATCH",
-            "locator",
-            "locator canonical hash does not match stored canonical hash",
-            false,
-            serde_json::json!({ "expected": stored_hash, "actual": locator.canonical_hash.0 }),
-        ));
-    }
+    let stored_obj_hash = if stored_hash == locator.canonical_hash.0 {
+        stored_obj_hash
+    } else {
+        // Locators cut before a re-extraction resolve against the canonical version they cite.
+        let historical: Option<String> = conn
+            .query_row(
+                "SELECT canonical_object_hash FROM canonical_text_history
+                 WHERE doc_id=?1 AND canonical_hash=?2",
+                [locator.doc_id.0.clone(), locator.canonical_hash.0.clone()],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                AppError::new(
+                    "KC_LOCATOR_INVALID_SCHEMA",
+                    "locator",
+                    "failed to load canonical history for doc_id",
+                    false,
+                    serde_json::json!({ "error": e.to_string(), "doc_id": locator.doc_id.0 }),
+                )
+            })?;
+        historical.ok_or_else(|| {
+            AppError::new(
+                "KC_LOCATOR_CANONICAL_HASH_MISMATCH",
+                "locator",
+                "locator canonical hash does not match stored canonical hash",
+                false,
+                serde_json::json!({ "expected": stored_hash, "actual": locator.canonical_hash.0 }),
+            )
+        })?
+    };
 
     let bytes = object_store.get_bytes(&ObjectHash(stored_obj_hash))?;
     let text = String::from_utf8(bytes).map_err(|e| {
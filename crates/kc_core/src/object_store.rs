@@ -62,35 +62,20 @@ pub fn derive_object_store_key(
     Ok(key)
 }
 
-impl ObjectStore {
-    pub fn new(objects_dir: PathBuf) -> Self {
-        Self {
-            objects_dir,
-            encryption: None,
-        }
-    }
-
-    pub fn with_encryption(objects_dir: PathBuf, encryption: ObjectStoreEncryptionContext) -> Self {
-        Self {
-            objects_dir,
-            encryption: Some(encryption),
-        }
-    }
-
-    fn deterministic_nonce(object_hash: &ObjectHash, key_reference: &str) -> [u8; 24] {
-        let material = format!("{}:{}", object_hash.0, key_reference);
+impl ObjectStoreEncryptionContext {
+    fn deterministic_nonce(&self, label: &str) -> [u8; 24] {
+        let material = format!("{}:{}", label, self.key_reference);
         let digest = blake3::hash(material.as_bytes());
         let mut nonce = [0u8; 24];
         nonce.copy_from_slice(&digest.as_bytes()[0..24]);
         nonce
     }
 
-    fn maybe_encrypt_bytes(&self, object_hash: &ObjectHash, bytes: &[u8]) -> AppResult<Vec<u8>> {
-        let Some(enc) = &self.encryption else {
-            return Ok(bytes.to_vec());
-        };
-        let nonce = Self::deterministic_nonce(object_hash, &enc.key_reference);
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(&enc.key));
+    // Encrypts `bytes` as `KCE1 || nonce || ciphertext`. The nonce derives from `label` (the
+    // object hash for store objects), so a label must never be reused for different bytes.
+    pub fn seal(&self, label: &str, bytes: &[u8]) -> AppResult<Vec<u8>> {
+        let nonce = self.deterministic_nonce(label);
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&self.key));
         let ciphertext = cipher
             .encrypt(XNonce::from_slice(&nonce), bytes)
             .map_err(|e| {
@@ -110,41 +95,29 @@ impl ObjectStore {
         Ok(out)
     }
 
-    fn maybe_decrypt_bytes(&self, object_hash: &ObjectHash, bytes: &[u8]) -> AppResult<Vec<u8>> {
-        if !bytes.starts_with(ENCRYPTED_MAGIC) {
-            return Ok(bytes.to_vec());
-        }
-        let enc = self.encryption.as_ref().ok_or_else(|| {
-            AppError::new(
-                "KC_ENCRYPTION_REQUIRED",
-                "encryption",
-                "encrypted object payload requires encryption context",
-                false,
-                serde_json::json!({ "object_hash": object_hash.0 }),
-            )
-        })?;
-        if bytes.len() < ENCRYPTED_MAGIC.len() + 24 {
+    // Reverses `seal` for the same `label`.
+    pub fn open(&self, label: &str, bytes: &[u8]) -> AppResult<Vec<u8>> {
+        if !bytes.starts_with(ENCRYPTED_MAGIC) || bytes.len() < ENCRYPTED_MAGIC.len() + 24 {
             return Err(AppError::new(
                 "KC_ENCRYPTION_UNSUPPORTED",
                 "encryption",
                 "encrypted object payload has invalid format",
                 false,
-                serde_json::json!({ "object_hash": object_hash.0, "bytes": bytes.len() }),
+                serde_json::json!({ "label": label, "bytes": bytes.len() }),
             ));
         }
         let nonce = &bytes[ENCRYPTED_MAGIC.len()..ENCRYPTED_MAGIC.len() + 24];
         let ciphertext = &bytes[ENCRYPTED_MAGIC.len() + 24..];
-        let expected_nonce = Self::deterministic_nonce(object_hash, &enc.key_reference);
-        if nonce != expected_nonce {
+        if nonce != self.deterministic_nonce(label) {
             return Err(AppError::new(
                 "KC_ENCRYPTION_KEY_INVALID",
                 "encryption",
                 "encryption key context does not match stored object payload",
                 false,
-                serde_json::json!({ "object_hash": object_hash.0 }),
+                serde_json::json!({ "label": label }),
             ));
         }
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(&enc.key));
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&self.key));
         cipher
             .decrypt(XNonce::from_slice(nonce), ciphertext)
             .map_err(|e| {
@@ -153,10 +126,49 @@ impl ObjectStore {
                     "encryption",
                     "failed decrypting object payload",
                     false,
-                    serde_json::json!({ "error": e.to_string(), "object_hash": object_hash.0 }),
+                    serde_json::json!({ "error": e.to_string(), "label": label }),
                 )
             })
     }
+}
+
+impl ObjectStore {
+    pub fn new(objects_dir: PathBuf) -> Self {
+        Self {
+            objects_dir,
+            encryption: None,
+        }
+    }
+
+    pub fn with_encryption(objects_dir: PathBuf, encryption: ObjectStoreEncryptionContext) -> Self {
+        Self {
+            objects_dir,
+            encryption: Some(encryption),
+        }
+    }
+
+    fn maybe_encrypt_bytes(&self, object_hash: &ObjectHash, bytes: &[u8]) -> AppResult<Vec<u8>> {
+        match &self.encryption {
+            Some(enc) => enc.seal(&object_hash.0, bytes),
+            None => Ok(bytes.to_vec()),
+        }
+    }
+
+    fn maybe_decrypt_bytes(&self, object_hash: &ObjectHash, bytes: &[u8]) -> AppResult<Vec<u8>> {
+        if !bytes.starts_with(ENCRYPTED_MAGIC) {
+            return Ok(bytes.to_vec());
+        }
+        let enc = self.encryption.as_ref().ok_or_else(|| {
+            AppError::new(
+                "KC_ENCRYPTION_REQUIRED",
+                "encryption",
+                "encrypted object payload requires encryption context",
+                false,
+                serde_json::json!({ "object_hash": object_hash.0 }),
+            )
+        })?;
+        enc.open(&object_hash.0, bytes)
+    }
 
     fn file_path_for_hash(&self, object_hash: &ObjectHash) -> AppResult<PathBuf> {
         if !object_hash.0.starts_with("blake3:") || object_hash.0.len() < 9 {
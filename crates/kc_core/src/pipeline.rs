@@ -0,0 +1,224 @@
+use crate::app_error::{AppError, AppResult};
+use crate::canonical::persist_canonical_text;
+use crate::chunking::{chunk_document, hash_chunking_config, ChunkRecord, ChunkingConfigV1};
+use crate::events::append_event;
+use crate::index_traits::{IndexChunk, LexicalIndex, VectorIndex};
+use crate::object_store::ObjectStore;
+use crate::services::{ExtractInput, ExtractService};
+use crate::types::{CanonicalHash, ConfigHash, DocId, ObjectHash};
+use rusqlite::{params, Connection};
+
+pub struct PipelineServices<'a> {
+    pub extractor: &'a dyn ExtractService,
+    pub lexical: &'a dyn LexicalIndex,
+    pub vector: &'a dyn VectorIndex,
+    pub chunking: &'a ChunkingConfigV1,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipelineDocResult {
+    pub doc_id: DocId,
+    pub canonical_hash: CanonicalHash,
+    pub chunking_config_hash: ConfigHash,
+    pub chunk_count: i64,
+    pub stage_event_ids: Vec<i64>,
+}
+
+struct DocRow {
+    original_object_hash: String,
+    mime: String,
+    source_kind: String,
+}
+
+fn load_doc_row(conn: &Connection, doc_id: &DocId) -> AppResult<DocRow> {
+    conn.query_row(
+        "SELECT original_object_hash, mime, source_kind FROM docs WHERE doc_id=?1",
+        params![doc_id.0],
+        |row| {
+            Ok(DocRow {
+                original_object_hash: row.get(0)?,
+                mime: row.get(1)?,
+                source_kind: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| {
+        AppError::new(
+            "KC_INGEST_DOC_NOT_FOUND",
+            "ingest",
+            "failed to load doc for pipeline",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id.0 }),
+        )
+    })
+}
+
+fn slice_chars(text: &str, start: i64, end: i64) -> String {
+    text.chars()
+        .skip(start.max(0) as usize)
+        .take((end - start).max(0) as usize)
+        .collect()
+}
+
+fn replace_chunk_rows(
+    conn: &Connection,
+    doc_id: &DocId,
+    source_kind: &str,
+    chunks: &[ChunkRecord],
+) -> AppResult<()> {
+    let tx = conn.unchecked_transaction().map_err(|e| {
+        AppError::new(
+            "KC_CHUNK_PERSIST_FAILED",
+            "chunking",
+            "failed to start chunk transaction",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id.0 }),
+        )
+    })?;
+
+    tx.execute("DELETE FROM chunks WHERE doc_id=?1", params![doc_id.0])
+        .map_err(|e| {
+            AppError::new(
+                "KC_CHUNK_PERSIST_FAILED",
+                "chunking",
+                "failed to clear previous chunk rows",
+                false,
+                serde_json::json!({ "error": e.to_string(), "doc_id": doc_id.0 }),
+            )
+        })?;
+
+    for chunk in chunks {
+        tx.execute(
+            "INSERT INTO chunks (chunk_id, doc_id, ordinal, start_char, end_char, chunking_config_hash, source_kind, simhash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                chunk.chunk_id.0,
+                chunk.doc_id.0,
+                chunk.ordinal,
+                chunk.start_char,
+                chunk.end_char,
+                chunk.chunking_config_hash.0,
+                source_kind,
+                chunk.simhash
+            ],
+        )
+        .map_err(|e| {
+            AppError::new(
+                "KC_CHUNK_PERSIST_FAILED",
+                "chunking",
+                "failed to insert chunk row",
+                false,
+                serde_json::json!({ "error": e.to_string(), "chunk_id": chunk.chunk_id.0 }),
+            )
+        })?;
+    }
+
+    tx.commit().map_err(|e| {
+        AppError::new(
+            "KC_CHUNK_PERSIST_FAILED",
+            "chunking",
+            "failed to commit chunk rows",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id.0 }),
+        )
+    })
+}
+
+pub fn run_doc_pipeline(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    doc_id: &DocId,
+    now_ms: i64,
+) -> AppResult<PipelineDocResult> {
+    let doc = load_doc_row(conn, doc_id)?;
+    let original = object_store.get_bytes(&ObjectHash(doc.original_object_hash.clone()))?;
+    let mut stage_event_ids = Vec::new();
+
+    let artifact = services.extractor.extract_canonical(ExtractInput {
+        doc_id,
+        bytes: &original,
+        mime: &doc.mime,
+        source_kind: &doc.source_kind,
+    })?;
+    let extract_event = append_event(
+        conn,
+        now_ms,
+        "pipeline.extract",
+        &serde_json::json!({
+            "doc_id": doc_id.0,
+            "extractor_name": artifact.extractor_name,
+            "extractor_version": artifact.extractor_version,
+            "canonical_hash": artifact.canonical_hash.0,
+        }),
+    )?;
+    stage_event_ids.push(extract_event.event_id);
+
+    let canonical_event = append_event(
+        conn,
+        now_ms,
+        "pipeline.canonical",
+        &serde_json::json!({
+            "doc_id": doc_id.0,
+            "canonical_hash": artifact.canonical_hash.0,
+            "canonical_object_hash": artifact.canonical_object_hash.0,
+        }),
+    )?;
+    persist_canonical_text(conn, object_store, &artifact, canonical_event.event_id)?;
+    stage_event_ids.push(canonical_event.event_id);
+
+    let canonical = String::from_utf8(artifact.canonical_bytes.clone()).map_err(|e| {
+        AppError::new(
+            "KC_CHUNK_PERSIST_FAILED",
+            "chunking",
+            "canonical text is not utf8",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id.0 }),
+        )
+    })?;
+    let chunking_config_hash = hash_chunking_config(services.chunking)?;
+    let chunks = chunk_document(doc_id, &canonical, &doc.mime, services.chunking)?;
+    replace_chunk_rows(conn, doc_id, &doc.source_kind, &chunks)?;
+    let chunk_event = append_event(
+        conn,
+        now_ms,
+        "pipeline.chunk",
+        &serde_json::json!({
+            "doc_id": doc_id.0,
+            "chunking_config_hash": chunking_config_hash.0,
+            "chunk_count": chunks.len(),
+        }),
+    )?;
+    stage_event_ids.push(chunk_event.event_id);
+
+    let index_chunks: Vec<IndexChunk> = chunks
+        .iter()
+        .map(|chunk| IndexChunk {
+            chunk_id: chunk.chunk_id.clone(),
+            doc_id: chunk.doc_id.clone(),
+            ordinal: chunk.ordinal,
+            text: slice_chars(&canonical, chunk.start_char, chunk.end_char),
+        })
+        .collect();
+    services.lexical.rebuild_for_doc(doc_id, &index_chunks)?;
+    services.vector.rebuild_for_doc(doc_id, &index_chunks)?;
+    let index_event = append_event(
+        conn,
+        now_ms,
+        "pipeline.index",
+        &serde_json::json!({
+            "doc_id": doc_id.0,
+            "lexical_rows": index_chunks.len(),
+            "vector_rows": index_chunks.len(),
+        }),
+    )?;
+    stage_event_ids.push(index_event.event_id);
+
+    Ok(PipelineDocResult {
+        doc_id: doc_id.clone(),
+        canonical_hash: artifact.canonical_hash,
+        chunking_config_hash,
+        chunk_count: chunks.len() as i64,
+        stage_event_ids,
+    })
+}
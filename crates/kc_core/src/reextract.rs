@@ -0,0 +1,171 @@
+use crate::app_error::{AppError, AppResult};
+use crate::events::append_event;
+use crate::object_store::ObjectStore;
+use crate::pipeline::{run_doc_pipeline, PipelineServices};
+use crate::services::ExtractorIdentity;
+use crate::types::DocId;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReextractDocV1 {
+    pub doc_id: String,
+    pub stale_reasons: Vec<String>,
+    pub previous_canonical_hash: String,
+    pub canonical_hash: Option<String>,
+    pub chunk_count: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReextractReportV1 {
+    pub schema_version: i64,
+    pub dry_run: bool,
+    pub identity: ExtractorIdentity,
+    pub scanned_docs: i64,
+    pub docs: Vec<ReextractDocV1>,
+}
+
+struct CanonicalIdentityRow {
+    doc_id: String,
+    canonical_hash: String,
+    extractor_name: String,
+    extractor_version: String,
+    normalization_version: i64,
+    toolchain_json: String,
+}
+
+fn db_error(message: &str, e: rusqlite::Error) -> AppError {
+    AppError::new(
+        "KC_DB_INTEGRITY_FAILED",
+        "reextract",
+        message,
+        false,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn toolchain_identity_field(toolchain_json: &str, pointer: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(toolchain_json)
+        .ok()
+        .and_then(|v| {
+            v.pointer(pointer)
+                .and_then(|x| x.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_default()
+}
+
+fn stale_reasons(row: &CanonicalIdentityRow, identity: &ExtractorIdentity) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if row.extractor_name != identity.extractor_name {
+        reasons.push("extractor_name".to_string());
+    }
+    if row.extractor_version != identity.extractor_version {
+        reasons.push("extractor_version".to_string());
+    }
+    if row.normalization_version != identity.normalization_version {
+        reasons.push("normalization_version".to_string());
+    }
+    if toolchain_identity_field(&row.toolchain_json, "/pdfium/identity")
+        != identity.toolchain.pdfium_identity
+    {
+        reasons.push("pdfium_identity".to_string());
+    }
+    if toolchain_identity_field(&row.toolchain_json, "/tesseract/identity")
+        != identity.toolchain.tesseract_identity
+    {
+        reasons.push("tesseract_identity".to_string());
+    }
+    reasons
+}
+
+pub fn select_stale_docs(
+    conn: &Connection,
+    identity: &ExtractorIdentity,
+) -> AppResult<(i64, Vec<ReextractDocV1>)> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT doc_id, canonical_hash, extractor_name, extractor_version, normalization_version, toolchain_json
+             FROM canonical_text ORDER BY doc_id ASC",
+        )
+        .map_err(|e| db_error("failed preparing canonical identity query", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(CanonicalIdentityRow {
+                doc_id: row.get(0)?,
+                canonical_hash: row.get(1)?,
+                extractor_name: row.get(2)?,
+                extractor_version: row.get(3)?,
+                normalization_version: row.get(4)?,
+                toolchain_json: row.get(5)?,
+            })
+        })
+        .map_err(|e| db_error("failed querying canonical identities", e))?;
+
+    let mut scanned = 0i64;
+    let mut out = Vec::new();
+    for row in rows {
+        let row = row.map_err(|e| db_error("failed decoding canonical identity row", e))?;
+        scanned += 1;
+        let reasons = stale_reasons(&row, identity);
+        if reasons.is_empty() {
+            continue;
+        }
+        out.push(ReextractDocV1 {
+            doc_id: row.doc_id,
+            stale_reasons: reasons,
+            previous_canonical_hash: row.canonical_hash,
+            canonical_hash: None,
+            chunk_count: None,
+        });
+    }
+    Ok((scanned, out))
+}
+
+pub fn reextract_stale_docs(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    services: &PipelineServices<'_>,
+    identity: &ExtractorIdentity,
+    dry_run: bool,
+    limit: Option<usize>,
+    now_ms: i64,
+) -> AppResult<ReextractReportV1> {
+    let (scanned_docs, mut docs) = select_stale_docs(conn, identity)?;
+    if let Some(limit) = limit {
+        docs.truncate(limit);
+    }
+
+    if !dry_run {
+        for doc in &mut docs {
+            let result = run_doc_pipeline(
+                conn,
+                object_store,
+                services,
+                &DocId(doc.doc_id.clone()),
+                now_ms,
+            )?;
+            append_event(
+                conn,
+                now_ms,
+                "pipeline.reextract",
+                &serde_json::json!({
+                    "doc_id": doc.doc_id,
+                    "stale_reasons": doc.stale_reasons,
+                    "previous_canonical_hash": doc.previous_canonical_hash,
+                    "canonical_hash": result.canonical_hash.0,
+                }),
+            )?;
+            doc.canonical_hash = Some(result.canonical_hash.0);
+            doc.chunk_count = Some(result.chunk_count);
+        }
+    }
+
+    Ok(ReextractReportV1 {
+        schema_version: 1,
+        dry_run,
+        identity: identity.clone(),
+        scanned_docs,
+        docs,
+    })
+}
@@ -0,0 +1,195 @@
+use crate::app_error::{AppError, AppResult};
+use crate::types::ChunkId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub const DEFAULT_RERANK_TOP_K: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct RerankInput {
+    pub chunk_id: ChunkId,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RerankScore {
+    pub score: f64,
+    pub reason: String,
+}
+
+// One entry per reranked candidate, in reranked order; `merged_rank` is the 1-based position
+// the candidate had after merging.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RerankedV1 {
+    pub chunk_id: String,
+    pub merged_rank: usize,
+    pub score: f64,
+    pub reason: String,
+}
+
+pub trait Reranker: Send + Sync {
+    fn reranker_id(&self) -> String;
+    // Exactly one score per candidate, in candidate order; higher is more relevant.
+    fn score(&self, query: &str, candidates: &[RerankInput]) -> AppResult<Vec<RerankScore>>;
+}
+
+pub trait CrossEncoder: Send + Sync {
+    fn model_id(&self) -> String;
+    fn score_pairs(&self, query: &str, passages: &[&str]) -> AppResult<Vec<f64>>;
+}
+
+pub struct CrossEncoderReranker<C> {
+    pub model: C,
+}
+
+impl<C: CrossEncoder> Reranker for CrossEncoderReranker<C> {
+    fn reranker_id(&self) -> String {
+        format!("cross_encoder:{}", self.model.model_id())
+    }
+
+    fn score(&self, query: &str, candidates: &[RerankInput]) -> AppResult<Vec<RerankScore>> {
+        let passages: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        let model_id = self.model.model_id();
+        Ok(self
+            .model
+            .score_pairs(query, &passages)?
+            .into_iter()
+            .map(|score| RerankScore {
+                score,
+                reason: format!("cross-encoder relevance from {model_id}"),
+            })
+            .collect())
+    }
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_lowercase())
+        .collect()
+}
+
+// Share of distinct query terms found in the passage, plus 0.1 x the share of adjacent query
+// term pairs that also appear adjacent in the passage.
+#[derive(Debug, Default)]
+pub struct LexicalOverlapReranker;
+
+impl LexicalOverlapReranker {
+    fn score_one(
+        query_terms: &BTreeSet<String>,
+        bigrams: &BTreeSet<(String, String)>,
+        text: &str,
+    ) -> RerankScore {
+        if query_terms.is_empty() {
+            return RerankScore {
+                score: 0.0,
+                reason: "no query terms".to_string(),
+            };
+        }
+        let passage = terms(text);
+        let present: BTreeSet<&String> = passage.iter().collect();
+        let matched: Vec<&str> = query_terms
+            .iter()
+            .filter(|t| present.contains(t))
+            .map(|t| t.as_str())
+            .collect();
+        let passage_bigrams: BTreeSet<(&String, &String)> =
+            passage.windows(2).map(|w| (&w[0], &w[1])).collect();
+        let matched_bigrams = bigrams
+            .iter()
+            .filter(|(a, b)| passage_bigrams.contains(&(a, b)))
+            .count();
+
+        let mut score = matched.len() as f64 / query_terms.len() as f64;
+        let mut reason = format!(
+            "matched {}/{} terms [{}]",
+            matched.len(),
+            query_terms.len(),
+            matched.join(", ")
+        );
+        if !bigrams.is_empty() {
+            score += 0.1 * matched_bigrams as f64 / bigrams.len() as f64;
+            reason.push_str(&format!(
+                "; {}/{} adjacent pairs",
+                matched_bigrams,
+                bigrams.len()
+            ));
+        }
+        RerankScore { score, reason }
+    }
+}
+
+impl Reranker for LexicalOverlapReranker {
+    fn reranker_id(&self) -> String {
+        "lexical_overlap".to_string()
+    }
+
+    fn score(&self, query: &str, candidates: &[RerankInput]) -> AppResult<Vec<RerankScore>> {
+        let query = terms(query);
+        let query_terms: BTreeSet<String> = query.iter().cloned().collect();
+        let bigrams: BTreeSet<(String, String)> = query
+            .windows(2)
+            .filter(|w| w[0] != w[1])
+            .map(|w| (w[0].clone(), w[1].clone()))
+            .collect();
+        Ok(candidates
+            .iter()
+            .map(|c| Self::score_one(&query_terms, &bigrams, &c.text))
+            .collect())
+    }
+}
+
+fn round12(value: f64) -> f64 {
+    (value * 1_000_000_000_000.0).round() / 1_000_000_000_000.0
+}
+
+// Scores `candidates` (already in merged order) and returns them in reranked order: score
+// desc, ties keep their merged order.
+pub fn rerank_candidates(
+    reranker: &dyn Reranker,
+    query: &str,
+    candidates: &[RerankInput],
+) -> AppResult<Vec<RerankedV1>> {
+    let scores = reranker.score(query, candidates)?;
+    if scores.len() != candidates.len() {
+        return Err(AppError::new(
+            "KC_RETRIEVAL_RERANK_FAILED",
+            "retrieval",
+            "reranker returned a different number of scores than candidates",
+            false,
+            serde_json::json!({
+                "reranker": reranker.reranker_id(),
+                "candidates": candidates.len(),
+                "scores": scores.len(),
+            }),
+        ));
+    }
+    let mut out = Vec::with_capacity(candidates.len());
+    for (idx, (candidate, scored)) in candidates.iter().zip(scores).enumerate() {
+        if !scored.score.is_finite() {
+            return Err(AppError::new(
+                "KC_RETRIEVAL_RERANK_FAILED",
+                "retrieval",
+                "reranker returned a non-finite score",
+                false,
+                serde_json::json!({
+                    "reranker": reranker.reranker_id(),
+                    "chunk_id": candidate.chunk_id.0,
+                }),
+            ));
+        }
+        out.push(RerankedV1 {
+            chunk_id: candidate.chunk_id.0.clone(),
+            merged_rank: idx + 1,
+            score: round12(scored.score),
+            reason: scored.reason,
+        });
+    }
+    out.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.merged_rank.cmp(&b.merged_rank))
+    });
+    Ok(out)
+}
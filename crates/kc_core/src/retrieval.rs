@@ -1,15 +1,50 @@
-use crate::app_error::AppResult;
+use crate::app_error::{AppError, AppResult};
+use crate::canon_json::hash_canonical;
+use crate::diversify::DiversifyConfigV1;
 use crate::index_traits::{LexicalCandidate, VectorCandidate};
-use crate::types::{ChunkId, DocId};
+use crate::types::{ChunkId, ConfigHash, DocId};
+use crate::vault::VaultJsonV3;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+pub const DEFAULT_RETRIEVAL_PROFILE: &str = "default";
+const PRIOR_MIN: f64 = 0.90;
+const PRIOR_MAX: f64 = 1.15;
+
+// A retrieval profile. `source_priors` maps source_kind to its multiplier and kinds not listed
+// use `default_prior`; both default to the built-in priors when omitted from vault.json.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetrievalConfigV1 {
     pub rrf_k: i64,
     pub w_lex: f64,
     pub w_vec: f64,
     pub recency: RecencyConfigV1,
+    #[serde(default)]
+    pub fusion: FusionModeV1,
+    #[serde(default = "default_source_priors")]
+    pub source_priors: BTreeMap<String, f64>,
+    #[serde(default = "default_other_prior")]
+    pub default_prior: f64,
+    #[serde(default)]
+    pub diversify: DiversifyConfigV1,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetrievalProfileV1 {
+    pub name: String,
+    pub config_hash: ConfigHash,
+    pub config: RetrievalConfigV1,
+}
+
+// `rrf` fuses both lists by rank only. `score` scales each lexical contribution by its bm25
+// relative to the best lexical candidate, keeping the top one at its rank-1 RRF value;
+// candidates without a bm25 fall back to RRF.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionModeV1 {
+    #[default]
+    Rrf,
+    Score,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +59,9 @@ pub struct MergedHit {
     pub chunk_id: ChunkId,
     pub doc_id: DocId,
     pub ordinal: i64,
+    pub lexical_rank: Option<i64>,
+    pub lexical_score: Option<f64>,
+    pub vector_rank: Option<i64>,
     pub final_score: f64,
 }
 
@@ -34,19 +72,193 @@ struct Interim {
     ordinal: i64,
     source_kind: String,
     effective_ts_ms: i64,
+    lexical_rank: Option<i64>,
+    lexical_score: Option<f64>,
+    vector_rank: Option<i64>,
     score: f64,
 }
 
-fn source_prior(source_kind: &str) -> f64 {
-    let raw: f64 = match source_kind {
-        "manuals" => 1.10,
-        "confluence_exports" => 1.07,
-        "notes" => 1.05,
-        "evidence_packs" => 1.08,
-        "inbox" => 0.98,
-        _ => 1.00,
+pub fn default_source_priors() -> BTreeMap<String, f64> {
+    [
+        ("manuals", 1.10),
+        ("confluence_exports", 1.07),
+        ("notes", 1.05),
+        ("evidence_packs", 1.08),
+        ("inbox", 0.98),
+    ]
+    .into_iter()
+    .map(|(kind, prior)| (kind.to_string(), prior))
+    .collect()
+}
+
+fn default_other_prior() -> f64 {
+    1.00
+}
+
+// The built-in `default` profile.
+pub fn retrieval_config_for_vault(vault: &VaultJsonV3) -> RetrievalConfigV1 {
+    RetrievalConfigV1 {
+        rrf_k: 60,
+        w_lex: 1.0,
+        w_vec: 1.0,
+        recency: RecencyConfigV1 {
+            enabled: vault.defaults.recency.enabled,
+            window_days: 365,
+            max_boost: 0.20,
+        },
+        fusion: vault.defaults.fusion,
+        source_priors: default_source_priors(),
+        default_prior: default_other_prior(),
+        diversify: DiversifyConfigV1::default(),
+    }
+}
+
+fn profile_invalid(name: &str, field: &str, value: serde_json::Value) -> AppError {
+    AppError::new(
+        "KC_RETRIEVAL_PROFILE_INVALID",
+        "retrieval",
+        "retrieval profile field is out of range",
+        false,
+        serde_json::json!({ "profile": name, "field": field, "value": value }),
+    )
+}
+
+pub fn validate_retrieval_config(name: &str, cfg: &RetrievalConfigV1) -> AppResult<()> {
+    if cfg.rrf_k < 1 {
+        return Err(profile_invalid(name, "rrf_k", serde_json::json!(cfg.rrf_k)));
+    }
+    for (field, value) in [
+        ("w_lex", cfg.w_lex),
+        ("w_vec", cfg.w_vec),
+        ("recency.max_boost", cfg.recency.max_boost),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            return Err(profile_invalid(name, field, serde_json::json!(value)));
+        }
+    }
+    if !(0.0..=1.0).contains(&cfg.diversify.lambda) {
+        return Err(profile_invalid(
+            name,
+            "diversify.lambda",
+            serde_json::json!(cfg.diversify.lambda),
+        ));
+    }
+    if cfg.diversify.max_per_doc < 1 {
+        return Err(profile_invalid(
+            name,
+            "diversify.max_per_doc",
+            serde_json::json!(cfg.diversify.max_per_doc),
+        ));
+    }
+    if cfg.recency.window_days < 0 {
+        return Err(profile_invalid(
+            name,
+            "recency.window_days",
+            serde_json::json!(cfg.recency.window_days),
+        ));
+    }
+    let priors = cfg
+        .source_priors
+        .iter()
+        .map(|(kind, prior)| (kind.as_str(), *prior))
+        .chain(std::iter::once(("default_prior", cfg.default_prior)));
+    for (kind, prior) in priors {
+        if !(PRIOR_MIN..=PRIOR_MAX).contains(&prior) {
+            return Err(AppError::new(
+                "KC_RETRIEVAL_PRIOR_OUT_OF_RANGE",
+                "retrieval",
+                "source prior is outside the allowed range",
+                false,
+                serde_json::json!({
+                    "profile": name,
+                    "source_kind": kind,
+                    "prior": prior,
+                    "min": PRIOR_MIN,
+                    "max": PRIOR_MAX,
+                }),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Canonical JSON forbids floats, so each float hashes as its shortest round-trip decimal
+// string.
+fn floats_as_strings(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Number(n) if n.is_f64() => {
+            serde_json::Value::String(format!("{:?}", n.as_f64().unwrap_or_default()))
+        }
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(floats_as_strings).collect())
+        }
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, floats_as_strings(v)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+pub fn hash_retrieval_config(cfg: &RetrievalConfigV1) -> AppResult<ConfigHash> {
+    let value = serde_json::to_value(cfg).map_err(|e| {
+        AppError::new(
+            "KC_RETRIEVAL_PROFILE_INVALID",
+            "retrieval",
+            "retrieval config must be serializable",
+            false,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
+    Ok(ConfigHash(hash_canonical(&floats_as_strings(value))?))
+}
+
+// `name` falls back to the vault's `defaults.retrieval_profile`, then to `default`. Vault
+// profiles shadow the built-in `default`.
+pub fn retrieval_profile_for_vault(
+    vault: &VaultJsonV3,
+    name: Option<&str>,
+) -> AppResult<RetrievalProfileV1> {
+    let name = name
+        .or(vault.defaults.retrieval_profile.as_deref())
+        .unwrap_or(DEFAULT_RETRIEVAL_PROFILE);
+    let config = match vault.defaults.retrieval_profiles.get(name) {
+        Some(config) => config.clone(),
+        None if name == DEFAULT_RETRIEVAL_PROFILE => retrieval_config_for_vault(vault),
+        None => {
+            let mut available: Vec<&str> = vault
+                .defaults
+                .retrieval_profiles
+                .keys()
+                .map(String::as_str)
+                .collect();
+            if !available.contains(&DEFAULT_RETRIEVAL_PROFILE) {
+                available.insert(0, DEFAULT_RETRIEVAL_PROFILE);
+            }
+            return Err(AppError::new(
+                "KC_RETRIEVAL_PROFILE_NOT_FOUND",
+                "retrieval",
+                "retrieval profile is not defined in the vault",
+                false,
+                serde_json::json!({ "profile": name, "available": available }),
+            ));
+        }
     };
-    raw.clamp(0.90, 1.15)
+    validate_retrieval_config(name, &config)?;
+    Ok(RetrievalProfileV1 {
+        name: name.to_string(),
+        config_hash: hash_retrieval_config(&config)?,
+        config,
+    })
+}
+
+fn source_prior(cfg: &RetrievalConfigV1, source_kind: &str) -> f64 {
+    cfg.source_priors
+        .get(source_kind)
+        .copied()
+        .unwrap_or(cfg.default_prior)
+        .clamp(PRIOR_MIN, PRIOR_MAX)
 }
 
 fn recency_boost(now_ms: i64, ts_ms: i64, cfg: &RecencyConfigV1) -> f64 {
@@ -75,20 +287,36 @@ pub fn merge_candidates(
     now_ms: i64,
 ) -> AppResult<Vec<MergedHit>> {
     let mut by_chunk: HashMap<String, Interim> = HashMap::new();
+    let best_bm25 = lexical
+        .iter()
+        .filter_map(|c| c.bm25)
+        .fold(0.0_f64, f64::max);
 
     for c in lexical {
         let (doc_id, ordinal, source_kind, effective_ts_ms) = meta_lookup(&c.chunk_id)?;
-        let rrf = cfg.w_lex * (1.0 / (cfg.rrf_k as f64 + c.rank as f64));
+        let rrf = match (cfg.fusion, c.bm25) {
+            (FusionModeV1::Score, Some(bm25)) if best_bm25 > 0.0 => {
+                cfg.w_lex * (bm25.max(0.0) / best_bm25) / (cfg.rrf_k as f64 + 1.0)
+            }
+            _ => cfg.w_lex * (1.0 / (cfg.rrf_k as f64 + c.rank as f64)),
+        };
 
         by_chunk
             .entry(c.chunk_id.0.clone())
-            .and_modify(|s| s.score += rrf)
+            .and_modify(|s| {
+                s.score += rrf;
+                s.lexical_rank = Some(c.rank);
+                s.lexical_score = c.bm25;
+            })
             .or_insert(Interim {
                 chunk_id: c.chunk_id.clone(),
                 doc_id,
                 ordinal,
                 source_kind,
                 effective_ts_ms,
+                lexical_rank: Some(c.rank),
+                lexical_score: c.bm25,
+                vector_rank: None,
                 score: rrf,
             });
     }
@@ -99,13 +327,19 @@ pub fn merge_candidates(
 
         by_chunk
             .entry(c.chunk_id.0.clone())
-            .and_modify(|s| s.score += rrf)
+            .and_modify(|s| {
+                s.score += rrf;
+                s.vector_rank = Some(c.rank);
+            })
             .or_insert(Interim {
                 chunk_id: c.chunk_id.clone(),
                 doc_id,
                 ordinal,
                 source_kind,
                 effective_ts_ms,
+                lexical_rank: None,
+                lexical_score: None,
+                vector_rank: Some(c.rank),
                 score: rrf,
             });
     }
@@ -113,13 +347,16 @@ pub fn merge_candidates(
     let mut hits: Vec<MergedHit> = by_chunk
         .into_values()
         .map(|it| {
-            let prior = source_prior(&it.source_kind);
+            let prior = source_prior(cfg, &it.source_kind);
             let boost = recency_boost(now_ms, it.effective_ts_ms, &cfg.recency);
             let final_score = round12(it.score * prior * (1.0 + boost));
             MergedHit {
                 chunk_id: it.chunk_id,
                 doc_id: it.doc_id,
                 ordinal: it.ordinal,
+                lexical_rank: it.lexical_rank,
+                lexical_score: it.lexical_score,
+                vector_rank: it.vector_rank,
                 final_score,
             }
         })
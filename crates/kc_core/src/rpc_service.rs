@@ -1,11 +1,17 @@
 use crate::app_error::{AppError, AppResult};
-use crate::canonical::load_canonical_text;
 use crate::db::{
     db_is_unlocked, db_lock, db_unlock, migrate_db_to_sqlcipher, open_db, DbMigrationOutcome,
 };
+use crate::doc_versions::{doc_version_history, DocVersionHistoryV1};
 use crate::events::append_event;
 use crate::hashing::blake3_hex_prefixed;
-use crate::ingest::{ingest_bytes, IngestBytesReq};
+use crate::inbox::{inbox_watch_run, InboxWatchConfigV1, InboxWatchSummaryV1};
+use crate::index_traits::{LexicalIndex, VectorIndex};
+use crate::ingest::{ingest_bytes, mime_for_path, IngestBytesReq};
+use crate::jobs::{
+    job_cancel, job_enqueue, jobs_list, run_pending_jobs, JobRecordV1, JOB_DEFAULT_MAX_ATTEMPTS,
+    JOB_KIND_PIPELINE_DOC,
+};
 use crate::lineage_governance::{
     lineage_lock_acquire_scope, lineage_role_grant, lineage_role_list, lineage_role_revoke,
     LineageRoleBindingV2, LineageScopeLockLeaseV2,
@@ -16,6 +22,7 @@ use crate::lineage_policy::{
 };
 use crate::locator::{resolve_locator_strict, LocatorV1};
 use crate::object_store::{is_encrypted_payload, ObjectStore};
+use crate::pipeline::{run_doc_pipeline, PipelineServices};
 use crate::recovery::{
     generate_recovery_bundle, read_recovery_manifest, verify_recovery_bundle,
     write_recovery_manifest, RecoveryManifestV2,
@@ -32,6 +39,12 @@ use crate::recovery_escrow_local::LocalRecoveryEscrowProvider;
 use crate::recovery_escrow_private_kms::{
     PrivateKmsRecoveryEscrowConfig, PrivateKmsRecoveryEscrowProvider,
 };
+use crate::retrieval::retrieval_profile_for_vault;
+use crate::search::{search_hybrid, SearchReq, SearchResultV1};
+use crate::tombstone::{
+    doc_delete, doc_tombstones_list, doc_tombstones_purge_indexes, DocDeleteResultV1,
+    DocTombstoneV1,
+};
 use crate::trust::{
     trust_device_init, trust_device_list, trust_device_verify, TrustedDeviceRecord,
 };
@@ -46,12 +59,19 @@ use crate::trust_policy::{
 };
 use crate::types::{DocId, ObjectHash};
 use crate::vault::{vault_init, vault_open, vault_paths, vault_save};
-use std::collections::BTreeSet;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+
+struct InboxWatcherHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<AppResult<InboxWatchSummaryV1>>,
+}
 
-static ACTIVE_JOBS: OnceLock<Mutex<BTreeSet<String>>> = OnceLock::new();
+static INBOX_WATCHERS: OnceLock<Mutex<BTreeMap<PathBuf, InboxWatcherHandle>>> = OnceLock::new();
 
 #[derive(Debug, Clone)]
 pub struct VaultSummary {
@@ -59,13 +79,6 @@ pub struct VaultSummary {
     pub vault_slug: String,
 }
 
-#[derive(Debug, Clone)]
-pub struct SearchHit {
-    pub doc_id: String,
-    pub score: f64,
-    pub snippet: String,
-}
-
 #[derive(Debug, Clone)]
 pub struct EventItem {
     pub event_id: i64,
@@ -194,8 +207,21 @@ pub struct TrustDeviceEnrollResult {
     pub certificate: DeviceCertificateRecord,
 }
 
-fn jobs_set() -> &'static Mutex<BTreeSet<String>> {
-    ACTIVE_JOBS.get_or_init(|| Mutex::new(BTreeSet::new()))
+fn inbox_watchers() -> &'static Mutex<BTreeMap<PathBuf, InboxWatcherHandle>> {
+    INBOX_WATCHERS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn lock_inbox_watchers(
+) -> AppResult<std::sync::MutexGuard<'static, BTreeMap<PathBuf, InboxWatcherHandle>>> {
+    inbox_watchers().lock().map_err(|_| {
+        AppError::new(
+            "KC_INTERNAL_ERROR",
+            "ingest",
+            "failed acquiring inbox watcher lock",
+            true,
+            serde_json::json!({}),
+        )
+    })
 }
 
 fn recovery_state_file(vault_path: &Path) -> PathBuf {
@@ -683,20 +709,6 @@ fn recovery_escrow_provider_item_from_row(
     })
 }
 
-fn mime_for_path(path: &Path) -> String {
-    match path
-        .extension()
-        .and_then(|x| x.to_str())
-        .unwrap_or_default()
-    {
-        "md" => "text/markdown".to_string(),
-        "html" | "htm" => "text/html".to_string(),
-        "pdf" => "application/pdf".to_string(),
-        "txt" => "text/plain".to_string(),
-        _ => "application/octet-stream".to_string(),
-    }
-}
-
 fn object_store_without_passphrase(
     vault: &crate::vault::VaultJsonV2,
     vault_path: &Path,
@@ -734,6 +746,7 @@ pub fn ingest_scan_folder_service(
     scan_root: &Path,
     source_kind: &str,
     now_ms: i64,
+    services: &PipelineServices<'_>,
 ) -> AppResult<i64> {
     let vault = vault_open(vault_path)?;
     let conn = open_db(&vault_path.join(vault.db.relative_path.clone()))?;
@@ -758,7 +771,7 @@ pub fn ingest_scan_folder_service(
                 serde_json::json!({ "error": e.to_string(), "path": path }),
             )
         })?;
-        ingest_bytes(
+        let doc = ingest_bytes(
             &conn,
             &store,
             IngestBytesReq {
@@ -770,6 +783,7 @@ pub fn ingest_scan_folder_service(
                 now_ms,
             },
         )?;
+        run_doc_pipeline(&conn, &store, services, &doc.doc_id, now_ms)?;
         ingested += 1;
     }
 
@@ -809,97 +823,107 @@ pub fn ingest_inbox_start_service(
         },
     )?;
 
-    let job_id = format!(
-        "inbox:{}",
-        blake3_hex_prefixed(format!("{}\n{}", out.doc_id.0, now_ms).as_bytes())
-    );
-    let mut jobs = jobs_set().lock().map_err(|_| {
-        AppError::new(
-            "KC_INTERNAL_ERROR",
-            "jobs",
-            "failed acquiring active jobs lock",
-            true,
-            serde_json::json!({}),
-        )
-    })?;
-    jobs.insert(job_id.clone());
+    let job = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": out.doc_id.0 }),
+        JOB_DEFAULT_MAX_ATTEMPTS,
+        now_ms,
+    )?;
 
     Ok(IngestInboxStartResult {
-        job_id,
+        job_id: job.job_id,
         doc_id: out.doc_id.0,
     })
 }
 
-pub fn ingest_inbox_stop_service(job_id: &str) -> AppResult<bool> {
-    let mut jobs = jobs_set().lock().map_err(|_| {
+pub fn ingest_inbox_stop_service(vault_path: &Path, job_id: &str, now_ms: i64) -> AppResult<bool> {
+    jobs_cancel_service(vault_path, job_id, now_ms)
+}
+
+pub fn inbox_watch_start_service<F>(
+    vault_path: &Path,
+    config: InboxWatchConfigV1,
+    with_services: F,
+) -> AppResult<bool>
+where
+    F: FnOnce(
+            &dyn Fn(&PipelineServices<'_>) -> AppResult<InboxWatchSummaryV1>,
+        ) -> AppResult<InboxWatchSummaryV1>
+        + Send
+        + 'static,
+{
+    let vault = vault_open(vault_path)?;
+    object_store_without_passphrase(&vault, vault_path)?;
+
+    let mut watchers = lock_inbox_watchers()?;
+    watchers.retain(|_, handle| !handle.thread.is_finished());
+    if watchers.contains_key(vault_path) {
+        return Ok(false);
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let thread_vault_path = vault_path.to_path_buf();
+    let thread = std::thread::spawn(move || {
+        with_services(&|services| {
+            let vault = vault_open(&thread_vault_path)?;
+            let conn = open_db(&thread_vault_path.join(vault.db.relative_path.clone()))?;
+            let store = object_store_without_passphrase(&vault, &thread_vault_path)?;
+            inbox_watch_run(
+                &conn,
+                &store,
+                services,
+                &thread_vault_path,
+                &config,
+                &thread_stop,
+            )
+        })
+    });
+    watchers.insert(
+        vault_path.to_path_buf(),
+        InboxWatcherHandle { stop, thread },
+    );
+    Ok(true)
+}
+
+pub fn inbox_watch_stop_service(vault_path: &Path) -> AppResult<Option<InboxWatchSummaryV1>> {
+    let handle = lock_inbox_watchers()?.remove(vault_path);
+    let Some(handle) = handle else {
+        return Ok(None);
+    };
+    handle.stop.store(true, Ordering::SeqCst);
+    let summary = handle.thread.join().map_err(|_| {
         AppError::new(
-            "KC_INTERNAL_ERROR",
-            "jobs",
-            "failed acquiring active jobs lock",
-            true,
-            serde_json::json!({}),
+            "KC_INBOX_WATCH_FAILED",
+            "ingest",
+            "inbox watcher thread panicked",
+            false,
+            serde_json::json!({ "vault_path": vault_path }),
         )
-    })?;
-    Ok(jobs.remove(job_id))
+    })??;
+    Ok(Some(summary))
+}
+
+pub fn inbox_watch_status_service(vault_path: &Path) -> AppResult<bool> {
+    let watchers = lock_inbox_watchers()?;
+    Ok(watchers
+        .get(vault_path)
+        .map(|handle| !handle.thread.is_finished())
+        .unwrap_or(false))
 }
 
 pub fn search_query_service(
     vault_path: &Path,
-    query: &str,
-    _now_ms: i64,
-    limit: usize,
-) -> AppResult<Vec<SearchHit>> {
+    lexical: &dyn LexicalIndex,
+    vector: &dyn VectorIndex,
+    req: SearchReq<'_>,
+) -> AppResult<SearchResultV1> {
     let vault = vault_open(vault_path)?;
     let conn = open_db(&vault_path.join(vault.db.relative_path.clone()))?;
     let store = object_store_without_passphrase(&vault, vault_path)?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT doc_id FROM canonical_text ORDER BY created_event_id DESC, doc_id ASC LIMIT ?1",
-        )
-        .map_err(|e| {
-            AppError::new(
-                "KC_RETRIEVAL_FAILED",
-                "search",
-                "failed preparing search query",
-                false,
-                serde_json::json!({ "error": e.to_string() }),
-            )
-        })?;
-    let rows = stmt
-        .query_map([limit as i64], |row| row.get::<_, String>(0))
-        .map_err(|e| {
-            AppError::new(
-                "KC_RETRIEVAL_FAILED",
-                "search",
-                "failed running search query",
-                false,
-                serde_json::json!({ "error": e.to_string() }),
-            )
-        })?;
-
-    let query_lower = query.to_lowercase();
-    let mut hits = Vec::new();
-    for row in rows {
-        let doc_id = row.map_err(|e| {
-            AppError::new(
-                "KC_RETRIEVAL_FAILED",
-                "search",
-                "failed reading search row",
-                false,
-                serde_json::json!({ "error": e.to_string() }),
-            )
-        })?;
-        let text = String::from_utf8(load_canonical_text(&conn, &store, &DocId(doc_id.clone()))?)
-            .unwrap_or_default();
-        if text.to_lowercase().contains(&query_lower) {
-            hits.push(SearchHit {
-                doc_id,
-                score: 1.0,
-                snippet: text.chars().take(120).collect(),
-            });
-        }
-    }
-    Ok(hits)
+    let profile = retrieval_profile_for_vault(&vault, req.profile)?;
+    search_hybrid(&conn, &store, lexical, vector, &profile.config, req)
 }
 
 pub fn locator_resolve_service(vault_path: &Path, locator: &LocatorV1) -> AppResult<String> {
@@ -973,17 +997,69 @@ pub fn events_list_service(vault_path: &Path, limit: i64) -> AppResult<Vec<Event
     Ok(events)
 }
 
-pub fn jobs_list_service(_vault_path: &Path) -> AppResult<Vec<String>> {
-    let jobs = jobs_set().lock().map_err(|_| {
-        AppError::new(
-            "KC_INTERNAL_ERROR",
-            "jobs",
-            "failed acquiring active jobs lock",
-            true,
-            serde_json::json!({}),
-        )
-    })?;
-    Ok(jobs.iter().cloned().collect())
+pub fn jobs_list_service(vault_path: &Path) -> AppResult<Vec<JobRecordV1>> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    jobs_list(&conn)
+}
+
+pub fn jobs_cancel_service(vault_path: &Path, job_id: &str, now_ms: i64) -> AppResult<bool> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    job_cancel(&conn, job_id, now_ms)
+}
+
+pub fn jobs_run_service(
+    vault_path: &Path,
+    now_ms: i64,
+    max_jobs: usize,
+    services: &PipelineServices<'_>,
+) -> AppResult<Vec<JobRecordV1>> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path.clone()))?;
+    let store = object_store_without_passphrase(&vault, vault_path)?;
+    run_pending_jobs(&conn, &store, services, now_ms, max_jobs)
+}
+
+pub fn doc_delete_service(
+    vault_path: &Path,
+    doc_id: &str,
+    now_ms: i64,
+    services: &PipelineServices<'_>,
+) -> AppResult<DocDeleteResultV1> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    doc_delete(
+        &conn,
+        services.lexical,
+        services.vector,
+        &DocId(doc_id.to_string()),
+        now_ms,
+    )
+}
+
+pub fn doc_versions_service(
+    vault_path: &Path,
+    doc_id: &str,
+) -> AppResult<Vec<DocVersionHistoryV1>> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    doc_version_history(&conn, doc_id)
+}
+
+pub fn doc_tombstones_list_service(vault_path: &Path) -> AppResult<Vec<DocTombstoneV1>> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    doc_tombstones_list(&conn)
+}
+
+pub fn doc_tombstones_purge_service(
+    vault_path: &Path,
+    services: &PipelineServices<'_>,
+) -> AppResult<usize> {
+    let vault = vault_open(vault_path)?;
+    let conn = open_db(&vault_path.join(vault.db.relative_path))?;
+    doc_tombstones_purge_indexes(&conn, services.lexical, services.vector)
 }
 
 pub fn sync_status_service(
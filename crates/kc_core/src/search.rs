@@ -0,0 +1,394 @@
+use crate::app_error::{AppError, AppResult};
+use crate::diversify::{diversify_hits, DiversifyCandidate, DiversifyReportV1};
+use crate::doc_versions::SUPERSEDED_DOC_IDS_SQL;
+use crate::index_traits::{LexicalCandidate, LexicalIndex, VectorCandidate, VectorIndex};
+use crate::locator::{resolve_locator_strict, LocatorRange, LocatorV1};
+use crate::object_store::ObjectStore;
+use crate::retrieval::{merge_candidates, RetrievalConfigV1};
+use crate::snippet::render_snippet_display_only;
+use crate::types::{CanonicalHash, ChunkId, DocId};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const MIN_CANDIDATES: usize = 32;
+const SNIPPET_CHARS: usize = 240;
+
+// All set fields must match. `effective_ts_from_ms` is inclusive and `effective_ts_to_ms`
+// exclusive; `extractor_name` matches the doc's current canonical text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFilterV1 {
+    pub source_kinds: Vec<String>,
+    pub mimes: Vec<String>,
+    pub effective_ts_from_ms: Option<i64>,
+    pub effective_ts_to_ms: Option<i64>,
+    pub source_path_prefix: Option<String>,
+    pub extractor_name: Option<String>,
+}
+
+impl SearchFilterV1 {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchReq<'a> {
+    pub query: &'a str,
+    pub limit: usize,
+    pub include_superseded: bool,
+    pub filter: &'a SearchFilterV1,
+    // Retrieval profile name, resolved by `search_query_service`; `search_hybrid` takes the
+    // resolved config directly.
+    pub profile: Option<&'a str>,
+    pub now_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitV1 {
+    pub chunk_id: String,
+    pub doc_id: String,
+    pub ordinal: i64,
+    pub locator: LocatorV1,
+    pub lexical_rank: Option<i64>,
+    pub lexical_score: Option<f64>,
+    pub vector_rank: Option<i64>,
+    pub final_score: f64,
+    pub snippet: String,
+    // Chunks of the same doc overlapping or touching this hit's chunk, folded into it; `locator` spans
+    // all of them.
+    pub collapsed_chunk_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCountV1 {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFacetsV1 {
+    pub source_kind: Vec<FacetCountV1>,
+    pub mime: Vec<FacetCountV1>,
+    pub year: Vec<FacetCountV1>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResultV1 {
+    pub hits: Vec<SearchHitV1>,
+    pub facets: SearchFacetsV1,
+    pub diversify: DiversifyReportV1,
+}
+
+struct ChunkMeta {
+    doc_id: String,
+    ordinal: i64,
+    start_char: i64,
+    end_char: i64,
+    source_kind: String,
+    mime: String,
+    year: String,
+    effective_ts_ms: i64,
+    canonical_hash: String,
+    superseded: bool,
+    simhash: Option<i64>,
+}
+
+fn search_error(message: &str, e: rusqlite::Error) -> AppError {
+    AppError::new(
+        "KC_RETRIEVAL_FAILED",
+        "search",
+        message,
+        false,
+        serde_json::json!({ "error": e.to_string() }),
+    )
+}
+
+fn load_chunk_meta(conn: &Connection, chunk_id: &str) -> AppResult<Option<ChunkMeta>> {
+    let sql = format!(
+        "SELECT c.doc_id, c.ordinal, c.start_char, c.end_char, d.source_kind, d.mime,
+                strftime('%Y', d.effective_ts_ms / 1000, 'unixepoch'), d.effective_ts_ms,
+                ct.canonical_hash, c.doc_id IN ({SUPERSEDED_DOC_IDS_SQL}), c.simhash
+         FROM chunks c
+         JOIN docs d ON d.doc_id=c.doc_id
+         JOIN canonical_text ct ON ct.doc_id=c.doc_id
+         WHERE c.chunk_id=?1"
+    );
+    conn.query_row(&sql, [chunk_id], |row| {
+        Ok(ChunkMeta {
+            doc_id: row.get(0)?,
+            ordinal: row.get(1)?,
+            start_char: row.get(2)?,
+            end_char: row.get(3)?,
+            source_kind: row.get(4)?,
+            mime: row.get(5)?,
+            year: row.get(6)?,
+            effective_ts_ms: row.get(7)?,
+            canonical_hash: row.get(8)?,
+            superseded: row.get(9)?,
+            simhash: row.get(10)?,
+        })
+    })
+    .optional()
+    .map_err(|e| search_error("failed loading chunk metadata for search", e))
+}
+
+fn push_param(params: &mut Vec<Value>, value: Value) -> String {
+    params.push(value);
+    format!("?{}", params.len())
+}
+
+fn push_filter_clauses(
+    filter: &SearchFilterV1,
+    clauses: &mut Vec<String>,
+    params: &mut Vec<Value>,
+) {
+    if !filter.source_kinds.is_empty() {
+        let slots: Vec<String> = filter
+            .source_kinds
+            .iter()
+            .map(|v| push_param(params, Value::Text(v.clone())))
+            .collect();
+        clauses.push(format!("d.source_kind IN ({})", slots.join(", ")));
+    }
+    if !filter.mimes.is_empty() {
+        let slots: Vec<String> = filter
+            .mimes
+            .iter()
+            .map(|v| push_param(params, Value::Text(v.clone())))
+            .collect();
+        clauses.push(format!("d.mime IN ({})", slots.join(", ")));
+    }
+    if let Some(from) = filter.effective_ts_from_ms {
+        let slot = push_param(params, Value::Integer(from));
+        clauses.push(format!("d.effective_ts_ms >= {slot}"));
+    }
+    if let Some(to) = filter.effective_ts_to_ms {
+        let slot = push_param(params, Value::Integer(to));
+        clauses.push(format!("d.effective_ts_ms < {slot}"));
+    }
+    if let Some(prefix) = &filter.source_path_prefix {
+        let slot = push_param(params, Value::Text(prefix.clone()));
+        clauses.push(format!(
+            "EXISTS (SELECT 1 FROM doc_sources s WHERE s.doc_id=d.doc_id
+                     AND substr(s.source_path, 1, length({slot})) = {slot})"
+        ));
+    }
+    if let Some(extractor) = &filter.extractor_name {
+        let slot = push_param(params, Value::Text(extractor.clone()));
+        clauses.push(format!(
+            "EXISTS (SELECT 1 FROM canonical_text ct WHERE ct.doc_id=d.doc_id
+                     AND ct.extractor_name = {slot})"
+        ));
+    }
+}
+
+// Doc ids matching every set field of every filter, ascending; `None` when all filters are
+// empty.
+pub fn filter_doc_ids(
+    conn: &Connection,
+    filters: &[&SearchFilterV1],
+) -> AppResult<Option<Vec<DocId>>> {
+    if filters.iter().all(|f| f.is_empty()) {
+        return Ok(None);
+    }
+    let mut clauses: Vec<String> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+    for filter in filters {
+        push_filter_clauses(filter, &mut clauses, &mut params);
+    }
+
+    let sql = format!(
+        "SELECT d.doc_id FROM docs d WHERE {} ORDER BY d.doc_id ASC",
+        clauses.join(" AND ")
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| search_error("failed preparing search filter query", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params), |row| {
+            row.get::<_, String>(0)
+        })
+        .map_err(|e| search_error("failed running search filter query", e))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(DocId(row.map_err(|e| {
+            search_error("failed reading search filter row", e)
+        })?));
+    }
+    Ok(Some(out))
+}
+
+fn facet_counts(values: BTreeMap<&str, BTreeSet<&str>>) -> Vec<FacetCountV1> {
+    values
+        .into_iter()
+        .map(|(value, docs)| FacetCountV1 {
+            value: value.to_string(),
+            count: docs.len() as i64,
+        })
+        .collect()
+}
+
+// Distinct docs per value across every merged candidate, not just the returned page, so
+// drill-down counts do not depend on `limit`.
+fn search_facets<'a>(rows: impl Iterator<Item = &'a ChunkMeta>) -> SearchFacetsV1 {
+    let mut source_kind: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut mime: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut year: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for row in rows {
+        source_kind
+            .entry(&row.source_kind)
+            .or_default()
+            .insert(&row.doc_id);
+        mime.entry(&row.mime).or_default().insert(&row.doc_id);
+        year.entry(&row.year).or_default().insert(&row.doc_id);
+    }
+    SearchFacetsV1 {
+        source_kind: facet_counts(source_kind),
+        mime: facet_counts(mime),
+        year: facet_counts(year),
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut out: String = text.chars().take(max).collect();
+        out.push('…');
+        out
+    }
+}
+
+// Candidates whose chunk no longer exists (stale index rows) or whose doc is superseded are
+// dropped before merging; surviving candidates keep the rank their index assigned. A filter
+// is resolved to doc ids and pushed into both indexes, so it never eats into the depth.
+pub fn search_hybrid(
+    conn: &Connection,
+    object_store: &ObjectStore,
+    lexical: &dyn LexicalIndex,
+    vector: &dyn VectorIndex,
+    cfg: &RetrievalConfigV1,
+    req: SearchReq<'_>,
+) -> AppResult<SearchResultV1> {
+    let SearchReq {
+        query,
+        limit,
+        include_superseded,
+        filter,
+        profile: _,
+        now_ms,
+    } = req;
+    let query = query.trim();
+    if query.is_empty() || limit == 0 {
+        return Ok(SearchResultV1 {
+            hits: Vec::new(),
+            facets: SearchFacetsV1::default(),
+            diversify: DiversifyReportV1::default(),
+        });
+    }
+    let depth = (limit * 4).max(MIN_CANDIDATES);
+    let query_filter = lexical.query_filter(query)?;
+    let (lexical_candidates, vector_candidates) =
+        match filter_doc_ids(conn, &[filter, &query_filter])? {
+            None => (lexical.query(query, depth)?, vector.query(query, depth)?),
+            Some(doc_ids) => (
+                lexical.query_in_docs(query, depth, &doc_ids)?,
+                vector.query_in_docs(query, depth, &doc_ids)?,
+            ),
+        };
+
+    let mut meta: HashMap<String, ChunkMeta> = HashMap::new();
+    for chunk_id in lexical_candidates
+        .iter()
+        .map(|c| &c.chunk_id)
+        .chain(vector_candidates.iter().map(|c| &c.chunk_id))
+    {
+        if meta.contains_key(&chunk_id.0) {
+            continue;
+        }
+        if let Some(row) = load_chunk_meta(conn, &chunk_id.0)? {
+            if include_superseded || !row.superseded {
+                meta.insert(chunk_id.0.clone(), row);
+            }
+        }
+    }
+    let lexical_candidates: Vec<LexicalCandidate> = lexical_candidates
+        .into_iter()
+        .filter(|c| meta.contains_key(&c.chunk_id.0))
+        .collect();
+    let vector_candidates: Vec<VectorCandidate> = vector_candidates
+        .into_iter()
+        .filter(|c| meta.contains_key(&c.chunk_id.0))
+        .collect();
+
+    let merged = merge_candidates(
+        &lexical_candidates,
+        &vector_candidates,
+        |chunk_id: &ChunkId| {
+            let row = &meta[&chunk_id.0];
+            Ok((
+                DocId(row.doc_id.clone()),
+                row.ordinal,
+                row.source_kind.clone(),
+                row.effective_ts_ms,
+            ))
+        },
+        cfg,
+        now_ms,
+    )?;
+
+    let facets = search_facets(merged.iter().map(|hit| &meta[&hit.chunk_id.0]));
+    let candidates = merged
+        .into_iter()
+        .map(|hit| {
+            let row = &meta[&hit.chunk_id.0];
+            DiversifyCandidate {
+                start_char: row.start_char,
+                end_char: row.end_char,
+                simhash: row.simhash,
+                hit,
+            }
+        })
+        .collect();
+    let (diversified, diversify) = diversify_hits(candidates, &cfg.diversify, limit);
+    let mut hits = Vec::new();
+    for diversified_hit in diversified {
+        let hit = diversified_hit.hit;
+        let row = &meta[&hit.chunk_id.0];
+        let locator = LocatorV1 {
+            v: 1,
+            doc_id: hit.doc_id.clone(),
+            canonical_hash: CanonicalHash(row.canonical_hash.clone()),
+            range: LocatorRange {
+                start: diversified_hit.start_char,
+                end: diversified_hit.end_char,
+            },
+            hints: None,
+        };
+        let text = resolve_locator_strict(conn, object_store, &locator)?;
+        hits.push(SearchHitV1 {
+            chunk_id: hit.chunk_id.0,
+            doc_id: hit.doc_id.0,
+            ordinal: hit.ordinal,
+            locator,
+            lexical_rank: hit.lexical_rank,
+            lexical_score: hit.lexical_score,
+            vector_rank: hit.vector_rank,
+            final_score: hit.final_score,
+            snippet: truncate_chars(&render_snippet_display_only(&text)?, SNIPPET_CHARS),
+            collapsed_chunk_ids: diversified_hit
+                .collapsed
+                .into_iter()
+                .map(|chunk_id| chunk_id.0)
+                .collect(),
+        });
+    }
+    Ok(SearchResultV1 {
+        hits,
+        facets,
+        diversify,
+    })
+}
@@ -2,12 +2,20 @@ use crate::app_error::AppResult;
 use crate::types::{CanonicalHash, DocId, ObjectHash};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ToolchainIdentity {
     pub pdfium_identity: String,
     pub tesseract_identity: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExtractorIdentity {
+    pub extractor_name: String,
+    pub extractor_version: String,
+    pub normalization_version: i64,
+    pub toolchain: ToolchainIdentity,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanonicalTextArtifact {
     pub doc_id: DocId,
@@ -11,6 +11,7 @@ use crate::sync_merge::{
 };
 use crate::sync_s3::S3SyncTransport;
 use crate::sync_transport::{FsSyncTransport, SyncTargetUri, SyncTransport};
+use crate::tombstone::{doc_tombstones_list, doc_tombstones_reapply};
 use crate::trust_identity;
 use crate::vault::{vault_open, VaultJsonV2};
 use rusqlite::{params, Connection};
@@ -24,6 +25,8 @@ use std::sync::atomic::{AtomicU64, Ordering};
 const TRUST_MODEL_PASSPHRASE_V1: &str = "passphrase_v1";
 const S3_LOCK_KEY: &str = "locks/write.lock";
 const S3_LOCK_TTL_MS: i64 = 60_000;
+const LOCAL_SYNC_LOCK_RELPATH: &str = "locks/sync_write.lock";
+const LOCAL_SYNC_LOCK_TTL_MS: i64 = 60_000;
 static SYNC_TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 fn next_sync_temp_suffix() -> u64 {
@@ -123,6 +126,14 @@ struct SyncS3WriteLockV1 {
     trust_fingerprint: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncLocalWriteLockV1 {
+    pub schema_version: i64,
+    pub holder: String,
+    pub acquired_at_ms: i64,
+    pub expires_at_ms: i64,
+}
+
 fn sync_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
     AppError::new(code, "sync", message, false, details)
 }
@@ -489,15 +500,18 @@ fn extract_change_set_from_bundle_dir(bundle_dir: &Path) -> AppResult<SyncMergeC
         .and_then(|x| x.as_str())
         .unwrap_or("db/knowledge.sqlite");
     let db_path = bundle_dir.join(db_rel);
-    let lineage_overlay_ids = read_overlay_ids_from_snapshot_db(&db_path)?;
+    let lineage_overlay_ids =
+        read_ids_from_snapshot_db(&db_path, "lineage_overlays", "overlay_id")?;
+    let doc_tombstone_ids = read_ids_from_snapshot_db(&db_path, "doc_tombstones", "doc_id")?;
 
     Ok(SyncMergeChangeSetV1 {
         object_hashes,
         lineage_overlay_ids,
+        doc_tombstone_ids,
     })
 }
 
-fn read_overlay_ids_from_snapshot_db(db_path: &Path) -> AppResult<Vec<String>> {
+fn read_ids_from_snapshot_db(db_path: &Path, table: &str, column: &str) -> AppResult<Vec<String>> {
     if !db_path.exists() {
         return Ok(Vec::new());
     }
@@ -510,30 +524,32 @@ fn read_overlay_ids_from_snapshot_db(db_path: &Path) -> AppResult<Vec<String>> {
         )
     })?;
 
-    let has_lineage_overlays: i64 = conn
+    let has_table: i64 = conn
         .query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='lineage_overlays'",
-            [],
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name=?1",
+            [table],
             |row| row.get(0),
         )
         .map_err(|e| {
             sync_error(
                 "KC_SYNC_MERGE_PRECONDITION_FAILED",
-                "failed checking lineage overlay table in snapshot db",
-                serde_json::json!({ "error": e.to_string(), "path": db_path }),
+                "failed checking table in snapshot db",
+                serde_json::json!({ "error": e.to_string(), "path": db_path, "table": table }),
             )
         })?;
-    if has_lineage_overlays == 0 {
+    if has_table == 0 {
         return Ok(Vec::new());
     }
 
     let mut stmt = conn
-        .prepare("SELECT overlay_id FROM lineage_overlays ORDER BY overlay_id ASC")
+        .prepare(&format!(
+            "SELECT {column} FROM {table} ORDER BY {column} ASC"
+        ))
         .map_err(|e| {
             sync_error(
                 "KC_SYNC_MERGE_PRECONDITION_FAILED",
-                "failed preparing lineage overlay query for merge preview",
-                serde_json::json!({ "error": e.to_string(), "path": db_path }),
+                "failed preparing snapshot id query for merge preview",
+                serde_json::json!({ "error": e.to_string(), "path": db_path, "table": table }),
             )
         })?;
     let rows = stmt
@@ -541,21 +557,21 @@ fn read_overlay_ids_from_snapshot_db(db_path: &Path) -> AppResult<Vec<String>> {
         .map_err(|e| {
             sync_error(
                 "KC_SYNC_MERGE_PRECONDITION_FAILED",
-                "failed querying lineage overlay ids for merge preview",
-                serde_json::json!({ "error": e.to_string(), "path": db_path }),
+                "failed querying snapshot ids for merge preview",
+                serde_json::json!({ "error": e.to_string(), "path": db_path, "table": table }),
             )
         })?;
-    let mut overlay_ids = Vec::new();
+    let mut ids = Vec::new();
     for row in rows {
-        overlay_ids.push(row.map_err(|e| {
+        ids.push(row.map_err(|e| {
             sync_error(
                 "KC_SYNC_MERGE_PRECONDITION_FAILED",
-                "failed decoding lineage overlay id for merge preview",
-                serde_json::json!({ "error": e.to_string(), "path": db_path }),
+                "failed decoding snapshot id for merge preview",
+                serde_json::json!({ "error": e.to_string(), "path": db_path, "table": table }),
             )
         })?);
     }
-    Ok(overlay_ids)
+    Ok(ids)
 }
 
 fn delta_change_set(
@@ -564,6 +580,7 @@ fn delta_change_set(
 ) -> SyncMergeChangeSetV1 {
     let base_object_hashes: BTreeSet<String> = base.object_hashes.iter().cloned().collect();
     let base_overlay_ids: BTreeSet<String> = base.lineage_overlay_ids.iter().cloned().collect();
+    let base_tombstone_ids: BTreeSet<String> = base.doc_tombstone_ids.iter().cloned().collect();
 
     SyncMergeChangeSetV1 {
         object_hashes: current
@@ -578,6 +595,12 @@ fn delta_change_set(
             .filter(|h| !base_overlay_ids.contains(*h))
             .cloned()
             .collect(),
+        doc_tombstone_ids: current
+            .doc_tombstone_ids
+            .iter()
+            .filter(|h| !base_tombstone_ids.contains(*h))
+            .cloned()
+            .collect(),
     }
 }
 
@@ -1172,6 +1195,99 @@ fn with_s3_lock<T>(
     }
 }
 
+fn local_sync_lock_path(vault_path: &Path) -> PathBuf {
+    vault_path.join(LOCAL_SYNC_LOCK_RELPATH)
+}
+
+pub fn sync_local_write_lock(
+    vault_path: &Path,
+    now_ms: i64,
+) -> AppResult<Option<SyncLocalWriteLockV1>> {
+    let path = local_sync_lock_path(vault_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(&path).map_err(|e| {
+        sync_error(
+            "KC_SYNC_STATE_FAILED",
+            "failed reading local sync write lock",
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    })?;
+    let lock: SyncLocalWriteLockV1 = serde_json::from_slice(&bytes).map_err(|e| {
+        sync_error(
+            "KC_SYNC_STATE_FAILED",
+            "failed parsing local sync write lock",
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    })?;
+    Ok((lock.expires_at_ms > now_ms).then_some(lock))
+}
+
+fn with_local_sync_lock<T>(
+    vault_path: &Path,
+    now_ms: i64,
+    f: impl FnOnce() -> AppResult<T>,
+) -> AppResult<T> {
+    if let Some(lock) = sync_local_write_lock(vault_path, now_ms)? {
+        return Err(sync_error(
+            "KC_SYNC_LOCKED",
+            "local vault is already locked by another sync",
+            serde_json::json!({
+                "holder": lock.holder,
+                "expires_at_ms": lock.expires_at_ms,
+                "vault_path": vault_path
+            }),
+        ));
+    }
+
+    let path = local_sync_lock_path(vault_path);
+    let lock = SyncLocalWriteLockV1 {
+        schema_version: 1,
+        holder: format!("{}:{}", std::process::id(), now_ms),
+        acquired_at_ms: now_ms,
+        expires_at_ms: now_ms + LOCAL_SYNC_LOCK_TTL_MS,
+    };
+    let bytes = to_canonical_bytes(&serde_json::to_value(&lock).map_err(|e| {
+        sync_error(
+            "KC_SYNC_STATE_FAILED",
+            "failed serializing local sync write lock",
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            sync_error(
+                "KC_SYNC_STATE_FAILED",
+                "failed creating local sync lock directory",
+                serde_json::json!({ "error": e.to_string(), "path": parent }),
+            )
+        })?;
+    }
+    fs::write(&path, bytes).map_err(|e| {
+        sync_error(
+            "KC_SYNC_STATE_FAILED",
+            "failed writing local sync write lock",
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    })?;
+
+    let result = f();
+    let release_result = fs::remove_file(&path).map_err(|e| {
+        sync_error(
+            "KC_SYNC_STATE_FAILED",
+            "failed releasing local sync write lock",
+            serde_json::json!({ "error": e.to_string(), "path": path }),
+        )
+    });
+
+    match (result, release_result) {
+        (Ok(value), Ok(())) => Ok(value),
+        (Ok(_), Err(e)) => Err(e),
+        (Err(e), _) => Err(e),
+    }
+}
+
 pub fn sync_status(conn: &Connection, target_path: &Path) -> AppResult<SyncStatusV1> {
     ensure_sync_tables(conn)?;
     let remote_head = read_head(target_path)?;
@@ -1402,6 +1518,17 @@ pub fn sync_push(
     vault_path: &Path,
     target_path: &Path,
     now_ms: i64,
+) -> AppResult<SyncPushResultV1> {
+    with_local_sync_lock(vault_path, now_ms, || {
+        sync_push_fs(conn, vault_path, target_path, now_ms)
+    })
+}
+
+fn sync_push_fs(
+    conn: &Connection,
+    vault_path: &Path,
+    target_path: &Path,
+    now_ms: i64,
 ) -> AppResult<SyncPushResultV1> {
     ensure_sync_tables(conn)?;
     let vault = vault_open(vault_path)?;
@@ -1498,6 +1625,17 @@ fn sync_push_s3_target(
     vault_path: &Path,
     transport: S3SyncTransport,
     now_ms: i64,
+) -> AppResult<SyncPushResultV1> {
+    with_local_sync_lock(vault_path, now_ms, || {
+        sync_push_s3_target_locked(conn, vault_path, transport, now_ms)
+    })
+}
+
+fn sync_push_s3_target_locked(
+    conn: &Connection,
+    vault_path: &Path,
+    transport: S3SyncTransport,
+    now_ms: i64,
 ) -> AppResult<SyncPushResultV1> {
     ensure_sync_tables(conn)?;
     let vault = vault_open(vault_path)?;
@@ -1641,6 +1779,18 @@ fn sync_pull_with_mode(
     target_path: &Path,
     now_ms: i64,
     auto_merge_mode: Option<SyncAutoMergeMode>,
+) -> AppResult<SyncPullResultV1> {
+    with_local_sync_lock(vault_path, now_ms, || {
+        sync_pull_fs(conn, vault_path, target_path, now_ms, auto_merge_mode)
+    })
+}
+
+fn sync_pull_fs(
+    conn: &Connection,
+    vault_path: &Path,
+    target_path: &Path,
+    now_ms: i64,
+    auto_merge_mode: Option<SyncAutoMergeMode>,
 ) -> AppResult<SyncPullResultV1> {
     ensure_sync_tables(conn)?;
     let db_path = main_db_path(conn)?;
@@ -1737,9 +1887,11 @@ fn sync_pull_with_mode(
         ));
     }
 
+    let local_tombstones = doc_tombstones_list(conn)?;
     apply_snapshot_to_vault(&snapshot_dir, vault_path)?;
 
     let post_conn = open_db(&db_path)?;
+    doc_tombstones_reapply(&post_conn, &local_tombstones, now_ms)?;
     write_state(
         &post_conn,
         "sync_remote_head_seen",
@@ -1789,6 +1941,18 @@ fn sync_pull_s3_target(
     transport: S3SyncTransport,
     now_ms: i64,
     auto_merge_mode: Option<SyncAutoMergeMode>,
+) -> AppResult<SyncPullResultV1> {
+    with_local_sync_lock(vault_path, now_ms, || {
+        sync_pull_s3_target_locked(conn, vault_path, transport, now_ms, auto_merge_mode)
+    })
+}
+
+fn sync_pull_s3_target_locked(
+    conn: &Connection,
+    vault_path: &Path,
+    transport: S3SyncTransport,
+    now_ms: i64,
+    auto_merge_mode: Option<SyncAutoMergeMode>,
 ) -> AppResult<SyncPullResultV1> {
     ensure_sync_tables(conn)?;
     let db_path = main_db_path(conn)?;
@@ -2022,9 +2186,11 @@ fn sync_pull_s3_target(
             })?;
         }
         unpack_zip_snapshot(&zip_bytes, &unpack_dir)?;
+        let local_tombstones = doc_tombstones_list(conn)?;
         apply_snapshot_to_vault(&unpack_dir, vault_path)?;
 
         let post_conn = open_db(&db_path)?;
+        doc_tombstones_reapply(&post_conn, &local_tombstones, now_ms)?;
         write_state(
             &post_conn,
             "sync_remote_head_seen",
@@ -14,6 +14,8 @@ pub struct SyncMergeChangeSetV1 {
     pub object_hashes: Vec<String>,
     #[serde(default)]
     pub lineage_overlay_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doc_tombstone_ids: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -72,6 +74,7 @@ pub fn merge_preview_conservative(
         .intersection(&remote_overlays)
         .cloned()
         .collect();
+    let overlap_tombstones = tombstone_overlap(&local_norm, &remote_norm);
 
     let mut reasons = Vec::new();
     if !overlap_objects.is_empty() {
@@ -80,6 +83,9 @@ pub fn merge_preview_conservative(
     if !overlap_overlays.is_empty() {
         reasons.push("lineage_overlay_overlap".to_string());
     }
+    if !overlap_tombstones.is_empty() {
+        reasons.push("doc_tombstone_overlap".to_string());
+    }
     reasons.sort();
 
     Ok(SyncMergePreviewReportV1 {
@@ -92,6 +98,7 @@ pub fn merge_preview_conservative(
         overlap: SyncMergeChangeSetV1 {
             object_hashes: overlap_objects,
             lineage_overlay_ids: overlap_overlays,
+            doc_tombstone_ids: overlap_tombstones,
         },
         reasons,
         decision_trace: None,
@@ -174,6 +181,7 @@ pub fn merge_preview_conservative_plus_v2(
         .intersection(&remote_overlays)
         .cloned()
         .collect();
+    let overlap_tombstones = tombstone_overlap(&local_norm, &remote_norm);
 
     let mut reasons = Vec::new();
     if !overlap_objects.is_empty() {
@@ -182,6 +190,9 @@ pub fn merge_preview_conservative_plus_v2(
     if !overlap_overlays.is_empty() {
         reasons.push("lineage_overlay_overlap".to_string());
     }
+    if !overlap_tombstones.is_empty() {
+        reasons.push("doc_tombstone_overlap".to_string());
+    }
     if ctx.trust_chain_mismatch {
         reasons.push("trust_chain_mismatch".to_string());
     }
@@ -219,6 +230,7 @@ pub fn merge_preview_conservative_plus_v2(
         overlap: SyncMergeChangeSetV1 {
             object_hashes: overlap_objects,
             lineage_overlay_ids: overlap_overlays,
+            doc_tombstone_ids: overlap_tombstones,
         },
         reasons,
         decision_trace,
@@ -248,6 +260,7 @@ pub fn merge_preview_conservative_plus_v3(
         .intersection(&remote_overlays)
         .cloned()
         .collect();
+    let overlap_tombstones = tombstone_overlap(&local_norm, &remote_norm);
 
     let mut reasons = Vec::new();
     if !overlap_objects.is_empty() {
@@ -256,6 +269,9 @@ pub fn merge_preview_conservative_plus_v3(
     if !overlap_overlays.is_empty() {
         reasons.push("unsafe_overlay_overlap".to_string());
     }
+    if !overlap_tombstones.is_empty() {
+        reasons.push("unsafe_tombstone_overlap".to_string());
+    }
     if ctx.trust_chain_mismatch {
         reasons.push("unsafe_trust".to_string());
     }
@@ -298,6 +314,7 @@ pub fn merge_preview_conservative_plus_v3(
         overlap: SyncMergeChangeSetV1 {
             object_hashes: overlap_objects,
             lineage_overlay_ids: overlap_overlays,
+            doc_tombstone_ids: overlap_tombstones,
         },
         reasons,
         decision_trace,
@@ -393,6 +410,7 @@ pub fn merge_preview_conservative_plus_v4(
         .intersection(&remote_overlays)
         .cloned()
         .collect();
+    let overlap_tombstones = tombstone_overlap(&local_norm, &remote_norm);
 
     let mut reasons = Vec::new();
     if !overlap_objects.is_empty() {
@@ -401,6 +419,9 @@ pub fn merge_preview_conservative_plus_v4(
     if !overlap_overlays.is_empty() {
         reasons.push("unsafe_overlay_overlap_v4".to_string());
     }
+    if !overlap_tombstones.is_empty() {
+        reasons.push("unsafe_tombstone_overlap_v4".to_string());
+    }
     if ctx.trust_chain_mismatch {
         reasons.push("unsafe_trust_chain_v4".to_string());
     }
@@ -444,6 +465,7 @@ pub fn merge_preview_conservative_plus_v4(
         overlap: SyncMergeChangeSetV1 {
             object_hashes: overlap_objects,
             lineage_overlay_ids: overlap_overlays,
+            doc_tombstone_ids: overlap_tombstones,
         },
         reasons,
         decision_trace,
@@ -544,8 +566,46 @@ fn normalize_change_set(
         lineage_overlay_ids.insert(overlay_id.clone());
     }
 
+    let mut doc_tombstone_ids = BTreeSet::new();
+    for doc_id in &input.doc_tombstone_ids {
+        validate_blake3_prefixed(doc_id).map_err(|e| {
+            AppError::new(
+                "KC_SYNC_MERGE_PRECONDITION_FAILED",
+                "sync",
+                "sync merge preview input has invalid doc tombstone id",
+                false,
+                serde_json::json!({
+                    "side": side,
+                    "doc_id": doc_id,
+                    "source_code": e.code
+                }),
+            )
+        })?;
+        doc_tombstone_ids.insert(doc_id.clone());
+    }
+
     Ok(SyncMergeChangeSetV1 {
         object_hashes: object_hashes.into_iter().collect(),
         lineage_overlay_ids: lineage_overlay_ids.into_iter().collect(),
+        doc_tombstone_ids: doc_tombstone_ids.into_iter().collect(),
     })
 }
+
+// Doc ids are original object hashes, so a tombstone on one side collides with the
+// other side (re-)adding the same object.
+fn tombstone_overlap(local: &SyncMergeChangeSetV1, remote: &SyncMergeChangeSetV1) -> Vec<String> {
+    let local_objects: BTreeSet<&String> = local.object_hashes.iter().collect();
+    let remote_objects: BTreeSet<&String> = remote.object_hashes.iter().collect();
+    let mut overlap = BTreeSet::new();
+    for doc_id in &local.doc_tombstone_ids {
+        if remote_objects.contains(doc_id) {
+            overlap.insert(doc_id.clone());
+        }
+    }
+    for doc_id in &remote.doc_tombstone_ids {
+        if local_objects.contains(doc_id) {
+            overlap.insert(doc_id.clone());
+        }
+    }
+    overlap.into_iter().collect()
+}
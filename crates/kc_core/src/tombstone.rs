@@ -0,0 +1,330 @@
+use crate::app_error::{AppError, AppResult};
+use crate::events::append_event;
+use crate::index_traits::{LexicalIndex, VectorIndex};
+use crate::types::DocId;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub const DOC_DELETE_ORIGIN_LOCAL: &str = "local";
+pub const DOC_DELETE_ORIGIN_SYNC_PULL: &str = "sync_pull";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocTombstoneV1 {
+    pub doc_id: String,
+    pub original_object_hash: String,
+    pub canonical_object_hash: Option<String>,
+    pub deleted_event_id: i64,
+    pub deleted_at_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DocDeleteResultV1 {
+    pub tombstone: DocTombstoneV1,
+    pub event_hash: String,
+    pub removed_chunks: i64,
+    pub removed_source_paths: Vec<String>,
+    pub unreferenced_object_hashes: Vec<String>,
+}
+
+fn doc_error(code: &str, message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(code, "doc", message, false, details)
+}
+
+fn db_error(message: &str, doc_id: &str, e: rusqlite::Error) -> AppError {
+    doc_error(
+        "KC_DOC_DELETE_FAILED",
+        message,
+        serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
+    )
+}
+
+fn row_to_tombstone(row: &rusqlite::Row<'_>) -> rusqlite::Result<DocTombstoneV1> {
+    Ok(DocTombstoneV1 {
+        doc_id: row.get(0)?,
+        original_object_hash: row.get(1)?,
+        canonical_object_hash: row.get(2)?,
+        deleted_event_id: row.get(3)?,
+        deleted_at_ms: row.get(4)?,
+    })
+}
+
+pub fn doc_tombstone_get(conn: &Connection, doc_id: &str) -> AppResult<Option<DocTombstoneV1>> {
+    conn.query_row(
+        "SELECT doc_id, original_object_hash, canonical_object_hash, deleted_event_id, deleted_at_ms
+         FROM doc_tombstones WHERE doc_id=?1",
+        params![doc_id],
+        row_to_tombstone,
+    )
+    .optional()
+    .map_err(|e| db_error("failed to load doc tombstone", doc_id, e))
+}
+
+pub fn doc_tombstones_list(conn: &Connection) -> AppResult<Vec<DocTombstoneV1>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT doc_id, original_object_hash, canonical_object_hash, deleted_event_id, deleted_at_ms
+             FROM doc_tombstones ORDER BY doc_id ASC",
+        )
+        .map_err(|e| db_error("failed to prepare doc tombstone query", "", e))?;
+    let rows = stmt
+        .query_map([], row_to_tombstone)
+        .map_err(|e| db_error("failed to query doc tombstones", "", e))?;
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row.map_err(|e| db_error("failed to decode doc tombstone", "", e))?);
+    }
+    Ok(out)
+}
+
+fn object_referenced(conn: &Connection, object_hash: &str) -> AppResult<bool> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT
+               (SELECT COUNT(*) FROM docs WHERE original_object_hash=?1)
+             + (SELECT COUNT(*) FROM canonical_text WHERE canonical_object_hash=?1)
+             + (SELECT COUNT(*) FROM canonical_text_history WHERE canonical_object_hash=?1)",
+            params![object_hash],
+            |row| row.get(0),
+        )
+        .map_err(|e| db_error("failed to count object references", object_hash, e))?;
+    Ok(count > 0)
+}
+
+fn tombstone_doc_rows(
+    conn: &Connection,
+    doc_id: &str,
+    deleted_at_ms: i64,
+    event_ts_ms: i64,
+    origin: &str,
+) -> AppResult<DocDeleteResultV1> {
+    let original_object_hash: String = conn
+        .query_row(
+            "SELECT original_object_hash FROM docs WHERE doc_id=?1",
+            params![doc_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| db_error("failed to load doc for delete", doc_id, e))?
+        .ok_or_else(|| {
+            doc_error(
+                "KC_DOC_NOT_FOUND",
+                "doc does not exist",
+                serde_json::json!({ "doc_id": doc_id }),
+            )
+        })?;
+    let canonical_object_hash: Option<String> = conn
+        .query_row(
+            "SELECT canonical_object_hash FROM canonical_text WHERE doc_id=?1",
+            params![doc_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| db_error("failed to load canonical text for delete", doc_id, e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT canonical_object_hash FROM canonical_text_history
+             WHERE doc_id=?1 ORDER BY canonical_object_hash ASC",
+        )
+        .map_err(|e| db_error("failed to prepare canonical history query", doc_id, e))?;
+    let historical_object_hashes = stmt
+        .query_map(params![doc_id], |row| row.get::<_, String>(0))
+        .map_err(|e| db_error("failed to query canonical history", doc_id, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| db_error("failed to decode canonical history", doc_id, e))?;
+    drop(stmt);
+
+    let mut stmt = conn
+        .prepare("SELECT source_path FROM doc_sources WHERE doc_id=?1 ORDER BY source_path ASC")
+        .map_err(|e| db_error("failed to prepare doc source query", doc_id, e))?;
+    let removed_source_paths = stmt
+        .query_map(params![doc_id], |row| row.get::<_, String>(0))
+        .map_err(|e| db_error("failed to query doc sources", doc_id, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| db_error("failed to decode doc source", doc_id, e))?;
+    drop(stmt);
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| db_error("failed to start doc delete transaction", doc_id, e))?;
+
+    let removed_chunks =
+        tx.execute("DELETE FROM chunks WHERE doc_id=?1", params![doc_id])
+            .map_err(|e| db_error("failed to delete chunk rows", doc_id, e))? as i64;
+    for sql in [
+        "DELETE FROM canonical_text WHERE doc_id=?1",
+        "DELETE FROM canonical_text_history WHERE doc_id=?1",
+        "DELETE FROM doc_sources WHERE doc_id=?1",
+        "DELETE FROM doc_versions WHERE doc_id=?1",
+        "DELETE FROM lineage_overlays WHERE doc_id=?1",
+        "DELETE FROM lineage_edit_locks WHERE doc_id=?1",
+        "DELETE FROM docs WHERE doc_id=?1",
+    ] {
+        tx.execute(sql, params![doc_id])
+            .map_err(|e| db_error("failed to delete doc rows", doc_id, e))?;
+    }
+
+    let mut candidates = BTreeSet::new();
+    candidates.insert(original_object_hash.clone());
+    if let Some(hash) = &canonical_object_hash {
+        candidates.insert(hash.clone());
+    }
+    candidates.extend(historical_object_hashes);
+    let mut unreferenced_object_hashes = Vec::new();
+    for hash in candidates {
+        if !object_referenced(&tx, &hash)? {
+            tx.execute("DELETE FROM objects WHERE object_hash=?1", params![hash])
+                .map_err(|e| db_error("failed to unreference object", doc_id, e))?;
+            unreferenced_object_hashes.push(hash);
+        }
+    }
+
+    let event = append_event(
+        &tx,
+        event_ts_ms,
+        "doc.delete",
+        &serde_json::json!({
+            "doc_id": doc_id,
+            "origin": origin,
+            "deleted_at_ms": deleted_at_ms,
+            "original_object_hash": original_object_hash,
+            "canonical_object_hash": canonical_object_hash,
+            "removed_chunks": removed_chunks,
+            "removed_source_paths": removed_source_paths,
+            "unreferenced_object_hashes": unreferenced_object_hashes,
+        }),
+    )?;
+    tx.execute(
+        "INSERT OR REPLACE INTO doc_tombstones
+           (doc_id, original_object_hash, canonical_object_hash, deleted_event_id, deleted_at_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            doc_id,
+            original_object_hash,
+            canonical_object_hash,
+            event.event_id,
+            deleted_at_ms
+        ],
+    )
+    .map_err(|e| db_error("failed to insert doc tombstone", doc_id, e))?;
+
+    tx.commit()
+        .map_err(|e| db_error("failed to commit doc delete", doc_id, e))?;
+
+    Ok(DocDeleteResultV1 {
+        tombstone: DocTombstoneV1 {
+            doc_id: doc_id.to_string(),
+            original_object_hash,
+            canonical_object_hash,
+            deleted_event_id: event.event_id,
+            deleted_at_ms,
+        },
+        event_hash: event.event_hash,
+        removed_chunks,
+        removed_source_paths,
+        unreferenced_object_hashes,
+    })
+}
+
+pub fn doc_delete(
+    conn: &Connection,
+    lexical: &dyn LexicalIndex,
+    vector: &dyn VectorIndex,
+    doc_id: &DocId,
+    now_ms: i64,
+) -> AppResult<DocDeleteResultV1> {
+    if let Some(existing) = doc_tombstone_get(conn, &doc_id.0)? {
+        return Err(doc_error(
+            "KC_DOC_NOT_FOUND",
+            "doc has already been deleted",
+            serde_json::json!({
+                "doc_id": doc_id.0,
+                "deleted_at_ms": existing.deleted_at_ms,
+            }),
+        ));
+    }
+
+    let held_lock: Option<(String, i64)> = conn
+        .query_row(
+            "SELECT owner, expires_at_ms FROM lineage_edit_locks WHERE doc_id=?1 AND expires_at_ms > ?2",
+            params![doc_id.0, now_ms],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| db_error("failed to check lineage lock for delete", &doc_id.0, e))?;
+    if let Some((owner, expires_at_ms)) = held_lock {
+        return Err(AppError::new(
+            "KC_LINEAGE_LOCK_HELD",
+            "lineage",
+            "doc cannot be deleted while a lineage edit lock is held",
+            false,
+            serde_json::json!({
+                "doc_id": doc_id.0,
+                "owner": owner,
+                "expires_at_ms": expires_at_ms,
+            }),
+        ));
+    }
+
+    let result = tombstone_doc_rows(conn, &doc_id.0, now_ms, now_ms, DOC_DELETE_ORIGIN_LOCAL)?;
+    lexical.delete_for_doc(doc_id)?;
+    vector.delete_for_doc(doc_id)?;
+    Ok(result)
+}
+
+pub fn doc_tombstones_purge_indexes(
+    conn: &Connection,
+    lexical: &dyn LexicalIndex,
+    vector: &dyn VectorIndex,
+) -> AppResult<usize> {
+    let tombstones = doc_tombstones_list(conn)?;
+    for tombstone in &tombstones {
+        let doc_id = DocId(tombstone.doc_id.clone());
+        lexical.delete_for_doc(&doc_id)?;
+        vector.delete_for_doc(&doc_id)?;
+    }
+    Ok(tombstones.len())
+}
+
+// A pulled snapshot replaces the local DB wholesale, so tombstones written locally
+// since the last sync are re-applied afterwards. A delete wins unless the pulled doc
+// was (re-)ingested after the local delete happened.
+pub(crate) fn doc_tombstones_reapply(
+    conn: &Connection,
+    local: &[DocTombstoneV1],
+    now_ms: i64,
+) -> AppResult<Vec<String>> {
+    let mut reapplied = Vec::new();
+    for tombstone in local {
+        if doc_tombstone_get(conn, &tombstone.doc_id)?.is_some() {
+            continue;
+        }
+        let ingested_at_ms: Option<i64> = conn
+            .query_row(
+                "SELECT e.ts_ms FROM docs d JOIN events e ON e.event_id = d.ingested_event_id
+                 WHERE d.doc_id=?1",
+                params![tombstone.doc_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| {
+                db_error(
+                    "failed to load pulled doc ingest time",
+                    &tombstone.doc_id,
+                    e,
+                )
+            })?;
+        if ingested_at_ms.is_some_and(|ts| ts <= tombstone.deleted_at_ms) {
+            tombstone_doc_rows(
+                conn,
+                &tombstone.doc_id,
+                tombstone.deleted_at_ms,
+                now_ms,
+                DOC_DELETE_ORIGIN_SYNC_PULL,
+            )?;
+            reapplied.push(tombstone.doc_id.clone());
+        }
+    }
+    Ok(reapplied)
+}
@@ -0,0 +1,198 @@
+use crate::app_error::{AppError, AppResult};
+use crate::vault::{VaultJsonV3, VaultTraceRedactionDefaults};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_REDACTION_DETECTORS: &[&str] = &["email", "ipv4", "credit_card", "phone"];
+
+// Built-in detectors. They run in configured order, so list broad patterns such as `phone`
+// after the ones whose matches they could split.
+fn detector_regex(name: &str) -> Option<&'static str> {
+    match name {
+        "email" => Some(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b"),
+        "ipv4" => Some(r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b"),
+        "credit_card" => Some(r"\b\d(?:[ -]?\d){12,18}\b"),
+        "phone" => Some(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)|\b\d{3})[ .-]?\d{3}[ .-]?\d{4}\b"),
+        _ => None,
+    }
+}
+
+// A detector match is only redacted when its check passes; this keeps timestamps and other
+// long digit runs out of `credit_card`.
+fn detector_check(name: &str) -> Option<fn(&str) -> bool> {
+    match name {
+        "credit_card" => Some(luhn_valid),
+        _ => None,
+    }
+}
+
+fn luhn_valid(text: &str) -> bool {
+    let mut sum = 0;
+    for (i, digit) in text
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+    {
+        sum += if i % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 {
+                doubled - 9
+            } else {
+                doubled
+            }
+        } else {
+            digit
+        };
+    }
+    sum % 10 == 0
+}
+
+// What redaction did to one trace: the rules in effect, how many matches each replaced and
+// which trace fields changed. Traces written before rules existed carry only `enabled`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceRedactionV1 {
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub matches: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+impl TraceRedactionV1 {
+    pub fn redacted_any(&self) -> bool {
+        !self.fields.is_empty()
+    }
+}
+
+fn redaction_error(message: &str, details: serde_json::Value) -> AppError {
+    AppError::new(
+        "KC_TRACE_REDACTION_FAILED",
+        "trace",
+        message,
+        false,
+        details,
+    )
+}
+
+struct RedactionRule {
+    name: String,
+    regex: Regex,
+    check: Option<fn(&str) -> bool>,
+}
+
+// Replaces every match of a rule with `[REDACTED:<rule>]`, counting replacements per rule.
+pub struct TraceRedactor {
+    enabled: bool,
+    rules: Vec<RedactionRule>,
+    matches: BTreeMap<String, u64>,
+    fields: Vec<String>,
+}
+
+impl TraceRedactor {
+    pub fn from_config(config: &VaultTraceRedactionDefaults) -> AppResult<Self> {
+        let mut rules: Vec<RedactionRule> = Vec::new();
+        if config.enabled {
+            for detector in &config.detectors {
+                let pattern = detector_regex(detector).ok_or_else(|| {
+                    redaction_error(
+                        "unknown redaction detector",
+                        serde_json::json!({
+                            "detector": detector,
+                            "supported": DEFAULT_REDACTION_DETECTORS,
+                        }),
+                    )
+                })?;
+                rules.push(RedactionRule {
+                    name: detector.clone(),
+                    regex: compile(detector, pattern)?,
+                    check: detector_check(detector),
+                });
+            }
+            for pattern in &config.patterns {
+                if pattern.name.trim().is_empty() {
+                    return Err(redaction_error(
+                        "redaction pattern requires a name",
+                        serde_json::json!({ "regex": pattern.regex }),
+                    ));
+                }
+                rules.push(RedactionRule {
+                    name: pattern.name.clone(),
+                    regex: compile(&pattern.name, &pattern.regex)?,
+                    check: None,
+                });
+            }
+            let mut names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+            names.sort_unstable();
+            if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(redaction_error(
+                    "redaction rule names must be unique",
+                    serde_json::json!({ "name": pair[0] }),
+                ));
+            }
+        }
+        Ok(Self {
+            enabled: config.enabled,
+            matches: rules.iter().map(|rule| (rule.name.clone(), 0)).collect(),
+            rules,
+            fields: Vec::new(),
+        })
+    }
+
+    // The vault's `defaults.trace.redaction`, or the built-in detectors when unset.
+    pub fn for_vault(vault: &VaultJsonV3) -> AppResult<Self> {
+        match &vault.defaults.trace {
+            Some(trace) => Self::from_config(&trace.redaction),
+            None => Self::from_config(&VaultTraceRedactionDefaults::default()),
+        }
+    }
+
+    // `field` names where `text` came from (e.g. `retrieval.chunks[0].snippet`) and is recorded
+    // once if anything in it was replaced.
+    pub fn redact(&mut self, field: &str, text: &str) -> String {
+        let mut out = text.to_string();
+        let mut changed = false;
+        for rule in &self.rules {
+            let placeholder = format!("[REDACTED:{}]", rule.name);
+            let mut found = 0u64;
+            let replaced = rule.regex.replace_all(&out, |caps: &Captures| {
+                if rule.check.is_some_and(|check| !check(&caps[0])) {
+                    return caps[0].to_string();
+                }
+                found += 1;
+                placeholder.clone()
+            });
+            if found == 0 {
+                continue;
+            }
+            out = replaced.into_owned();
+            *self.matches.entry(rule.name.clone()).or_insert(0) += found;
+            changed = true;
+        }
+        if changed && !self.fields.iter().any(|f| f == field) {
+            self.fields.push(field.to_string());
+        }
+        out
+    }
+
+    pub fn report(&self) -> TraceRedactionV1 {
+        TraceRedactionV1 {
+            enabled: self.enabled,
+            rules: self.rules.iter().map(|rule| rule.name.clone()).collect(),
+            matches: self.matches.clone(),
+            fields: self.fields.clone(),
+        }
+    }
+}
+
+fn compile(name: &str, pattern: &str) -> AppResult<Regex> {
+    Regex::new(pattern).map_err(|e| {
+        redaction_error(
+            "invalid redaction pattern",
+            serde_json::json!({ "name": name, "regex": pattern, "error": e.to_string() }),
+        )
+    })
+}
@@ -1,5 +1,10 @@
 use crate::app_error::{AppError, AppResult};
+use crate::faithfulness::DEFAULT_SUPPORT_THRESHOLD;
+use crate::rerank::DEFAULT_RERANK_TOP_K;
+use crate::retrieval::{FusionModeV1, RetrievalConfigV1};
+use crate::trace_redaction::DEFAULT_REDACTION_DETECTORS;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
@@ -33,6 +38,149 @@ pub struct VaultDefaults {
     pub chunking_config_id: String,
     pub embedding_model_id: String,
     pub recency: VaultRecencyDefaults,
+    #[serde(default)]
+    pub vector_index: VaultVectorIndexDefaults,
+    #[serde(default)]
+    pub fts: VaultFtsDefaults,
+    #[serde(default)]
+    pub fusion: FusionModeV1,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub embedding_models: BTreeMap<String, VaultEmbeddingModel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reranker: Option<VaultRerankerDefaults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retrieval_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub retrieval_profiles: BTreeMap<String, RetrievalConfigV1>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ask_provider: Option<VaultAskProviderDefaults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<VaultVerifierDefaults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<VaultTraceDefaults>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultEmbeddingModel {
+    pub backend: String,
+    pub path: String,
+}
+
+// `path` is the model directory for model-backed rerankers, relative to the vault root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultRerankerDefaults {
+    pub backend: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default = "default_rerank_top_k")]
+    pub top_k: usize,
+}
+
+fn default_rerank_top_k() -> usize {
+    DEFAULT_RERANK_TOP_K
+}
+
+// An HTTP answer provider. `endpoint` is the server base URL (`http(s)://host:port[/prefix]`);
+// `api_key_env` names an environment variable holding a bearer token, never the token itself,
+// and needs an https or loopback endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultAskProviderDefaults {
+    pub backend: String,
+    pub endpoint: String,
+    pub model: String,
+    #[serde(default)]
+    pub temperature: f64,
+    #[serde(default = "default_ask_max_tokens")]
+    pub max_tokens: u32,
+    #[serde(default = "default_ask_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+}
+
+fn default_ask_max_tokens() -> u32 {
+    512
+}
+
+fn default_ask_timeout_ms() -> u64 {
+    60_000
+}
+
+// Answer verification always applies the lexical heuristics; `alignment` adds a local token
+// alignment model whose `path` is the model directory, relative to the vault root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultVerifierDefaults {
+    #[serde(default = "default_support_threshold")]
+    pub threshold: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<VaultAlignmentModelDefaults>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultAlignmentModelDefaults {
+    pub backend: String,
+    pub path: String,
+}
+
+fn default_support_threshold() -> f64 {
+    DEFAULT_SUPPORT_THRESHOLD
+}
+
+// Ask trace logging. Without this block traces are redacted with the built-in detectors and
+// kept forever.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultTraceDefaults {
+    #[serde(default)]
+    pub redaction: VaultTraceRedactionDefaults,
+    #[serde(default)]
+    pub retention: VaultTraceRetentionDefaults,
+}
+
+// `detectors` name built-in PII detectors; `patterns` add vault-specific regexes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultTraceRedactionDefaults {
+    #[serde(default = "default_redaction_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_redaction_detectors")]
+    pub detectors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<VaultRedactionPattern>,
+}
+
+impl Default for VaultTraceRedactionDefaults {
+    fn default() -> Self {
+        Self {
+            enabled: default_redaction_enabled(),
+            detectors: default_redaction_detectors(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultRedactionPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+fn default_redaction_enabled() -> bool {
+    true
+}
+
+fn default_redaction_detectors() -> Vec<String> {
+    DEFAULT_REDACTION_DETECTORS
+        .iter()
+        .map(|d| d.to_string())
+        .collect()
+}
+
+// Unset limits keep every trace.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultTraceRetentionDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_count: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +188,40 @@ pub struct VaultRecencyDefaults {
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultVectorIndexDefaults {
+    pub ann_index: String,
+    pub ann_row_threshold: u64,
+}
+
+impl Default for VaultVectorIndexDefaults {
+    fn default() -> Self {
+        Self {
+            ann_index: "ivf_hnsw_sq".to_string(),
+            ann_row_threshold: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultFtsDefaults {
+    pub tokenizer: String,
+    pub remove_diacritics: u8,
+    pub content_weight: f64,
+    pub heading_weight: f64,
+}
+
+impl Default for VaultFtsDefaults {
+    fn default() -> Self {
+        Self {
+            tokenizer: "unicode61".to_string(),
+            remove_diacritics: 1,
+            content_weight: 1.0,
+            heading_weight: 2.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VaultToolchain {
     pub pdfium: ToolIdentity,
@@ -152,6 +334,7 @@ pub struct VaultPaths {
     pub objects_dir: PathBuf,
     pub inbox_dir: PathBuf,
     pub inbox_processed_dir: PathBuf,
+    pub inbox_failed_dir: PathBuf,
     pub vectors_dir: PathBuf,
 }
 
@@ -228,6 +411,7 @@ pub fn vault_paths(vault_path: &Path) -> VaultPaths {
         objects_dir: vault_path.join("store/objects"),
         inbox_dir: vault_path.join("Inbox"),
         inbox_processed_dir: vault_path.join("Inbox/processed"),
+        inbox_failed_dir: vault_path.join("Inbox/failed"),
         vectors_dir: vault_path.join("index/vectors"),
     }
 }
@@ -270,6 +454,15 @@ pub fn vault_init(vault_path: &Path, vault_slug: &str, now_ms: i64) -> AppResult
             serde_json::json!({ "error": e.to_string() }),
         )
     })?;
+    fs::create_dir_all(&paths.inbox_failed_dir).map_err(|e| {
+        AppError::new(
+            "KC_VAULT_INIT_FAILED",
+            "vault",
+            "failed to create inbox failed directory",
+            false,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })?;
     fs::create_dir_all(&paths.vectors_dir).map_err(|e| {
         AppError::new(
             "KC_VAULT_INIT_FAILED",
@@ -292,6 +485,16 @@ pub fn vault_init(vault_path: &Path, vault_slug: &str, now_ms: i64) -> AppResult
             chunking_config_id: "chunking/default-v1".to_string(),
             embedding_model_id: "embedding/default-v1".to_string(),
             recency: VaultRecencyDefaults { enabled: false },
+            vector_index: VaultVectorIndexDefaults::default(),
+            fts: VaultFtsDefaults::default(),
+            fusion: FusionModeV1::default(),
+            embedding_models: BTreeMap::new(),
+            reranker: None,
+            retrieval_profile: None,
+            retrieval_profiles: BTreeMap::new(),
+            ask_provider: None,
+            verifier: None,
+            trace: None,
         },
         toolchain: VaultToolchain {
             pdfium: ToolIdentity {
@@ -0,0 +1,202 @@
+// Index and extractor doubles shared by the integration tests. Each test binary compiles this
+// module on its own and uses only part of it.
+#![allow(dead_code)]
+
+use kc_core::app_error::AppResult;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::index_traits::{
+    IndexChunk, LexicalCandidate, LexicalIndex, VectorCandidate, VectorIndex,
+};
+use kc_core::services::{CanonicalTextArtifact, ExtractInput, ExtractService};
+use kc_core::types::{CanonicalHash, DocId, ObjectHash};
+use std::sync::Mutex;
+
+// Canonical text is the input bytes as-is.
+pub struct PlainExtractor;
+
+impl ExtractService for PlainExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        let canonical_bytes = input.bytes.to_vec();
+        let hash = blake3_hex_prefixed(&canonical_bytes);
+        Ok(CanonicalTextArtifact {
+            doc_id: input.doc_id.clone(),
+            canonical_bytes,
+            canonical_hash: CanonicalHash(hash.clone()),
+            canonical_object_hash: ObjectHash(hash),
+            extractor_name: "test.plain".to_string(),
+            extractor_version: "1".to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: "{}".to_string(),
+        })
+    }
+}
+
+// Accepts every write and finds nothing.
+pub struct NullIndex;
+
+impl LexicalIndex for NullIndex {
+    fn rebuild_for_doc(&self, _doc_id: &DocId, _chunks: &[IndexChunk]) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn delete_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn query(&self, _query: &str, _limit: usize) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(Vec::new())
+    }
+
+    fn query_in_docs(
+        &self,
+        _query: &str,
+        _limit: usize,
+        _doc_ids: &[DocId],
+    ) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(Vec::new())
+    }
+}
+
+impl VectorIndex for NullIndex {
+    fn rebuild_for_doc(&self, _doc_id: &DocId, _chunks: &[IndexChunk]) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn delete_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn query(&self, _query: &str, _limit: usize) -> AppResult<Vec<VectorCandidate>> {
+        Ok(Vec::new())
+    }
+
+    fn query_in_docs(
+        &self,
+        _query: &str,
+        _limit: usize,
+        _doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>> {
+        Ok(Vec::new())
+    }
+}
+
+// Keeps each doc's latest chunks, in the order docs were last rebuilt, and logs every rebuild.
+// Lexical queries return the chunks containing the query text in that order; vector queries
+// return nothing.
+#[derive(Default)]
+pub struct MemoryIndex {
+    chunks: Mutex<Vec<IndexChunk>>,
+    rebuilds: Mutex<Vec<(String, Vec<String>)>>,
+}
+
+impl MemoryIndex {
+    pub fn chunks_for(&self, doc_id: &DocId) -> Vec<IndexChunk> {
+        self.chunks
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|c| &c.doc_id == doc_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn rows_for(&self, doc_id: &str) -> usize {
+        self.chunks
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|c| c.doc_id.0 == doc_id)
+            .count()
+    }
+
+    // Drains the rebuild log: `(doc_id, chunk texts)` per rebuild.
+    pub fn take_rebuilds(&self) -> Vec<(String, Vec<String>)> {
+        std::mem::take(&mut *self.rebuilds.lock().expect("lock"))
+    }
+
+    fn replace(&self, doc_id: &DocId, chunks: &[IndexChunk]) {
+        let mut all = self.chunks.lock().expect("lock");
+        all.retain(|c| &c.doc_id != doc_id);
+        all.extend_from_slice(chunks);
+    }
+
+    fn rebuild(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        self.replace(doc_id, chunks);
+        self.rebuilds.lock().expect("lock").push((
+            doc_id.0.clone(),
+            chunks.iter().map(|c| c.text.clone()).collect(),
+        ));
+        Ok(())
+    }
+
+    fn matching(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: Option<&[DocId]>,
+    ) -> Vec<LexicalCandidate> {
+        self.chunks
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|c| c.text.contains(query))
+            .filter(|c| doc_ids.is_none_or(|ids| ids.contains(&c.doc_id)))
+            .take(limit)
+            .enumerate()
+            .map(|(idx, c)| LexicalCandidate {
+                chunk_id: c.chunk_id.clone(),
+                rank: idx as i64 + 1,
+                bm25: None,
+            })
+            .collect()
+    }
+}
+
+impl LexicalIndex for MemoryIndex {
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        self.rebuild(doc_id, chunks)
+    }
+
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()> {
+        self.replace(doc_id, &[]);
+        Ok(())
+    }
+
+    fn query(&self, query: &str, limit: usize) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(self.matching(query, limit, None))
+    }
+
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(self.matching(query, limit, Some(doc_ids)))
+    }
+}
+
+impl VectorIndex for MemoryIndex {
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        self.rebuild(doc_id, chunks)
+    }
+
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()> {
+        self.replace(doc_id, &[]);
+        Ok(())
+    }
+
+    fn query(&self, _query: &str, _limit: usize) -> AppResult<Vec<VectorCandidate>> {
+        Ok(Vec::new())
+    }
+
+    fn query_in_docs(
+        &self,
+        _query: &str,
+        _limit: usize,
+        _doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>> {
+        Ok(Vec::new())
+    }
+}
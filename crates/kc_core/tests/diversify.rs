@@ -0,0 +1,149 @@
+use kc_core::diversify::{
+    diversify_hits, simhash64, simhash_distance, DiversifyCandidate, DiversifyConfigV1,
+};
+use kc_core::retrieval::MergedHit;
+use kc_core::types::{ChunkId, DocId};
+
+fn candidate(
+    chunk_id: &str,
+    doc_id: &str,
+    range: (i64, i64),
+    score: f64,
+    text: &str,
+) -> DiversifyCandidate {
+    DiversifyCandidate {
+        hit: MergedHit {
+            chunk_id: ChunkId(chunk_id.to_string()),
+            doc_id: DocId(doc_id.to_string()),
+            ordinal: range.0,
+            lexical_rank: None,
+            lexical_score: None,
+            vector_rank: None,
+            final_score: score,
+        },
+        start_char: range.0,
+        end_char: range.1,
+        simhash: Some(simhash64(text)),
+    }
+}
+
+#[test]
+fn simhash_is_stable_and_close_for_near_duplicates() {
+    let text = "Rotate the signing keys every ninety days and record the rotation in the audit log";
+    assert_eq!(simhash64(text), simhash64(&text.to_uppercase()));
+    assert_eq!(simhash64(""), 0);
+
+    let edited =
+        "Rotate the signing keys every ninety days and record each rotation in the audit log";
+    let unrelated = "The cafeteria menu changes on Fridays when the vendor delivers fresh produce";
+    let near = simhash_distance(simhash64(text), simhash64(edited));
+    let far = simhash_distance(simhash64(text), simhash64(unrelated));
+    assert!(near < far, "near={near} far={far}");
+}
+
+#[test]
+fn diversify_collapses_ranges_drops_duplicates_and_caps_docs() {
+    let candidates = vec![
+        candidate("a1", "doc_a", (0, 100), 0.9, "alpha window one"),
+        candidate(
+            "b1",
+            "doc_b",
+            (0, 50),
+            0.8,
+            "bravo unique passage about keys",
+        ),
+        candidate("a2", "doc_a", (80, 180), 0.7, "alpha window two"),
+        candidate(
+            "c1",
+            "doc_c",
+            (0, 50),
+            0.6,
+            "Bravo unique passage, about keys.",
+        ),
+        candidate("b2", "doc_b", (500, 550), 0.5, "charlie far section"),
+        candidate("b3", "doc_b", (900, 950), 0.4, "delta another section"),
+    ];
+    let cfg = DiversifyConfigV1 {
+        max_per_doc: 2,
+        ..DiversifyConfigV1::default()
+    };
+
+    let (hits, report) = diversify_hits(candidates.clone(), &cfg, 10);
+    let ids: Vec<&str> = hits.iter().map(|h| h.hit.chunk_id.0.as_str()).collect();
+    assert_eq!(ids, vec!["a1", "b1", "b2"]);
+    assert_eq!((hits[0].start_char, hits[0].end_char), (0, 180));
+    assert_eq!(hits[0].collapsed, vec![ChunkId("a2".to_string())]);
+    assert_eq!(report.collapsed[0].into, "a1");
+    assert_eq!(report.near_duplicates[0].chunk_id, "c1");
+    assert_eq!(report.near_duplicates[0].duplicate_of, "b1");
+    assert_eq!(report.capped, vec!["b3".to_string()]);
+
+    let disabled = DiversifyConfigV1 {
+        enabled: false,
+        ..DiversifyConfigV1::default()
+    };
+    let (hits, report) = diversify_hits(candidates, &disabled, 4);
+    let ids: Vec<&str> = hits.iter().map(|h| h.hit.chunk_id.0.as_str()).collect();
+    assert_eq!(ids, vec!["a1", "b1", "a2", "c1"]);
+    assert!(report.collapsed.is_empty() && report.near_duplicates.is_empty());
+}
+
+#[test]
+fn diversify_mmr_prefers_a_dissimilar_hit_over_a_similar_one() {
+    let base = "rotate signing keys every ninety days and record the rotation in the audit log";
+    let similar =
+        "rotate signing keys every ninety days and record the rotation in the audit log today";
+    let candidates = vec![
+        candidate("top", "doc_a", (0, 10), 1.0, base),
+        candidate("similar", "doc_b", (0, 10), 0.95, similar),
+        candidate(
+            "other",
+            "doc_c",
+            (0, 10),
+            0.9,
+            "vendor contracts renew each spring",
+        ),
+    ];
+    let cfg = DiversifyConfigV1 {
+        near_duplicate_distance: 0,
+        lambda: 0.5,
+        ..DiversifyConfigV1::default()
+    };
+    let (hits, _) = diversify_hits(candidates, &cfg, 3);
+    let ids: Vec<&str> = hits.iter().map(|h| h.hit.chunk_id.0.as_str()).collect();
+    assert_eq!(ids, vec!["top", "other", "similar"]);
+}
+
+#[test]
+fn diversify_collapses_only_chunks_overlapping_or_touching_the_kept_chunk() {
+    let candidates = vec![
+        candidate("a1", "doc_a", (100, 200), 0.9, "kept window"),
+        candidate("a2", "doc_a", (150, 260), 0.8, "overlapping window"),
+        candidate(
+            "a3",
+            "doc_a",
+            (240, 340),
+            0.7,
+            "overlaps only the folded chunk",
+        ),
+        candidate("a4", "doc_a", (0, 100), 0.6, "touching window before"),
+    ];
+    let cfg = DiversifyConfigV1 {
+        max_per_doc: 5,
+        near_duplicate_distance: 0,
+        ..DiversifyConfigV1::default()
+    };
+
+    let (hits, report) = diversify_hits(candidates, &cfg, 10);
+    let mut ids: Vec<&str> = hits.iter().map(|h| h.hit.chunk_id.0.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["a1", "a3"]);
+    let kept = hits.iter().find(|h| h.hit.chunk_id.0 == "a1").expect("a1");
+    assert_eq!((kept.start_char, kept.end_char), (0, 260));
+    let folded: Vec<&str> = report
+        .collapsed
+        .iter()
+        .map(|c| c.chunk_id.as_str())
+        .collect();
+    assert_eq!(folded, vec!["a2", "a4"]);
+}
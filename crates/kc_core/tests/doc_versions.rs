@@ -0,0 +1,220 @@
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::doc_versions::{doc_is_superseded, doc_version_history, doc_versions_for_source};
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::lineage::query_lineage;
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::rpc_service::search_query_service;
+use kc_core::search::{SearchFilterV1, SearchReq};
+use kc_core::tombstone::doc_delete;
+use kc_core::types::DocId;
+use kc_core::vault::vault_init;
+use std::path::Path;
+
+mod common;
+
+use common::{MemoryIndex, NullIndex, PlainExtractor};
+
+fn ingest_source(
+    conn: &rusqlite::Connection,
+    lexical: &MemoryIndex,
+    vault_root: &Path,
+    bytes: &[u8],
+    source_path: &str,
+    now_ms: i64,
+) -> DocId {
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let doc = ingest_bytes(
+        conn,
+        &store,
+        IngestBytesReq {
+            bytes,
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: now_ms,
+            source_path: Some(source_path),
+            now_ms,
+        },
+    )
+    .expect("ingest");
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+    run_doc_pipeline(conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+    doc.doc_id
+}
+
+#[test]
+fn reingesting_a_source_path_links_versions_and_supersedes_the_old_one() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let lexical = MemoryIndex::default();
+
+    let v1 = ingest_source(
+        &conn,
+        &lexical,
+        &vault_root,
+        b"alpha draft",
+        "/notes/a.md",
+        10,
+    );
+    let again = ingest_source(
+        &conn,
+        &lexical,
+        &vault_root,
+        b"alpha draft",
+        "/notes/a.md",
+        11,
+    );
+    assert_eq!(again, v1);
+    let v2 = ingest_source(
+        &conn,
+        &lexical,
+        &vault_root,
+        b"alpha final",
+        "/notes/a.md",
+        20,
+    );
+    let other = ingest_source(
+        &conn,
+        &lexical,
+        &vault_root,
+        b"alpha other",
+        "/notes/b.md",
+        21,
+    );
+
+    let history = doc_versions_for_source(&conn, "/notes/a.md")
+        .expect("history")
+        .expect("history exists");
+    assert_eq!(history.current_doc_id, v2.0);
+    assert_eq!(history.versions.len(), 2);
+    assert_eq!(history.versions[0].version, 1);
+    assert_eq!(history.versions[0].doc_id, v1.0);
+    assert_eq!(history.versions[0].ingested_at_ms, 10);
+    assert_eq!(history.versions[0].superseded_by_doc_id, Some(v2.0.clone()));
+    assert_eq!(history.versions[1].version, 2);
+    assert_eq!(history.versions[1].superseded_by_doc_id, None);
+    assert_eq!(
+        doc_version_history(&conn, &v1.0).expect("by doc"),
+        vec![history]
+    );
+
+    assert!(doc_is_superseded(&conn, &v1.0).expect("v1"));
+    assert!(!doc_is_superseded(&conn, &v2.0).expect("v2"));
+    assert!(!doc_is_superseded(&conn, &other.0).expect("other"));
+
+    let search = |include_superseded| {
+        search_query_service(
+            &vault_root,
+            &lexical,
+            &NullIndex,
+            SearchReq {
+                query: "alpha",
+                limit: 10,
+                include_superseded,
+                filter: &SearchFilterV1::default(),
+                profile: None,
+                now_ms: 30,
+            },
+        )
+        .expect("search")
+        .hits
+    };
+    let hits = search(false);
+    let mut doc_ids: Vec<String> = hits.into_iter().map(|h| h.doc_id).collect();
+    doc_ids.sort();
+    let mut expected = vec![v2.0.clone(), other.0.clone()];
+    expected.sort();
+    assert_eq!(doc_ids, expected);
+    let all = search(true);
+    assert_eq!(all.len(), 3);
+
+    let reverted = ingest_source(
+        &conn,
+        &lexical,
+        &vault_root,
+        b"alpha draft",
+        "/notes/a.md",
+        40,
+    );
+    assert_eq!(reverted, v1);
+    assert!(!doc_is_superseded(&conn, &v1.0).expect("v1 head again"));
+    assert!(doc_is_superseded(&conn, &v2.0).expect("v2 superseded"));
+}
+
+#[test]
+fn lineage_reports_supersedes_edges_between_versions() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let lexical = MemoryIndex::default();
+
+    let v1 = ingest_source(&conn, &lexical, &vault_root, b"one", "/notes/a.md", 10);
+    let v2 = ingest_source(&conn, &lexical, &vault_root, b"two", "/notes/a.md", 20);
+    let v3 = ingest_source(&conn, &lexical, &vault_root, b"three", "/notes/a.md", 30);
+
+    let lineage = query_lineage(&conn, &v2.0, 1, 40).expect("lineage");
+    let mut supersedes: Vec<(String, String, String)> = lineage
+        .edges
+        .iter()
+        .filter(|e| e.relation == "supersedes")
+        .map(|e| {
+            (
+                e.from_node_id.clone(),
+                e.to_node_id.clone(),
+                e.evidence.clone(),
+            )
+        })
+        .collect();
+    supersedes.sort_by(|a, b| a.2.cmp(&b.2));
+    assert_eq!(
+        supersedes,
+        vec![
+            (
+                format!("doc:{}", v2.0),
+                format!("doc:{}", v1.0),
+                "doc_versions:/notes/a.md@2".to_string()
+            ),
+            (
+                format!("doc:{}", v3.0),
+                format!("doc:{}", v2.0),
+                "doc_versions:/notes/a.md@3".to_string()
+            ),
+        ]
+    );
+    let neighbour = lineage
+        .nodes
+        .iter()
+        .find(|n| n.node_id == format!("doc:{}", v3.0))
+        .expect("newer version node");
+    assert_eq!(neighbour.metadata["version"], 3);
+}
+
+#[test]
+fn deleting_the_head_version_restores_the_previous_one() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let lexical = MemoryIndex::default();
+
+    let v1 = ingest_source(&conn, &lexical, &vault_root, b"first", "/notes/a.md", 10);
+    let v2 = ingest_source(&conn, &lexical, &vault_root, b"second", "/notes/a.md", 20);
+    assert!(doc_is_superseded(&conn, &v1.0).expect("superseded"));
+
+    doc_delete(&conn, &lexical, &NullIndex, &v2, 30).expect("delete head");
+    assert!(!doc_is_superseded(&conn, &v1.0).expect("restored"));
+    let history = doc_version_history(&conn, &v1.0).expect("history");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].current_doc_id, v1.0);
+    assert_eq!(history[0].versions.len(), 1);
+}
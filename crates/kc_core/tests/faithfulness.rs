@@ -0,0 +1,165 @@
+use kc_core::app_error::AppResult;
+use kc_core::faithfulness::{
+    lexical_support, split_sentences, verify_paragraphs, AlignmentModel, NliModel,
+    ParagraphEvidence, SupportModel,
+};
+
+fn evidence(paragraph_index: i64, text: &str, spans: &[&str]) -> ParagraphEvidence {
+    ParagraphEvidence {
+        paragraph_index,
+        text: text.to_string(),
+        spans: spans.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn split_sentences_drops_citation_markers_and_keeps_decimals() {
+    assert_eq!(
+        split_sentences("Q: signing keys\nA: Keys rotate every 3.5 months [1]. Really? [1, 2]"),
+        vec![
+            "Q: signing keys",
+            "A: Keys rotate every 3.5 months.",
+            "Really?"
+        ]
+    );
+    assert_eq!(
+        split_sentences("See [note] here."),
+        vec!["See [note] here."]
+    );
+    assert!(split_sentences(" [1] ").is_empty());
+}
+
+#[test]
+fn lexical_support_scores_coverage_and_penalizes_numbers_and_negation() {
+    let span = "Signing keys rotate every quarter.";
+    assert_eq!(
+        lexical_support("The signing key rotated every quarter.", span),
+        Some((0.8, "matched 4/5 terms; missing [rotated]".to_string()))
+    );
+    assert_eq!(
+        lexical_support("Keys rotate every 2 quarters.", span),
+        Some((
+            0.4,
+            "matched 4/5 terms; missing [2]; numbers not in evidence [2]".to_string()
+        ))
+    );
+    assert_eq!(
+        lexical_support("Signing keys never rotate.", span),
+        Some((
+            0.5,
+            "matched 3/3 terms; negation not in evidence".to_string()
+        ))
+    );
+    assert_eq!(lexical_support("Is it so?", span), None);
+}
+
+#[test]
+fn verify_paragraphs_flags_unsupported_sentences_per_paragraph() {
+    let report = verify_paragraphs(
+        &[
+            evidence(
+                0,
+                "Signing keys rotate every quarter [1]. The budget is 10 [1].",
+                &["Signing keys rotate every quarter."],
+            ),
+            evidence(1, "Lunch is pasta [2].", &["Lunch menu is pasta."]),
+            evidence(2, "It is [1].", &["Signing keys rotate every quarter."]),
+        ],
+        None,
+        0.5,
+    )
+    .expect("verify");
+
+    assert_eq!(report.verifier, "lexical_entailment");
+    assert!(!report.supported);
+    let first = &report.paragraphs[0];
+    assert_eq!(first.paragraph_index, 0);
+    assert!(!first.supported);
+    assert_eq!(first.sentences_checked, 2);
+    assert_eq!(first.score, 0.0);
+    assert_eq!(first.unsupported_sentences.len(), 1);
+    assert_eq!(first.unsupported_sentences[0].sentence_index, 1);
+    assert_eq!(first.unsupported_sentences[0].text, "The budget is 10.");
+    assert_eq!(first.unsupported_sentences[0].nli_score, None);
+    assert!(report.paragraphs[1].supported);
+    assert_eq!(report.paragraphs[1].score, 1.0);
+    assert_eq!(report.paragraphs[2].sentences_checked, 0);
+    assert_eq!(report.paragraphs[2].score, 1.0);
+}
+
+struct FixedNli {
+    scores: Vec<f64>,
+}
+
+impl NliModel for FixedNli {
+    fn model_id(&self) -> String {
+        "test/fixed".to_string()
+    }
+
+    fn entailment(&self, _premise: &str, _hypotheses: &[&str]) -> AppResult<Vec<f64>> {
+        Ok(self.scores.clone())
+    }
+}
+
+struct FixedAlignment {
+    scores: Vec<f64>,
+}
+
+impl AlignmentModel for FixedAlignment {
+    fn model_id(&self) -> String {
+        "test/aligned".to_string()
+    }
+
+    fn alignment(&self, _premise: &str, _hypotheses: &[&str]) -> AppResult<Vec<f64>> {
+        Ok(self.scores.clone())
+    }
+}
+
+#[test]
+fn verify_paragraphs_averages_model_scores_by_kind_and_rejects_malformed_output() {
+    let paragraphs = [evidence(
+        0,
+        "Signing keys rotate. Keys never expire.",
+        &["Signing keys rotate every quarter."],
+    )];
+    let nli = FixedNli {
+        scores: vec![0.5, 0.0],
+    };
+    let report =
+        verify_paragraphs(&paragraphs, Some(SupportModel::Nli(&nli)), 0.6).expect("verify");
+    assert_eq!(report.verifier, "lexical_entailment+nli:test/fixed");
+    let paragraph = &report.paragraphs[0];
+    assert_eq!(paragraph.score, 0.125);
+    let flagged: Vec<(usize, f64, f64, Option<f64>)> = paragraph
+        .unsupported_sentences
+        .iter()
+        .map(|s| (s.sentence_index, s.score, s.lexical_score, s.nli_score))
+        .collect();
+    assert_eq!(flagged, vec![(1, 0.125, 0.25, Some(0.0))]);
+    assert_eq!(paragraph.unsupported_sentences[0].alignment_score, None);
+
+    let aligner = FixedAlignment {
+        scores: vec![0.5, 0.0],
+    };
+    let report = verify_paragraphs(&paragraphs, Some(SupportModel::Alignment(&aligner)), 0.6)
+        .expect("verify");
+    assert_eq!(
+        report.verifier,
+        "lexical_entailment+token_alignment:test/aligned"
+    );
+    let flagged = &report.paragraphs[0].unsupported_sentences[0];
+    assert_eq!((flagged.score, flagged.nli_score), (0.125, None));
+    assert_eq!(flagged.alignment_score, Some(0.0));
+
+    for scores in [vec![1.0], vec![1.0, 1.5], vec![f64::NAN, 0.0]] {
+        let err = verify_paragraphs(
+            &paragraphs,
+            Some(SupportModel::Nli(&FixedNli { scores })),
+            0.5,
+        )
+        .expect_err("malformed");
+        assert_eq!(err.code, "KC_ASK_VERIFIER_FAILED");
+    }
+    let err = verify_paragraphs(&paragraphs, None, 1.5).expect_err("threshold");
+    assert_eq!(err.code, "KC_ASK_VERIFIER_FAILED");
+}
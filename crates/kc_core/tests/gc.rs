@@ -0,0 +1,157 @@
+use kc_core::db::open_db;
+use kc_core::gc::gc_run;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::lineage::lineage_lock_acquire;
+use kc_core::object_store::ObjectStore;
+use kc_core::tombstone::doc_delete;
+use kc_core::types::{DocId, ObjectHash};
+use kc_core::vault::vault_init;
+use std::path::Path;
+
+mod common;
+
+use common::NullIndex;
+
+fn ingest(conn: &rusqlite::Connection, vault_root: &Path, bytes: &[u8], now_ms: i64) -> DocId {
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    ingest_bytes(
+        conn,
+        &store,
+        IngestBytesReq {
+            bytes,
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: now_ms,
+            source_path: None,
+            now_ms,
+        },
+    )
+    .expect("ingest")
+    .doc_id
+}
+
+fn object_rows(conn: &rusqlite::Connection) -> i64 {
+    conn.query_row("SELECT COUNT(*) FROM objects", [], |row| row.get(0))
+        .expect("count objects")
+}
+
+#[test]
+fn gc_dry_run_reports_and_run_sweeps_unreachable_objects() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+
+    let kept = ingest(&conn, &vault_root, b"kept", 10);
+    let stray = store.put_bytes(&conn, b"stray", 1).expect("stray object");
+    let orphan_path = vault_root.join("store/objects/zz/blake3:orphan");
+    std::fs::create_dir_all(orphan_path.parent().expect("parent")).expect("mkdir");
+    std::fs::write(&orphan_path, b"orphan-bytes").expect("orphan file");
+
+    let preview = gc_run(&conn, &vault_root, true, 20).expect("dry run");
+    assert!(preview.dry_run);
+    assert_eq!(preview.reachable_objects, 1);
+    assert_eq!(preview.unreachable_object_hashes, vec![stray.0.clone()]);
+    assert_eq!(preview.orphan_files.len(), 2);
+    assert!(preview
+        .orphan_files
+        .contains(&"zz/blake3:orphan".to_string()));
+    assert_eq!(preview.reclaimable_bytes, 5 + 12);
+    assert!(preview.event_hash.is_none());
+    assert_eq!(object_rows(&conn), 2);
+    assert!(orphan_path.exists());
+
+    let report = gc_run(&conn, &vault_root, false, 21).expect("gc run");
+    assert_eq!(
+        report.unreachable_object_hashes,
+        preview.unreachable_object_hashes
+    );
+    assert_eq!(report.orphan_files, preview.orphan_files);
+    assert_eq!(report.reclaimable_bytes, preview.reclaimable_bytes);
+    assert_eq!(object_rows(&conn), 1);
+    assert!(!orphan_path.exists());
+    assert!(store.get_bytes(&ObjectHash(kept.0.clone())).is_ok());
+    assert!(store.get_bytes(&stray).is_err());
+
+    let event_type: String = conn
+        .query_row(
+            "SELECT type FROM events WHERE event_hash=?1",
+            [report.event_hash.as_deref().expect("event hash")],
+            |row| row.get(0),
+        )
+        .expect("gc event");
+    assert_eq!(event_type, "gc.run");
+
+    let rerun = gc_run(&conn, &vault_root, true, 22).expect("rerun");
+    assert!(rerun.unreachable_object_hashes.is_empty());
+    assert!(rerun.orphan_files.is_empty());
+    assert_eq!(rerun.reclaimable_bytes, 0);
+}
+
+#[test]
+fn gc_retains_tombstoned_objects_until_a_newer_sync_snapshot() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+
+    let doc_id = ingest(&conn, &vault_root, b"synced", 10);
+    conn.execute(
+        "INSERT INTO sync_snapshots(snapshot_id, direction, created_at_ms, bundle_relpath, manifest_hash)
+         VALUES('snap-1', 'push', 15, 'snapshots/snap-1', 'blake3:manifest')",
+        [],
+    )
+    .expect("snapshot row");
+    doc_delete(&conn, &NullIndex, &NullIndex, &doc_id, 20).expect("delete");
+
+    let retained = gc_run(&conn, &vault_root, true, 30).expect("dry run");
+    assert_eq!(retained.retained_by_snapshot, vec![doc_id.0.clone()]);
+    assert!(retained.orphan_files.is_empty());
+
+    conn.execute(
+        "INSERT INTO sync_snapshots(snapshot_id, direction, created_at_ms, bundle_relpath, manifest_hash)
+         VALUES('snap-2', 'push', 25, 'snapshots/snap-2', 'blake3:manifest2')",
+        [],
+    )
+    .expect("newer snapshot row");
+    let released = gc_run(&conn, &vault_root, true, 30).expect("dry run");
+    assert!(released.retained_by_snapshot.is_empty());
+    assert_eq!(released.orphan_files.len(), 1);
+    assert!(released.orphan_files[0].ends_with(&doc_id.0));
+}
+
+#[test]
+fn gc_refuses_while_sync_or_lineage_lock_is_held() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let doc_id = ingest(&conn, &vault_root, b"locked", 10);
+
+    let lease = lineage_lock_acquire(&conn, &doc_id.0, "editor", 11).expect("lineage lock");
+    let err = gc_run(&conn, &vault_root, true, 12).expect_err("lineage lock held");
+    assert_eq!(err.code, "KC_GC_LOCKED");
+    assert_eq!(err.details["lock"], "lineage_edit");
+    gc_run(&conn, &vault_root, true, lease.expires_at_ms + 1).expect("lineage lock expired");
+
+    let after_lease = lease.expires_at_ms + 1;
+    let lock_path = vault_root.join("locks/sync_write.lock");
+    std::fs::create_dir_all(lock_path.parent().expect("parent")).expect("mkdir");
+    std::fs::write(
+        &lock_path,
+        serde_json::to_vec(&serde_json::json!({
+            "schema_version": 1,
+            "holder": "other:1",
+            "acquired_at_ms": after_lease,
+            "expires_at_ms": after_lease + 100
+        }))
+        .expect("lock json"),
+    )
+    .expect("write lock");
+    let err = gc_run(&conn, &vault_root, false, after_lease + 50).expect_err("sync lock held");
+    assert_eq!(err.code, "KC_GC_LOCKED");
+    assert_eq!(err.details["lock"], "sync_write");
+    assert_eq!(object_rows(&conn), 1);
+    gc_run(&conn, &vault_root, true, after_lease + 100).expect("sync lock expired");
+}
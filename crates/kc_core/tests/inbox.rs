@@ -0,0 +1,281 @@
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::inbox::{
+    inbox_candidates, inbox_process_file, inbox_watch_poll_once, inbox_watch_request_stop,
+    inbox_watch_run, InboxCandidate, InboxDebouncer, InboxWatchConfigV1,
+};
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::PipelineServices;
+use kc_core::services::{CanonicalTextArtifact, ExtractInput, ExtractService};
+use kc_core::types::{CanonicalHash, ObjectHash};
+use kc_core::vault::{vault_init, vault_paths};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+
+mod common;
+
+use common::NullIndex;
+
+struct PlainExtractor;
+
+impl ExtractService for PlainExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        if input.bytes.starts_with(b"corrupt") {
+            return Err(AppError::new(
+                "KC_CANONICAL_EXTRACT_FAILED",
+                "extract",
+                "corrupt input",
+                false,
+                serde_json::json!({}),
+            ));
+        }
+        let canonical_bytes = input.bytes.to_vec();
+        let hash = blake3_hex_prefixed(&canonical_bytes);
+        Ok(CanonicalTextArtifact {
+            doc_id: input.doc_id.clone(),
+            canonical_bytes,
+            canonical_hash: CanonicalHash(hash.clone()),
+            canonical_object_hash: ObjectHash(hash),
+            extractor_name: "test.plain".to_string(),
+            extractor_version: "1".to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: "{}".to_string(),
+        })
+    }
+}
+
+// Deletes another inbox file mid-poll, as if a user removed it after it was debounced.
+struct VanishingExtractor {
+    victim: PathBuf,
+}
+
+impl ExtractService for VanishingExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        let _ = std::fs::remove_file(&self.victim);
+        PlainExtractor.extract_canonical(input)
+    }
+}
+
+fn candidate(path: &str, len: u64, modified_ms: i64) -> InboxCandidate {
+    InboxCandidate {
+        path: PathBuf::from(path),
+        len,
+        modified_ms,
+    }
+}
+
+#[test]
+fn inbox_debouncer_waits_for_stable_size_and_mtime() {
+    let mut debouncer = InboxDebouncer::new();
+    assert!(debouncer
+        .observe(&[candidate("/i/a.txt", 10, 1)], 0, 500)
+        .is_empty());
+    assert!(debouncer
+        .observe(&[candidate("/i/a.txt", 20, 2)], 400, 500)
+        .is_empty());
+    assert!(debouncer
+        .observe(&[candidate("/i/a.txt", 20, 2)], 800, 500)
+        .is_empty());
+    assert_eq!(
+        debouncer.observe(&[candidate("/i/a.txt", 20, 2)], 900, 500),
+        vec![PathBuf::from("/i/a.txt")]
+    );
+    assert!(!debouncer.has_pending());
+
+    debouncer.observe(&[candidate("/i/b.txt", 1, 1)], 1000, 500);
+    assert!(debouncer.has_pending());
+    debouncer.observe(&[], 1100, 500);
+    assert!(!debouncer.has_pending());
+}
+
+#[test]
+fn inbox_candidates_skip_partial_files_and_subdirectories() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let inbox = vault_paths(&vault_root).inbox_dir;
+    std::fs::write(inbox.join("b.md"), b"b").expect("write b");
+    std::fs::write(inbox.join("a.txt"), b"a").expect("write a");
+    std::fs::write(inbox.join("c.txt.part"), b"c").expect("write partial");
+    std::fs::write(inbox.join(".hidden"), b"h").expect("write hidden");
+
+    let names: Vec<String> = inbox_candidates(&inbox)
+        .expect("candidates")
+        .into_iter()
+        .map(|c| c.path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, vec!["a.txt".to_string(), "b.md".to_string()]);
+}
+
+#[test]
+fn inbox_process_file_moves_successes_and_quarantines_failures() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let paths = vault_paths(&vault_root);
+    let conn = open_db(&paths.db).expect("open db");
+    let store = ObjectStore::new(paths.objects_dir.clone());
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &NullIndex,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+
+    let good = paths.inbox_dir.join("note.txt");
+    std::fs::write(&good, b"hello inbox").expect("write good");
+    let processed = inbox_process_file(&conn, &store, &services, &vault_root, &good, "notes", 5)
+        .expect("process good");
+    assert_eq!(processed.status, "processed");
+    let doc_id = processed.doc_id.clone().expect("doc id");
+    let expected = paths.inbox_processed_dir.join(format!(
+        "note__{}.txt",
+        doc_id.chars().skip(7).take(8).collect::<String>()
+    ));
+    assert_eq!(PathBuf::from(&processed.target_path), expected);
+    assert!(expected.exists());
+    assert!(!good.exists());
+
+    let bad = paths.inbox_dir.join("broken.txt");
+    std::fs::write(&bad, b"corrupt bytes").expect("write bad");
+    let failed = inbox_process_file(&conn, &store, &services, &vault_root, &bad, "notes", 6)
+        .expect("process bad");
+    assert_eq!(failed.status, "failed");
+    let failed_path = paths.inbox_failed_dir.join("broken__6.txt");
+    assert_eq!(PathBuf::from(&failed.target_path), failed_path);
+    assert!(failed_path.exists());
+    assert!(!bad.exists());
+
+    let sidecar: serde_json::Value = serde_json::from_slice(
+        &std::fs::read(paths.inbox_failed_dir.join("broken__6.txt.error.json"))
+            .expect("read sidecar"),
+    )
+    .expect("parse sidecar");
+    assert_eq!(sidecar["schema_version"], 1);
+    assert_eq!(sidecar["failed_at_ms"], 6);
+    assert_eq!(sidecar["error"]["code"], "KC_CANONICAL_EXTRACT_FAILED");
+}
+
+#[test]
+fn inbox_watch_poll_once_reports_files_removed_after_debounce_and_keeps_going() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let paths = vault_paths(&vault_root);
+    let conn = open_db(&paths.db).expect("open db");
+    let store = ObjectStore::new(paths.objects_dir.clone());
+    let chunking = default_chunking_config_v1();
+    let gone = paths.inbox_dir.join("b.txt");
+    let extractor = VanishingExtractor {
+        victim: gone.clone(),
+    };
+    let services = PipelineServices {
+        extractor: &extractor,
+        lexical: &NullIndex,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+    std::fs::write(paths.inbox_dir.join("a.txt"), b"alpha").expect("write a");
+    std::fs::write(&gone, b"beta").expect("write b");
+
+    let config = InboxWatchConfigV1 {
+        source_kind: "notes".to_string(),
+        debounce_ms: 0,
+        poll_interval_ms: 20,
+        backend: "poll".to_string(),
+        max_runtime_ms: None,
+    };
+    let mut debouncer = InboxDebouncer::new();
+    let outcomes = inbox_watch_poll_once(
+        &conn,
+        &store,
+        &services,
+        &vault_root,
+        &config,
+        &mut debouncer,
+        10,
+    )
+    .expect("poll survives a vanished file");
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].status, "processed");
+    assert_eq!(outcomes[1].status, "failed");
+    assert_eq!(PathBuf::from(&outcomes[1].target_path), gone);
+    let error = outcomes[1].error.as_ref().expect("move error");
+    assert_eq!(error.code, "KC_INBOX_MOVE_FAILED");
+    assert_eq!(error.details["cause"]["code"], "KC_INGEST_READ_FAILED");
+
+    std::fs::write(paths.inbox_dir.join("c.txt"), b"gamma").expect("write c");
+    let next = inbox_watch_poll_once(
+        &conn,
+        &store,
+        &services,
+        &vault_root,
+        &config,
+        &mut debouncer,
+        20,
+    )
+    .expect("next poll");
+    assert_eq!(next.len(), 1);
+    assert_eq!(next[0].status, "processed");
+}
+
+#[test]
+fn inbox_watch_run_polls_until_stop_request() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let paths = vault_paths(&vault_root);
+    let conn = open_db(&paths.db).expect("open db");
+    let store = ObjectStore::new(paths.objects_dir.clone());
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &NullIndex,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+    std::fs::write(paths.inbox_dir.join("a.txt"), b"alpha").expect("write a");
+
+    let config = InboxWatchConfigV1 {
+        source_kind: "notes".to_string(),
+        debounce_ms: 0,
+        poll_interval_ms: 20,
+        backend: "poll".to_string(),
+        max_runtime_ms: Some(10_000),
+    };
+    let stop = AtomicBool::new(false);
+    let vault_for_stop = vault_root.clone();
+    let stopper = std::thread::spawn(move || {
+        let processed_dir = vault_paths(&vault_for_stop).inbox_processed_dir;
+        for _ in 0..400 {
+            let moved = std::fs::read_dir(&processed_dir)
+                .map(|entries| entries.count())
+                .unwrap_or(0);
+            if moved > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        inbox_watch_request_stop(&vault_for_stop).expect("request stop");
+    });
+
+    let summary =
+        inbox_watch_run(&conn, &store, &services, &vault_root, &config, &stop).expect("watch run");
+    stopper.join().expect("stopper");
+    assert_eq!(summary.backend, "poll");
+    assert_eq!(summary.processed, 1);
+    assert_eq!(summary.failed, 0);
+    assert!(!paths.inbox_dir.join(".kc_watch_stop").exists());
+
+    let bad_backend = InboxWatchConfigV1 {
+        backend: "fanotify".to_string(),
+        ..config
+    };
+    let err = inbox_watch_run(&conn, &store, &services, &vault_root, &bad_backend, &stop)
+        .expect_err("unsupported backend");
+    assert_eq!(err.code, "KC_INBOX_WATCH_FAILED");
+}
@@ -0,0 +1,265 @@
+use kc_core::app_error::AppError;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::jobs::{
+    job_backoff_ms, job_cancel, job_claim_next, job_enqueue, job_fail, job_get,
+    job_report_progress, jobs_list, run_pending_jobs, JOB_KIND_INGEST_FILE,
+    JOB_KIND_INGEST_SCAN_FOLDER, JOB_KIND_PIPELINE_DOC, JOB_LEASE_MS,
+};
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::PipelineServices;
+
+mod common;
+
+use common::{NullIndex, PlainExtractor};
+
+#[test]
+fn jobs_claim_in_deterministic_order_and_survive_reopen() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let db_path = temp.path().join("db/knowledge.sqlite");
+    let conn = open_db(&db_path).expect("open db");
+
+    let first = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": "a" }),
+        3,
+        10,
+    )
+    .expect("enqueue first");
+    let second = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": "b" }),
+        3,
+        20,
+    )
+    .expect("enqueue second");
+    assert_eq!(first.state, "queued");
+    assert_ne!(first.job_id, second.job_id);
+    drop(conn);
+
+    let conn = open_db(&db_path).expect("reopen db");
+    let listed = jobs_list(&conn).expect("list");
+    assert_eq!(
+        listed.iter().map(|j| j.job_id.clone()).collect::<Vec<_>>(),
+        vec![first.job_id.clone(), second.job_id.clone()]
+    );
+
+    let claimed = job_claim_next(&conn, 30)
+        .expect("claim")
+        .expect("job available");
+    assert_eq!(claimed.job_id, first.job_id);
+    assert_eq!(claimed.state, "running");
+    assert_eq!(claimed.attempts, 1);
+    assert_eq!(claimed.lease_expires_at_ms, Some(30 + JOB_LEASE_MS));
+
+    let unknown = job_enqueue(&conn, "unknown.kind", &serde_json::json!({}), 1, 40)
+        .expect_err("unsupported kind");
+    assert_eq!(unknown.code, "KC_JOB_KIND_UNSUPPORTED");
+}
+
+#[test]
+fn jobs_retry_retryable_errors_with_backoff_until_max_attempts() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let conn = open_db(&temp.path().join("db/knowledge.sqlite")).expect("open db");
+    let job = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": "a" }),
+        2,
+        0,
+    )
+    .expect("enqueue");
+    let transient = AppError::new(
+        "KC_INGEST_FAILED",
+        "ingest",
+        "transient",
+        true,
+        serde_json::json!({}),
+    );
+
+    job_claim_next(&conn, 100).expect("claim").expect("job");
+    let retried = job_fail(&conn, &job.job_id, &transient, 100).expect("fail once");
+    assert_eq!(retried.state, "queued");
+    assert_eq!(retried.next_run_at_ms, 100 + job_backoff_ms(1));
+    assert_eq!(
+        retried.last_error.as_ref().map(|e| e.code.as_str()),
+        Some("KC_INGEST_FAILED")
+    );
+    assert!(job_claim_next(&conn, 100).expect("claim early").is_none());
+
+    job_claim_next(&conn, retried.next_run_at_ms)
+        .expect("claim retry")
+        .expect("job due");
+    let failed =
+        job_fail(&conn, &job.job_id, &transient, retried.next_run_at_ms).expect("fail twice");
+    assert_eq!(failed.state, "failed");
+    assert_eq!(failed.attempts, 2);
+
+    let permanent = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": "b" }),
+        5,
+        200,
+    )
+    .expect("enqueue permanent");
+    job_claim_next(&conn, 200).expect("claim").expect("job");
+    let failed = job_fail(
+        &conn,
+        &permanent.job_id,
+        &AppError::new("KC_X", "x", "permanent", false, serde_json::json!({})),
+        200,
+    )
+    .expect("fail permanent");
+    assert_eq!(failed.state, "failed");
+    assert_eq!(failed.attempts, 1);
+
+    assert_eq!(job_backoff_ms(1), 1000);
+    assert_eq!(job_backoff_ms(3), 4000);
+    assert_eq!(job_backoff_ms(30), 60_000);
+}
+
+#[test]
+fn jobs_cancel_stops_progress_and_expired_leases_resume() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let conn = open_db(&temp.path().join("db/knowledge.sqlite")).expect("open db");
+    let job = job_enqueue(
+        &conn,
+        JOB_KIND_PIPELINE_DOC,
+        &serde_json::json!({ "doc_id": "a" }),
+        3,
+        0,
+    )
+    .expect("enqueue");
+
+    job_claim_next(&conn, 10).expect("claim").expect("job");
+    assert!(job_report_progress(&conn, &job.job_id, 1, 4, 11).expect("progress"));
+    assert!(job_claim_next(&conn, 12).expect("claim held").is_none());
+
+    let resumed = job_claim_next(&conn, 11 + JOB_LEASE_MS)
+        .expect("claim after lease expiry")
+        .expect("job recovered");
+    assert_eq!(resumed.job_id, job.job_id);
+    assert_eq!(resumed.attempts, 2);
+    assert_eq!(resumed.progress_done, 1);
+    assert_eq!(resumed.progress_total, 4);
+
+    assert!(job_cancel(&conn, &job.job_id, 20).expect("cancel"));
+    assert!(!job_report_progress(&conn, &job.job_id, 2, 4, 21).expect("progress after cancel"));
+    assert!(!job_cancel(&conn, &job.job_id, 22).expect("cancel again"));
+    assert_eq!(job_get(&conn, &job.job_id).expect("get").state, "cancelled");
+
+    let missing = job_cancel(&conn, "job:missing", 23).expect_err("missing job");
+    assert_eq!(missing.code, "KC_JOB_NOT_FOUND");
+}
+
+#[test]
+fn run_pending_jobs_ingests_files_and_fails_missing_files_without_retry() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let conn = open_db(&temp.path().join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(temp.path().join("store/objects"));
+    let scan_root = temp.path().join("inbox");
+    std::fs::create_dir_all(&scan_root).expect("mkdir");
+    std::fs::write(scan_root.join("a.txt"), b"alpha").expect("write a");
+    std::fs::write(scan_root.join("b.txt"), b"beta").expect("write b");
+
+    let extractor = PlainExtractor;
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &extractor,
+        lexical: &NullIndex,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+
+    let scan = job_enqueue(
+        &conn,
+        JOB_KIND_INGEST_SCAN_FOLDER,
+        &serde_json::json!({
+            "scan_root": scan_root.to_string_lossy(),
+            "source_kind": "notes"
+        }),
+        3,
+        1,
+    )
+    .expect("enqueue scan");
+    let missing = job_enqueue(
+        &conn,
+        JOB_KIND_INGEST_FILE,
+        &serde_json::json!({
+            "file_path": temp.path().join("missing.txt").to_string_lossy(),
+            "source_kind": "notes"
+        }),
+        3,
+        2,
+    )
+    .expect("enqueue missing");
+
+    let finished = run_pending_jobs(&conn, &store, &services, 5, 10).expect("run jobs");
+    assert_eq!(finished.len(), 2);
+    assert_eq!(finished[0].job_id, scan.job_id);
+    assert_eq!(finished[0].state, "succeeded");
+    assert_eq!(finished[0].progress_done, 2);
+    assert_eq!(finished[0].progress_total, 2);
+    assert_eq!(finished[1].job_id, missing.job_id);
+    assert_eq!(finished[1].state, "failed");
+    assert_eq!(finished[1].attempts, 1);
+    let error = finished[1].last_error.as_ref().expect("missing file error");
+    assert_eq!(error.code, "KC_INGEST_FAILED");
+    assert!(!error.retryable);
+
+    let docs: i64 = conn
+        .query_row("SELECT COUNT(*) FROM docs", [], |row| row.get(0))
+        .expect("count docs");
+    assert_eq!(docs, 2);
+    let chunks: i64 = conn
+        .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))
+        .expect("count chunks");
+    assert_eq!(chunks, 2);
+
+    assert!(run_pending_jobs(&conn, &store, &services, 5, 10)
+        .expect("run again")
+        .is_empty());
+}
+
+#[test]
+fn ingest_file_jobs_take_the_effective_timestamp_from_the_file_mtime() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let conn = open_db(&temp.path().join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(temp.path().join("store/objects"));
+    let file = temp.path().join("note.txt");
+    std::fs::write(&file, b"dated").expect("write");
+    let mtime_ms = 1_600_000_000_000;
+    std::fs::File::options()
+        .write(true)
+        .open(&file)
+        .and_then(|f| {
+            f.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_millis(mtime_ms))
+        })
+        .expect("set mtime");
+
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &NullIndex,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+    job_enqueue(
+        &conn,
+        JOB_KIND_INGEST_FILE,
+        &serde_json::json!({ "file_path": file.to_string_lossy(), "source_kind": "notes" }),
+        3,
+        1,
+    )
+    .expect("enqueue");
+    let finished = run_pending_jobs(&conn, &store, &services, 5, 10).expect("run jobs");
+    assert_eq!(finished[0].state, "succeeded");
+
+    let effective_ts_ms: i64 = conn
+        .query_row("SELECT effective_ts_ms FROM docs", [], |row| row.get(0))
+        .expect("effective ts");
+    assert_eq!(effective_ts_ms, mtime_ms as i64);
+}
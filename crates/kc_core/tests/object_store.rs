@@ -58,4 +58,11 @@ fn object_store_encrypted_round_trip_requires_key() {
 
     let round_trip = encrypted_store.get_bytes(&hash).expect("decrypt with key");
     assert_eq!(round_trip, payload);
+
+    let enc = encrypted_store.encryption.as_ref().expect("context");
+    let sealed = enc.seal("trace:t1", payload).expect("seal");
+    assert!(sealed.starts_with(b"KCE1"));
+    assert_eq!(enc.open("trace:t1", &sealed).expect("open"), payload);
+    let err = enc.open("trace:t2", &sealed).expect_err("label mismatch");
+    assert_eq!(err.code, "KC_ENCRYPTION_KEY_INVALID");
 }
@@ -0,0 +1,125 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::services::{CanonicalTextArtifact, ExtractInput, ExtractService};
+use kc_core::types::{CanonicalHash, ObjectHash};
+
+mod common;
+
+use common::MemoryIndex;
+
+struct UppercaseExtractor;
+
+impl ExtractService for UppercaseExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        let canonical_bytes = String::from_utf8_lossy(input.bytes)
+            .to_uppercase()
+            .into_bytes();
+        let hash = blake3_hex_prefixed(&canonical_bytes);
+        Ok(CanonicalTextArtifact {
+            doc_id: input.doc_id.clone(),
+            canonical_bytes,
+            canonical_hash: CanonicalHash(hash.clone()),
+            canonical_object_hash: ObjectHash(hash),
+            extractor_name: "test.upper".to_string(),
+            extractor_version: "1".to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: "{}".to_string(),
+        })
+    }
+}
+
+#[test]
+fn pipeline_persists_canonical_chunks_and_index_rows_with_stage_events() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let conn = open_db(&temp.path().join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(temp.path().join("store/objects"));
+
+    let body = "alpha beta gamma\n".repeat(200);
+    let doc = ingest_bytes(
+        &conn,
+        &store,
+        IngestBytesReq {
+            bytes: body.as_bytes(),
+            mime: "text/markdown",
+            source_kind: "notes",
+            effective_ts_ms: 100,
+            source_path: Some("/tmp/notes.md"),
+            now_ms: 200,
+        },
+    )
+    .expect("ingest");
+
+    let extractor = UppercaseExtractor;
+    let lexical = MemoryIndex::default();
+    let vector = MemoryIndex::default();
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &extractor,
+        lexical: &lexical,
+        vector: &vector,
+        chunking: &chunking,
+    };
+
+    let out = run_doc_pipeline(&conn, &store, &services, &doc.doc_id, 300).expect("pipeline");
+    assert!(out.chunk_count > 1);
+    assert_eq!(out.stage_event_ids.len(), 4);
+
+    let stage_types: Vec<String> = conn
+        .prepare("SELECT type FROM events WHERE event_id > ?1 ORDER BY event_id ASC")
+        .expect("prepare")
+        .query_map([out.stage_event_ids[0] - 1], |row| row.get(0))
+        .expect("query")
+        .map(|x| x.expect("row"))
+        .collect();
+    assert_eq!(
+        stage_types,
+        vec![
+            "pipeline.extract",
+            "pipeline.canonical",
+            "pipeline.chunk",
+            "pipeline.index"
+        ]
+    );
+
+    let canonical_hash: String = conn
+        .query_row(
+            "SELECT canonical_hash FROM canonical_text WHERE doc_id=?1",
+            [&doc.doc_id.0],
+            |r| r.get(0),
+        )
+        .expect("canonical row");
+    assert_eq!(canonical_hash, out.canonical_hash.0);
+
+    let (chunk_rows, cfg_hash, source_kind): (i64, String, String) = conn
+        .query_row(
+            "SELECT COUNT(*), MIN(chunking_config_hash), MIN(source_kind) FROM chunks WHERE doc_id=?1",
+            [&doc.doc_id.0],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .expect("chunk rows");
+    assert_eq!(chunk_rows, out.chunk_count);
+    assert_eq!(cfg_hash, out.chunking_config_hash.0);
+    assert_eq!(source_kind, "notes");
+
+    let indexed = lexical.chunks_for(&doc.doc_id);
+    assert_eq!(indexed.len() as i64, out.chunk_count);
+    assert!(indexed[0].text.starts_with("ALPHA BETA GAMMA"));
+    assert_eq!(vector.chunks_for(&doc.doc_id).len() as i64, out.chunk_count);
+
+    let rerun = run_doc_pipeline(&conn, &store, &services, &doc.doc_id, 400).expect("rerun");
+    assert_eq!(rerun.chunk_count, out.chunk_count);
+    let chunk_rows_after: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM chunks WHERE doc_id=?1",
+            [&doc.doc_id.0],
+            |r| r.get(0),
+        )
+        .expect("chunk rows after rerun");
+    assert_eq!(chunk_rows_after, out.chunk_count);
+}
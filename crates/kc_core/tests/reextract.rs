@@ -0,0 +1,196 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::gc::gc_run;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::locator::{resolve_locator_strict, LocatorRange, LocatorV1};
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::reextract::reextract_stale_docs;
+use kc_core::services::{
+    CanonicalTextArtifact, ExtractInput, ExtractService, ExtractorIdentity, ToolchainIdentity,
+};
+use kc_core::types::{CanonicalHash, ObjectHash};
+use kc_core::vault::vault_init;
+
+mod common;
+
+use common::{MemoryIndex, NullIndex};
+
+struct VersionedExtractor {
+    version: &'static str,
+    pdfium_identity: &'static str,
+}
+
+impl VersionedExtractor {
+    fn identity(&self) -> ExtractorIdentity {
+        ExtractorIdentity {
+            extractor_name: "test.versioned".to_string(),
+            extractor_version: self.version.to_string(),
+            normalization_version: 1,
+            toolchain: ToolchainIdentity {
+                pdfium_identity: self.pdfium_identity.to_string(),
+                tesseract_identity: "tesseract:test".to_string(),
+            },
+        }
+    }
+}
+
+impl ExtractService for VersionedExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        let mut canonical_bytes = format!("v{} ", self.version).into_bytes();
+        canonical_bytes.extend_from_slice(input.bytes);
+        let hash = blake3_hex_prefixed(&canonical_bytes);
+        Ok(CanonicalTextArtifact {
+            doc_id: input.doc_id.clone(),
+            canonical_bytes,
+            canonical_hash: CanonicalHash(hash.clone()),
+            canonical_object_hash: ObjectHash(hash),
+            extractor_name: "test.versioned".to_string(),
+            extractor_version: self.version.to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: serde_json::json!({
+                "pdfium": { "identity": self.pdfium_identity },
+                "tesseract": { "identity": "tesseract:test" },
+            })
+            .to_string(),
+        })
+    }
+}
+
+#[test]
+fn reextract_rebuilds_stale_docs_and_keeps_old_locators_resolvable() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let chunking = default_chunking_config_v1();
+    let index = MemoryIndex::default();
+
+    let v1 = VersionedExtractor {
+        version: "1",
+        pdfium_identity: "pdfium:a",
+    };
+    let mut doc_ids = Vec::new();
+    for (bytes, now_ms) in [(&b"alpha text"[..], 10), (&b"beta text"[..], 11)] {
+        let doc = ingest_bytes(
+            &conn,
+            &store,
+            IngestBytesReq {
+                bytes,
+                mime: "text/plain",
+                source_kind: "notes",
+                effective_ts_ms: now_ms,
+                source_path: None,
+                now_ms,
+            },
+        )
+        .expect("ingest");
+        let services = PipelineServices {
+            extractor: &v1,
+            lexical: &index,
+            vector: &NullIndex,
+            chunking: &chunking,
+        };
+        run_doc_pipeline(&conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+        doc_ids.push(doc.doc_id);
+    }
+    let old_hash = CanonicalHash(blake3_hex_prefixed(b"v1 alpha text"));
+    let old_locator = LocatorV1 {
+        v: 1,
+        doc_id: doc_ids[0].clone(),
+        canonical_hash: old_hash.clone(),
+        range: LocatorRange { start: 3, end: 8 },
+        hints: None,
+    };
+
+    let v2 = VersionedExtractor {
+        version: "2",
+        pdfium_identity: "pdfium:b",
+    };
+    let services = PipelineServices {
+        extractor: &v2,
+        lexical: &index,
+        vector: &NullIndex,
+        chunking: &chunking,
+    };
+    let preview = reextract_stale_docs(&conn, &store, &services, &v2.identity(), true, None, 20)
+        .expect("dry run");
+    assert_eq!(preview.scanned_docs, 2);
+    assert_eq!(preview.docs.len(), 2);
+    let first = preview
+        .docs
+        .iter()
+        .find(|d| d.doc_id == doc_ids[0].0)
+        .expect("first doc selected");
+    assert_eq!(
+        first.stale_reasons,
+        vec![
+            "extractor_version".to_string(),
+            "pdfium_identity".to_string()
+        ]
+    );
+    assert_eq!(first.previous_canonical_hash, old_hash.0);
+    assert!(first.canonical_hash.is_none());
+
+    index.take_rebuilds();
+    let report = reextract_stale_docs(&conn, &store, &services, &v2.identity(), false, Some(1), 21)
+        .expect("reextract");
+    assert_eq!(report.docs.len(), 1);
+    let new_hash = blake3_hex_prefixed(
+        format!(
+            "v2 {}",
+            if report.docs[0].doc_id == doc_ids[0].0 {
+                "alpha text"
+            } else {
+                "beta text"
+            }
+        )
+        .as_bytes(),
+    );
+    assert_eq!(
+        report.docs[0].canonical_hash.as_deref(),
+        Some(new_hash.as_str())
+    );
+    assert_eq!(report.docs[0].chunk_count, Some(1));
+    let rebuilt = index.take_rebuilds();
+    assert_eq!(rebuilt.len(), 1);
+    assert!(rebuilt[0].1[0].starts_with("v2 "));
+
+    let rest = reextract_stale_docs(&conn, &store, &services, &v2.identity(), false, None, 22)
+        .expect("reextract rest");
+    assert_eq!(rest.docs.len(), 1);
+    let after = reextract_stale_docs(&conn, &store, &services, &v2.identity(), true, None, 23)
+        .expect("nothing stale");
+    assert!(after.docs.is_empty());
+
+    assert_eq!(
+        resolve_locator_strict(&conn, &store, &old_locator).expect("old locator"),
+        "alpha"
+    );
+    let new_locator = LocatorV1 {
+        canonical_hash: CanonicalHash(blake3_hex_prefixed(b"v2 alpha text")),
+        ..old_locator.clone()
+    };
+    assert_eq!(
+        resolve_locator_strict(&conn, &store, &new_locator).expect("new locator"),
+        "alpha"
+    );
+    let unknown = LocatorV1 {
+        canonical_hash: CanonicalHash(blake3_hex_prefixed(b"never extracted")),
+        ..old_locator
+    };
+    assert_eq!(
+        resolve_locator_strict(&conn, &store, &unknown)
+            .expect_err("unknown hash")
+            .code,
+        "KC_LOCATOR_CANONICAL_HASH_MISMATCH"
+    );
+
+    let gc = gc_run(&conn, &vault_root, true, 30).expect("gc dry run");
+    assert!(gc.unreachable_object_hashes.is_empty());
+    assert!(gc.orphan_files.is_empty());
+}
@@ -0,0 +1,96 @@
+use kc_core::app_error::AppResult;
+use kc_core::rerank::{
+    rerank_candidates, CrossEncoder, CrossEncoderReranker, LexicalOverlapReranker, RerankInput,
+    Reranker,
+};
+use kc_core::types::ChunkId;
+
+fn inputs(texts: &[&str]) -> Vec<RerankInput> {
+    texts
+        .iter()
+        .enumerate()
+        .map(|(idx, text)| RerankInput {
+            chunk_id: ChunkId(format!("c{}", idx + 1)),
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn lexical_overlap_scores_term_coverage_and_adjacent_pairs() {
+    let candidates = inputs(&[
+        "Keys are rotated yearly.",
+        "Rotate signing keys every quarter.",
+        "The signing keys rotate on schedule.",
+        "Unrelated text.",
+    ]);
+    let reranked = rerank_candidates(&LexicalOverlapReranker, "rotate signing keys", &candidates)
+        .expect("rerank");
+
+    let order: Vec<(&str, usize)> = reranked
+        .iter()
+        .map(|r| (r.chunk_id.as_str(), r.merged_rank))
+        .collect();
+    assert_eq!(order, vec![("c2", 2), ("c3", 3), ("c1", 1), ("c4", 4)]);
+    assert_eq!(reranked[0].score, 1.1);
+    assert_eq!(
+        reranked[0].reason,
+        "matched 3/3 terms [keys, rotate, signing]; 2/2 adjacent pairs"
+    );
+    assert_eq!(reranked[1].score, 1.05);
+    assert_eq!(
+        reranked[2].reason,
+        "matched 1/3 terms [keys]; 0/2 adjacent pairs"
+    );
+    assert_eq!(reranked[3].score, 0.0);
+
+    let empty = rerank_candidates(&LexicalOverlapReranker, "  ", &candidates).expect("empty");
+    assert!(empty
+        .iter()
+        .all(|r| r.score == 0.0 && r.reason == "no query terms"));
+    let merged_order: Vec<usize> = empty.iter().map(|r| r.merged_rank).collect();
+    assert_eq!(merged_order, vec![1, 2, 3, 4]);
+}
+
+struct LengthModel {
+    scores: Option<Vec<f64>>,
+}
+
+impl CrossEncoder for LengthModel {
+    fn model_id(&self) -> String {
+        "test/length".to_string()
+    }
+
+    fn score_pairs(&self, _query: &str, passages: &[&str]) -> AppResult<Vec<f64>> {
+        Ok(match &self.scores {
+            Some(scores) => scores.clone(),
+            None => passages.iter().map(|p| p.len() as f64).collect(),
+        })
+    }
+}
+
+#[test]
+fn cross_encoder_reranker_uses_model_scores_and_rejects_malformed_output() {
+    let candidates = inputs(&["short", "much longer passage", "mid length"]);
+    let reranker = CrossEncoderReranker {
+        model: LengthModel { scores: None },
+    };
+    assert_eq!(reranker.reranker_id(), "cross_encoder:test/length");
+    let reranked = rerank_candidates(&reranker, "q", &candidates).expect("rerank");
+    let order: Vec<&str> = reranked.iter().map(|r| r.chunk_id.as_str()).collect();
+    assert_eq!(order, vec!["c2", "c3", "c1"]);
+    assert_eq!(
+        reranked[0].reason,
+        "cross-encoder relevance from test/length"
+    );
+
+    for scores in [vec![1.0], vec![1.0, f64::NAN, 0.0]] {
+        let bad = CrossEncoderReranker {
+            model: LengthModel {
+                scores: Some(scores),
+            },
+        };
+        let err = rerank_candidates(&bad, "q", &candidates).expect_err("malformed");
+        assert_eq!(err.code, "KC_RETRIEVAL_RERANK_FAILED");
+    }
+}
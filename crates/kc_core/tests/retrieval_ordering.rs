@@ -1,6 +1,11 @@
+use kc_core::diversify::DiversifyConfigV1;
 use kc_core::index_traits::{LexicalCandidate, VectorCandidate};
-use kc_core::retrieval::{merge_candidates, RecencyConfigV1, RetrievalConfigV1};
+use kc_core::retrieval::{
+    default_source_priors, merge_candidates, retrieval_config_for_vault,
+    retrieval_profile_for_vault, FusionModeV1, RecencyConfigV1, RetrievalConfigV1,
+};
 use kc_core::types::{ChunkId, DocId};
+use kc_core::vault::vault_init;
 
 #[test]
 fn retrieval_ordering_is_deterministic_with_tiebreaks() {
@@ -8,10 +13,12 @@ fn retrieval_ordering_is_deterministic_with_tiebreaks() {
         LexicalCandidate {
             chunk_id: ChunkId("c1".to_string()),
             rank: 1,
+            bm25: None,
         },
         LexicalCandidate {
             chunk_id: ChunkId("c2".to_string()),
             rank: 2,
+            bm25: None,
         },
     ];
 
@@ -35,6 +42,10 @@ fn retrieval_ordering_is_deterministic_with_tiebreaks() {
             window_days: 30,
             max_boost: 0.03,
         },
+        fusion: FusionModeV1::Rrf,
+        source_priors: default_source_priors(),
+        default_prior: 1.0,
+        diversify: DiversifyConfigV1::default(),
     };
 
     let hits = merge_candidates(
@@ -63,6 +74,7 @@ fn retrieval_recency_changes_order_when_enabled() {
     let lexical = vec![LexicalCandidate {
         chunk_id: ChunkId("c_old".to_string()),
         rank: 1,
+        bm25: None,
     }];
 
     let vector = vec![VectorCandidate {
@@ -79,6 +91,10 @@ fn retrieval_recency_changes_order_when_enabled() {
             window_days: 30,
             max_boost: 0.03,
         },
+        fusion: FusionModeV1::Rrf,
+        source_priors: default_source_priors(),
+        default_prior: 1.0,
+        diversify: DiversifyConfigV1::default(),
     };
 
     let now_ms = 1_700_000_000_000i64;
@@ -109,3 +125,160 @@ fn retrieval_recency_changes_order_when_enabled() {
 
     assert_eq!(hits[0].chunk_id.0, "c_new");
 }
+
+#[test]
+fn retrieval_score_fusion_scales_lexical_contribution_by_bm25() {
+    let lexical = vec![
+        LexicalCandidate {
+            chunk_id: ChunkId("c1".to_string()),
+            rank: 1,
+            bm25: Some(4.0),
+        },
+        LexicalCandidate {
+            chunk_id: ChunkId("c2".to_string()),
+            rank: 2,
+            bm25: Some(1.0),
+        },
+        LexicalCandidate {
+            chunk_id: ChunkId("c3".to_string()),
+            rank: 3,
+            bm25: None,
+        },
+    ];
+    let mut cfg = RetrievalConfigV1 {
+        rrf_k: 60,
+        w_lex: 1.0,
+        w_vec: 1.0,
+        recency: RecencyConfigV1 {
+            enabled: false,
+            window_days: 30,
+            max_boost: 0.03,
+        },
+        fusion: FusionModeV1::Rrf,
+        source_priors: default_source_priors(),
+        default_prior: 1.0,
+        diversify: DiversifyConfigV1::default(),
+    };
+    let merge = |cfg: &RetrievalConfigV1| {
+        merge_candidates(
+            &lexical,
+            &[],
+            |chunk_id| Ok((DocId(chunk_id.0.clone()), 0, "other".to_string(), 0)),
+            cfg,
+            0,
+        )
+        .expect("merge")
+    };
+
+    let by_rank = merge(&cfg);
+    assert_eq!(by_rank[1].chunk_id.0, "c2");
+    assert!((by_rank[1].final_score - 1.0 / 62.0).abs() < 1e-9);
+
+    cfg.fusion = FusionModeV1::Score;
+    let by_score = merge(&cfg);
+    let ids: Vec<&str> = by_score.iter().map(|h| h.chunk_id.0.as_str()).collect();
+    assert_eq!(ids, vec!["c1", "c3", "c2"]);
+    assert!((by_score[0].final_score - 1.0 / 61.0).abs() < 1e-9);
+    assert!((by_score[1].final_score - 1.0 / 63.0).abs() < 1e-9);
+    assert!((by_score[2].final_score - 0.25 / 61.0).abs() < 1e-9);
+    assert_eq!(by_score[0].lexical_score, Some(4.0));
+}
+
+#[test]
+fn retrieval_profiles_resolve_by_name_and_priors_change_order() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let mut vault = vault_init(&temp.path().join("vault"), "demo", 1).expect("vault init");
+
+    let default = retrieval_profile_for_vault(&vault, None).expect("default profile");
+    assert_eq!(default.name, "default");
+    assert_eq!(
+        default.config_hash,
+        retrieval_profile_for_vault(&vault, Some("default"))
+            .expect("default by name")
+            .config_hash
+    );
+
+    let mut inbox_first = retrieval_config_for_vault(&vault);
+    inbox_first.source_priors.insert("inbox".to_string(), 1.15);
+    inbox_first
+        .source_priors
+        .insert("manuals".to_string(), 0.95);
+    vault
+        .defaults
+        .retrieval_profiles
+        .insert("inbox_first".to_string(), inbox_first);
+
+    let selected = retrieval_profile_for_vault(&vault, Some("inbox_first")).expect("profile");
+    assert_eq!(selected.name, "inbox_first");
+    assert_ne!(selected.config_hash, default.config_hash);
+
+    vault.defaults.retrieval_profile = Some("inbox_first".to_string());
+    assert_eq!(
+        retrieval_profile_for_vault(&vault, None)
+            .expect("vault default profile")
+            .config_hash,
+        selected.config_hash
+    );
+
+    let lexical = vec![
+        LexicalCandidate {
+            chunk_id: ChunkId("c_manual".to_string()),
+            rank: 1,
+            bm25: None,
+        },
+        LexicalCandidate {
+            chunk_id: ChunkId("c_inbox".to_string()),
+            rank: 2,
+            bm25: None,
+        },
+    ];
+    let merge = |cfg: &RetrievalConfigV1| {
+        merge_candidates(
+            &lexical,
+            &[],
+            |chunk_id| {
+                let kind = if chunk_id.0 == "c_manual" {
+                    "manuals"
+                } else {
+                    "inbox"
+                };
+                Ok((DocId(chunk_id.0.clone()), 0, kind.to_string(), 0))
+            },
+            cfg,
+            0,
+        )
+        .expect("merge")
+    };
+    assert_eq!(merge(&default.config)[0].chunk_id.0, "c_manual");
+    assert_eq!(merge(&selected.config)[0].chunk_id.0, "c_inbox");
+}
+
+#[test]
+fn retrieval_profiles_reject_unknown_names_and_out_of_range_priors() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let mut vault = vault_init(&temp.path().join("vault"), "demo", 1).expect("vault init");
+
+    let err = retrieval_profile_for_vault(&vault, Some("missing")).expect_err("unknown profile");
+    assert_eq!(err.code, "KC_RETRIEVAL_PROFILE_NOT_FOUND");
+    assert_eq!(err.details["available"], serde_json::json!(["default"]));
+
+    let mut loud = retrieval_config_for_vault(&vault);
+    loud.source_priors.insert("notes".to_string(), 3.0);
+    vault
+        .defaults
+        .retrieval_profiles
+        .insert("loud".to_string(), loud);
+    let err = retrieval_profile_for_vault(&vault, Some("loud")).expect_err("prior out of range");
+    assert_eq!(err.code, "KC_RETRIEVAL_PRIOR_OUT_OF_RANGE");
+    assert_eq!(err.details["source_kind"], "notes");
+
+    let mut broken = retrieval_config_for_vault(&vault);
+    broken.rrf_k = 0;
+    vault
+        .defaults
+        .retrieval_profiles
+        .insert("broken".to_string(), broken);
+    let err = retrieval_profile_for_vault(&vault, Some("broken")).expect_err("invalid rrf_k");
+    assert_eq!(err.code, "KC_RETRIEVAL_PROFILE_INVALID");
+    assert_eq!(err.details["field"], "rrf_k");
+}
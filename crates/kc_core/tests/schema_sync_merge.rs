@@ -27,7 +27,11 @@ fn sync_merge_preview_schema() -> serde_json::Value {
           "type": "array",
           "items": {
             "type": "string",
-            "enum": ["object_hash_overlap", "lineage_overlay_overlap"]
+            "enum": [
+              "doc_tombstone_overlap",
+              "object_hash_overlap",
+              "lineage_overlay_overlap"
+            ]
           }
         }
       },
@@ -43,6 +47,10 @@ fn sync_merge_preview_schema() -> serde_json::Value {
             "lineage_overlay_ids": {
               "type": "array",
               "items": { "type": "string", "minLength": 1 }
+            },
+            "doc_tombstone_ids": {
+              "type": "array",
+              "items": { "type": "string", "pattern": "^blake3:[0-9a-f]{64}$" }
             }
           },
           "additionalProperties": false
@@ -81,6 +89,7 @@ fn sync_merge_preview_schema_v2() -> serde_json::Value {
           "items": {
             "type": "string",
             "enum": [
+              "doc_tombstone_overlap",
               "object_hash_overlap",
               "lineage_overlay_overlap",
               "trust_chain_mismatch",
@@ -105,6 +114,10 @@ fn sync_merge_preview_schema_v2() -> serde_json::Value {
             "lineage_overlay_ids": {
               "type": "array",
               "items": { "type": "string", "minLength": 1 }
+            },
+            "doc_tombstone_ids": {
+              "type": "array",
+              "items": { "type": "string", "pattern": "^blake3:[0-9a-f]{64}$" }
             }
           },
           "additionalProperties": false
@@ -146,6 +159,7 @@ fn sync_merge_preview_schema_v3() -> serde_json::Value {
               "safe_disjoint",
               "unsafe_overlap_object",
               "unsafe_overlay_overlap",
+              "unsafe_tombstone_overlap",
               "unsafe_trust",
               "unsafe_lock",
               "unsafe_rbac"
@@ -169,6 +183,10 @@ fn sync_merge_preview_schema_v3() -> serde_json::Value {
             "lineage_overlay_ids": {
               "type": "array",
               "items": { "type": "string", "minLength": 1 }
+            },
+            "doc_tombstone_ids": {
+              "type": "array",
+              "items": { "type": "string", "pattern": "^blake3:[0-9a-f]{64}$" }
             }
           },
           "additionalProperties": false
@@ -210,6 +228,7 @@ fn sync_merge_preview_schema_v4() -> serde_json::Value {
               "safe_disjoint_v4",
               "unsafe_object_overlap_v4",
               "unsafe_overlay_overlap_v4",
+              "unsafe_tombstone_overlap_v4",
               "unsafe_trust_chain_v4",
               "unsafe_lock_scope_v4",
               "unsafe_rbac_v4"
@@ -233,6 +252,10 @@ fn sync_merge_preview_schema_v4() -> serde_json::Value {
             "lineage_overlay_ids": {
               "type": "array",
               "items": { "type": "string", "minLength": 1 }
+            },
+            "doc_tombstone_ids": {
+              "type": "array",
+              "items": { "type": "string", "pattern": "^blake3:[0-9a-f]{64}$" }
             }
           },
           "additionalProperties": false
@@ -271,6 +294,39 @@ fn schema_sync_merge_preview_accepts_valid_payload() {
     assert!(schema.is_valid(&payload));
 }
 
+#[test]
+fn schema_sync_merge_preview_accepts_tombstone_overlap() {
+    let schema = validator_for(&sync_merge_preview_schema()).expect("compile sync merge schema");
+    let payload = serde_json::json!({
+      "schema_version": 1,
+      "merge_policy": "conservative_v1",
+      "safe": false,
+      "generated_at_ms": 123,
+      "local": {
+        "object_hashes": [],
+        "lineage_overlay_ids": [],
+        "doc_tombstone_ids": [
+          "blake3:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+        ]
+      },
+      "remote": {
+        "object_hashes": [
+          "blake3:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+        ],
+        "lineage_overlay_ids": []
+      },
+      "overlap": {
+        "object_hashes": [],
+        "lineage_overlay_ids": [],
+        "doc_tombstone_ids": [
+          "blake3:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+        ]
+      },
+      "reasons": ["doc_tombstone_overlap"]
+    });
+    assert!(schema.is_valid(&payload));
+}
+
 #[test]
 fn schema_sync_merge_preview_rejects_unknown_reason() {
     let schema = validator_for(&sync_merge_preview_schema()).expect("compile sync merge schema");
@@ -0,0 +1,493 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::diversify::DiversifyConfigV1;
+use kc_core::index_traits::{
+    IndexChunk, LexicalCandidate, LexicalIndex, VectorCandidate, VectorIndex,
+};
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::locator::resolve_locator_strict;
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::retrieval::{
+    default_source_priors, retrieval_config_for_vault, FusionModeV1, RecencyConfigV1,
+    RetrievalConfigV1,
+};
+use kc_core::search::{search_hybrid, FacetCountV1, SearchFilterV1, SearchReq};
+use kc_core::types::{ChunkId, DocId};
+use kc_core::vault::{vault_init, vault_open};
+use std::sync::Mutex;
+
+mod common;
+
+use common::PlainExtractor;
+
+// Lexical order is ingest order; vector order is the reverse plus an id that no longer
+// exists in `chunks`, as a stale vector row would produce.
+#[derive(Default)]
+struct ScriptedIndex {
+    chunks: Mutex<Vec<IndexChunk>>,
+}
+
+impl ScriptedIndex {
+    fn matching(&self, query: &str, doc_ids: Option<&[DocId]>) -> Vec<ChunkId> {
+        self.chunks
+            .lock()
+            .expect("lock")
+            .iter()
+            .filter(|c| c.text.contains(query))
+            .filter(|c| doc_ids.is_none_or(|ids| ids.contains(&c.doc_id)))
+            .map(|c| c.chunk_id.clone())
+            .collect()
+    }
+
+    fn lexical(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: Option<&[DocId]>,
+    ) -> Vec<LexicalCandidate> {
+        self.matching(query, doc_ids)
+            .into_iter()
+            .take(limit)
+            .enumerate()
+            .map(|(idx, chunk_id)| LexicalCandidate {
+                chunk_id,
+                rank: idx as i64 + 1,
+                bm25: None,
+            })
+            .collect()
+    }
+
+    fn vector(&self, query: &str, limit: usize, doc_ids: Option<&[DocId]>) -> Vec<VectorCandidate> {
+        let mut ids = vec![ChunkId("stale-chunk".to_string())];
+        ids.extend(self.matching(query, doc_ids).into_iter().rev());
+        ids.into_iter()
+            .take(limit)
+            .enumerate()
+            .map(|(idx, chunk_id)| VectorCandidate {
+                chunk_id,
+                rank: idx as i64 + 1,
+            })
+            .collect()
+    }
+}
+
+impl LexicalIndex for ScriptedIndex {
+    fn rebuild_for_doc(&self, _doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        self.chunks.lock().expect("lock").extend_from_slice(chunks);
+        Ok(())
+    }
+
+    fn delete_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn query(&self, query: &str, limit: usize) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(self.lexical(query, limit, None))
+    }
+
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<LexicalCandidate>> {
+        Ok(self.lexical(query, limit, Some(doc_ids)))
+    }
+}
+
+impl VectorIndex for ScriptedIndex {
+    fn rebuild_for_doc(&self, _doc_id: &DocId, _chunks: &[IndexChunk]) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn delete_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
+        Ok(())
+    }
+
+    fn query(&self, query: &str, limit: usize) -> AppResult<Vec<VectorCandidate>> {
+        Ok(self.vector(query, limit, None))
+    }
+
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>> {
+        Ok(self.vector(query, limit, Some(doc_ids)))
+    }
+}
+
+#[test]
+fn search_merges_lexical_and_vector_ranks_into_chunk_hits_with_locators() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let chunking = default_chunking_config_v1();
+    let index = ScriptedIndex::default();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &index,
+        vector: &index,
+        chunking: &chunking,
+    };
+
+    let mut doc_ids = Vec::new();
+    for (bytes, source_kind, now_ms) in [
+        (&b"alpha in the manual"[..], "manuals", 10),
+        (&b"alpha in a note"[..], "notes", 11),
+        (&b"unrelated"[..], "notes", 12),
+    ] {
+        let doc = ingest_bytes(
+            &conn,
+            &store,
+            IngestBytesReq {
+                bytes,
+                mime: "text/plain",
+                source_kind,
+                effective_ts_ms: now_ms,
+                source_path: None,
+                now_ms,
+            },
+        )
+        .expect("ingest");
+        run_doc_pipeline(&conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+        doc_ids.push(doc.doc_id);
+    }
+
+    let vault = vault_open(&vault_root).expect("vault open");
+    let cfg = retrieval_config_for_vault(&vault);
+    let hits = search_hybrid(
+        &conn,
+        &store,
+        &index,
+        &index,
+        &cfg,
+        SearchReq {
+            query: "alpha",
+            limit: 10,
+            include_superseded: false,
+            filter: &SearchFilterV1::default(),
+            profile: None,
+            now_ms: 20,
+        },
+    )
+    .expect("search")
+    .hits;
+
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].doc_id, doc_ids[0].0);
+    assert_eq!(hits[0].lexical_rank, Some(1));
+    assert_eq!(hits[0].vector_rank, Some(3));
+    assert_eq!(hits[1].doc_id, doc_ids[1].0);
+    assert_eq!(hits[1].lexical_rank, Some(2));
+    assert_eq!(hits[1].vector_rank, Some(2));
+    let expected = ((1.0 / 61.0) + (1.0 / 63.0)) * 1.10;
+    assert!((hits[0].final_score - expected).abs() < 1e-9);
+    assert_eq!(hits[0].snippet, "alpha in the manual");
+    assert_eq!(
+        resolve_locator_strict(&conn, &store, &hits[1].locator).expect("resolve"),
+        "alpha in a note"
+    );
+
+    let lexical_only = RetrievalConfigV1 {
+        rrf_k: 60,
+        w_lex: 1.0,
+        w_vec: 0.0,
+        recency: RecencyConfigV1 {
+            enabled: false,
+            window_days: 365,
+            max_boost: 0.2,
+        },
+        fusion: FusionModeV1::Rrf,
+        source_priors: default_source_priors(),
+        default_prior: 1.0,
+        diversify: DiversifyConfigV1::default(),
+    };
+    let top = search_hybrid(
+        &conn,
+        &store,
+        &index,
+        &index,
+        &lexical_only,
+        SearchReq {
+            query: "alpha",
+            limit: 1,
+            include_superseded: false,
+            filter: &SearchFilterV1::default(),
+            profile: None,
+            now_ms: 20,
+        },
+    )
+    .expect("search limit")
+    .hits;
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].doc_id, doc_ids[0].0);
+
+    let empty = search_hybrid(
+        &conn,
+        &store,
+        &index,
+        &index,
+        &cfg,
+        SearchReq {
+            query: "   ",
+            limit: 10,
+            include_superseded: false,
+            filter: &SearchFilterV1::default(),
+            profile: None,
+            now_ms: 20,
+        },
+    )
+    .expect("empty query")
+    .hits;
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn search_filters_push_doc_scope_into_indexes_and_report_facets() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let chunking = default_chunking_config_v1();
+    let index = ScriptedIndex::default();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &index,
+        vector: &index,
+        chunking: &chunking,
+    };
+
+    let mut doc_ids = Vec::new();
+    for (bytes, source_kind, mime, effective_ts_ms, path) in [
+        (
+            &b"alpha runbook"[..],
+            "manuals",
+            "text/markdown",
+            1_686_000_000_000,
+            "/vault/runbooks/a.md",
+        ),
+        (
+            &b"alpha note"[..],
+            "notes",
+            "text/plain",
+            1_710_000_000_000,
+            "/vault/notes/b.txt",
+        ),
+        (
+            &b"alpha second note"[..],
+            "notes",
+            "text/markdown",
+            1_725_000_000_000,
+            "/vault/notes/c.md",
+        ),
+    ] {
+        let doc = ingest_bytes(
+            &conn,
+            &store,
+            IngestBytesReq {
+                bytes,
+                mime,
+                source_kind,
+                effective_ts_ms,
+                source_path: Some(path),
+                now_ms: 10,
+            },
+        )
+        .expect("ingest");
+        run_doc_pipeline(&conn, &store, &services, &doc.doc_id, 10).expect("pipeline");
+        doc_ids.push(doc.doc_id.0);
+    }
+
+    let vault = vault_open(&vault_root).expect("vault open");
+    let cfg = retrieval_config_for_vault(&vault);
+    let search = |filter: &SearchFilterV1, limit| {
+        search_hybrid(
+            &conn,
+            &store,
+            &index,
+            &index,
+            &cfg,
+            SearchReq {
+                query: "alpha",
+                limit,
+                include_superseded: false,
+                filter,
+                profile: None,
+                now_ms: 20,
+            },
+        )
+        .expect("search")
+    };
+    let hit_docs = |filter: &SearchFilterV1| {
+        let mut docs: Vec<String> = search(filter, 10)
+            .hits
+            .into_iter()
+            .map(|h| h.doc_id)
+            .collect();
+        docs.sort();
+        docs
+    };
+    let sorted = |idx: &[usize]| {
+        let mut docs: Vec<String> = idx.iter().map(|i| doc_ids[*i].clone()).collect();
+        docs.sort();
+        docs
+    };
+    let facet = |value: &str, count| FacetCountV1 {
+        value: value.to_string(),
+        count,
+    };
+
+    let all = search(&SearchFilterV1::default(), 1);
+    assert_eq!(all.hits.len(), 1);
+    assert_eq!(
+        all.facets.source_kind,
+        vec![facet("manuals", 1), facet("notes", 2)]
+    );
+    assert_eq!(
+        all.facets.mime,
+        vec![facet("text/markdown", 2), facet("text/plain", 1)]
+    );
+    assert_eq!(all.facets.year, vec![facet("2023", 1), facet("2024", 2)]);
+
+    let notes_markdown = SearchFilterV1 {
+        source_kinds: vec!["notes".to_string()],
+        mimes: vec!["text/markdown".to_string()],
+        ..SearchFilterV1::default()
+    };
+    let filtered = search(&notes_markdown, 1);
+    assert_eq!(filtered.hits[0].doc_id, doc_ids[2]);
+    assert_eq!(filtered.hits[0].lexical_rank, Some(1));
+    assert_eq!(filtered.facets.source_kind, vec![facet("notes", 1)]);
+
+    let since_2024 = SearchFilterV1 {
+        effective_ts_from_ms: Some(1_704_067_200_000),
+        ..SearchFilterV1::default()
+    };
+    assert_eq!(hit_docs(&since_2024), sorted(&[1, 2]));
+
+    let notes_before = SearchFilterV1 {
+        source_path_prefix: Some("/vault/notes/".to_string()),
+        effective_ts_to_ms: Some(1_720_000_000_000),
+        ..SearchFilterV1::default()
+    };
+    assert_eq!(hit_docs(&notes_before), sorted(&[1]));
+
+    let by_extractor = |name: &str| SearchFilterV1 {
+        extractor_name: Some(name.to_string()),
+        ..SearchFilterV1::default()
+    };
+    assert_eq!(hit_docs(&by_extractor("test.plain")), sorted(&[0, 1, 2]));
+    let none = search(&by_extractor("other"), 10);
+    assert!(none.hits.is_empty());
+    assert!(none.facets.source_kind.is_empty());
+}
+
+#[test]
+fn search_collapses_overlapping_windows_and_drops_near_duplicate_docs() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let mut chunking = default_chunking_config_v1();
+    chunking.pdf.window_chars = 24;
+    chunking.pdf.overlap_chars = 8;
+    let index = ScriptedIndex::default();
+    let services = PipelineServices {
+        extractor: &PlainExtractor,
+        lexical: &index,
+        vector: &index,
+        chunking: &chunking,
+    };
+
+    for (bytes, mime, source_path, now_ms) in [
+        (
+            &b"alpha alpha alpha alpha alpha alpha alpha alpha"[..],
+            "application/pdf",
+            "/pdf/windows.pdf",
+            10,
+        ),
+        (
+            &b"the alpha rotation guide for keys"[..],
+            "text/plain",
+            "/a/guide.txt",
+            11,
+        ),
+        (
+            &b"The alpha rotation guide, for keys."[..],
+            "text/plain",
+            "/b/guide.txt",
+            12,
+        ),
+    ] {
+        let doc = ingest_bytes(
+            &conn,
+            &store,
+            IngestBytesReq {
+                bytes,
+                mime,
+                source_kind: "notes",
+                effective_ts_ms: now_ms,
+                source_path: Some(source_path),
+                now_ms,
+            },
+        )
+        .expect("ingest");
+        run_doc_pipeline(&conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+    }
+
+    let vault = vault_open(&vault_root).expect("vault open");
+    let mut cfg = retrieval_config_for_vault(&vault);
+    let search = |cfg: &RetrievalConfigV1| {
+        search_hybrid(
+            &conn,
+            &store,
+            &index,
+            &index,
+            cfg,
+            SearchReq {
+                query: "alpha",
+                limit: 10,
+                include_superseded: false,
+                filter: &SearchFilterV1::default(),
+                profile: None,
+                now_ms: 20,
+            },
+        )
+        .expect("search")
+    };
+
+    // Windows [0,24), [16,40) and [32,47): the second folds into the first, the third only
+    // overlaps the folded window and stays a hit of its own.
+    let result = search(&cfg);
+    assert_eq!(result.hits.len(), 3);
+    let pdf = result
+        .hits
+        .iter()
+        .find(|hit| hit.collapsed_chunk_ids.len() == 1)
+        .expect("collapsed pdf hit");
+    assert_eq!(pdf.locator.range.start, 0);
+    assert_eq!(pdf.locator.range.end, 40);
+    assert!(result
+        .hits
+        .iter()
+        .any(|hit| hit.locator.range.start == 32 && hit.collapsed_chunk_ids.is_empty()));
+    assert_eq!(result.diversify.collapsed.len(), 1);
+    assert_eq!(result.diversify.near_duplicates.len(), 1);
+    assert_eq!(result.diversify.near_duplicates[0].distance, 0);
+
+    cfg.diversify.enabled = false;
+    let plain = search(&cfg);
+    assert_eq!(plain.hits.len(), 5);
+    assert!(plain
+        .hits
+        .iter()
+        .all(|hit| hit.collapsed_chunk_ids.is_empty()));
+}
@@ -1,8 +1,9 @@
 use kc_core::db::open_db;
 use kc_core::object_store::ObjectStore;
 use kc_core::sync::{
-    sync_merge_preview_target, sync_pull, sync_pull_target, sync_pull_target_with_mode, sync_push,
-    sync_push_target, sync_status, sync_status_target, SyncHeadV1,
+    sync_local_write_lock, sync_merge_preview_target, sync_pull, sync_pull_target,
+    sync_pull_target_with_mode, sync_push, sync_push_target, sync_status, sync_status_target,
+    SyncHeadV1,
 };
 use kc_core::trust::{trust_device_init, trust_device_verify};
 use kc_core::trust_identity::{
@@ -277,6 +278,42 @@ fn sync_s3_reports_locked_when_lock_file_is_active() {
     std::env::remove_var("KC_SYNC_S3_EMULATE_ROOT");
 }
 
+#[test]
+fn sync_push_holds_local_write_lock_and_releases_it() {
+    let root = tempfile::tempdir().expect("tempdir").keep();
+    let vault_root = root.join("vault");
+    let target_root = root.join("sync-target");
+
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    insert_object(&conn, &vault_root, b"one", 1);
+
+    sync_push(&conn, &vault_root, &target_root, 100).expect("sync push");
+    assert!(sync_local_write_lock(&vault_root, 100)
+        .expect("read lock")
+        .is_none());
+
+    let lock_path = vault_root.join("locks/sync_write.lock");
+    std::fs::write(
+        &lock_path,
+        serde_json::to_vec(&serde_json::json!({
+            "schema_version": 1,
+            "holder": "other:1",
+            "acquired_at_ms": 150,
+            "expires_at_ms": 250
+        }))
+        .expect("lock json"),
+    )
+    .expect("write lock");
+    let err = sync_push(&conn, &vault_root, &target_root, 200).expect_err("locked");
+    assert_eq!(err.code, "KC_SYNC_LOCKED");
+    let err = sync_pull(&conn, &vault_root, &target_root, 200).expect_err("locked");
+    assert_eq!(err.code, "KC_SYNC_LOCKED");
+
+    sync_pull(&conn, &vault_root, &target_root, 300).expect("stale lock is replaced");
+    assert!(!lock_path.exists());
+}
+
 #[test]
 fn sync_merge_preview_reports_safe_for_disjoint_local_and_remote_changes() {
     let root = tempfile::tempdir().expect("tempdir").keep();
@@ -18,6 +18,7 @@ fn sync_merge_preview_normalizes_and_dedupes_deterministically() {
             "overlay-a".to_string(),
             "overlay-a".to_string(),
         ],
+        doc_tombstone_ids: vec![],
     };
     let remote = SyncMergeChangeSetV1::default();
 
@@ -50,6 +51,7 @@ fn sync_merge_preview_reports_overlap_and_unsafe_decision() {
             "blake3:cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc".to_string(),
         ],
         lineage_overlay_ids: vec!["overlay-1".to_string(), "overlay-2".to_string()],
+        doc_tombstone_ids: vec![],
     };
     let remote = SyncMergeChangeSetV1 {
         object_hashes: vec![
@@ -57,6 +59,7 @@ fn sync_merge_preview_reports_overlap_and_unsafe_decision() {
             "blake3:cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc".to_string(),
         ],
         lineage_overlay_ids: vec!["overlay-2".to_string(), "overlay-9".to_string()],
+        doc_tombstone_ids: vec![],
     };
 
     let preview = merge_preview_conservative(&local, &remote, 999).expect("preview");
@@ -86,6 +89,7 @@ fn sync_merge_preview_rejects_invalid_input_hash() {
     let local = SyncMergeChangeSetV1 {
         object_hashes: vec!["blake3:not-hex".to_string()],
         lineage_overlay_ids: vec![],
+        doc_tombstone_ids: vec![],
     };
     let remote = SyncMergeChangeSetV1::default();
 
@@ -93,6 +97,58 @@ fn sync_merge_preview_rejects_invalid_input_hash() {
     assert_eq!(err.code, "KC_SYNC_MERGE_PRECONDITION_FAILED");
 }
 
+#[test]
+fn sync_merge_preview_flags_tombstone_against_remote_re_add() {
+    let doc = "blake3:dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd";
+    let local = SyncMergeChangeSetV1 {
+        object_hashes: vec![],
+        lineage_overlay_ids: vec![],
+        doc_tombstone_ids: vec![doc.to_string(), doc.to_string()],
+    };
+    let remote_disjoint = SyncMergeChangeSetV1 {
+        object_hashes: vec![
+            "blake3:eeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee".to_string(),
+        ],
+        lineage_overlay_ids: vec![],
+        doc_tombstone_ids: vec![],
+    };
+    let safe = merge_preview_conservative(&local, &remote_disjoint, 100).expect("disjoint");
+    assert!(safe.safe);
+    assert_eq!(safe.local.doc_tombstone_ids, vec![doc.to_string()]);
+
+    let remote_re_add = SyncMergeChangeSetV1 {
+        object_hashes: vec![doc.to_string()],
+        lineage_overlay_ids: vec![],
+        doc_tombstone_ids: vec![],
+    };
+    let unsafe_v1 = merge_preview_conservative(&local, &remote_re_add, 101).expect("re-add");
+    assert!(!unsafe_v1.safe);
+    assert_eq!(unsafe_v1.reasons, vec!["doc_tombstone_overlap".to_string()]);
+    assert_eq!(unsafe_v1.overlap.doc_tombstone_ids, vec![doc.to_string()]);
+
+    let unsafe_v4 = merge_preview_with_policy_v2(
+        &remote_re_add,
+        &local,
+        &SyncMergeContextV2::default(),
+        "conservative_plus_v4",
+        102,
+    )
+    .expect("v4 re-add");
+    assert_eq!(
+        unsafe_v4.reasons,
+        vec!["unsafe_tombstone_overlap_v4".to_string()]
+    );
+    let err = ensure_conservative_plus_v4_merge_safe(&unsafe_v4).expect_err("unsafe");
+    assert_eq!(err.code, "KC_SYNC_MERGE_NOT_SAFE");
+
+    let bad = SyncMergeChangeSetV1 {
+        doc_tombstone_ids: vec!["doc-1".to_string()],
+        ..SyncMergeChangeSetV1::default()
+    };
+    let err = merge_preview_conservative(&bad, &remote_disjoint, 103).expect_err("bad id");
+    assert_eq!(err.code, "KC_SYNC_MERGE_PRECONDITION_FAILED");
+}
+
 #[test]
 fn sync_merge_preview_v2_supports_disjoint_safe_merge() {
     let local = SyncMergeChangeSetV1 {
@@ -100,12 +156,14 @@ fn sync_merge_preview_v2_supports_disjoint_safe_merge() {
             "blake3:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string(),
         ],
         lineage_overlay_ids: vec!["overlay-a".to_string()],
+        doc_tombstone_ids: vec![],
     };
     let remote = SyncMergeChangeSetV1 {
         object_hashes: vec![
             "blake3:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb".to_string(),
         ],
         lineage_overlay_ids: vec!["overlay-b".to_string()],
+        doc_tombstone_ids: vec![],
     };
 
     let report = merge_preview_with_policy_v2(
@@ -212,10 +270,12 @@ fn sync_merge_preview_v2_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: true,
@@ -226,10 +286,12 @@ fn sync_merge_preview_v2_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone(), hash_c.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_c.clone(), hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -240,10 +302,12 @@ fn sync_merge_preview_v2_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -254,10 +318,12 @@ fn sync_merge_preview_v2_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: true,
@@ -272,10 +338,12 @@ fn sync_merge_preview_v2_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: false,
@@ -338,10 +406,12 @@ fn sync_merge_preview_v3_supports_safe_disjoint_and_unsafe_reasons() {
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_a.clone()],
             lineage_overlay_ids: vec!["overlay-a".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_b.clone()],
             lineage_overlay_ids: vec!["overlay-b".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeContextV2::default(),
         "conservative_plus_v3",
@@ -362,10 +432,12 @@ fn sync_merge_preview_v3_supports_safe_disjoint_and_unsafe_reasons() {
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_a],
             lineage_overlay_ids: vec!["overlay-a".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_b],
             lineage_overlay_ids: vec!["overlay-b".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeContextV2 {
             trust_chain_mismatch: false,
@@ -405,10 +477,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: true,
@@ -419,10 +493,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone(), hash_c.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_c.clone(), hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -433,10 +509,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -447,10 +525,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: true,
@@ -465,10 +545,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: false,
@@ -483,10 +565,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: false,
@@ -501,10 +585,12 @@ fn sync_merge_preview_v3_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone(), hash_c.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_c.clone(), hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: true,
@@ -573,10 +659,12 @@ fn sync_merge_preview_v4_supports_safe_disjoint_and_unsafe_reasons() {
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_a.clone()],
             lineage_overlay_ids: vec!["overlay-a".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_b.clone()],
             lineage_overlay_ids: vec!["overlay-b".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeContextV2::default(),
         "conservative_plus_v4",
@@ -597,10 +685,12 @@ fn sync_merge_preview_v4_supports_safe_disjoint_and_unsafe_reasons() {
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_a],
             lineage_overlay_ids: vec!["overlay-a".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeChangeSetV1 {
             object_hashes: vec![hash_b],
             lineage_overlay_ids: vec!["overlay-b".to_string()],
+            doc_tombstone_ids: vec![],
         },
         &SyncMergeContextV2 {
             trust_chain_mismatch: false,
@@ -640,10 +730,12 @@ fn sync_merge_preview_v4_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: true,
@@ -654,10 +746,12 @@ fn sync_merge_preview_v4_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone(), hash_c.clone()],
                 lineage_overlay_ids: vec!["overlay-a".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_c.clone(), hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-b".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -668,10 +762,12 @@ fn sync_merge_preview_v4_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec!["overlay-shared".to_string()],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2::default(),
             expected_safe: false,
@@ -682,10 +778,12 @@ fn sync_merge_preview_v4_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: true,
@@ -700,10 +798,12 @@ fn sync_merge_preview_v4_matrix_and_deterministic_ordering() {
             local: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_a.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             remote: SyncMergeChangeSetV1 {
                 object_hashes: vec![hash_b.clone()],
                 lineage_overlay_ids: vec![],
+                doc_tombstone_ids: vec![],
             },
             ctx: SyncMergeContextV2 {
                 trust_chain_mismatch: false,
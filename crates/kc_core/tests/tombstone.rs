@@ -0,0 +1,299 @@
+use kc_core::app_error::AppResult;
+use kc_core::chunking::default_chunking_config_v1;
+use kc_core::db::open_db;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::ingest::{ingest_bytes, IngestBytesReq};
+use kc_core::lineage::lineage_lock_acquire;
+use kc_core::object_store::ObjectStore;
+use kc_core::pipeline::{run_doc_pipeline, PipelineServices};
+use kc_core::services::{CanonicalTextArtifact, ExtractInput, ExtractService};
+use kc_core::sync::{sync_merge_preview_target, sync_pull, sync_pull_target_with_mode, sync_push};
+use kc_core::tombstone::{doc_delete, doc_tombstones_list, doc_tombstones_purge_indexes};
+use kc_core::types::{CanonicalHash, DocId, ObjectHash};
+use kc_core::vault::vault_init;
+use std::path::Path;
+
+mod common;
+
+use common::MemoryIndex;
+
+// Canonical text differs from the source bytes, so the doc owns two objects.
+struct PrefixedExtractor;
+
+impl ExtractService for PrefixedExtractor {
+    fn extract_canonical(&self, input: ExtractInput<'_>) -> AppResult<CanonicalTextArtifact> {
+        let canonical_bytes = format!("canonical: {}", String::from_utf8_lossy(input.bytes))
+            .as_bytes()
+            .to_vec();
+        let hash = blake3_hex_prefixed(&canonical_bytes);
+        Ok(CanonicalTextArtifact {
+            doc_id: input.doc_id.clone(),
+            canonical_bytes,
+            canonical_hash: CanonicalHash(hash.clone()),
+            canonical_object_hash: ObjectHash(hash),
+            extractor_name: "test.prefixed".to_string(),
+            extractor_version: "1".to_string(),
+            extractor_flags_json: "{}".to_string(),
+            normalization_version: 1,
+            toolchain_json: "{}".to_string(),
+        })
+    }
+}
+
+fn ingest_and_index(
+    conn: &rusqlite::Connection,
+    vault_root: &Path,
+    lexical: &MemoryIndex,
+    vector: &MemoryIndex,
+    bytes: &[u8],
+    now_ms: i64,
+) -> DocId {
+    let store = ObjectStore::new(vault_root.join("store/objects"));
+    let doc = ingest_bytes(
+        conn,
+        &store,
+        IngestBytesReq {
+            bytes,
+            mime: "text/plain",
+            source_kind: "notes",
+            effective_ts_ms: now_ms,
+            source_path: Some("/notes/a.txt"),
+            now_ms,
+        },
+    )
+    .expect("ingest");
+    let chunking = default_chunking_config_v1();
+    let services = PipelineServices {
+        extractor: &PrefixedExtractor,
+        lexical,
+        vector,
+        chunking: &chunking,
+    };
+    run_doc_pipeline(conn, &store, &services, &doc.doc_id, now_ms).expect("pipeline");
+    doc.doc_id
+}
+
+fn count(conn: &rusqlite::Connection, sql: &str, doc_id: &DocId) -> i64 {
+    conn.query_row(sql, [&doc_id.0], |row| row.get(0))
+        .expect("count")
+}
+
+#[test]
+fn doc_delete_removes_rows_and_index_entries_and_records_tombstone() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let lexical = MemoryIndex::default();
+    let vector = MemoryIndex::default();
+
+    let doc_id = ingest_and_index(&conn, &vault_root, &lexical, &vector, b"alpha", 10);
+    assert_eq!(lexical.rows_for(&doc_id.0), 1);
+
+    let deleted = doc_delete(&conn, &lexical, &vector, &doc_id, 20).expect("delete");
+    assert_eq!(deleted.tombstone.doc_id, doc_id.0);
+    assert_eq!(deleted.tombstone.deleted_at_ms, 20);
+    assert_eq!(deleted.removed_chunks, 1);
+    assert_eq!(
+        deleted.removed_source_paths,
+        vec!["/notes/a.txt".to_string()]
+    );
+    assert_eq!(deleted.unreferenced_object_hashes.len(), 2);
+    assert!(deleted.unreferenced_object_hashes.contains(&doc_id.0));
+    assert_eq!(lexical.rows_for(&doc_id.0), 0);
+    assert_eq!(vector.rows_for(&doc_id.0), 0);
+
+    for sql in [
+        "SELECT COUNT(*) FROM docs WHERE doc_id=?1",
+        "SELECT COUNT(*) FROM chunks WHERE doc_id=?1",
+        "SELECT COUNT(*) FROM canonical_text WHERE doc_id=?1",
+        "SELECT COUNT(*) FROM doc_sources WHERE doc_id=?1",
+        "SELECT COUNT(*) FROM objects WHERE object_hash=?1",
+    ] {
+        assert_eq!(count(&conn, sql, &doc_id), 0, "{sql}");
+    }
+
+    let (event_type, event_hash, prev_hash): (String, String, Option<String>) = conn
+        .query_row(
+            "SELECT type, event_hash, prev_event_hash FROM events WHERE event_id=?1",
+            [deleted.tombstone.deleted_event_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .expect("tombstone event");
+    assert_eq!(event_type, "doc.delete");
+    assert_eq!(event_hash, deleted.event_hash);
+    assert!(prev_hash.is_some());
+
+    assert_eq!(
+        doc_tombstones_list(&conn).expect("list"),
+        vec![deleted.tombstone.clone()]
+    );
+    let again = doc_delete(&conn, &lexical, &vector, &doc_id, 21).expect_err("already deleted");
+    assert_eq!(again.code, "KC_DOC_NOT_FOUND");
+    let missing = doc_delete(
+        &conn,
+        &lexical,
+        &vector,
+        &DocId("blake3:missing".to_string()),
+        22,
+    )
+    .expect_err("missing doc");
+    assert_eq!(missing.code, "KC_DOC_NOT_FOUND");
+
+    let reingested = ingest_and_index(&conn, &vault_root, &lexical, &vector, b"alpha", 30);
+    assert_eq!(reingested, doc_id);
+    assert!(doc_tombstones_list(&conn).expect("list").is_empty());
+    assert_eq!(lexical.rows_for(&doc_id.0), 1);
+}
+
+#[test]
+fn doc_delete_refuses_while_lineage_lock_is_held() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    let conn = open_db(&vault_root.join("db/knowledge.sqlite")).expect("open db");
+    let lexical = MemoryIndex::default();
+    let vector = MemoryIndex::default();
+    let doc_id = ingest_and_index(&conn, &vault_root, &lexical, &vector, b"locked", 10);
+
+    let lease = lineage_lock_acquire(&conn, &doc_id.0, "editor", 11).expect("lock");
+    let err = doc_delete(&conn, &lexical, &vector, &doc_id, 12).expect_err("lock held");
+    assert_eq!(err.code, "KC_LINEAGE_LOCK_HELD");
+    assert_eq!(
+        count(&conn, "SELECT COUNT(*) FROM docs WHERE doc_id=?1", &doc_id),
+        1
+    );
+
+    doc_delete(&conn, &lexical, &vector, &doc_id, lease.expires_at_ms + 1)
+        .expect("delete after lease expiry");
+    assert_eq!(
+        count(
+            &conn,
+            "SELECT COUNT(*) FROM lineage_edit_locks WHERE doc_id=?1",
+            &doc_id
+        ),
+        0
+    );
+}
+
+#[test]
+fn sync_pull_does_not_resurrect_locally_deleted_doc() {
+    let root = tempfile::tempdir().expect("tempdir");
+    let vault_a = root.path().join("vault_a");
+    let vault_b = root.path().join("vault_b");
+    let target = root.path().join("sync-target");
+    vault_init(&vault_a, "a", 1).expect("vault a init");
+    vault_init(&vault_b, "b", 1).expect("vault b init");
+    let lexical = MemoryIndex::default();
+    let vector = MemoryIndex::default();
+
+    let conn_a = open_db(&vault_a.join("db/knowledge.sqlite")).expect("open db a");
+    let doc_id = ingest_and_index(&conn_a, &vault_a, &lexical, &vector, b"shared", 10);
+    sync_push(&conn_a, &vault_a, &target, 100).expect("push baseline");
+
+    let conn_b = open_db(&vault_b.join("db/knowledge.sqlite")).expect("open db b");
+    sync_pull(&conn_b, &vault_b, &target, 101).expect("pull baseline");
+    let conn_b = open_db(&vault_b.join("db/knowledge.sqlite")).expect("reopen db b");
+    assert_eq!(
+        count(
+            &conn_b,
+            "SELECT COUNT(*) FROM docs WHERE doc_id=?1",
+            &doc_id
+        ),
+        1
+    );
+
+    doc_delete(&conn_b, &lexical, &vector, &doc_id, 200).expect("delete on b");
+    ingest_and_index(&conn_a, &vault_a, &lexical, &vector, b"unrelated", 210);
+    sync_push(&conn_a, &vault_a, &target, 220).expect("push unrelated change");
+
+    let preview =
+        sync_merge_preview_target(&conn_b, &vault_b, target.to_string_lossy().as_ref(), 300)
+            .expect("preview");
+    assert_eq!(
+        preview.report.local.doc_tombstone_ids,
+        vec![doc_id.0.clone()]
+    );
+    assert!(preview.report.safe);
+
+    sync_pull_target_with_mode(
+        &conn_b,
+        &vault_b,
+        target.to_string_lossy().as_ref(),
+        301,
+        Some("conservative"),
+    )
+    .expect("merge pull");
+    let conn_b = open_db(&vault_b.join("db/knowledge.sqlite")).expect("reopen db b");
+    assert_eq!(
+        count(
+            &conn_b,
+            "SELECT COUNT(*) FROM docs WHERE doc_id=?1",
+            &doc_id
+        ),
+        0
+    );
+    assert_eq!(
+        count(
+            &conn_b,
+            "SELECT COUNT(*) FROM chunks WHERE doc_id=?1",
+            &doc_id
+        ),
+        0
+    );
+    let tombstones = doc_tombstones_list(&conn_b).expect("tombstones");
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].deleted_at_ms, 200);
+    let docs_b: i64 = conn_b
+        .query_row("SELECT COUNT(*) FROM docs", [], |row| row.get(0))
+        .expect("docs");
+    assert_eq!(docs_b, 1);
+
+    let purged = doc_tombstones_purge_indexes(&conn_b, &lexical, &vector).expect("purge");
+    assert_eq!(purged, 1);
+
+    sync_push(&conn_b, &vault_b, &target, 400).expect("push delete");
+    sync_pull_target_with_mode(
+        &conn_a,
+        &vault_a,
+        target.to_string_lossy().as_ref(),
+        401,
+        Some("conservative"),
+    )
+    .expect("pull delete into a");
+    let conn_a = open_db(&vault_a.join("db/knowledge.sqlite")).expect("reopen db a");
+    assert_eq!(
+        count(
+            &conn_a,
+            "SELECT COUNT(*) FROM docs WHERE doc_id=?1",
+            &doc_id
+        ),
+        0
+    );
+    assert_eq!(doc_tombstones_list(&conn_a).expect("tombstones a").len(), 1);
+
+    let readded = ingest_and_index(&conn_a, &vault_a, &lexical, &vector, b"shared", 500);
+    assert_eq!(readded, doc_id);
+    sync_push(&conn_a, &vault_a, &target, 510).expect("push re-add");
+    let conn_b = open_db(&vault_b.join("db/knowledge.sqlite")).expect("reopen db b");
+    sync_pull_target_with_mode(
+        &conn_b,
+        &vault_b,
+        target.to_string_lossy().as_ref(),
+        520,
+        Some("conservative"),
+    )
+    .expect("pull re-add");
+    let conn_b = open_db(&vault_b.join("db/knowledge.sqlite")).expect("reopen db b");
+    assert_eq!(
+        count(
+            &conn_b,
+            "SELECT COUNT(*) FROM docs WHERE doc_id=?1",
+            &doc_id
+        ),
+        1
+    );
+    assert!(doc_tombstones_list(&conn_b)
+        .expect("tombstones b")
+        .is_empty());
+}
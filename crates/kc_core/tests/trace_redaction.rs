@@ -0,0 +1,105 @@
+use kc_core::trace_redaction::TraceRedactor;
+use kc_core::vault::{VaultRedactionPattern, VaultTraceRedactionDefaults};
+
+#[test]
+fn trace_redactor_replaces_builtin_detectors_and_counts_matches() {
+    let mut redactor =
+        TraceRedactor::from_config(&VaultTraceRedactionDefaults::default()).expect("redactor");
+    let out = redactor.redact(
+        "question",
+        "Mail alice.smith@example.co.uk or bob@corp.io from 10.0.12.7, call +1 (555) 123-4567, card 4111 1111 1111 1111, rotate keys every 3.5 months.",
+    );
+    assert_eq!(
+        out,
+        "Mail [REDACTED:email] or [REDACTED:email] from [REDACTED:ipv4], call [REDACTED:phone], card [REDACTED:credit_card], rotate keys every 3.5 months."
+    );
+    assert_eq!(
+        redactor.redact("answer.text", "nothing to hide"),
+        "nothing to hide"
+    );
+
+    let report = redactor.report();
+    assert!(report.enabled);
+    assert!(report.redacted_any());
+    assert_eq!(report.rules, vec!["email", "ipv4", "credit_card", "phone"]);
+    assert_eq!(
+        serde_json::to_value(&report.matches).expect("matches"),
+        serde_json::json!({ "credit_card": 1, "email": 2, "ipv4": 1, "phone": 1 })
+    );
+    assert_eq!(report.fields, vec!["question"]);
+}
+
+#[test]
+fn trace_redactor_keeps_digit_runs_that_fail_the_card_checksum() {
+    let mut redactor =
+        TraceRedactor::from_config(&VaultTraceRedactionDefaults::default()).expect("redactor");
+    let out = redactor.redact(
+        "retrieval.chunks[0].snippet",
+        "Rotated at 1760000000000 (ms), card 4111-1111-1111-1112 was declined, 5500 0000 0000 0004 was not.",
+    );
+    assert_eq!(
+        out,
+        "Rotated at 1760000000000 (ms), card 4111-1111-1111-1112 was declined, [REDACTED:credit_card] was not."
+    );
+    assert_eq!(redactor.report().matches["credit_card"], 1);
+}
+
+#[test]
+fn trace_redactor_applies_custom_patterns_and_rejects_bad_rules() {
+    let config = VaultTraceRedactionDefaults {
+        enabled: true,
+        detectors: vec!["email".to_string()],
+        patterns: vec![VaultRedactionPattern {
+            name: "ticket".to_string(),
+            regex: r"\bOPS-\d+\b".to_string(),
+        }],
+    };
+    let mut redactor = TraceRedactor::from_config(&config).expect("redactor");
+    assert_eq!(
+        redactor.redact("answer.text", "See OPS-42 and OPS-7 ($1 budget)."),
+        "See [REDACTED:ticket] and [REDACTED:ticket] ($1 budget)."
+    );
+    assert_eq!(redactor.report().matches["ticket"], 2);
+    assert_eq!(redactor.report().matches["email"], 0);
+
+    let mut disabled = TraceRedactor::from_config(&VaultTraceRedactionDefaults {
+        enabled: false,
+        ..config.clone()
+    })
+    .expect("disabled redactor");
+    assert_eq!(disabled.redact("question", "bob@corp.io"), "bob@corp.io");
+    let report = disabled.report();
+    assert!(!report.enabled);
+    assert!(report.rules.is_empty());
+    assert!(!report.redacted_any());
+
+    let legacy: kc_core::trace_redaction::TraceRedactionV1 =
+        serde_json::from_value(serde_json::json!({ "enabled": true })).expect("legacy report");
+    assert!(legacy.enabled && legacy.rules.is_empty() && !legacy.redacted_any());
+
+    for bad in [
+        VaultTraceRedactionDefaults {
+            detectors: vec!["ssn".to_string()],
+            ..config.clone()
+        },
+        VaultTraceRedactionDefaults {
+            patterns: vec![VaultRedactionPattern {
+                name: "broken".to_string(),
+                regex: "(".to_string(),
+            }],
+            ..config.clone()
+        },
+        VaultTraceRedactionDefaults {
+            patterns: vec![VaultRedactionPattern {
+                name: "email".to_string(),
+                regex: "x".to_string(),
+            }],
+            ..config.clone()
+        },
+    ] {
+        let err = TraceRedactor::from_config(&bad)
+            .err()
+            .expect("invalid rule");
+        assert_eq!(err.code, "KC_TRACE_REDACTION_FAILED");
+    }
+}
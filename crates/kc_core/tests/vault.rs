@@ -14,6 +14,7 @@ fn vault_init_creates_structure_and_vault_json() {
     assert!(vault_root.join("db").exists());
     assert!(vault_root.join("store/objects").exists());
     assert!(vault_root.join("Inbox/processed").exists());
+    assert!(vault_root.join("Inbox/failed").exists());
     assert!(vault_root.join("index/vectors").exists());
 
     let opened = vault_open(&vault_root).expect("vault_open");
@@ -8,8 +8,15 @@ use crate::pdf::{extract_pdf_text, PdfiumConfig};
 use kc_core::app_error::{AppError, AppResult};
 use kc_core::canon_json::to_canonical_bytes;
 use kc_core::hashing::blake3_hex_prefixed;
-use kc_core::services::{CanonicalTextArtifact, ExtractInput, ExtractService, ToolchainIdentity};
+use kc_core::services::{
+    CanonicalTextArtifact, ExtractInput, ExtractService, ExtractorIdentity, ToolchainIdentity,
+};
 use kc_core::types::{CanonicalHash, ObjectHash};
+use kc_core::vault::VaultToolchain;
+
+pub const EXTRACTOR_NAME: &str = "kc_extract.default";
+pub const EXTRACTOR_VERSION: &str = "1";
+pub const NORMALIZATION_VERSION: i64 = 1;
 
 pub struct DefaultExtractor {
     pub toolchain: ToolchainIdentity,
@@ -19,6 +26,22 @@ impl DefaultExtractor {
     pub fn new(toolchain: ToolchainIdentity) -> Self {
         Self { toolchain }
     }
+
+    pub fn for_vault_toolchain(toolchain: &VaultToolchain) -> Self {
+        Self::new(ToolchainIdentity {
+            pdfium_identity: toolchain.pdfium.identity.clone(),
+            tesseract_identity: toolchain.tesseract.identity.clone(),
+        })
+    }
+
+    pub fn identity(&self) -> ExtractorIdentity {
+        ExtractorIdentity {
+            extractor_name: EXTRACTOR_NAME.to_string(),
+            extractor_version: EXTRACTOR_VERSION.to_string(),
+            normalization_version: NORMALIZATION_VERSION,
+            toolchain: self.toolchain.clone(),
+        }
+    }
 }
 
 impl ExtractService for DefaultExtractor {
@@ -120,10 +143,10 @@ impl ExtractService for DefaultExtractor {
             canonical_bytes,
             canonical_hash: CanonicalHash(hash.clone()),
             canonical_object_hash: ObjectHash(hash),
-            extractor_name: "kc_extract.default".to_string(),
-            extractor_version: "1".to_string(),
+            extractor_name: EXTRACTOR_NAME.to_string(),
+            extractor_version: EXTRACTOR_VERSION.to_string(),
             extractor_flags_json,
-            normalization_version: 1,
+            normalization_version: NORMALIZATION_VERSION,
             toolchain_json,
         })
     }
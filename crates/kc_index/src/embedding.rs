@@ -1,7 +1,7 @@
 use kc_core::app_error::AppResult;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EmbeddingIdentity {
     pub model_id: String,
     pub model_hash: String,
@@ -15,3 +15,46 @@ pub trait Embedder: Send + Sync {
     fn identity(&self) -> EmbeddingIdentity;
     fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
 }
+
+impl Embedder for Box<dyn Embedder> {
+    fn identity(&self) -> EmbeddingIdentity {
+        self.as_ref().identity()
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        self.as_ref().embed(texts)
+    }
+}
+
+pub struct DeterministicEmbedder;
+
+impl Embedder for DeterministicEmbedder {
+    fn identity(&self) -> EmbeddingIdentity {
+        EmbeddingIdentity {
+            model_id: "deterministic-v1".to_string(),
+            model_hash: "blake3:deterministic-v1".to_string(),
+            dims: 8,
+            provider: "kc_cli".to_string(),
+            provider_version: "1".to_string(),
+            flags_json: serde_json::json!({ "algorithm": "byte-histogram-8" }).to_string(),
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut bins = [0f32; 8];
+                for byte in text.bytes() {
+                    let idx = (byte as usize) % 8;
+                    bins[idx] += 1.0;
+                }
+                let norm = bins.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norm > 0.0 {
+                    bins.iter_mut().for_each(|x| *x /= norm);
+                }
+                bins.to_vec()
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::embedding::{DeterministicEmbedder, Embedder};
+use crate::local_embedder::{StaticEmbedder, STATIC_BACKEND};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::vault::{vault_open, VaultJsonV3};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_EMBEDDING_MODEL_ID: &str = "embedding/default-v1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbedderSpec {
+    Deterministic,
+    Static { model_dir: PathBuf },
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbedderRegistry {
+    models: BTreeMap<String, EmbedderSpec>,
+}
+
+impl EmbedderRegistry {
+    pub fn builtin() -> Self {
+        let mut models = BTreeMap::new();
+        models.insert(
+            DEFAULT_EMBEDDING_MODEL_ID.to_string(),
+            EmbedderSpec::Deterministic,
+        );
+        Self { models }
+    }
+
+    // Built-in models plus the vault's `defaults.embedding_models`; relative model paths
+    // resolve against the vault root.
+    pub fn for_vault(vault_path: &Path, vault: &VaultJsonV3) -> AppResult<Self> {
+        let mut registry = Self::builtin();
+        for (model_id, model) in &vault.defaults.embedding_models {
+            if model.backend != STATIC_BACKEND {
+                return Err(AppError::new(
+                    "KC_EMBEDDING_MODEL_INVALID",
+                    "vector",
+                    "unsupported embedding model backend",
+                    false,
+                    serde_json::json!({
+                        "model_id": model_id,
+                        "backend": model.backend,
+                        "supported": [STATIC_BACKEND],
+                    }),
+                ));
+            }
+            registry.register(
+                model_id,
+                EmbedderSpec::Static {
+                    model_dir: vault_path.join(&model.path),
+                },
+            );
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, model_id: &str, spec: EmbedderSpec) {
+        self.models.insert(model_id.to_string(), spec);
+    }
+
+    pub fn model_ids(&self) -> Vec<String> {
+        self.models.keys().cloned().collect()
+    }
+
+    pub fn load(&self, model_id: &str) -> AppResult<Box<dyn Embedder>> {
+        match self.models.get(model_id) {
+            Some(EmbedderSpec::Deterministic) => Ok(Box::new(DeterministicEmbedder)),
+            Some(EmbedderSpec::Static { model_dir }) => {
+                Ok(Box::new(StaticEmbedder::load(model_id, model_dir)?))
+            }
+            None => Err(AppError::new(
+                "KC_EMBEDDING_MODEL_NOT_FOUND",
+                "vector",
+                "embedding model id is not registered",
+                false,
+                serde_json::json!({
+                    "model_id": model_id,
+                    "registered": self.model_ids(),
+                }),
+            )),
+        }
+    }
+}
+
+pub fn open_vault_embedder(vault_path: &Path) -> AppResult<Box<dyn Embedder>> {
+    let vault = vault_open(vault_path)?;
+    EmbedderRegistry::for_vault(vault_path, &vault)?.load(&vault.defaults.embedding_model_id)
+}
@@ -1,7 +1,13 @@
+use crate::query::parse_query;
 use kc_core::app_error::{AppError, AppResult};
-use kc_core::index_traits::LexicalCandidate;
-use kc_core::types::ChunkId;
-use rusqlite::{params, Connection};
+use kc_core::db::open_db;
+use kc_core::doc_versions::SUPERSEDED_DOC_IDS_SQL;
+use kc_core::index_traits::{IndexChunk, LexicalCandidate, LexicalIndex};
+use kc_core::search::SearchFilterV1;
+use kc_core::types::{ChunkId, DocId};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
 
 #[derive(Debug, Clone)]
 pub struct FtsRow {
@@ -11,33 +17,236 @@ pub struct FtsRow {
     pub content: String,
 }
 
-pub fn init_fts(conn: &Connection) -> AppResult<()> {
-    conn.execute_batch(
-        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts
-         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FtsTokenizer {
+    Unicode61,
+    Porter,
+    Trigram,
+}
+
+impl FtsTokenizer {
+    pub fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "unicode61" => Ok(Self::Unicode61),
+            "porter" => Ok(Self::Porter),
+            "trigram" => Ok(Self::Trigram),
+            other => Err(AppError::new(
+                "KC_FTS_INIT_FAILED",
+                "fts",
+                "unsupported FTS tokenizer",
+                false,
+                serde_json::json!({
+                    "tokenizer": other,
+                    "supported": ["unicode61", "porter", "trigram"],
+                }),
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unicode61 => "unicode61",
+            Self::Porter => "porter",
+            Self::Trigram => "trigram",
+        }
+    }
+}
+
+// The tokenizer and diacritic handling are baked into the table; the column weights are the
+// table's persistent `rank` function and can change without reindexing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FtsConfig {
+    pub tokenizer: FtsTokenizer,
+    pub remove_diacritics: u8,
+    pub content_weight: f64,
+    pub heading_weight: f64,
+}
+
+impl Default for FtsConfig {
+    fn default() -> Self {
+        Self {
+            tokenizer: FtsTokenizer::Unicode61,
+            remove_diacritics: 1,
+            content_weight: 1.0,
+            heading_weight: 2.0,
+        }
+    }
+}
+
+impl FtsConfig {
+    pub fn validate(&self) -> AppResult<()> {
+        let max_diacritics = match self.tokenizer {
+            FtsTokenizer::Trigram => 1,
+            FtsTokenizer::Unicode61 | FtsTokenizer::Porter => 2,
+        };
+        if self.remove_diacritics > max_diacritics {
+            return Err(AppError::new(
+                "KC_FTS_INIT_FAILED",
+                "fts",
+                "remove_diacritics is out of range for the FTS tokenizer",
+                false,
+                serde_json::json!({
+                    "tokenizer": self.tokenizer.as_str(),
+                    "remove_diacritics": self.remove_diacritics,
+                    "max": max_diacritics,
+                }),
+            ));
+        }
+        for (column, weight) in [
+            ("content", self.content_weight),
+            ("headings", self.heading_weight),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(AppError::new(
+                    "KC_FTS_INIT_FAILED",
+                    "fts",
+                    "FTS column weight must be a finite non-negative number",
+                    false,
+                    serde_json::json!({ "column": column, "weight": weight }),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn tokenize(&self) -> String {
+        let base = match self.tokenizer {
+            FtsTokenizer::Unicode61 => "unicode61",
+            FtsTokenizer::Porter => "porter unicode61",
+            FtsTokenizer::Trigram => "trigram",
+        };
+        format!("{base} remove_diacritics {}", self.remove_diacritics)
+    }
+
+    fn columns(&self) -> String {
+        format!(
+            "chunk_id UNINDEXED, doc_id UNINDEXED, content, headings, tokenize='{}'",
+            self.tokenize()
+        )
+    }
+
+    fn rank_function(&self) -> String {
+        format!(
+            "bm25(0.0, 0.0, {:?}, {:?})",
+            self.content_weight, self.heading_weight
+        )
+    }
+}
+
+// Titles of the `[[H<level>:title]]` markers inside a chunk, one per line.
+pub fn heading_text(content: &str) -> String {
+    let mut titles = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("[[H") {
+        let after = &rest[start + 3..];
+        let level_len = after.chars().take_while(|c| c.is_ascii_digit()).count();
+        let marker = &after[level_len..];
+        match (level_len, marker.strip_prefix(':'), marker.find("]]")) {
+            (1.., Some(_), Some(close)) => {
+                let title = marker[1..close].trim();
+                if !title.is_empty() {
+                    titles.push(title);
+                }
+                rest = &marker[close + 2..];
+            }
+            _ => rest = after,
+        }
+    }
+    titles.join("\n")
+}
+
+fn init_error(message: &str, e: rusqlite::Error) -> AppError {
+    AppError::new(
+        "KC_FTS_INIT_FAILED",
+        "fts",
+        message,
+        false,
+        serde_json::json!({ "error": e.to_string() }),
     )
-    .map_err(|e| {
-        AppError::new(
-            "KC_FTS_INIT_FAILED",
+}
+
+// An existing table built with a different tokenizer (or before the headings column) fails
+// with KC_FTS_CONFIG_MISMATCH until `kc_cli index rebuild` recreates it.
+pub fn init_fts(conn: &Connection, config: &FtsConfig) -> AppResult<()> {
+    config.validate()?;
+    let columns = config.columns();
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS chunks_fts USING fts5({columns});"
+    ))
+    .map_err(|e| init_error("failed to initialize FTS table", e))?;
+
+    let stored: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type='table' AND name='chunks_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| init_error("failed reading FTS table definition", e))?;
+    if !stored.ends_with(&format!("fts5({columns})")) {
+        return Err(AppError::new(
+            "KC_FTS_CONFIG_MISMATCH",
             "fts",
-            "failed to initialize FTS table",
+            "FTS table was built with a different configuration; run index rebuild",
             false,
-            serde_json::json!({ "error": e.to_string() }),
+            serde_json::json!({ "stored": stored, "configured": columns }),
+        ));
+    }
+
+    let rank_function = config.rank_function();
+    let current: Option<String> = conn
+        .query_row(
+            "SELECT v FROM chunks_fts_config WHERE k='rank'",
+            [],
+            |row| row.get(0),
         )
-    })
+        .optional()
+        .map_err(|e| init_error("failed reading FTS rank function", e))?;
+    if current.as_deref() != Some(rank_function.as_str()) {
+        conn.execute(
+            "INSERT INTO chunks_fts(chunks_fts, rank) VALUES('rank', ?1)",
+            params![rank_function],
+        )
+        .map_err(|e| init_error("failed setting FTS rank function", e))?;
+    }
+    Ok(())
 }
 
-pub fn rebuild_rows(conn: &Connection, rows: &[FtsRow]) -> AppResult<()> {
-    init_fts(conn)?;
-    conn.execute("DELETE FROM chunks_fts", []).map_err(|e| {
+fn insert_row(conn: &Connection, doc_id: &str, row: &FtsRow) -> AppResult<()> {
+    conn.execute(
+        "INSERT INTO chunks_fts(chunk_id, doc_id, content, headings) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            row.chunk_id,
+            doc_id,
+            row.content,
+            heading_text(&row.content)
+        ],
+    )
+    .map_err(|e| {
         AppError::new(
             "KC_FTS_REBUILD_FAILED",
             "fts",
-            "failed clearing FTS table",
+            "failed inserting FTS row",
             false,
-            serde_json::json!({ "error": e.to_string() }),
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
         )
     })?;
+    Ok(())
+}
+
+// Drops and recreates the table, so it also applies a changed tokenizer configuration.
+pub fn rebuild_rows(conn: &Connection, config: &FtsConfig, rows: &[FtsRow]) -> AppResult<()> {
+    config.validate()?;
+    conn.execute_batch("DROP TABLE IF EXISTS chunks_fts;")
+        .map_err(|e| {
+            AppError::new(
+                "KC_FTS_REBUILD_FAILED",
+                "fts",
+                "failed dropping FTS table",
+                false,
+                serde_json::json!({ "error": e.to_string() }),
+            )
+        })?;
+    init_fts(conn, config)?;
 
     let mut sorted = rows.to_vec();
     sorted.sort_by(|a, b| {
@@ -47,27 +256,105 @@ pub fn rebuild_rows(conn: &Connection, rows: &[FtsRow]) -> AppResult<()> {
             .then(a.chunk_id.cmp(&b.chunk_id))
     });
 
-    for row in sorted {
-        conn.execute(
-            "INSERT INTO chunks_fts(chunk_id, doc_id, content) VALUES (?1, ?2, ?3)",
-            params![row.chunk_id, row.doc_id, row.content],
+    for row in &sorted {
+        insert_row(conn, &row.doc_id, row)?;
+    }
+    Ok(())
+}
+
+pub fn replace_doc_rows(conn: &Connection, doc_id: &str, rows: &[FtsRow]) -> AppResult<()> {
+    let tx = conn.unchecked_transaction().map_err(|e| {
+        AppError::new(
+            "KC_FTS_REBUILD_FAILED",
+            "fts",
+            "failed starting FTS doc transaction",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
         )
+    })?;
+    tx.execute("DELETE FROM chunks_fts WHERE doc_id=?1", params![doc_id])
         .map_err(|e| {
             AppError::new(
                 "KC_FTS_REBUILD_FAILED",
                 "fts",
-                "failed inserting FTS row",
+                "failed clearing FTS rows for doc",
                 false,
-                serde_json::json!({ "error": e.to_string() }),
+                serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
             )
         })?;
+
+    let mut sorted = rows.to_vec();
+    sorted.sort_by(|a, b| a.ordinal.cmp(&b.ordinal).then(a.chunk_id.cmp(&b.chunk_id)));
+
+    for row in &sorted {
+        insert_row(&tx, doc_id, row)?;
     }
-    Ok(())
+
+    tx.commit().map_err(|e| {
+        AppError::new(
+            "KC_FTS_REBUILD_FAILED",
+            "fts",
+            "failed committing FTS doc rows",
+            false,
+            serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
+        )
+    })
+}
+
+pub fn delete_doc_rows(conn: &Connection, doc_id: &str) -> AppResult<usize> {
+    conn.execute("DELETE FROM chunks_fts WHERE doc_id=?1", params![doc_id])
+        .map_err(|e| {
+            AppError::new(
+                "KC_FTS_REBUILD_FAILED",
+                "fts",
+                "failed deleting FTS rows for doc",
+                false,
+                serde_json::json!({ "error": e.to_string(), "doc_id": doc_id }),
+            )
+        })
 }
 
 pub fn query(conn: &Connection, q: &str, limit: usize) -> AppResult<Vec<LexicalCandidate>> {
+    query_scoped(conn, q, limit, None, false)
+}
+
+pub fn query_in_docs(
+    conn: &Connection,
+    q: &str,
+    limit: usize,
+    doc_ids: &[DocId],
+) -> AppResult<Vec<LexicalCandidate>> {
+    if doc_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    query_scoped(conn, q, limit, Some(doc_scope(doc_ids)), false)
+}
+
+fn doc_scope(doc_ids: &[DocId]) -> String {
+    let scope: Vec<&str> = doc_ids.iter().map(|d| d.0.as_str()).collect();
+    serde_json::json!(scope).to_string()
+}
+
+// `scope` is a JSON array of doc ids; NULL leaves the query unrestricted. Excluding superseded
+// docs needs the vault's `doc_versions` table.
+fn query_scoped(
+    conn: &Connection,
+    q: &str,
+    limit: usize,
+    scope: Option<String>,
+    exclude_superseded: bool,
+) -> AppResult<Vec<LexicalCandidate>> {
+    let superseded = if exclude_superseded {
+        format!("AND doc_id NOT IN ({SUPERSEDED_DOC_IDS_SQL})")
+    } else {
+        String::new()
+    };
     let mut stmt = conn
-        .prepare("SELECT chunk_id, rank FROM chunks_fts WHERE chunks_fts MATCH ?1 ORDER BY rank LIMIT ?2")
+        .prepare(&format!(
+            "SELECT chunk_id, rank FROM chunks_fts WHERE chunks_fts MATCH ?1 {superseded}
+               AND (?3 IS NULL OR doc_id IN (SELECT value FROM json_each(?3)))
+             ORDER BY rank ASC, chunk_id ASC LIMIT ?2"
+        ))
         .map_err(|e| {
             AppError::new(
                 "KC_FTS_QUERY_FAILED",
@@ -79,7 +366,7 @@ pub fn query(conn: &Connection, q: &str, limit: usize) -> AppResult<Vec<LexicalC
         })?;
 
     let rows = stmt
-        .query_map(params![q, limit as i64], |row| {
+        .query_map(params![q, limit as i64, scope], |row| {
             let chunk_id: String = row.get(0)?;
             let rank: f64 = row.get(1)?;
             Ok((chunk_id, rank))
@@ -96,7 +383,7 @@ pub fn query(conn: &Connection, q: &str, limit: usize) -> AppResult<Vec<LexicalC
 
     let mut out = Vec::new();
     for (idx, row) in rows.enumerate() {
-        let (chunk_id, _rank) = row.map_err(|e| {
+        let (chunk_id, rank) = row.map_err(|e| {
             AppError::new(
                 "KC_FTS_QUERY_FAILED",
                 "fts",
@@ -108,8 +395,101 @@ pub fn query(conn: &Connection, q: &str, limit: usize) -> AppResult<Vec<LexicalC
         out.push(LexicalCandidate {
             chunk_id: ChunkId(chunk_id),
             rank: idx as i64 + 1,
+            bm25: Some(-rank),
         });
     }
 
     Ok(out)
 }
+
+pub struct SqliteFtsIndex {
+    conn: Mutex<Connection>,
+    exclude_superseded: bool,
+}
+
+impl SqliteFtsIndex {
+    pub fn new(conn: Connection, config: &FtsConfig) -> AppResult<Self> {
+        init_fts(&conn, config)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            exclude_superseded: false,
+        })
+    }
+
+    // Drops chunks of superseded docs inside the query, so they never count against the
+    // limit. Search keeps them for `include_superseded`; ask never cites them.
+    pub fn excluding_superseded(mut self) -> Self {
+        self.exclude_superseded = true;
+        self
+    }
+
+    pub fn open(db_path: &Path, config: &FtsConfig) -> AppResult<Self> {
+        Self::new(open_db(db_path)?, config)
+    }
+
+    fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> AppResult<T>) -> AppResult<T> {
+        let conn = self.conn.lock().map_err(|_| {
+            AppError::new(
+                "KC_INTERNAL_ERROR",
+                "fts",
+                "failed acquiring FTS connection lock",
+                true,
+                serde_json::json!({}),
+            )
+        })?;
+        f(&conn)
+    }
+}
+
+impl LexicalIndex for SqliteFtsIndex {
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        let rows: Vec<FtsRow> = chunks
+            .iter()
+            .map(|chunk| FtsRow {
+                chunk_id: chunk.chunk_id.0.clone(),
+                doc_id: doc_id.0.clone(),
+                ordinal: chunk.ordinal,
+                content: chunk.text.clone(),
+            })
+            .collect();
+        self.with_conn(|conn| replace_doc_rows(conn, &doc_id.0, &rows))
+    }
+
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()> {
+        self.with_conn(|conn| delete_doc_rows(conn, &doc_id.0).map(|_| ()))
+    }
+
+    fn query(&self, q: &str, limit: usize) -> AppResult<Vec<LexicalCandidate>> {
+        let Some(expression) = parse_query(q)?.fts_expression else {
+            return Ok(Vec::new());
+        };
+        self.with_conn(|conn| query_scoped(conn, &expression, limit, None, self.exclude_superseded))
+    }
+
+    fn query_in_docs(
+        &self,
+        q: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<LexicalCandidate>> {
+        let Some(expression) = parse_query(q)?.fts_expression else {
+            return Ok(Vec::new());
+        };
+        if doc_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.with_conn(|conn| {
+            query_scoped(
+                conn,
+                &expression,
+                limit,
+                Some(doc_scope(doc_ids)),
+                self.exclude_superseded,
+            )
+        })
+    }
+
+    fn query_filter(&self, q: &str) -> AppResult<SearchFilterV1> {
+        Ok(parse_query(q)?.filter)
+    }
+}
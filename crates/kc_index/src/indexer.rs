@@ -1,4 +1,10 @@
+use crate::embedding::Embedder;
+use crate::embedding_registry::EmbedderRegistry;
+use crate::fts::{FtsConfig, FtsTokenizer, SqliteFtsIndex};
+use crate::vector::{AnnIndexKind, LanceDbVectorIndex, VectorIndexConfig};
 use kc_core::app_error::AppResult;
+use kc_core::vault::{vault_open, vault_paths, VaultJsonV3};
+use std::path::Path;
 
 #[derive(Debug, Clone)]
 pub struct LexicalCandidates;
@@ -9,3 +15,53 @@ pub struct VectorCandidates;
 pub trait IndexService {
     fn rebuild(&self) -> AppResult<()>;
 }
+
+pub struct VaultIndexes {
+    pub lexical: SqliteFtsIndex,
+    pub vector: LanceDbVectorIndex<Box<dyn Embedder>>,
+}
+
+pub fn vault_vector_index_config(vault: &VaultJsonV3) -> AppResult<VectorIndexConfig> {
+    Ok(VectorIndexConfig {
+        ann_index: AnnIndexKind::parse(&vault.defaults.vector_index.ann_index)?,
+        ann_row_threshold: vault.defaults.vector_index.ann_row_threshold as usize,
+        ..VectorIndexConfig::default()
+    })
+}
+
+pub fn vault_fts_config(vault: &VaultJsonV3) -> AppResult<FtsConfig> {
+    let fts = &vault.defaults.fts;
+    let config = FtsConfig {
+        tokenizer: FtsTokenizer::parse(&fts.tokenizer)?,
+        remove_diacritics: fts.remove_diacritics,
+        content_weight: fts.content_weight,
+        heading_weight: fts.heading_weight,
+    };
+    config.validate()?;
+    Ok(config)
+}
+
+pub fn open_vault_indexes(vault_path: &Path) -> AppResult<VaultIndexes> {
+    let vault = vault_open(vault_path)?;
+    Ok(VaultIndexes {
+        lexical: SqliteFtsIndex::open(
+            &vault_path.join(&vault.db.relative_path),
+            &vault_fts_config(&vault)?,
+        )?,
+        vector: open_vault_vector_index(vault_path)?,
+    })
+}
+
+// Opens only the vector index, for readers that query FTS through their own connection.
+pub fn open_vault_vector_index(
+    vault_path: &Path,
+) -> AppResult<LanceDbVectorIndex<Box<dyn Embedder>>> {
+    let vault = vault_open(vault_path)?;
+    let embedder = EmbedderRegistry::for_vault(vault_path, &vault)?
+        .load(&vault.defaults.embedding_model_id)?;
+    LanceDbVectorIndex::open_with_config(
+        embedder,
+        vault_paths(vault_path).vectors_dir.join("lancedb-v1"),
+        vault_vector_index_config(&vault)?,
+    )
+}
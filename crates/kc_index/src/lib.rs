@@ -1,6 +1,14 @@
 pub mod embedding;
+pub mod embedding_registry;
 pub mod fts;
 pub mod indexer;
+pub mod local_alignment;
+pub mod local_embedder;
+pub mod local_reranker;
+pub mod query;
 pub mod vector;
 
-pub use indexer::{IndexService, LexicalCandidates, VectorCandidates};
+pub use indexer::{
+    open_vault_indexes, open_vault_vector_index, vault_fts_config, vault_vector_index_config,
+    IndexService, LexicalCandidates, VaultIndexes, VectorCandidates,
+};
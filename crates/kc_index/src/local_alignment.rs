@@ -0,0 +1,79 @@
+use crate::local_embedder::STATIC_BACKEND;
+use crate::local_reranker::StaticMaxSim;
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::faithfulness::AlignmentModel;
+use kc_core::vault::{vault_open, VaultJsonV3};
+use std::path::Path;
+
+// Token alignment over a static model: each hypothesis token takes its best cosine match among
+// the premise tokens, and the mean of those maxima is clamped to [0, 1]. It measures shared
+// meaning, not entailment, so a negated or reversed sentence can still align well.
+pub struct StaticAlignmentModel {
+    encoder: StaticMaxSim,
+}
+
+impl StaticAlignmentModel {
+    pub fn load(model_id: &str, model_dir: &Path) -> AppResult<Self> {
+        Ok(Self {
+            encoder: StaticMaxSim::load(model_id, model_dir)?,
+        })
+    }
+}
+
+impl AlignmentModel for StaticAlignmentModel {
+    fn model_id(&self) -> String {
+        self.encoder.model_id().to_string()
+    }
+
+    fn alignment(&self, premise: &str, hypotheses: &[&str]) -> AppResult<Vec<f64>> {
+        Ok(hypotheses
+            .iter()
+            .map(|hypothesis| {
+                self.encoder
+                    .score_pairs(hypothesis, &[premise])
+                    .first()
+                    .copied()
+                    .unwrap_or(0.0)
+                    .clamp(0.0, 1.0)
+            })
+            .collect())
+    }
+}
+
+// The vault's `defaults.verifier.alignment`, if any. The model path resolves against the vault
+// root and doubles as the model id.
+pub fn vault_alignment_model(
+    vault_path: &Path,
+    vault: &VaultJsonV3,
+) -> AppResult<Option<Box<dyn AlignmentModel>>> {
+    let Some(config) = vault
+        .defaults
+        .verifier
+        .as_ref()
+        .and_then(|verifier| verifier.alignment.as_ref())
+    else {
+        return Ok(None);
+    };
+    match config.backend.as_str() {
+        STATIC_BACKEND => Ok(Some(Box::new(StaticAlignmentModel::load(
+            &config.path,
+            &vault_path.join(&config.path),
+        )?))),
+        backend => Err(AppError::new(
+            "KC_ASK_VERIFIER_INVALID",
+            "ask",
+            "unsupported alignment model configuration",
+            false,
+            serde_json::json!({
+                "backend": backend,
+                "path": config.path,
+                "supported": [STATIC_BACKEND],
+            }),
+        )),
+    }
+}
+
+pub fn open_vault_alignment_model(vault_path: &Path) -> AppResult<Option<Box<dyn AlignmentModel>>> {
+    let vault = vault_open(vault_path)?;
+    vault_alignment_model(vault_path, &vault)
+}
@@ -0,0 +1,363 @@
+use crate::embedding::{Embedder, EmbeddingIdentity};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::hashing::blake3_hex_prefixed;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const STATIC_BACKEND: &str = "static_safetensors";
+pub const MODEL_FILE: &str = "model.safetensors";
+pub const TOKENIZER_FILE: &str = "tokenizer.json";
+
+const EMBEDDINGS_TENSOR: &str = "embeddings";
+const DEFAULT_MAX_WORD_CHARS: usize = 100;
+
+struct WordPieceTokenizer {
+    vocab: HashMap<String, usize>,
+    unk_id: Option<usize>,
+    prefix: String,
+    lowercase: bool,
+    max_word_chars: usize,
+}
+
+// Static sentence embeddings (Model2Vec layout): a directory holding `model.safetensors`
+// with a [vocab, dims] `embeddings` tensor and a WordPiece `tokenizer.json`. A text embeds
+// as the L2-normalized mean of its token rows, so inference is a table lookup on CPU.
+pub struct StaticEmbedder {
+    model_id: String,
+    model_hash: String,
+    tokenizer_hash: String,
+    dims: usize,
+    embeddings: Vec<f32>,
+    tokenizer: WordPieceTokenizer,
+}
+
+fn model_error(message: &str, path: &Path, details: serde_json::Value) -> AppError {
+    let mut details = details;
+    if let Some(obj) = details.as_object_mut() {
+        obj.insert("path".to_string(), serde_json::json!(path));
+    }
+    AppError::new(
+        "KC_EMBEDDING_MODEL_INVALID",
+        "vector",
+        message,
+        false,
+        details,
+    )
+}
+
+fn read_file(path: &Path) -> AppResult<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        model_error(
+            "failed reading embedding model file",
+            path,
+            serde_json::json!({ "error": e.to_string() }),
+        )
+    })
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exp = ((bits >> 10) & 0x1f) as u32;
+    let frac = (bits & 0x3ff) as u32;
+    let out = match (exp, frac) {
+        (0, 0) => sign,
+        (0, _) => {
+            // Subnormal: renormalize into an f32 exponent.
+            let shift = frac.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | (((frac << shift) & 0x3ff) << 13)
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (frac << 13),
+        _ => sign | ((exp + 112) << 23) | (frac << 13),
+    };
+    f32::from_bits(out)
+}
+
+fn parse_safetensors(bytes: &[u8], path: &Path) -> AppResult<(usize, usize, Vec<f32>)> {
+    let invalid = |message: &str| model_error(message, path, serde_json::json!({}));
+    if bytes.len() < 8 {
+        return Err(invalid("safetensors file is truncated"));
+    }
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes")) as usize;
+    let data_start = 8usize
+        .checked_add(header_len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or_else(|| invalid("safetensors header length exceeds file size"))?;
+    let header: serde_json::Value = serde_json::from_slice(&bytes[8..data_start])
+        .map_err(|_| invalid("safetensors header is not valid json"))?;
+    let tensors = header
+        .as_object()
+        .ok_or_else(|| invalid("safetensors header is not an object"))?;
+
+    let (name, tensor) = match tensors.get(EMBEDDINGS_TENSOR) {
+        Some(t) => (EMBEDDINGS_TENSOR, t),
+        None => {
+            let mut matrices = tensors.iter().filter(|(k, v)| {
+                k.as_str() != "__metadata__"
+                    && v.get("shape").and_then(|s| s.as_array()).map(|s| s.len()) == Some(2)
+            });
+            match (matrices.next(), matrices.next()) {
+                (Some((k, v)), None) => (k.as_str(), v),
+                _ => return Err(invalid("safetensors file has no embeddings tensor")),
+            }
+        }
+    };
+
+    let shape: Vec<usize> = tensor
+        .get("shape")
+        .and_then(|s| s.as_array())
+        .map(|s| {
+            s.iter()
+                .filter_map(|d| d.as_u64())
+                .map(|d| d as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let [rows, dims] = shape[..] else {
+        return Err(model_error(
+            "embeddings tensor must be two-dimensional",
+            path,
+            serde_json::json!({ "tensor": name, "shape": shape }),
+        ));
+    };
+    let offsets: Vec<usize> = tensor
+        .get("data_offsets")
+        .and_then(|s| s.as_array())
+        .map(|s| {
+            s.iter()
+                .filter_map(|d| d.as_u64())
+                .map(|d| d as usize)
+                .collect()
+        })
+        .unwrap_or_default();
+    let [start, end] = offsets[..] else {
+        return Err(invalid("embeddings tensor has no data offsets"));
+    };
+    let data = bytes
+        .get(data_start + start..data_start + end)
+        .ok_or_else(|| invalid("embeddings tensor data exceeds file size"))?;
+
+    let dtype = tensor.get("dtype").and_then(|d| d.as_str()).unwrap_or("");
+    let values: Vec<f32> = match dtype {
+        "F32" => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        "F16" => data
+            .chunks_exact(2)
+            .map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]])))
+            .collect(),
+        other => {
+            return Err(model_error(
+                "unsupported embeddings tensor dtype",
+                path,
+                serde_json::json!({ "dtype": other, "supported": ["F32", "F16"] }),
+            ))
+        }
+    };
+    if dims == 0 || values.len() != rows * dims {
+        return Err(model_error(
+            "embeddings tensor size does not match its shape",
+            path,
+            serde_json::json!({ "shape": [rows, dims], "values": values.len() }),
+        ));
+    }
+    Ok((rows, dims, values))
+}
+
+fn parse_tokenizer(bytes: &[u8], path: &Path) -> AppResult<WordPieceTokenizer> {
+    let invalid = |message: &str| model_error(message, path, serde_json::json!({}));
+    let json: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(|_| invalid("tokenizer.json is not valid json"))?;
+    let model = json
+        .get("model")
+        .ok_or_else(|| invalid("tokenizer.json has no model section"))?;
+    let kind = model.get("type").and_then(|t| t.as_str()).unwrap_or("");
+    if kind != "WordPiece" {
+        return Err(model_error(
+            "unsupported tokenizer model type",
+            path,
+            serde_json::json!({ "type": kind, "supported": ["WordPiece"] }),
+        ));
+    }
+    let vocab: HashMap<String, usize> = model
+        .get("vocab")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| invalid("tokenizer.json has no vocab"))?
+        .iter()
+        .filter_map(|(token, id)| id.as_u64().map(|id| (token.clone(), id as usize)))
+        .collect();
+    let unk_id = model
+        .get("unk_token")
+        .and_then(|t| t.as_str())
+        .and_then(|t| vocab.get(t).copied());
+    Ok(WordPieceTokenizer {
+        vocab,
+        unk_id,
+        prefix: model
+            .get("continuing_subword_prefix")
+            .and_then(|p| p.as_str())
+            .unwrap_or("##")
+            .to_string(),
+        lowercase: json
+            .pointer("/normalizer/lowercase")
+            .and_then(|l| l.as_bool())
+            .unwrap_or(true),
+        max_word_chars: model
+            .get("max_input_chars_per_word")
+            .and_then(|m| m.as_u64())
+            .map(|m| m as usize)
+            .unwrap_or(DEFAULT_MAX_WORD_CHARS),
+    })
+}
+
+impl WordPieceTokenizer {
+    // Bert-style pre-tokenization: split on whitespace and isolate punctuation.
+    fn words(&self, text: &str) -> Vec<String> {
+        let text = if self.lowercase {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        };
+        let mut words = Vec::new();
+        let mut current = String::new();
+        for ch in text.chars() {
+            if ch.is_whitespace() || ch.is_ascii_punctuation() {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                if ch.is_ascii_punctuation() {
+                    words.push(ch.to_string());
+                }
+            } else {
+                current.push(ch);
+            }
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+        words
+    }
+
+    // Greedy longest-match WordPiece; unknown tokens are dropped from pooling.
+    fn token_ids(&self, text: &str) -> Vec<usize> {
+        let mut ids = Vec::new();
+        for word in self.words(text) {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() > self.max_word_chars {
+                continue;
+            }
+            let mut pieces = Vec::new();
+            let mut start = 0;
+            while start < chars.len() {
+                let mut end = chars.len();
+                let mut found = None;
+                while end > start {
+                    let mut piece: String = chars[start..end].iter().collect();
+                    if start > 0 {
+                        piece.insert_str(0, &self.prefix);
+                    }
+                    if let Some(id) = self.vocab.get(&piece) {
+                        found = Some(*id);
+                        break;
+                    }
+                    end -= 1;
+                }
+                match found {
+                    Some(id) => {
+                        pieces.push(id);
+                        start = end;
+                    }
+                    None => {
+                        pieces.clear();
+                        break;
+                    }
+                }
+            }
+            ids.extend(pieces.into_iter().filter(|id| Some(*id) != self.unk_id));
+        }
+        ids
+    }
+}
+
+impl StaticEmbedder {
+    pub fn load(model_id: &str, model_dir: &Path) -> AppResult<Self> {
+        let model_path: PathBuf = model_dir.join(MODEL_FILE);
+        let tokenizer_path: PathBuf = model_dir.join(TOKENIZER_FILE);
+        let model_bytes = read_file(&model_path)?;
+        let tokenizer_bytes = read_file(&tokenizer_path)?;
+
+        let (rows, dims, embeddings) = parse_safetensors(&model_bytes, &model_path)?;
+        let tokenizer = parse_tokenizer(&tokenizer_bytes, &tokenizer_path)?;
+        if let Some(max_id) = tokenizer.vocab.values().max() {
+            if *max_id >= rows {
+                return Err(model_error(
+                    "tokenizer vocab exceeds embeddings rows",
+                    &tokenizer_path,
+                    serde_json::json!({ "max_token_id": max_id, "rows": rows }),
+                ));
+            }
+        }
+
+        Ok(Self {
+            model_id: model_id.to_string(),
+            model_hash: blake3_hex_prefixed(&model_bytes),
+            tokenizer_hash: blake3_hex_prefixed(&tokenizer_bytes),
+            dims,
+            embeddings,
+            tokenizer,
+        })
+    }
+
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    // L2-normalized rows of the known tokens of `text`, in token order.
+    pub(crate) fn token_rows(&self, text: &str) -> Vec<Vec<f32>> {
+        self.tokenizer
+            .token_ids(text)
+            .into_iter()
+            .filter_map(|id| {
+                let row = &self.embeddings[id * self.dims..(id + 1) * self.dims];
+                let norm = row.iter().map(|x| x * x).sum::<f32>().sqrt();
+                (norm > 0.0).then(|| row.iter().map(|x| x / norm).collect())
+            })
+            .collect()
+    }
+
+    fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut out = vec![0f32; self.dims];
+        let ids = self.tokenizer.token_ids(text);
+        for id in &ids {
+            let row = &self.embeddings[id * self.dims..(id + 1) * self.dims];
+            out.iter_mut().zip(row).for_each(|(o, v)| *o += v);
+        }
+        let norm = out.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            out.iter_mut().for_each(|x| *x /= norm);
+        }
+        out
+    }
+}
+
+impl Embedder for StaticEmbedder {
+    fn identity(&self) -> EmbeddingIdentity {
+        EmbeddingIdentity {
+            model_id: self.model_id.clone(),
+            model_hash: self.model_hash.clone(),
+            dims: self.dims,
+            provider: "kc_index.static".to_string(),
+            provider_version: "1".to_string(),
+            flags_json: serde_json::json!({
+                "pooling": "mean",
+                "normalize": "l2",
+                "tokenizer_hash": self.tokenizer_hash,
+            })
+            .to_string(),
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_one(text)).collect())
+    }
+}
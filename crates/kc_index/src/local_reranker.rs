@@ -0,0 +1,110 @@
+use crate::local_embedder::{StaticEmbedder, STATIC_BACKEND};
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::rerank::{LexicalOverlapReranker, RerankInput, RerankScore, Reranker};
+use kc_core::vault::{vault_open, VaultJsonV3};
+use std::path::Path;
+
+pub const LEXICAL_OVERLAP_BACKEND: &str = "lexical_overlap";
+
+// Late-interaction (MaxSim) scoring over a static (Model2Vec layout) model directory: each
+// query token takes its best cosine match among the passage tokens and the pair scores the
+// mean of those maxima, in [-1, 1]. Query and passage tokens are embedded independently, so
+// this is not a cross-encoder; joint-scoring models plug in through `CrossEncoder`.
+pub struct StaticMaxSim {
+    embedder: StaticEmbedder,
+}
+
+impl StaticMaxSim {
+    pub fn load(model_id: &str, model_dir: &Path) -> AppResult<Self> {
+        Ok(Self {
+            embedder: StaticEmbedder::load(model_id, model_dir)?,
+        })
+    }
+
+    pub fn model_id(&self) -> &str {
+        self.embedder.model_id()
+    }
+
+    pub fn score_pairs(&self, query: &str, passages: &[&str]) -> Vec<f64> {
+        let query_rows = self.embedder.token_rows(query);
+        passages
+            .iter()
+            .map(|passage| self.score_one(&query_rows, passage))
+            .collect()
+    }
+
+    fn score_one(&self, query_rows: &[Vec<f32>], passage: &str) -> f64 {
+        let passage_rows = self.embedder.token_rows(passage);
+        if query_rows.is_empty() || passage_rows.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = query_rows
+            .iter()
+            .map(|q| {
+                passage_rows
+                    .iter()
+                    .map(|p| q.iter().zip(p).map(|(a, b)| a * b).sum::<f32>())
+                    .fold(f32::MIN, f32::max) as f64
+            })
+            .sum();
+        total / query_rows.len() as f64
+    }
+}
+
+pub struct MaxSimReranker {
+    pub model: StaticMaxSim,
+}
+
+impl Reranker for MaxSimReranker {
+    fn reranker_id(&self) -> String {
+        format!("token_maxsim:{}", self.model.model_id())
+    }
+
+    fn score(&self, query: &str, candidates: &[RerankInput]) -> AppResult<Vec<RerankScore>> {
+        let passages: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        let model_id = self.model.model_id();
+        Ok(self
+            .model
+            .score_pairs(query, &passages)
+            .into_iter()
+            .map(|score| RerankScore {
+                score,
+                reason: format!("token MaxSim relevance from {model_id}"),
+            })
+            .collect())
+    }
+}
+
+// The vault's `defaults.reranker`, if any. Model paths resolve against the vault root and
+// the path doubles as the model id.
+pub fn vault_reranker(
+    vault_path: &Path,
+    vault: &VaultJsonV3,
+) -> AppResult<Option<Box<dyn Reranker>>> {
+    let Some(config) = &vault.defaults.reranker else {
+        return Ok(None);
+    };
+    match (config.backend.as_str(), &config.path) {
+        (LEXICAL_OVERLAP_BACKEND, _) => Ok(Some(Box::new(LexicalOverlapReranker))),
+        (STATIC_BACKEND, Some(path)) => Ok(Some(Box::new(MaxSimReranker {
+            model: StaticMaxSim::load(path, &vault_path.join(path))?,
+        }))),
+        (backend, path) => Err(AppError::new(
+            "KC_RETRIEVAL_RERANKER_INVALID",
+            "retrieval",
+            "unsupported reranker configuration",
+            false,
+            serde_json::json!({
+                "backend": backend,
+                "path": path,
+                "supported": [LEXICAL_OVERLAP_BACKEND, STATIC_BACKEND],
+                "requires_path": [STATIC_BACKEND],
+            }),
+        )),
+    }
+}
+
+pub fn open_vault_reranker(vault_path: &Path) -> AppResult<Option<Box<dyn Reranker>>> {
+    let vault = vault_open(vault_path)?;
+    vault_reranker(vault_path, &vault)
+}
@@ -0,0 +1,457 @@
+use kc_core::app_error::{AppError, AppResult};
+use kc_core::search::SearchFilterV1;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// A user query split into what each consumer needs: the FTS5 MATCH expression, the positive
+// text to embed for vector search, and the doc-level filter from field operators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedQuery {
+    pub fts_expression: Option<String>,
+    pub vector_text: String,
+    pub filter: SearchFilterV1,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Phrase(String),
+    Term { text: String, prefix: bool },
+    Field { name: String, value: String },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    pos: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Phrase(String),
+    Term {
+        text: String,
+        prefix: bool,
+    },
+    Field {
+        name: String,
+        value: String,
+        pos: usize,
+    },
+    Not {
+        node: Box<Node>,
+        pos: usize,
+    },
+    And(Vec<Node>),
+    Or(Vec<Node>),
+}
+
+fn invalid(input: &str, position: usize, message: &str) -> AppError {
+    AppError::new(
+        "KC_QUERY_INVALID",
+        "query",
+        message,
+        false,
+        serde_json::json!({ "position": position, "query": input }),
+    )
+}
+
+fn is_field(name: &str) -> bool {
+    matches!(name, "source" | "mime" | "after")
+}
+
+fn is_delimiter(ch: char) -> bool {
+    ch.is_whitespace() || ch == '(' || ch == ')' || ch == '"'
+}
+
+// Reads a quoted run starting at `start` (the opening quote); returns the contents and the
+// index after the closing quote.
+fn read_quoted(input: &str, chars: &[char], start: usize) -> AppResult<(String, usize)> {
+    let Some(len) = chars[start + 1..].iter().position(|c| *c == '"') else {
+        return Err(invalid(input, start, "unterminated quoted phrase"));
+    };
+    let text: String = chars[start + 1..start + 1 + len].iter().collect();
+    if text.trim().is_empty() {
+        return Err(invalid(input, start, "quoted phrase is empty"));
+    }
+    Ok((text, start + len + 2))
+}
+
+// Positions are char offsets into the original query.
+fn tokenize(input: &str) -> AppResult<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let pos = i;
+        let kind = match ch {
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '"' => {
+                let (text, next) = read_quoted(input, &chars, i)?;
+                i = next;
+                TokenKind::Phrase(text)
+            }
+            _ => {
+                while i < chars.len() && !is_delimiter(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[pos..i].iter().collect();
+                match word.as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => match word.split_once(':') {
+                        Some((name, value)) if is_field(&name.to_lowercase()) => {
+                            let value = if value.is_empty() && chars.get(i) == Some(&'"') {
+                                let (text, next) = read_quoted(input, &chars, i)?;
+                                i = next;
+                                text
+                            } else {
+                                value.to_string()
+                            };
+                            if value.is_empty() {
+                                return Err(invalid(
+                                    input,
+                                    pos + name.chars().count() + 1,
+                                    "field operator needs a value",
+                                ));
+                            }
+                            TokenKind::Field {
+                                name: name.to_lowercase(),
+                                value,
+                            }
+                        }
+                        _ => {
+                            let (text, prefix) = match word.strip_suffix('*') {
+                                Some(stem) => (stem.to_string(), true),
+                                None => (word.clone(), false),
+                            };
+                            if let Some(star) = text.chars().position(|c| c == '*') {
+                                return Err(invalid(
+                                    input,
+                                    pos + star,
+                                    "wildcard is only supported at the end of a term",
+                                ));
+                            }
+                            if text.is_empty() {
+                                return Err(invalid(input, pos, "wildcard needs a term prefix"));
+                            }
+                            TokenKind::Term { text, prefix }
+                        }
+                    },
+                }
+            }
+        };
+        tokens.push(Token { kind, pos });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn end_pos(&self) -> usize {
+        self.input.chars().count()
+    }
+
+    fn pos(&self) -> usize {
+        self.peek().map(|t| t.pos).unwrap_or_else(|| self.end_pos())
+    }
+
+    fn parse_or(&mut self) -> AppResult<Node> {
+        let mut branches = vec![self.parse_and()?];
+        while matches!(
+            self.peek(),
+            Some(Token {
+                kind: TokenKind::Or,
+                ..
+            })
+        ) {
+            self.next += 1;
+            branches.push(self.parse_and()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Node::Or(branches)
+        })
+    }
+
+    // Adjacent operands are an implicit AND.
+    fn parse_and(&mut self) -> AppResult<Node> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                None | Some(TokenKind::RParen) | Some(TokenKind::Or) => break,
+                Some(TokenKind::And) => {
+                    if items.is_empty() {
+                        return Err(invalid(self.input, self.pos(), "AND needs a left operand"));
+                    }
+                    self.next += 1;
+                    items.push(self.parse_unary()?);
+                }
+                _ => items.push(self.parse_unary()?),
+            }
+        }
+        if items.is_empty() {
+            return Err(invalid(self.input, self.pos(), "expected a search term"));
+        }
+        Ok(Node::And(items))
+    }
+
+    fn parse_unary(&mut self) -> AppResult<Node> {
+        if let Some(Token {
+            kind: TokenKind::Not,
+            pos,
+        }) = self.peek()
+        {
+            let pos = *pos;
+            self.next += 1;
+            let node = self.parse_primary()?;
+            return Ok(Node::Not {
+                node: Box::new(node),
+                pos,
+            });
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> AppResult<Node> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(invalid(
+                self.input,
+                self.end_pos(),
+                "expected a search term",
+            ));
+        };
+        self.next += 1;
+        match token.kind {
+            TokenKind::LParen => {
+                let node = self.parse_or()?;
+                match self.peek() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => {
+                        self.next += 1;
+                        Ok(node)
+                    }
+                    _ => Err(invalid(self.input, token.pos, "unbalanced parenthesis")),
+                }
+            }
+            TokenKind::Phrase(text) => Ok(Node::Phrase(text)),
+            TokenKind::Term { text, prefix } => Ok(Node::Term { text, prefix }),
+            TokenKind::Field { name, value } => Ok(Node::Field {
+                name,
+                value,
+                pos: token.pos,
+            }),
+            TokenKind::RParen | TokenKind::And | TokenKind::Or | TokenKind::Not => {
+                Err(invalid(self.input, token.pos, "expected a search term"))
+            }
+        }
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// `after:` accepts YYYY, YYYY-MM or YYYY-MM-DD and means "on or after the start of that
+// period" in UTC.
+fn parse_after(input: &str, value: &str, pos: usize) -> AppResult<i64> {
+    let bad = || invalid(input, pos, "after: expects YYYY, YYYY-MM or YYYY-MM-DD");
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts.len() > 3
+        || parts[0].len() != 4
+        || parts[1..].iter().any(|p| p.len() != 2)
+        || parts.iter().any(|p| !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(bad());
+    }
+    let nums: Vec<i64> = parts.iter().map(|p| p.parse().unwrap_or(0)).collect();
+    let year = nums[0];
+    let month = nums.get(1).copied().unwrap_or(1);
+    let day = nums.get(2).copied().unwrap_or(1);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return Err(bad()),
+    };
+    if !(1..=month_days).contains(&day) {
+        return Err(bad());
+    }
+    Ok(days_from_civil(year, month, day) * DAY_MS)
+}
+
+struct Compiler<'a> {
+    input: &'a str,
+    vector_terms: Vec<String>,
+}
+
+impl Compiler<'_> {
+    fn compile(&mut self, node: &Node, negated: bool) -> AppResult<String> {
+        match node {
+            Node::Phrase(text) => {
+                if !negated {
+                    self.vector_terms.push(text.clone());
+                }
+                Ok(quote(text))
+            }
+            Node::Term { text, prefix } => {
+                if !negated {
+                    self.vector_terms.push(text.clone());
+                }
+                Ok(if *prefix {
+                    format!("{}*", quote(text))
+                } else {
+                    quote(text)
+                })
+            }
+            Node::Field { pos, .. } => Err(invalid(
+                self.input,
+                *pos,
+                "field operators must appear at the top level of the query",
+            )),
+            Node::Not { pos, .. } => Err(invalid(
+                self.input,
+                *pos,
+                "NOT needs a positive term in the same group",
+            )),
+            Node::Or(branches) => {
+                let parts = branches
+                    .iter()
+                    .map(|b| self.compile(b, negated))
+                    .collect::<AppResult<Vec<_>>>()?;
+                Ok(format!("({})", parts.join(" OR ")))
+            }
+            Node::And(items) => {
+                let mut positives = Vec::new();
+                let mut negatives = Vec::new();
+                for item in items {
+                    match item {
+                        Node::Not { node, .. } => negatives.push(self.compile(node, !negated)?),
+                        _ => positives.push(self.compile(item, negated)?),
+                    }
+                }
+                if positives.is_empty() {
+                    let pos = match &items[0] {
+                        Node::Not { pos, .. } => *pos,
+                        _ => 0,
+                    };
+                    return Err(invalid(
+                        self.input,
+                        pos,
+                        "NOT needs a positive term in the same group",
+                    ));
+                }
+                let mut out = if positives.len() == 1 {
+                    positives.remove(0)
+                } else {
+                    format!("({})", positives.join(" AND "))
+                };
+                for negative in negatives {
+                    out = format!("({out} NOT {negative})");
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+// Grammar: quoted phrases, bare terms, `term*` prefixes, parentheses, and the upper-case
+// operators AND, OR and NOT (NOT binds to the next operand and needs a positive sibling).
+// Adjacent operands are ANDed. `source:`, `mime:` and `after:` are doc filters and are only
+// valid at the top level, where they AND with the rest of the query. Every term is emitted
+// as an FTS5 string, so no user text is interpreted as FTS5 syntax.
+pub fn parse_query(input: &str) -> AppResult<ParsedQuery> {
+    let tokens = tokenize(input)?;
+    let mut filter = SearchFilterV1::default();
+    if tokens.is_empty() {
+        return Ok(ParsedQuery {
+            fts_expression: None,
+            vector_text: String::new(),
+            filter,
+        });
+    }
+    let mut parser = Parser {
+        input,
+        tokens,
+        next: 0,
+    };
+    let root = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(invalid(input, token.pos, "unbalanced parenthesis"));
+    }
+
+    let items = match root {
+        Node::And(items) => items,
+        other => vec![other],
+    };
+    let mut rest = Vec::new();
+    for item in items {
+        match item {
+            Node::Field { name, value, pos } => match name.as_str() {
+                "source" => filter.source_kinds.push(value),
+                "mime" => filter.mimes.push(value),
+                _ => {
+                    let from = parse_after(input, &value, pos)?;
+                    filter.effective_ts_from_ms =
+                        Some(filter.effective_ts_from_ms.map_or(from, |f| f.max(from)));
+                }
+            },
+            other => rest.push(other),
+        }
+    }
+
+    let mut compiler = Compiler {
+        input,
+        vector_terms: Vec::new(),
+    };
+    let fts_expression = if rest.is_empty() {
+        None
+    } else {
+        Some(compiler.compile(&Node::And(rest), false)?)
+    };
+    Ok(ParsedQuery {
+        fts_expression,
+        vector_text: compiler.vector_terms.join(" "),
+        filter,
+    })
+}
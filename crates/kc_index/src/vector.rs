@@ -1,4 +1,5 @@
 use crate::embedding::{Embedder, EmbeddingIdentity};
+use crate::query::parse_query;
 use arrow_array::{
     types::Float32Type, Array, FixedSizeListArray, Float32Array, Int64Array, RecordBatch,
     RecordBatchIterator, StringArray,
@@ -6,16 +7,23 @@ use arrow_array::{
 use arrow_schema::{DataType, Field, Schema};
 use futures::TryStreamExt;
 use kc_core::app_error::{AppError, AppResult};
-use kc_core::index_traits::{VectorCandidate, VectorIndex};
+use kc_core::index_traits::{IndexChunk, VectorCandidate, VectorIndex};
 use kc_core::types::{ChunkId, DocId};
-use lancedb::connect;
-use lancedb::query::ExecutableQuery;
+use lancedb::index::vector::{IvfHnswSqIndexBuilder, IvfPqIndexBuilder};
+use lancedb::index::Index;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::{OptimizeAction, OptimizeOptions};
+use lancedb::{connect, DistanceType, Table};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 const TABLE_NAME: &str = "chunks_vectors_v1";
 const IDENTITY_FILE: &str = "embedding_identity.json";
+const VECTOR_COLUMN: &str = "vector";
+const DISTANCE_COLUMN: &str = "_distance";
+const QUERY_OVERFETCH: usize = 16;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorRow {
@@ -26,22 +34,96 @@ pub struct VectorRow {
     pub vector: Vec<f32>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnIndexKind {
+    None,
+    IvfPq,
+    IvfHnswSq,
+}
+
+impl AnnIndexKind {
+    pub fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "none" => Ok(Self::None),
+            "ivf_pq" => Ok(Self::IvfPq),
+            "ivf_hnsw_sq" => Ok(Self::IvfHnswSq),
+            other => Err(AppError::new(
+                "KC_VECTOR_INDEX_INIT_FAILED",
+                "vector",
+                "unsupported ann index kind",
+                false,
+                serde_json::json!({
+                    "ann_index": other,
+                    "supported": ["none", "ivf_pq", "ivf_hnsw_sq"],
+                }),
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::IvfPq => "ivf_pq",
+            Self::IvfHnswSq => "ivf_hnsw_sq",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorIndexConfig {
+    pub ann_index: AnnIndexKind,
+    pub ann_row_threshold: usize,
+    pub nprobes: usize,
+    pub refine_factor: u32,
+}
+
+impl Default for VectorIndexConfig {
+    fn default() -> Self {
+        Self {
+            ann_index: AnnIndexKind::IvfHnswSq,
+            ann_row_threshold: 100_000,
+            nprobes: 20,
+            refine_factor: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorIndexStatus {
+    pub rows: usize,
+    pub ann_index: Option<String>,
+    pub indexed_rows: usize,
+    pub unindexed_rows: usize,
+}
+
 pub struct LanceDbVectorIndex<E: Embedder> {
     embedder: E,
     db_root: PathBuf,
-    rows: Vec<VectorRow>,
     identity: EmbeddingIdentity,
+    config: VectorIndexConfig,
+    write_lock: Mutex<()>,
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
-    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-    if na == 0.0 || nb == 0.0 {
-        0.0
-    } else {
-        dot / (na * nb)
-    }
+type ScoredRow = (ChunkId, DocId, i64, f32);
+
+// Cosine distance ascending, then the same doc/ordinal/chunk tie-break used across retrieval.
+fn sort_scored(scored: &mut [ScoredRow]) {
+    scored.sort_by(|a, b| {
+        a.3.total_cmp(&b.3)
+            .then(a.1 .0.cmp(&b.1 .0))
+            .then(a.2.cmp(&b.2))
+            .then(a.0 .0.cmp(&b.0 .0))
+    });
+}
+
+fn sort_rows(rows: &mut [VectorRow]) {
+    rows.sort_by(|a, b| {
+        a.doc_id
+            .0
+            .cmp(&b.doc_id.0)
+            .then(a.ordinal.cmp(&b.ordinal))
+            .then(a.chunk_id.0.cmp(&b.chunk_id.0))
+    });
 }
 
 fn to_db_root(path: &Path) -> PathBuf {
@@ -52,6 +134,25 @@ fn to_db_root(path: &Path) -> PathBuf {
     }
 }
 
+fn doc_predicate(doc_id: &DocId) -> String {
+    format!("doc_id = '{}'", doc_id.0.replace('\'', "''"))
+}
+
+fn docs_predicate(doc_ids: &[DocId]) -> String {
+    let quoted: Vec<String> = doc_ids
+        .iter()
+        .map(|d| format!("'{}'", d.0.replace('\'', "''")))
+        .collect();
+    format!("doc_id IN ({})", quoted.join(", "))
+}
+
+fn pq_sub_vectors(dims: usize) -> u32 {
+    (1..=(dims / 8).max(1))
+        .rev()
+        .find(|n| dims.is_multiple_of(*n))
+        .unwrap_or(1) as u32
+}
+
 fn with_rt<T>(fut: impl std::future::Future<Output = Result<T, lancedb::Error>>) -> AppResult<T> {
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -77,10 +178,148 @@ fn with_rt<T>(fut: impl std::future::Future<Output = Result<T, lancedb::Error>>)
     })
 }
 
+async fn open_table(db_uri: &str) -> Result<Option<Table>, lancedb::Error> {
+    let db = connect(db_uri).execute().await?;
+    let table_names = db.table_names().execute().await?;
+    if !table_names.iter().any(|n| n == TABLE_NAME) {
+        return Ok(None);
+    }
+    Ok(Some(db.open_table(TABLE_NAME).execute().await?))
+}
+
+async fn vector_index_name(table: &Table) -> Result<Option<String>, lancedb::Error> {
+    Ok(table
+        .list_indices()
+        .await?
+        .into_iter()
+        .find(|index| index.columns.iter().any(|c| c == VECTOR_COLUMN))
+        .map(|index| index.name))
+}
+
+fn string_column<'a>(
+    batch: &'a RecordBatch,
+    name: &str,
+) -> Result<&'a StringArray, lancedb::Error> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| lancedb::Error::Runtime {
+            message: format!("{name} column missing or invalid"),
+        })
+}
+
+fn int64_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a Int64Array, lancedb::Error> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+        .ok_or_else(|| lancedb::Error::Runtime {
+            message: format!("{name} column missing or invalid"),
+        })
+}
+
+fn decode_rows(batches: &[RecordBatch]) -> Result<Vec<VectorRow>, lancedb::Error> {
+    let mut rows = Vec::new();
+    for batch in batches {
+        let chunk_ids = string_column(batch, "chunk_id")?;
+        let doc_ids = string_column(batch, "doc_id")?;
+        let ordinals = int64_column(batch, "ordinal")?;
+        let texts = string_column(batch, "text")?;
+        let vectors = batch
+            .column_by_name(VECTOR_COLUMN)
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            .ok_or_else(|| lancedb::Error::Runtime {
+                message: "vector column missing or invalid".to_string(),
+            })?;
+
+        for idx in 0..batch.num_rows() {
+            let vector_values = vectors.value(idx);
+            let float_values = vector_values
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .ok_or_else(|| lancedb::Error::Runtime {
+                    message: "vector values were not float32".to_string(),
+                })?;
+            let mut vector = Vec::with_capacity(float_values.len());
+            for j in 0..float_values.len() {
+                vector.push(if float_values.is_null(j) {
+                    0.0
+                } else {
+                    float_values.value(j)
+                });
+            }
+
+            rows.push(VectorRow {
+                chunk_id: ChunkId(chunk_ids.value(idx).to_string()),
+                doc_id: DocId(doc_ids.value(idx).to_string()),
+                ordinal: ordinals.value(idx),
+                text: texts.value(idx).to_string(),
+                vector,
+            });
+        }
+    }
+    sort_rows(&mut rows);
+    Ok(rows)
+}
+
+fn decode_scored(batches: &[RecordBatch]) -> Result<Vec<ScoredRow>, lancedb::Error> {
+    let mut scored = Vec::new();
+    for batch in batches {
+        let chunk_ids = string_column(batch, "chunk_id")?;
+        let doc_ids = string_column(batch, "doc_id")?;
+        let ordinals = int64_column(batch, "ordinal")?;
+        let distances = batch
+            .column_by_name(DISTANCE_COLUMN)
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>())
+            .ok_or_else(|| lancedb::Error::Runtime {
+                message: "_distance column missing or invalid".to_string(),
+            })?;
+        for idx in 0..batch.num_rows() {
+            // Zero vectors have no cosine direction; rank them as orthogonal.
+            let distance = if distances.is_null(idx) || distances.value(idx).is_nan() {
+                1.0
+            } else {
+                distances.value(idx)
+            };
+            scored.push((
+                ChunkId(chunk_ids.value(idx).to_string()),
+                DocId(doc_ids.value(idx).to_string()),
+                ordinals.value(idx),
+                distance,
+            ));
+        }
+    }
+    sort_scored(&mut scored);
+    Ok(scored)
+}
+
 impl<E: Embedder> LanceDbVectorIndex<E> {
     pub fn open(embedder: E, db_path: impl AsRef<Path>) -> AppResult<Self> {
+        Self::open_with_config(embedder, db_path, VectorIndexConfig::default())
+    }
+
+    pub fn open_with_config(
+        embedder: E,
+        db_path: impl AsRef<Path>,
+        config: VectorIndexConfig,
+    ) -> AppResult<Self> {
+        let instance = Self::open_unchecked(embedder, db_path.as_ref(), config)?;
+        instance.check_stored_identity()?;
+        Ok(instance)
+    }
+
+    // Skips the stored identity check; `upsert_rows` replaces the table and rewrites the
+    // identity, which is how `kc_cli index rebuild` moves a vault to a different model.
+    pub fn open_for_rebuild(
+        embedder: E,
+        db_path: impl AsRef<Path>,
+        config: VectorIndexConfig,
+    ) -> AppResult<Self> {
+        Self::open_unchecked(embedder, db_path.as_ref(), config)
+    }
+
+    fn open_unchecked(embedder: E, db_path: &Path, config: VectorIndexConfig) -> AppResult<Self> {
         let identity = embedder.identity();
-        let db_root = to_db_root(db_path.as_ref());
+        let db_root = to_db_root(db_path);
 
         std::fs::create_dir_all(&db_root).map_err(|e| {
             AppError::new(
@@ -92,19 +331,96 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
             )
         })?;
 
-        let mut instance = Self {
+        Ok(Self {
             embedder,
             db_root,
-            rows: Vec::new(),
             identity,
-        };
-        instance.load_identity()?;
-        instance.load_rows()?;
-        Ok(instance)
+            config,
+            write_lock: Mutex::new(()),
+        })
     }
 
     pub fn upsert_rows(&mut self, mut rows: Vec<VectorRow>) -> AppResult<()> {
-        for row in &rows {
+        self.check_dims(&rows)?;
+        let _guard = self.lock_writes()?;
+        sort_rows(&mut rows);
+        self.persist_rows(&rows)?;
+        self.persist_identity()?;
+        self.maintain_ann_index()
+    }
+
+    pub fn replace_doc_rows(&self, doc_id: &DocId, mut rows: Vec<VectorRow>) -> AppResult<()> {
+        self.check_dims(&rows)?;
+        let _guard = self.lock_writes()?;
+        sort_rows(&mut rows);
+        self.write_doc_rows(doc_id, &rows)?;
+        self.persist_identity()?;
+        self.maintain_ann_index()
+    }
+
+    pub fn delete_doc_rows(&self, doc_id: &DocId) -> AppResult<usize> {
+        let _guard = self.lock_writes()?;
+        let predicate = doc_predicate(doc_id);
+        let db_uri = self.db_uri();
+        with_rt(async {
+            let Some(table) = open_table(&db_uri).await? else {
+                return Ok(0);
+            };
+            let removed = table.count_rows(Some(predicate.clone())).await?;
+            if removed > 0 {
+                table.delete(&predicate).await?;
+            }
+            Ok(removed)
+        })
+    }
+
+    pub fn rows_for_doc(&self, doc_id: &DocId) -> AppResult<Vec<VectorRow>> {
+        self.read_rows(Some(doc_predicate(doc_id)))
+    }
+
+    pub fn status(&self) -> AppResult<VectorIndexStatus> {
+        let db_uri = self.db_uri();
+        with_rt(async {
+            let Some(table) = open_table(&db_uri).await? else {
+                return Ok(VectorIndexStatus {
+                    rows: 0,
+                    ann_index: None,
+                    indexed_rows: 0,
+                    unindexed_rows: 0,
+                });
+            };
+            let rows = table.count_rows(None).await?;
+            let mut status = VectorIndexStatus {
+                rows,
+                ann_index: None,
+                indexed_rows: 0,
+                unindexed_rows: rows,
+            };
+            if let Some(name) = vector_index_name(&table).await? {
+                if let Some(stats) = table.index_stats(&name).await? {
+                    status.indexed_rows = stats.num_indexed_rows;
+                    status.unindexed_rows = stats.num_unindexed_rows;
+                }
+                status.ann_index = Some(name);
+            }
+            Ok(status)
+        })
+    }
+
+    pub fn embedding_identity(&self) -> &EmbeddingIdentity {
+        &self.identity
+    }
+
+    pub fn config(&self) -> &VectorIndexConfig {
+        &self.config
+    }
+
+    fn db_uri(&self) -> String {
+        self.db_root.to_string_lossy().to_string()
+    }
+
+    fn check_dims(&self, rows: &[VectorRow]) -> AppResult<()> {
+        for row in rows {
             if row.vector.len() != self.identity.dims {
                 return Err(AppError::new(
                     "KC_VECTOR_INDEX_INIT_FAILED",
@@ -119,23 +435,19 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
                 ));
             }
         }
-
-        rows.sort_by(|a, b| {
-            a.doc_id
-                .0
-                .cmp(&b.doc_id.0)
-                .then(a.ordinal.cmp(&b.ordinal))
-                .then(a.chunk_id.0.cmp(&b.chunk_id.0))
-        });
-
-        self.persist_rows(&rows)?;
-        self.rows = rows;
-        self.persist_identity()?;
         Ok(())
     }
 
-    pub fn embedding_identity(&self) -> &EmbeddingIdentity {
-        &self.identity
+    fn lock_writes(&self) -> AppResult<MutexGuard<'_, ()>> {
+        self.write_lock.lock().map_err(|_| {
+            AppError::new(
+                "KC_INTERNAL_ERROR",
+                "vector",
+                "failed acquiring vector write lock",
+                true,
+                serde_json::json!({}),
+            )
+        })
     }
 
     fn identity_path(&self) -> PathBuf {
@@ -163,10 +475,10 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
         })
     }
 
-    fn load_identity(&mut self) -> AppResult<()> {
+    pub fn stored_identity(&self) -> AppResult<Option<EmbeddingIdentity>> {
         let path = self.identity_path();
         if !path.exists() {
-            return Ok(());
+            return Ok(None);
         }
         let bytes = std::fs::read(&path).map_err(|e| {
             AppError::new(
@@ -177,7 +489,7 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
                 serde_json::json!({ "error": e.to_string(), "path": path }),
             )
         })?;
-        self.identity = serde_json::from_slice::<EmbeddingIdentity>(&bytes).map_err(|e| {
+        let stored = serde_json::from_slice::<EmbeddingIdentity>(&bytes).map_err(|e| {
             AppError::new(
                 "KC_VECTOR_INDEX_INIT_FAILED",
                 "vector",
@@ -186,17 +498,34 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
                 serde_json::json!({ "error": e.to_string(), "path": path }),
             )
         })?;
-        Ok(())
+        Ok(Some(stored))
     }
 
-    fn persist_rows(&self, rows: &[VectorRow]) -> AppResult<()> {
+    fn check_stored_identity(&self) -> AppResult<()> {
+        match self.stored_identity()? {
+            Some(stored) if stored != self.identity => Err(AppError::new(
+                "KC_EMBEDDING_IDENTITY_MISMATCH",
+                "vector",
+                "stored embedding identity does not match the configured embedding model; run index rebuild",
+                false,
+                serde_json::json!({
+                    "path": self.identity_path(),
+                    "stored": stored,
+                    "configured": self.identity,
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_batch(&self, rows: &[VectorRow]) -> AppResult<(Arc<Schema>, RecordBatch)> {
         let schema = Arc::new(Schema::new(vec![
             Field::new("chunk_id", DataType::Utf8, false),
             Field::new("doc_id", DataType::Utf8, false),
             Field::new("ordinal", DataType::Int64, false),
             Field::new("text", DataType::Utf8, false),
             Field::new(
-                "vector",
+                VECTOR_COLUMN,
                 DataType::FixedSizeList(
                     Arc::new(Field::new("item", DataType::Float32, true)),
                     self.identity.dims as i32,
@@ -240,8 +569,13 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
             )
         })?;
 
+        Ok((schema, batch))
+    }
+
+    fn persist_rows(&self, rows: &[VectorRow]) -> AppResult<()> {
+        let (schema, batch) = self.record_batch(rows)?;
         let batches = RecordBatchIterator::new(vec![Ok(batch)].into_iter(), schema);
-        let db_uri = self.db_root.to_string_lossy().to_string();
+        let db_uri = self.db_uri();
 
         with_rt(async {
             let db = connect(&db_uri).execute().await?;
@@ -256,105 +590,153 @@ impl<E: Embedder> LanceDbVectorIndex<E> {
         })
     }
 
-    fn load_rows(&mut self) -> AppResult<()> {
-        let db_uri = self.db_root.to_string_lossy().to_string();
-        self.rows = with_rt(async {
+    // Only the doc's rows are touched; the rest of the table is left as-is.
+    fn write_doc_rows(&self, doc_id: &DocId, rows: &[VectorRow]) -> AppResult<()> {
+        let predicate = doc_predicate(doc_id);
+        let batch = if rows.is_empty() {
+            None
+        } else {
+            Some(self.record_batch(rows)?)
+        };
+        let db_uri = self.db_uri();
+
+        with_rt(async {
             let db = connect(&db_uri).execute().await?;
             let table_names = db.table_names().execute().await?;
-            if !table_names.iter().any(|n| n == TABLE_NAME) {
-                return Ok(Vec::new());
-            }
-
-            let table = db.open_table(TABLE_NAME).execute().await?;
-            let batches = table
-                .query()
+            if table_names.iter().any(|n| n == TABLE_NAME) {
+                let table = db.open_table(TABLE_NAME).execute().await?;
+                table.delete(&predicate).await?;
+                if let Some((schema, batch)) = batch {
+                    table
+                        .add(RecordBatchIterator::new(
+                            vec![Ok(batch)].into_iter(),
+                            schema,
+                        ))
+                        .execute()
+                        .await?;
+                }
+            } else if let Some((schema, batch)) = batch {
+                db.create_table(
+                    TABLE_NAME,
+                    Box::new(RecordBatchIterator::new(
+                        vec![Ok(batch)].into_iter(),
+                        schema,
+                    )),
+                )
                 .execute()
-                .await?
-                .try_collect::<Vec<_>>()
                 .await?;
-            let mut rows = Vec::new();
-
-            for batch in batches {
-                let chunk_ids = batch
-                    .column_by_name("chunk_id")
-                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| lancedb::Error::Runtime {
-                        message: "chunk_id column missing or invalid".to_string(),
-                    })?;
-                let doc_ids = batch
-                    .column_by_name("doc_id")
-                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| lancedb::Error::Runtime {
-                        message: "doc_id column missing or invalid".to_string(),
-                    })?;
-                let ordinals = batch
-                    .column_by_name("ordinal")
-                    .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
-                    .ok_or_else(|| lancedb::Error::Runtime {
-                        message: "ordinal column missing or invalid".to_string(),
-                    })?;
-                let texts = batch
-                    .column_by_name("text")
-                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
-                    .ok_or_else(|| lancedb::Error::Runtime {
-                        message: "text column missing or invalid".to_string(),
-                    })?;
-                let vectors = batch
-                    .column_by_name("vector")
-                    .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
-                    .ok_or_else(|| lancedb::Error::Runtime {
-                        message: "vector column missing or invalid".to_string(),
-                    })?;
-
-                for idx in 0..batch.num_rows() {
-                    let vector_values = vectors.value(idx);
-                    let float_values = vector_values
-                        .as_any()
-                        .downcast_ref::<Float32Array>()
-                        .ok_or_else(|| lancedb::Error::Runtime {
-                            message: "vector values were not float32".to_string(),
-                        })?;
-                    let mut vector = Vec::with_capacity(float_values.len());
-                    for j in 0..float_values.len() {
-                        vector.push(if float_values.is_null(j) {
-                            0.0
-                        } else {
-                            float_values.value(j)
-                        });
-                    }
-
-                    rows.push(VectorRow {
-                        chunk_id: ChunkId(chunk_ids.value(idx).to_string()),
-                        doc_id: DocId(doc_ids.value(idx).to_string()),
-                        ordinal: ordinals.value(idx),
-                        text: texts.value(idx).to_string(),
-                        vector,
-                    });
-                }
             }
+            Ok(())
+        })
+    }
 
-            rows.sort_by(|a, b| {
-                a.doc_id
-                    .0
-                    .cmp(&b.doc_id.0)
-                    .then(a.ordinal.cmp(&b.ordinal))
-                    .then(a.chunk_id.0.cmp(&b.chunk_id.0))
-            });
+    // Builds the ANN index once the table crosses the row threshold and folds newly
+    // written rows into it; below the threshold queries stay exact flat scans.
+    fn maintain_ann_index(&self) -> AppResult<()> {
+        let index = match self.config.ann_index {
+            AnnIndexKind::None => return Ok(()),
+            AnnIndexKind::IvfPq => Index::IvfPq(
+                IvfPqIndexBuilder::default()
+                    .distance_type(DistanceType::Cosine)
+                    .num_sub_vectors(pq_sub_vectors(self.identity.dims)),
+            ),
+            AnnIndexKind::IvfHnswSq => Index::IvfHnswSq(
+                IvfHnswSqIndexBuilder::default().distance_type(DistanceType::Cosine),
+            ),
+        };
+        let threshold = self.config.ann_row_threshold;
+        let db_uri = self.db_uri();
 
-            Ok(rows)
-        })?;
+        with_rt(async {
+            let Some(table) = open_table(&db_uri).await? else {
+                return Ok(());
+            };
+            if table.count_rows(None).await? < threshold {
+                return Ok(());
+            }
+            match vector_index_name(&table).await? {
+                None => {
+                    table
+                        .create_index(&[VECTOR_COLUMN], index)
+                        .execute()
+                        .await?;
+                }
+                Some(name) => {
+                    let unindexed = table
+                        .index_stats(&name)
+                        .await?
+                        .map(|stats| stats.num_unindexed_rows)
+                        .unwrap_or(0);
+                    if unindexed >= (threshold / 10).max(1) {
+                        table
+                            .optimize(OptimizeAction::Index(OptimizeOptions::default()))
+                            .await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
 
-        Ok(())
+    fn read_rows(&self, filter: Option<String>) -> AppResult<Vec<VectorRow>> {
+        let db_uri = self.db_uri();
+        with_rt(async {
+            let Some(table) = open_table(&db_uri).await? else {
+                return Ok(Vec::new());
+            };
+            let mut query = table.query();
+            if let Some(filter) = filter {
+                query = query.only_if(filter);
+            }
+            let batches = query.execute().await?.try_collect::<Vec<_>>().await?;
+            decode_rows(&batches)
+        })
     }
-}
 
-impl<E: Embedder> VectorIndex for LanceDbVectorIndex<E> {
-    fn rebuild_for_doc(&self, _doc_id: &DocId) -> AppResult<()> {
-        Ok(())
+    // `filter` is applied as a Lance prefilter, before the nearest-neighbour cut-off.
+    fn search(
+        &self,
+        vector: &[f32],
+        fetch: usize,
+        filter: Option<&str>,
+    ) -> AppResult<Vec<ScoredRow>> {
+        let db_uri = self.db_uri();
+        let config = self.config.clone();
+        with_rt(async {
+            let Some(table) = open_table(&db_uri).await? else {
+                return Ok(Vec::new());
+            };
+            let mut query = table.vector_search(vector.to_vec())?;
+            if let Some(filter) = filter {
+                query = query.only_if(filter);
+            }
+            let batches = query
+                .column(VECTOR_COLUMN)
+                .distance_type(DistanceType::Cosine)
+                .nprobes(config.nprobes)
+                .refine_factor(config.refine_factor)
+                .select(Select::columns(&["chunk_id", "doc_id", "ordinal"]))
+                .limit(fetch)
+                .execute()
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+            decode_scored(&batches)
+        })
     }
 
-    fn query(&self, query: &str, limit: usize) -> AppResult<Vec<VectorCandidate>> {
-        let vectors = self.embedder.embed(&[query.to_string()])?;
+    fn ranked_candidates(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&str>,
+    ) -> AppResult<Vec<VectorCandidate>> {
+        // Operators, negated terms and field filters are not embedded.
+        let text = parse_query(query)?.vector_text;
+        if limit == 0 || text.is_empty() {
+            return Ok(Vec::new());
+        }
+        let vectors = self.embedder.embed(&[text])?;
         let q = vectors.first().ok_or_else(|| {
             AppError::new(
                 "KC_VECTOR_QUERY_FAILED",
@@ -365,26 +747,19 @@ impl<E: Embedder> VectorIndex for LanceDbVectorIndex<E> {
             )
         })?;
 
-        let mut scored: Vec<(ChunkId, DocId, i64, f32)> = self
-            .rows
-            .iter()
-            .map(|row| {
-                (
-                    row.chunk_id.clone(),
-                    row.doc_id.clone(),
-                    row.ordinal,
-                    cosine_similarity(&row.vector, q),
-                )
-            })
-            .collect();
-
-        scored.sort_by(|a, b| {
-            b.3.partial_cmp(&a.3)
-                .unwrap_or(std::cmp::Ordering::Equal)
-                .then(a.1 .0.cmp(&b.1 .0))
-                .then(a.2.cmp(&b.2))
-                .then(a.0 .0.cmp(&b.0 .0))
-        });
+        // Over-fetch so ties at the cut-off are broken by doc/ordinal/chunk rather than by
+        // scan order; widen until the tie group ends inside the fetched window.
+        let mut fetch = limit + QUERY_OVERFETCH;
+        let scored = loop {
+            let scored = self.search(q, fetch, filter)?;
+            let tie_crosses_window = scored.len() == fetch
+                && scored.len() > limit
+                && scored[limit - 1].3 == scored[scored.len() - 1].3;
+            if !tie_crosses_window {
+                break scored;
+            }
+            fetch *= 2;
+        };
 
         Ok(scored
             .into_iter()
@@ -397,3 +772,90 @@ impl<E: Embedder> VectorIndex for LanceDbVectorIndex<E> {
             .collect())
     }
 }
+
+impl<E: Embedder> VectorIndex for LanceDbVectorIndex<E> {
+    fn rebuild_for_doc(&self, doc_id: &DocId, chunks: &[IndexChunk]) -> AppResult<()> {
+        // Vectors of chunks whose id and text are unchanged are reused instead of re-embedded.
+        let current = self.rows_for_doc(doc_id)?;
+        let existing: HashMap<&str, &VectorRow> = current
+            .iter()
+            .map(|row| (row.chunk_id.0.as_str(), row))
+            .collect();
+        let unchanged = existing.len() == chunks.len()
+            && chunks.iter().all(|chunk| {
+                existing
+                    .get(chunk.chunk_id.0.as_str())
+                    .is_some_and(|row| row.text == chunk.text && row.ordinal == chunk.ordinal)
+            });
+        if unchanged {
+            return Ok(());
+        }
+        let mut vectors: Vec<Option<Vec<f32>>> = chunks
+            .iter()
+            .map(|chunk| {
+                existing
+                    .get(chunk.chunk_id.0.as_str())
+                    .filter(|row| row.text == chunk.text)
+                    .map(|row| row.vector.clone())
+            })
+            .collect();
+
+        let texts: Vec<String> = chunks
+            .iter()
+            .zip(&vectors)
+            .filter(|(_, vector)| vector.is_none())
+            .map(|(chunk, _)| chunk.text.clone())
+            .collect();
+        let embedded = if texts.is_empty() {
+            Vec::new()
+        } else {
+            self.embedder.embed(&texts)?
+        };
+        if embedded.len() != texts.len() {
+            return Err(AppError::new(
+                "KC_EMBEDDING_FAILED",
+                "vector",
+                "embedder returned an unexpected number of vectors",
+                false,
+                serde_json::json!({ "expected": texts.len(), "actual": embedded.len() }),
+            ));
+        }
+        let mut embedded = embedded.into_iter();
+        for slot in vectors.iter_mut().filter(|v| v.is_none()) {
+            *slot = embedded.next();
+        }
+
+        let rows = chunks
+            .iter()
+            .zip(vectors)
+            .map(|(chunk, vector)| VectorRow {
+                chunk_id: chunk.chunk_id.clone(),
+                doc_id: doc_id.clone(),
+                ordinal: chunk.ordinal,
+                text: chunk.text.clone(),
+                vector: vector.unwrap_or_default(),
+            })
+            .collect();
+        self.replace_doc_rows(doc_id, rows)
+    }
+
+    fn delete_for_doc(&self, doc_id: &DocId) -> AppResult<()> {
+        self.delete_doc_rows(doc_id).map(|_| ())
+    }
+
+    fn query(&self, query: &str, limit: usize) -> AppResult<Vec<VectorCandidate>> {
+        self.ranked_candidates(query, limit, None)
+    }
+
+    fn query_in_docs(
+        &self,
+        query: &str,
+        limit: usize,
+        doc_ids: &[DocId],
+    ) -> AppResult<Vec<VectorCandidate>> {
+        if doc_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.ranked_candidates(query, limit, Some(&docs_predicate(doc_ids)))
+    }
+}
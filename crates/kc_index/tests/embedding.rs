@@ -0,0 +1,322 @@
+use kc_core::faithfulness::AlignmentModel;
+use kc_core::hashing::blake3_hex_prefixed;
+use kc_core::index_traits::{IndexChunk, VectorIndex};
+use kc_core::rerank::RerankInput;
+use kc_core::types::{ChunkId, DocId};
+use kc_core::vault::{
+    vault_init, vault_open, vault_save, VaultAlignmentModelDefaults, VaultEmbeddingModel,
+    VaultRerankerDefaults, VaultVerifierDefaults,
+};
+use kc_index::embedding::Embedder;
+use kc_index::embedding_registry::{open_vault_embedder, EmbedderRegistry};
+use kc_index::local_alignment::open_vault_alignment_model;
+use kc_index::local_embedder::StaticEmbedder;
+use kc_index::local_reranker::open_vault_reranker;
+use kc_index::open_vault_indexes;
+use kc_index::vector::{LanceDbVectorIndex, VectorIndexConfig, VectorRow};
+use std::path::Path;
+
+fn safetensors_bytes(dtype: &str, shape: [usize; 2], data: &[u8]) -> Vec<u8> {
+    let header = serde_json::json!({
+        "__metadata__": { "format": "pt" },
+        "embeddings": {
+            "dtype": dtype,
+            "shape": shape,
+            "data_offsets": [0, data.len()],
+        },
+    })
+    .to_string();
+    let mut out = (header.len() as u64).to_le_bytes().to_vec();
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+fn write_static_model(dir: &Path, model: &[u8]) {
+    std::fs::create_dir_all(dir).expect("model dir");
+    std::fs::write(dir.join("model.safetensors"), model).expect("write model");
+    let tokenizer = serde_json::json!({
+        "normalizer": { "type": "BertNormalizer", "lowercase": true },
+        "model": {
+            "type": "WordPiece",
+            "unk_token": "[UNK]",
+            "continuing_subword_prefix": "##",
+            "max_input_chars_per_word": 100,
+            "vocab": { "[UNK]": 0, "alpha": 1, "beta": 2, "##s": 3 },
+        },
+    });
+    std::fs::write(
+        dir.join("tokenizer.json"),
+        serde_json::to_vec(&tokenizer).expect("tokenizer json"),
+    )
+    .expect("write tokenizer");
+}
+
+fn f32_model() -> Vec<u8> {
+    let rows: [[f32; 3]; 4] = [
+        [9.0, 9.0, 9.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+    let data: Vec<u8> = rows
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    safetensors_bytes("F32", [4, 3], &data)
+}
+
+fn register_static_model(vault_root: &Path, model_id: &str) {
+    let mut vault = vault_open(vault_root).expect("vault open");
+    vault.defaults.embedding_models.insert(
+        model_id.to_string(),
+        VaultEmbeddingModel {
+            backend: "static_safetensors".to_string(),
+            path: "models/static".to_string(),
+        },
+    );
+    vault.defaults.embedding_model_id = model_id.to_string();
+    vault_save(vault_root, &vault).expect("vault save");
+}
+
+#[test]
+fn static_embedder_mean_pools_wordpiece_tokens_and_hashes_model_file() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let model_dir = temp.path().join("model");
+    let model = f32_model();
+    write_static_model(&model_dir, &model);
+
+    let embedder = StaticEmbedder::load("embedding/static-test", &model_dir).expect("load");
+    let identity = embedder.identity();
+    assert_eq!(identity.model_id, "embedding/static-test");
+    assert_eq!(identity.model_hash, blake3_hex_prefixed(&model));
+    assert_eq!(identity.dims, 3);
+
+    let vectors = embedder
+        .embed(&[
+            "Alphas".to_string(),
+            "beta, zeta".to_string(),
+            "zeta".to_string(),
+        ])
+        .expect("embed");
+    let half = 1.0 / 2f32.sqrt();
+    assert_eq!(vectors[0], vec![half, 0.0, half]);
+    assert_eq!(vectors[1], vec![0.0, 1.0, 0.0]);
+    assert_eq!(vectors[2], vec![0.0, 0.0, 0.0]);
+
+    let f16: Vec<u8> = [0x3c00u16, 0x0000, 0x0000]
+        .iter()
+        .cycle()
+        .take(12)
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let f16_dir = temp.path().join("model-f16");
+    write_static_model(&f16_dir, &safetensors_bytes("F16", [4, 3], &f16));
+    let f16_embedder = StaticEmbedder::load("embedding/static-f16", &f16_dir).expect("load f16");
+    assert_eq!(
+        f16_embedder.embed(&["beta".to_string()]).expect("embed")[0],
+        vec![1.0, 0.0, 0.0]
+    );
+
+    std::fs::write(
+        model_dir.join("model.safetensors"),
+        safetensors_bytes("I8", [4, 3], &[0; 12]),
+    )
+    .expect("write bad model");
+    assert_eq!(
+        StaticEmbedder::load("embedding/static-test", &model_dir)
+            .err()
+            .expect("unsupported dtype")
+            .code,
+        "KC_EMBEDDING_MODEL_INVALID"
+    );
+}
+
+#[test]
+fn registry_resolves_vault_model_and_rejects_unknown_ids() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    write_static_model(&vault_root.join("models/static"), &f32_model());
+
+    let default = open_vault_embedder(&vault_root).expect("default embedder");
+    assert_eq!(default.identity().model_id, "deterministic-v1");
+
+    register_static_model(&vault_root, "embedding/static-test");
+    let vault = vault_open(&vault_root).expect("vault open");
+    let registry = EmbedderRegistry::for_vault(&vault_root, &vault).expect("registry");
+    assert_eq!(
+        registry.model_ids(),
+        vec!["embedding/default-v1", "embedding/static-test"]
+    );
+    let embedder = open_vault_embedder(&vault_root).expect("static embedder");
+    assert_eq!(
+        embedder.identity().model_hash,
+        blake3_hex_prefixed(&f32_model())
+    );
+
+    let missing = registry
+        .load("embedding/missing")
+        .err()
+        .expect("unknown model id");
+    assert_eq!(missing.code, "KC_EMBEDDING_MODEL_NOT_FOUND");
+}
+
+#[test]
+fn vault_indexes_reject_stored_identity_from_another_model_until_rebuild() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    write_static_model(&vault_root.join("models/static"), &f32_model());
+
+    let doc_id = DocId("d1".to_string());
+    let chunks = [IndexChunk {
+        chunk_id: ChunkId("c1".to_string()),
+        doc_id: doc_id.clone(),
+        ordinal: 0,
+        text: "alpha beta".to_string(),
+    }];
+    let indexes = open_vault_indexes(&vault_root).expect("open default indexes");
+    indexes
+        .vector
+        .rebuild_for_doc(&doc_id, &chunks)
+        .expect("index with default model");
+    drop(indexes);
+
+    register_static_model(&vault_root, "embedding/static-test");
+    let err = open_vault_indexes(&vault_root)
+        .err()
+        .expect("identity mismatch");
+    assert_eq!(err.code, "KC_EMBEDDING_IDENTITY_MISMATCH");
+    assert_eq!(err.details["stored"]["model_id"], "deterministic-v1");
+    assert_eq!(
+        err.details["configured"]["model_id"],
+        "embedding/static-test"
+    );
+
+    let embedder = open_vault_embedder(&vault_root).expect("static embedder");
+    let vectors_path = vault_root.join("index/vectors/lancedb-v1");
+    let vector = embedder
+        .embed(&[chunks[0].text.clone()])
+        .expect("embed")
+        .remove(0);
+    let mut rebuilt =
+        LanceDbVectorIndex::open_for_rebuild(embedder, &vectors_path, VectorIndexConfig::default())
+            .expect("open for rebuild");
+    rebuilt
+        .upsert_rows(vec![VectorRow {
+            chunk_id: chunks[0].chunk_id.clone(),
+            doc_id: doc_id.clone(),
+            ordinal: 0,
+            text: chunks[0].text.clone(),
+            vector,
+        }])
+        .expect("rebuild rows");
+    drop(rebuilt);
+
+    let indexes = open_vault_indexes(&vault_root).expect("open after rebuild");
+    assert_eq!(
+        indexes.vector.stored_identity().expect("stored identity"),
+        Some(indexes.vector.embedding_identity().clone())
+    );
+    assert_eq!(
+        indexes.vector.query("beta", 1).expect("query")[0]
+            .chunk_id
+            .0,
+        "c1"
+    );
+}
+
+#[test]
+fn vault_reranker_loads_static_maxsim_and_rejects_bad_config() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    assert!(open_vault_reranker(&vault_root).expect("none").is_none());
+
+    write_static_model(&vault_root.join("models/static"), &f32_model());
+    let mut vault = vault_open(&vault_root).expect("vault open");
+    vault.defaults.reranker = Some(VaultRerankerDefaults {
+        backend: "static_safetensors".to_string(),
+        path: Some("models/static".to_string()),
+        top_k: 10,
+    });
+    vault_save(&vault_root, &vault).expect("vault save");
+
+    let reranker = open_vault_reranker(&vault_root)
+        .expect("load")
+        .expect("configured");
+    assert_eq!(reranker.reranker_id(), "token_maxsim:models/static");
+    let inputs: Vec<RerankInput> = ["alpha", "alpha beta", "betas", "gamma"]
+        .iter()
+        .enumerate()
+        .map(|(idx, text)| RerankInput {
+            chunk_id: ChunkId(format!("c{idx}")),
+            text: text.to_string(),
+        })
+        .collect();
+    let scores: Vec<f64> = reranker
+        .score("alpha beta", &inputs)
+        .expect("score")
+        .into_iter()
+        .map(|s| s.score)
+        .collect();
+    assert_eq!(scores, vec![0.5, 1.0, 0.5, 0.0]);
+
+    vault.defaults.reranker = Some(VaultRerankerDefaults {
+        backend: "static_safetensors".to_string(),
+        path: None,
+        top_k: 10,
+    });
+    vault_save(&vault_root, &vault).expect("vault save");
+    let err = open_vault_reranker(&vault_root)
+        .err()
+        .expect("missing path");
+    assert_eq!(err.code, "KC_RETRIEVAL_RERANKER_INVALID");
+}
+
+#[test]
+fn vault_alignment_model_loads_static_model_and_rejects_bad_backend() {
+    let temp = tempfile::tempdir().expect("tempdir");
+    let vault_root = temp.path().join("vault");
+    vault_init(&vault_root, "demo", 1).expect("vault init");
+    assert!(open_vault_alignment_model(&vault_root)
+        .expect("none")
+        .is_none());
+
+    write_static_model(&vault_root.join("models/static"), &f32_model());
+    let mut vault = vault_open(&vault_root).expect("vault open");
+    vault.defaults.verifier = Some(VaultVerifierDefaults {
+        threshold: 0.5,
+        alignment: Some(VaultAlignmentModelDefaults {
+            backend: "static_safetensors".to_string(),
+            path: "models/static".to_string(),
+        }),
+    });
+    vault_save(&vault_root, &vault).expect("vault save");
+
+    let model = open_vault_alignment_model(&vault_root)
+        .expect("load")
+        .expect("configured");
+    assert_eq!(model.model_id(), "models/static");
+    assert_eq!(
+        model
+            .alignment("alpha beta", &["alpha", "betas", "gamma"])
+            .expect("alignment"),
+        vec![1.0, 0.5, 0.0]
+    );
+
+    vault.defaults.verifier = Some(VaultVerifierDefaults {
+        threshold: 0.5,
+        alignment: Some(VaultAlignmentModelDefaults {
+            backend: "onnx".to_string(),
+            path: "models/static".to_string(),
+        }),
+    });
+    vault_save(&vault_root, &vault).expect("vault save");
+    let err = open_vault_alignment_model(&vault_root)
+        .err()
+        .expect("unsupported backend");
+    assert_eq!(err.code, "KC_ASK_VERIFIER_INVALID");
+}
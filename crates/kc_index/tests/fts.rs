@@ -1,12 +1,17 @@
-use kc_index::fts::{init_fts, query, rebuild_rows, FtsRow};
+use kc_core::index_traits::LexicalIndex;
+use kc_index::fts::{
+    delete_doc_rows, heading_text, init_fts, query, rebuild_rows, replace_doc_rows, FtsConfig,
+    FtsRow, FtsTokenizer, SqliteFtsIndex,
+};
 
 #[test]
 fn fts_rebuild_order_is_deterministic() {
     let conn = rusqlite::Connection::open_in_memory().expect("memory db");
-    init_fts(&conn).expect("init");
+    init_fts(&conn, &FtsConfig::default()).expect("init");
 
     rebuild_rows(
         &conn,
+        &FtsConfig::default(),
         &[
             FtsRow {
                 chunk_id: "c3".to_string(),
@@ -46,6 +51,7 @@ fn fts_query_returns_candidates() {
     let conn = rusqlite::Connection::open_in_memory().expect("memory db");
     rebuild_rows(
         &conn,
+        &FtsConfig::default(),
         &[
             FtsRow {
                 chunk_id: "c1".to_string(),
@@ -67,3 +73,221 @@ fn fts_query_returns_candidates() {
     assert_eq!(hits.len(), 1);
     assert_eq!(hits[0].chunk_id.0, "c1");
 }
+
+#[test]
+fn fts_replace_doc_rows_only_touches_that_doc() {
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+    rebuild_rows(
+        &conn,
+        &FtsConfig::default(),
+        &[
+            FtsRow {
+                chunk_id: "c1".to_string(),
+                doc_id: "d1".to_string(),
+                ordinal: 0,
+                content: "hello world".to_string(),
+            },
+            FtsRow {
+                chunk_id: "c2".to_string(),
+                doc_id: "d2".to_string(),
+                ordinal: 0,
+                content: "hello again".to_string(),
+            },
+        ],
+    )
+    .expect("rebuild");
+
+    replace_doc_rows(
+        &conn,
+        "d1",
+        &[FtsRow {
+            chunk_id: "c3".to_string(),
+            doc_id: "d1".to_string(),
+            ordinal: 0,
+            content: "replacement text".to_string(),
+        }],
+    )
+    .expect("replace doc rows");
+
+    let hello: Vec<String> = query(&conn, "hello", 10)
+        .expect("query hello")
+        .into_iter()
+        .map(|c| c.chunk_id.0)
+        .collect();
+    assert_eq!(hello, vec!["c2"]);
+
+    let replaced = query(&conn, "replacement", 10).expect("query replacement");
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(replaced[0].chunk_id.0, "c3");
+
+    assert_eq!(delete_doc_rows(&conn, "d2").expect("delete d2"), 1);
+    assert_eq!(delete_doc_rows(&conn, "d2").expect("delete d2 again"), 0);
+    assert!(query(&conn, "hello", 10).expect("query hello").is_empty());
+    assert_eq!(query(&conn, "replacement", 10).expect("query").len(), 1);
+}
+
+fn row(chunk_id: &str, content: &str) -> FtsRow {
+    FtsRow {
+        chunk_id: chunk_id.to_string(),
+        doc_id: format!("d-{chunk_id}"),
+        ordinal: 0,
+        content: content.to_string(),
+    }
+}
+
+fn ids(conn: &rusqlite::Connection, q: &str) -> Vec<String> {
+    query(conn, q, 10)
+        .expect(q)
+        .into_iter()
+        .map(|c| c.chunk_id.0)
+        .collect()
+}
+
+#[test]
+fn fts_tokenizer_config_controls_stemming_substrings_and_diacritics() {
+    let rows = [
+        row("c1", "Rotating the signing keys"),
+        row("c2", "Café menu in Zürich"),
+    ];
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+
+    rebuild_rows(&conn, &FtsConfig::default(), &rows).expect("unicode61");
+    assert!(ids(&conn, "\"rotate\"").is_empty());
+    assert_eq!(ids(&conn, "\"cafe\" AND \"zurich\""), vec!["c2"]);
+
+    let porter = FtsConfig {
+        tokenizer: FtsTokenizer::Porter,
+        ..FtsConfig::default()
+    };
+    rebuild_rows(&conn, &porter, &rows).expect("porter");
+    assert_eq!(ids(&conn, "\"rotate\""), vec!["c1"]);
+    assert_eq!(ids(&conn, "\"key\""), vec!["c1"]);
+
+    let trigram = FtsConfig {
+        tokenizer: FtsTokenizer::Trigram,
+        ..FtsConfig::default()
+    };
+    rebuild_rows(&conn, &trigram, &rows).expect("trigram");
+    assert_eq!(ids(&conn, "\"igning\""), vec!["c1"]);
+    assert_eq!(ids(&conn, "\"zuri\""), vec!["c2"]);
+
+    let exact = FtsConfig {
+        remove_diacritics: 0,
+        ..FtsConfig::default()
+    };
+    rebuild_rows(&conn, &exact, &rows).expect("no diacritic folding");
+    assert!(ids(&conn, "\"cafe\"").is_empty());
+    assert_eq!(ids(&conn, "\"café\""), vec!["c2"]);
+
+    let invalid = FtsConfig {
+        remove_diacritics: 2,
+        ..trigram
+    };
+    assert_eq!(
+        init_fts(&conn, &invalid).expect_err("range").code,
+        "KC_FTS_INIT_FAILED"
+    );
+}
+
+#[test]
+fn fts_heading_weight_boosts_chunks_whose_headings_match() {
+    assert_eq!(
+        heading_text("[[PAGE:0001]]\n[[H1:Key rotation]]\nbody\n[[H2: Schedule ]]\n[[Hx:no]]"),
+        "Key rotation\nSchedule"
+    );
+
+    let rows = [
+        row("body", "rotation rotation is mentioned in passing here"),
+        row("heading", "[[H1:Rotation]]\nthe procedure for keys is long"),
+    ];
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+    rebuild_rows(
+        &conn,
+        &FtsConfig {
+            heading_weight: 0.0,
+            ..FtsConfig::default()
+        },
+        &rows,
+    )
+    .expect("rebuild");
+    assert_eq!(ids(&conn, "rotation"), vec!["body", "heading"]);
+
+    let index = SqliteFtsIndex::new(
+        conn,
+        &FtsConfig {
+            heading_weight: 10.0,
+            ..FtsConfig::default()
+        },
+    )
+    .expect("reweighted without rebuild");
+    let hits = index.query("rotation", 10).expect("query");
+    let order: Vec<&str> = hits.iter().map(|c| c.chunk_id.0.as_str()).collect();
+    assert_eq!(order, vec!["heading", "body"]);
+    assert_eq!(hits[0].rank, 1);
+    let (top, next) = (hits[0].bm25.expect("bm25"), hits[1].bm25.expect("bm25"));
+    assert!(top > next && next > 0.0, "{top} {next}");
+}
+
+#[test]
+fn fts_table_built_with_other_config_requires_rebuild() {
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE chunks_fts
+         USING fts5(chunk_id UNINDEXED, doc_id UNINDEXED, content, tokenize='unicode61');",
+    )
+    .expect("legacy table");
+    let err = init_fts(&conn, &FtsConfig::default()).expect_err("legacy");
+    assert_eq!(err.code, "KC_FTS_CONFIG_MISMATCH");
+
+    rebuild_rows(&conn, &FtsConfig::default(), &[row("c1", "alpha")]).expect("rebuild");
+    init_fts(&conn, &FtsConfig::default()).expect("current");
+    let porter = FtsConfig {
+        tokenizer: FtsTokenizer::Porter,
+        ..FtsConfig::default()
+    };
+    assert_eq!(
+        init_fts(&conn, &porter)
+            .expect_err("tokenizer changed")
+            .code,
+        "KC_FTS_CONFIG_MISMATCH"
+    );
+}
+
+#[test]
+fn fts_index_excluding_superseded_drops_old_versions_before_the_limit() {
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+    conn.execute_batch(
+        "CREATE TABLE doc_versions (source_path TEXT, version INTEGER, doc_id TEXT, event_id INTEGER);
+         INSERT INTO doc_versions VALUES ('notes.md', 1, 'd-old', 1), ('notes.md', 2, 'd-new', 2);",
+    )
+    .expect("versions");
+    rebuild_rows(
+        &conn,
+        &FtsConfig::default(),
+        &[
+            row("old", "rotation rotation rotation schedule"),
+            row("new", "rotation schedule"),
+        ],
+    )
+    .expect("rebuild");
+    assert_eq!(ids(&conn, "rotation"), vec!["old", "new"]);
+
+    let index = SqliteFtsIndex::new(conn, &FtsConfig::default())
+        .expect("index")
+        .excluding_superseded();
+    let hits = index.query("rotation", 1).expect("query");
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].chunk_id.0, "new");
+    let scoped = index
+        .query_in_docs(
+            "rotation",
+            10,
+            &[
+                kc_core::types::DocId("d-old".to_string()),
+                kc_core::types::DocId("d-new".to_string()),
+            ],
+        )
+        .expect("scoped");
+    assert_eq!(scoped.len(), 1);
+    assert_eq!(scoped[0].chunk_id.0, "new");
+}
@@ -0,0 +1,113 @@
+use kc_core::index_traits::LexicalIndex;
+use kc_index::fts::{rebuild_rows, FtsConfig, FtsRow, SqliteFtsIndex};
+use kc_index::query::parse_query;
+
+fn invalid_at(query: &str) -> i64 {
+    let err = parse_query(query).expect_err(query);
+    assert_eq!(err.code, "KC_QUERY_INVALID", "{query}");
+    err.details["position"].as_i64().expect("position")
+}
+
+#[test]
+fn query_compiles_phrases_booleans_and_prefixes_to_quoted_fts5() {
+    let parsed = parse_query("\"signing keys\" OR rotat* NOT draft").expect("parse");
+    assert_eq!(
+        parsed.fts_expression.as_deref(),
+        Some("(\"signing keys\" OR (\"rotat\"* NOT \"draft\"))")
+    );
+    assert_eq!(parsed.vector_text, "signing keys rotat");
+
+    let implicit = parse_query("cats and (dogs OR e-mail) AND C++").expect("parse");
+    assert_eq!(
+        implicit.fts_expression.as_deref(),
+        Some("(\"cats\" AND \"and\" AND (\"dogs\" OR \"e-mail\") AND \"C++\")")
+    );
+
+    let empty = parse_query("   ").expect("empty");
+    assert_eq!(empty.fts_expression, None);
+    assert_eq!(empty.vector_text, "");
+}
+
+#[test]
+fn query_field_operators_become_doc_filters() {
+    let parsed =
+        parse_query("source:notes mime:\"text/plain\" after:2024-02 budget source:manuals")
+            .expect("parse");
+    assert_eq!(parsed.fts_expression.as_deref(), Some("\"budget\""));
+    assert_eq!(parsed.vector_text, "budget");
+    assert_eq!(parsed.filter.source_kinds, vec!["notes", "manuals"]);
+    assert_eq!(parsed.filter.mimes, vec!["text/plain"]);
+    assert_eq!(parsed.filter.effective_ts_from_ms, Some(1_706_745_600_000));
+
+    let latest = parse_query("after:2023 after:2024-02-29").expect("leap day");
+    assert_eq!(latest.filter.effective_ts_from_ms, Some(1_709_164_800_000));
+    assert_eq!(latest.fts_expression, None);
+}
+
+#[test]
+fn query_errors_report_the_offending_position() {
+    assert_eq!(invalid_at("keys\" OR (menu"), 4);
+    assert_eq!(invalid_at("(alpha beta"), 0);
+    assert_eq!(invalid_at("alpha)"), 5);
+    assert_eq!(invalid_at("NOT alpha"), 0);
+    assert_eq!(invalid_at("alpha OR NOT beta"), 9);
+    assert_eq!(invalid_at("alpha AND"), 9);
+    assert_eq!(invalid_at("OR alpha"), 0);
+    assert_eq!(invalid_at("al*pha"), 2);
+    assert_eq!(invalid_at("alpha (source:notes)"), 7);
+    assert_eq!(invalid_at("source: alpha"), 7);
+    assert_eq!(invalid_at("alpha after:2024-13"), 6);
+    assert_eq!(invalid_at("alpha \"\""), 6);
+}
+
+#[test]
+fn fts_index_runs_parsed_queries_without_syntax_errors() {
+    let conn = rusqlite::Connection::open_in_memory().expect("memory db");
+    rebuild_rows(
+        &conn,
+        &FtsConfig::default(),
+        &[
+            FtsRow {
+                chunk_id: "c1".to_string(),
+                doc_id: "d1".to_string(),
+                ordinal: 0,
+                content: "rotate signing keys quarterly".to_string(),
+            },
+            FtsRow {
+                chunk_id: "c2".to_string(),
+                doc_id: "d2".to_string(),
+                ordinal: 0,
+                content: "rotation draft for e-mail keys".to_string(),
+            },
+        ],
+    )
+    .expect("rebuild");
+    let index = SqliteFtsIndex::new(conn, &FtsConfig::default()).expect("index");
+    let ids = |q: &str| -> Vec<String> {
+        let mut ids: Vec<String> = index
+            .query(q, 10)
+            .expect(q)
+            .into_iter()
+            .map(|c| c.chunk_id.0)
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    assert_eq!(ids("rotat*"), vec!["c1", "c2"]);
+    assert_eq!(ids("rotat* NOT draft"), vec!["c1"]);
+    assert_eq!(ids("\"signing keys\" OR e-mail"), vec!["c1", "c2"]);
+    assert_eq!(ids("keys source:notes"), ids("keys"));
+    assert!(ids("C++ (-)").is_empty());
+    assert_eq!(
+        index.query("keys \"", 10).expect_err("unterminated").code,
+        "KC_QUERY_INVALID"
+    );
+    assert_eq!(
+        index
+            .query_filter("keys source:notes")
+            .expect("filter")
+            .source_kinds,
+        vec!["notes"]
+    );
+}
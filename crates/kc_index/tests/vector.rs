@@ -1,8 +1,10 @@
 use kc_core::app_error::AppResult;
-use kc_core::index_traits::VectorIndex;
+use kc_core::index_traits::{IndexChunk, VectorIndex};
 use kc_core::types::{ChunkId, DocId};
 use kc_index::embedding::{Embedder, EmbeddingIdentity};
-use kc_index::vector::{LanceDbVectorIndex, VectorRow};
+use kc_index::vector::{AnnIndexKind, LanceDbVectorIndex, VectorIndexConfig, VectorRow};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 struct DummyEmbedder;
 
@@ -68,3 +70,201 @@ fn vector_query_returns_ranked_hits() {
         .expect("query from reloaded index");
     assert_eq!(reloaded_hits[0].chunk_id.0, "c1");
 }
+
+struct CountingEmbedder {
+    embedded: Arc<AtomicUsize>,
+}
+
+impl Embedder for CountingEmbedder {
+    fn identity(&self) -> EmbeddingIdentity {
+        DummyEmbedder.identity()
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        self.embedded.fetch_add(texts.len(), Ordering::SeqCst);
+        DummyEmbedder.embed(texts)
+    }
+}
+
+fn chunk(doc_id: &str, chunk_id: &str, ordinal: i64, text: &str) -> IndexChunk {
+    IndexChunk {
+        chunk_id: ChunkId(chunk_id.to_string()),
+        doc_id: DocId(doc_id.to_string()),
+        ordinal,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn vector_rebuild_for_doc_only_embeds_and_writes_changed_doc() {
+    let db_path = tempfile::tempdir()
+        .expect("tempdir")
+        .keep()
+        .join("vectors/lancedb-v1");
+    let embedded = Arc::new(AtomicUsize::new(0));
+    let index = LanceDbVectorIndex::open(
+        CountingEmbedder {
+            embedded: embedded.clone(),
+        },
+        &db_path,
+    )
+    .expect("open index");
+    let d1 = DocId("d1".to_string());
+    let d2 = DocId("d2".to_string());
+
+    index
+        .rebuild_for_doc(&d1, &[chunk("d1", "c1", 0, "alpha one")])
+        .expect("add d1");
+    index
+        .rebuild_for_doc(&d2, &[chunk("d2", "c2", 0, "beta two")])
+        .expect("add d2");
+    assert_eq!(embedded.load(Ordering::SeqCst), 2);
+
+    index
+        .rebuild_for_doc(&d1, &[chunk("d1", "c1", 0, "alpha one")])
+        .expect("unchanged d1");
+    assert_eq!(embedded.load(Ordering::SeqCst), 2);
+
+    index
+        .rebuild_for_doc(
+            &d1,
+            &[
+                chunk("d1", "c1", 0, "alpha one"),
+                chunk("d1", "c3", 1, "alpha three"),
+            ],
+        )
+        .expect("replace d1");
+    assert_eq!(embedded.load(Ordering::SeqCst), 3);
+
+    index.delete_for_doc(&d2).expect("delete d2");
+    index.delete_for_doc(&d2).expect("delete d2 again");
+
+    let reloaded = LanceDbVectorIndex::open(DummyEmbedder, &db_path).expect("reload index");
+    let mut ids: Vec<String> = reloaded
+        .query("beta", 10)
+        .expect("query")
+        .into_iter()
+        .map(|hit| hit.chunk_id.0)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec!["c1", "c3"]);
+}
+
+#[test]
+fn vector_query_breaks_distance_ties_by_doc_ordinal_chunk() {
+    let db_path = tempfile::tempdir()
+        .expect("tempdir")
+        .keep()
+        .join("vectors/lancedb-v1");
+    let mut index = LanceDbVectorIndex::open(DummyEmbedder, &db_path).expect("open index");
+    let rows = (0..30)
+        .rev()
+        .map(|i| VectorRow {
+            chunk_id: ChunkId(format!("c{:02}", 29 - i)),
+            doc_id: DocId(format!("d{:02}", i / 2)),
+            ordinal: i % 2,
+            text: "alpha".to_string(),
+            vector: vec![1.0, 0.0],
+        })
+        .collect();
+    index.upsert_rows(rows).expect("upsert rows");
+
+    let hits = index.query("alpha", 5).expect("query");
+    let ids: Vec<(String, i64)> = hits
+        .iter()
+        .map(|hit| (hit.chunk_id.0.clone(), hit.rank))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            ("c29".to_string(), 1),
+            ("c28".to_string(), 2),
+            ("c27".to_string(), 3),
+            ("c26".to_string(), 4),
+            ("c25".to_string(), 5),
+        ]
+    );
+    let again: Vec<(String, i64)> = index
+        .query("alpha", 5)
+        .expect("query again")
+        .into_iter()
+        .map(|hit| (hit.chunk_id.0, hit.rank))
+        .collect();
+    assert_eq!(again, ids);
+}
+
+struct WideEmbedder;
+
+impl Embedder for WideEmbedder {
+    fn identity(&self) -> EmbeddingIdentity {
+        EmbeddingIdentity {
+            dims: 8,
+            ..DummyEmbedder.identity()
+        }
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|t| wide_vector(t.trim_start_matches("row ").parse().unwrap_or(0)))
+            .collect())
+    }
+}
+
+fn wide_vector(i: usize) -> Vec<f32> {
+    (0..8)
+        .map(|j| ((i * 7 + j * 13) % 17) as f32 + if j == i % 8 { 40.0 } else { 0.0 })
+        .collect()
+}
+
+#[test]
+fn vector_builds_ann_index_above_row_threshold() {
+    let db_path = tempfile::tempdir()
+        .expect("tempdir")
+        .keep()
+        .join("vectors/lancedb-v1");
+    let config = VectorIndexConfig {
+        ann_index: AnnIndexKind::IvfPq,
+        ann_row_threshold: 300,
+        ..VectorIndexConfig::default()
+    };
+    let index =
+        LanceDbVectorIndex::open_with_config(WideEmbedder, &db_path, config).expect("open index");
+    let doc_rows = |doc: usize| -> Vec<VectorRow> {
+        (doc * 100..doc * 100 + 100)
+            .map(|i| VectorRow {
+                chunk_id: ChunkId(format!("c{i:04}")),
+                doc_id: DocId(format!("d{doc}")),
+                ordinal: i as i64,
+                text: format!("row {i}"),
+                vector: wide_vector(i),
+            })
+            .collect()
+    };
+
+    for doc in 0..2 {
+        index
+            .replace_doc_rows(&DocId(format!("d{doc}")), doc_rows(doc))
+            .expect("write rows");
+    }
+    let status = index.status().expect("status");
+    assert_eq!(status.rows, 200);
+    assert!(status.ann_index.is_none());
+
+    index
+        .replace_doc_rows(&DocId("d2".to_string()), doc_rows(2))
+        .expect("write rows");
+    let status = index.status().expect("status");
+    assert_eq!(status.rows, 300);
+    assert!(status.ann_index.is_some());
+    assert_eq!(status.unindexed_rows, 0);
+
+    let hits = index.query("row 123", 3).expect("query");
+    assert_eq!(hits[0].chunk_id.0, "c0123");
+    assert_eq!(hits.len(), 3);
+
+    assert_eq!(
+        AnnIndexKind::parse("flat").expect_err("unknown kind").code,
+        "KC_VECTOR_INDEX_INIT_FAILED"
+    );
+}